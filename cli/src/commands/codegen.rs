@@ -12,6 +12,7 @@ use std::{
 };
 use subxt_codegen::{
     DerivesRegistry,
+    RuntimeGeneratorOptions,
     TypeSubstitutes,
 };
 
@@ -40,6 +41,22 @@ pub struct Opts {
     /// Defaults to `::subxt`.
     #[clap(long = "crate")]
     crate_path: Option<String>,
+    /// Generate string constants naming each pallet and its calls, events, storage entries
+    /// and constants, for use with the dynamic APIs.
+    #[clap(long = "item-names")]
+    item_names: bool,
+    /// Generate `From` conversions between generated types from different crates which have
+    /// the same name and exactly the same fields or variants.
+    #[clap(long = "type-conversions")]
+    type_conversions: bool,
+    /// Refer to `alloc` rather than `std` in the generated code, for use from `no_std`
+    /// crates.
+    #[clap(long = "no-std")]
+    no_std: bool,
+    /// Canonicalize the type IDs in the metadata before generating code, so that equivalent
+    /// metadata from different sources generates the same code.
+    #[clap(long = "canonicalize-type-ids")]
+    canonicalize_type_ids: bool,
 }
 
 fn derive_for_type_parser(src: &str) -> Result<(String, String), String> {
@@ -69,7 +86,18 @@ pub async fn run(opts: Opts) -> color_eyre::Result<()> {
         subxt_codegen::utils::fetch_metadata_bytes(&url).await?
     };
 
-    codegen(&bytes, opts.derives, opts.derives_for_type, opts.crate_path)?;
+    codegen(
+        &bytes,
+        opts.derives,
+        opts.derives_for_type,
+        opts.crate_path,
+        RuntimeGeneratorOptions {
+            generate_item_names: opts.item_names,
+            generate_type_conversions: opts.type_conversions,
+            no_std: opts.no_std,
+            canonicalize_type_ids: opts.canonicalize_type_ids,
+        },
+    )?;
     Ok(())
 }
 
@@ -78,6 +106,7 @@ fn codegen(
     raw_derives: Vec<String>,
     derives_for_type: Vec<(String, String)>,
     crate_path: Option<String>,
+    options: RuntimeGeneratorOptions,
 ) -> color_eyre::Result<()> {
     let item_mod = syn::parse_quote!(
         pub mod api {}
@@ -100,12 +129,13 @@ fn codegen(
 
     let type_substitutes = TypeSubstitutes::new(&crate_path);
 
-    let runtime_api = subxt_codegen::generate_runtime_api_from_bytes(
+    let runtime_api = subxt_codegen::generate_runtime_api_from_bytes_with_options(
         item_mod,
         metadata_bytes,
         derives,
         type_substitutes,
         crate_path,
+        options,
     );
     println!("{runtime_api}");
     Ok(())
@@ -27,6 +27,9 @@ pub struct Opts {
     /// The format of the metadata to display: `json`, `hex` or `bytes`.
     #[clap(long, short, default_value = "bytes")]
     format: String,
+    /// Print `json` metadata on a single line rather than pretty printing it.
+    #[clap(long)]
+    compact: bool,
 }
 
 pub async fn run(opts: Opts) -> color_eyre::Result<()> {
@@ -36,9 +39,16 @@ pub async fn run(opts: Opts) -> color_eyre::Result<()> {
         "json" => {
             let bytes = hex::decode(hex_data.trim_start_matches("0x"))?;
             let metadata = <RuntimeMetadataPrefixed as Decode>::decode(&mut &bytes[..])?;
-            let json = serde_json::to_string_pretty(&metadata)?;
-            println!("{json}");
-            Ok(())
+            // The JSON for a large runtime runs to many megabytes, so it's written out as
+            // it's serialized rather than being built up in memory first.
+            let mut out = io::BufWriter::new(io::stdout().lock());
+            if opts.compact {
+                serde_json::to_writer(&mut out, &metadata)?;
+            } else {
+                serde_json::to_writer_pretty(&mut out, &metadata)?;
+            }
+            writeln!(out)?;
+            Ok(out.flush()?)
         }
         "hex" => {
             println!("{hex_data}");
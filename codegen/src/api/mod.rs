@@ -7,6 +7,7 @@
 mod calls;
 mod constants;
 mod events;
+mod names;
 mod storage;
 
 use subxt_metadata::get_metadata_per_pallet_hash;
@@ -21,7 +22,9 @@ use crate::{
         TypeSubstitutes,
     },
     utils::{
+        canonicalize_type_ids,
         fetch_metadata_bytes_blocking,
+        std_paths_to_alloc,
         Uri,
     },
     CratePath,
@@ -47,6 +50,11 @@ use std::{
 };
 use syn::parse_quote;
 
+/// The version of the interface between the generated code and the `subxt` crate that
+/// this release of `subxt-codegen` targets. Generated code fails to compile, with a
+/// message saying as much, unless this matches `subxt::GENERATED_CODE_VERSION`.
+pub const GENERATED_CODE_VERSION: u32 = 1;
+
 /// Generates the API for interacting with a Substrate runtime.
 ///
 /// # Arguments
@@ -65,6 +73,30 @@ pub fn generate_runtime_api_from_path<P>(
     type_substitutes: TypeSubstitutes,
     crate_path: CratePath,
 ) -> TokenStream2
+where
+    P: AsRef<path::Path>,
+{
+    generate_runtime_api_from_path_with_options(
+        item_mod,
+        path,
+        derives,
+        type_substitutes,
+        crate_path,
+        RuntimeGeneratorOptions::default(),
+    )
+}
+
+/// Generates the API for interacting with a Substrate runtime, like
+/// [`generate_runtime_api_from_path()`], along with the optional parts given in
+/// `options`.
+pub fn generate_runtime_api_from_path_with_options<P>(
+    item_mod: syn::ItemMod,
+    path: P,
+    derives: DerivesRegistry,
+    type_substitutes: TypeSubstitutes,
+    crate_path: CratePath,
+    options: RuntimeGeneratorOptions,
+) -> TokenStream2
 where
     P: AsRef<path::Path>,
 {
@@ -76,12 +108,13 @@ where
     file.read_to_end(&mut bytes)
         .unwrap_or_else(|e| abort_call_site!("Failed to read metadata file: {}", e));
 
-    generate_runtime_api_from_bytes(
+    generate_runtime_api_from_bytes_with_options(
         item_mod,
         &bytes,
         derives,
         type_substitutes,
         crate_path,
+        options,
     )
 }
 
@@ -104,16 +137,37 @@ pub fn generate_runtime_api_from_url(
     derives: DerivesRegistry,
     type_substitutes: TypeSubstitutes,
     crate_path: CratePath,
+) -> TokenStream2 {
+    generate_runtime_api_from_url_with_options(
+        item_mod,
+        url,
+        derives,
+        type_substitutes,
+        crate_path,
+        RuntimeGeneratorOptions::default(),
+    )
+}
+
+/// Generates the API for interacting with a substrate runtime, like
+/// [`generate_runtime_api_from_url()`], along with the optional parts given in `options`.
+pub fn generate_runtime_api_from_url_with_options(
+    item_mod: syn::ItemMod,
+    url: &Uri,
+    derives: DerivesRegistry,
+    type_substitutes: TypeSubstitutes,
+    crate_path: CratePath,
+    options: RuntimeGeneratorOptions,
 ) -> TokenStream2 {
     let bytes = fetch_metadata_bytes_blocking(url)
         .unwrap_or_else(|e| abort_call_site!("Failed to obtain metadata: {}", e));
 
-    generate_runtime_api_from_bytes(
+    generate_runtime_api_from_bytes_with_options(
         item_mod,
         &bytes,
         derives,
         type_substitutes,
         crate_path,
+        options,
     )
 }
 
@@ -134,12 +188,60 @@ pub fn generate_runtime_api_from_bytes(
     derives: DerivesRegistry,
     type_substitutes: TypeSubstitutes,
     crate_path: CratePath,
+) -> TokenStream2 {
+    generate_runtime_api_from_bytes_with_options(
+        item_mod,
+        bytes,
+        derives,
+        type_substitutes,
+        crate_path,
+        RuntimeGeneratorOptions::default(),
+    )
+}
+
+/// Generates the API for interacting with a substrate runtime, like
+/// [`generate_runtime_api_from_bytes()`], along with the optional parts given in
+/// `options`.
+pub fn generate_runtime_api_from_bytes_with_options(
+    item_mod: syn::ItemMod,
+    bytes: &[u8],
+    derives: DerivesRegistry,
+    type_substitutes: TypeSubstitutes,
+    crate_path: CratePath,
+    options: RuntimeGeneratorOptions,
 ) -> TokenStream2 {
     let metadata = frame_metadata::RuntimeMetadataPrefixed::decode(&mut &bytes[..])
         .unwrap_or_else(|e| abort_call_site!("Failed to decode metadata: {}", e));
 
     let generator = RuntimeGenerator::new(metadata);
-    generator.generate_runtime(item_mod, derives, type_substitutes, crate_path)
+    generator.generate_runtime_with_options(
+        item_mod,
+        derives,
+        type_substitutes,
+        crate_path,
+        options,
+    )
+}
+
+/// Optional parts of the generated API, and how it's generated. The default leaves them
+/// all out.
+#[derive(Debug, Clone, Default)]
+pub struct RuntimeGeneratorOptions {
+    /// Emit string constants naming the pallets and their items.
+    pub generate_item_names: bool,
+    /// Emit `From` conversions between generated types with the same shape (see
+    /// [`TypeGenerator::generate_type_conversions()`]).
+    pub generate_type_conversions: bool,
+    /// Refer to `alloc` rather than `std` in the generated code, so that it can be used
+    /// from a `no_std` crate which declares `extern crate alloc;` (see
+    /// [`std_paths_to_alloc`]). The `subxt` crate needs `std`, so the code only builds
+    /// without it if the crate path, and any custom derives and type substitutes, point at
+    /// `no_std` friendly crates which provide the same items.
+    pub no_std: bool,
+    /// Canonicalize the type IDs in the metadata before generating code from it (see
+    /// [`canonicalize_type_ids`]), so that equivalent metadata from different sources
+    /// always leads to the same generated code.
+    pub canonicalize_type_ids: bool,
 }
 
 /// Create the API for interacting with a Substrate runtime.
@@ -166,6 +268,8 @@ impl RuntimeGenerator {
     ///
     /// * `item_mod` - The module declaration for which the API is implemented.
     /// * `derives` - Provide custom derives for the generated types.
+    /// * `type_substitutes` - Provide custom type substitutes.
+    /// * `crate_path` - Path to the `subxt` crate.
     pub fn generate_runtime(
         &self,
         item_mod: syn::ItemMod,
@@ -173,6 +277,40 @@ impl RuntimeGenerator {
         type_substitutes: TypeSubstitutes,
         crate_path: CratePath,
     ) -> TokenStream2 {
+        self.generate_runtime_with_options(
+            item_mod,
+            derives,
+            type_substitutes,
+            crate_path,
+            RuntimeGeneratorOptions::default(),
+        )
+    }
+
+    /// Generate the API for interacting with a Substrate runtime, like
+    /// [`Self::generate_runtime()`], along with the optional parts given in `options`.
+    pub fn generate_runtime_with_options(
+        &self,
+        item_mod: syn::ItemMod,
+        derives: DerivesRegistry,
+        type_substitutes: TypeSubstitutes,
+        crate_path: CratePath,
+        options: RuntimeGeneratorOptions,
+    ) -> TokenStream2 {
+        if options.canonicalize_type_ids {
+            let mut metadata = self.metadata.clone();
+            canonicalize_type_ids(&mut metadata);
+            return RuntimeGenerator { metadata }.generate_runtime_with_options(
+                item_mod,
+                derives,
+                type_substitutes,
+                crate_path,
+                RuntimeGeneratorOptions {
+                    canonicalize_type_ids: false,
+                    ..options
+                },
+            )
+        }
+
         let item_mod_attrs = item_mod.attrs.clone();
         let item_mod_ir = ir::ItemMod::from(item_mod);
         let default_derives = derives.default_derives();
@@ -186,6 +324,11 @@ impl RuntimeGenerator {
         );
         let types_mod = type_gen.generate_types_mod();
         let types_mod_ident = types_mod.ident();
+        let type_conversions = if options.generate_type_conversions {
+            type_gen.generate_type_conversions()
+        } else {
+            quote!()
+        };
         let pallets_with_mod_names = self
             .metadata
             .pallets
@@ -239,10 +382,17 @@ impl RuntimeGenerator {
                 &crate_path,
             );
 
+            let item_names = if options.generate_item_names {
+                names::generate_item_names(&self.metadata, pallet)
+            } else {
+                quote!()
+            };
+
             quote! {
                 pub mod #mod_name {
                     use super::root_mod;
                     use super::#types_mod_ident;
+                    #item_names
                     #calls
                     #event
                     #storage_mod
@@ -294,8 +444,14 @@ impl RuntimeGenerator {
             .collect();
 
         let rust_items = item_mod_ir.rust_items();
+        let generated_code_version = GENERATED_CODE_VERSION;
+        let version_mismatch = format!(
+            "This code was generated for version {GENERATED_CODE_VERSION} of the generated \
+             code interface, which the subxt crate in use doesn't support. Regenerate it using \
+             the subxt-codegen or subxt-cli release matching your subxt dependency."
+        );
 
-        quote! {
+        let runtime = quote! {
             #( #item_mod_attrs )*
             #[allow(dead_code, unused_imports, non_camel_case_types)]
             #[allow(clippy::all)]
@@ -303,6 +459,13 @@ impl RuntimeGenerator {
                 // Preserve any Rust items that were previously defined in the adorned module
                 #( #rust_items ) *
 
+                // Check that the subxt crate in use understands this generated code:
+                const _: () = {
+                    if #crate_path::GENERATED_CODE_VERSION != #generated_code_version {
+                        panic!(#version_mismatch);
+                    }
+                };
+
                 // Make it easy to access the root via `root_mod` at different levels:
                 use super::#mod_ident as root_mod;
                 // Identify the pallets composing the static metadata by name.
@@ -311,6 +474,7 @@ impl RuntimeGenerator {
                 #outer_event
                 #( #modules )*
                 #types_mod
+                #type_conversions
 
                 /// The default error type returned when there is a runtime issue,
                 /// exposed here for ease of use.
@@ -365,6 +529,11 @@ impl RuntimeGenerator {
                     }
                 }
             }
+        };
+        if options.no_std {
+            std_paths_to_alloc(runtime)
+        } else {
+            runtime
         }
     }
 }
@@ -0,0 +1,140 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use frame_metadata::{
+    v14::RuntimeMetadataV14,
+    PalletMetadata,
+};
+use heck::ToShoutySnakeCase as _;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{
+    format_ident,
+    quote,
+};
+use scale_info::{
+    form::PortableForm,
+    TypeDef,
+};
+
+/// Generate string constants for the names of the provided pallet and its items.
+///
+/// The constants are placed directly in the pallet's module, and are prefixed by
+/// the kind of item that they name:
+/// ```ignore
+/// pub mod PalletName {
+///     pub const PALLET: &str = "PalletName";
+///     pub const CALL_CALL_NAME: &str = "call_name";
+///     pub const EVENT_EVENT_NAME: &str = "EventName";
+///     pub const STORAGE_STORAGE_NAME: &str = "StorageName";
+///     pub const CONSTANT_CONSTANT_NAME: &str = "ConstantName";
+/// }
+/// ```
+///
+/// These can be given to the dynamic APIs in place of string literals, so that the
+/// compiler catches mistyped names.
+///
+/// # Arguments
+///
+/// - `metadata` - Runtime metadata from which the names are generated.
+/// - `pallet` - Pallet metadata from which the names are generated.
+pub fn generate_item_names(
+    metadata: &RuntimeMetadataV14,
+    pallet: &PalletMetadata<PortableForm>,
+) -> TokenStream2 {
+    let variant_names = |ty_id: Option<u32>| -> Vec<String> {
+        let Some(ty) = ty_id.and_then(|id| metadata.types.resolve(id)) else {
+            return Vec::new()
+        };
+        match ty.type_def() {
+            TypeDef::Variant(variant) => {
+                variant
+                    .variants()
+                    .iter()
+                    .map(|v| v.name().to_string())
+                    .collect()
+            }
+            _ => Vec::new(),
+        }
+    };
+
+    let calls = variant_names(pallet.calls.as_ref().map(|c| c.ty.id()));
+    let events = variant_names(pallet.event.as_ref().map(|e| e.ty.id()));
+    let storage = pallet
+        .storage
+        .as_ref()
+        .map(|s| s.entries.iter().map(|e| e.name.clone()).collect())
+        .unwrap_or_default();
+    let constants = pallet.constants.iter().map(|c| c.name.clone()).collect();
+
+    let item_consts = [
+        ("CALL", calls),
+        ("EVENT", events),
+        ("STORAGE", storage),
+        ("CONSTANT", constants),
+    ]
+    .into_iter()
+    .flat_map(|(kind, names)| {
+        names.into_iter().map(move |name| {
+            let const_name = format_ident!("{}_{}", kind, name.to_shouty_snake_case());
+            quote! {
+                pub const #const_name: &str = #name;
+            }
+        })
+    });
+
+    let pallet_name = &pallet.name;
+    quote! {
+        pub const PALLET: &str = #pallet_name;
+        #( #item_consts )*
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Decode;
+    use frame_metadata::{
+        RuntimeMetadata,
+        RuntimeMetadataPrefixed,
+    };
+
+    #[test]
+    fn generates_names_for_pallet_items() {
+        let bytes = include_bytes!("../../../artifacts/polkadot_metadata.scale");
+        let metadata = RuntimeMetadataPrefixed::decode(&mut &bytes[..]).unwrap();
+        let RuntimeMetadata::V14(metadata) = metadata.1 else {
+            panic!("expected V14 metadata")
+        };
+        let balances = metadata
+            .pallets
+            .iter()
+            .find(|p| p.name == "Balances")
+            .unwrap();
+
+        let names = generate_item_names(&metadata, balances).to_string();
+        for expected in [
+            quote!(
+                pub const PALLET: &str = "Balances";
+            ),
+            quote!(
+                pub const CALL_TRANSFER: &str = "transfer";
+            ),
+            quote!(
+                pub const CALL_TRANSFER_KEEP_ALIVE: &str = "transfer_keep_alive";
+            ),
+            quote!(
+                pub const EVENT_TRANSFER: &str = "Transfer";
+            ),
+            quote!(
+                pub const STORAGE_TOTAL_ISSUANCE: &str = "TotalIssuance";
+            ),
+            quote!(
+                pub const CONSTANT_EXISTENTIAL_DEPOSIT: &str = "ExistentialDeposit";
+            ),
+        ] {
+            let expected = expected.to_string();
+            assert!(names.contains(&expected), "{expected} not in {names}");
+        }
+    }
+}
@@ -77,7 +77,7 @@ fn generate_storage_entry_fns(
     crate_path: &CratePath,
 ) -> TokenStream2 {
     let (fields, key_impl) = match storage_entry.ty {
-        StorageEntryType::Plain(_) => (vec![], quote!(vec![])),
+        StorageEntryType::Plain(_) => (vec![], quote!(::std::vec![])),
         StorageEntryType::Map {
             ref key,
             ref hashers,
@@ -124,7 +124,7 @@ fn generate_storage_entry_fns(
                                 quote!( #crate_path::storage::address::StorageMapKey::new(#field_name.borrow(), #hasher) )
                             });
                         quote! {
-                            vec![ #( #keys ),* ]
+                            ::std::vec![ #( #keys ),* ]
                         }
                     } else if hashers.len() == 1 {
                         // If there is one hasher, then however many fields we have, we want to hash a
@@ -134,7 +134,7 @@ fn generate_storage_entry_fns(
                         let items =
                             fields.iter().map(|(field_name, _)| quote!( #field_name ));
                         quote! {
-                            vec![ #crate_path::storage::address::StorageMapKey::new(&(#( #items.borrow() ),*), #hasher) ]
+                            ::std::vec![ #crate_path::storage::address::StorageMapKey::new(&(#( #items.borrow() ),*), #hasher) ]
                         }
                     } else {
                         // If we hit this condition, we don't know how to handle the number of hashes vs fields
@@ -155,7 +155,7 @@ fn generate_storage_entry_fns(
                         abort_call_site!("No hasher found for single key")
                     });
                     let key_impl = quote! {
-                        vec![ #crate_path::storage::address::StorageMapKey::new(_0.borrow(), #hasher) ]
+                        ::std::vec![ #crate_path::storage::address::StorageMapKey::new(_0.borrow(), #hasher) ]
                     };
                     (fields, key_impl)
                 }
@@ -231,7 +231,7 @@ fn generate_storage_entry_fns(
                 #crate_path::storage::address::StaticStorageAddress::new(
                     #pallet_name,
                     #storage_name,
-                    Vec::new(),
+                    ::std::vec::Vec::new(),
                     [#(#storage_hash,)*]
                 )
             }
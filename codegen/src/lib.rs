@@ -40,6 +40,19 @@
 //! let runtime_api = generator.generate_runtime(item_mod, derives, substs, CratePath::default());
 //! println!("{}", runtime_api);
 //! ```
+//!
+//! ## Compatibility with `subxt`
+//!
+//! The generated code makes use of the `subxt` crate, and so it must be compiled against a
+//! release of `subxt` which exposes what the code expects. Each release of this crate targets
+//! a [`GENERATED_CODE_VERSION`], and the generated code checks at compile time that this
+//! matches `subxt::GENERATED_CODE_VERSION`. If it doesn't, compilation fails with a message
+//! asking for the code to be regenerated, rather than with errors from deep within the
+//! generated code.
+//!
+//! When upgrading `subxt`, code generated ahead of time (for instance with `subxt codegen`)
+//! only needs regenerating if the version has changed. Otherwise, `subxt` and the generated
+//! code can be upgraded separately.
 
 #![deny(unused_crate_dependencies)]
 
@@ -52,9 +65,14 @@ pub mod utils;
 pub use self::{
     api::{
         generate_runtime_api_from_bytes,
+        generate_runtime_api_from_bytes_with_options,
         generate_runtime_api_from_path,
+        generate_runtime_api_from_path_with_options,
         generate_runtime_api_from_url,
+        generate_runtime_api_from_url_with_options,
         RuntimeGenerator,
+        RuntimeGeneratorOptions,
+        GENERATED_CODE_VERSION,
     },
     types::{
         CratePath,
@@ -0,0 +1,160 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::TypeGenerator;
+use proc_macro2::TokenStream;
+use quote::{
+    format_ident,
+    quote,
+};
+use scale_info::{
+    form::PortableForm,
+    Field,
+    TypeDef,
+};
+use std::collections::BTreeMap;
+
+// The shape of a type: everything about it that the generated type depends on, aside from
+// its path and docs. Types with the same shape generate the same Rust type in different
+// modules, and so values can be moved from one to the other field by field.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Shape {
+    Struct(Vec<FieldShape>),
+    Enum(Vec<(String, u8, Vec<FieldShape>)>),
+}
+
+// A field's name, type and the name it gave its type. The type name decides whether the
+// field is boxed, so it has to match too.
+type FieldShape = (Option<String>, u32, Option<String>);
+
+fn field_shapes(fields: &[Field<PortableForm>]) -> Vec<FieldShape> {
+    fields
+        .iter()
+        .map(|f| (f.name().cloned(), f.ty().id(), f.type_name().cloned()))
+        .collect()
+}
+
+impl<'a> TypeGenerator<'a> {
+    /// Generate `From` conversions, both ways, between each pair of generated types from
+    /// different crates which have the same name and shape: the same fields (or variants
+    /// and their fields) with the same names and of the same types. Large runtimes often
+    /// have several copies of the same type defined in different crates, and these let a
+    /// value of one be passed where another is expected.
+    ///
+    /// This is deliberately conservative, as types with the same shape often mean different
+    /// things. The many newtypes wrapping a `u32` have different names, and types with the
+    /// same name in one crate (such as `ed25519::Public` and `sr25519::Public` in `sp_core`)
+    /// are kept apart on purpose, so neither are converted between. Only types without type
+    /// parameters, and which aren't substituted, are considered.
+    ///
+    /// The conversions refer to types via the root module of the generated types, and so
+    /// must be placed where that is in scope.
+    pub fn generate_type_conversions(&self) -> TokenStream {
+        let mut by_shape: BTreeMap<(String, Shape), Vec<u32>> = BTreeMap::new();
+        for ty in self.type_registry.types() {
+            let path = ty.ty().path();
+            let Some(name) = path.ident() else { continue };
+            if path.namespace().is_empty()
+                || !ty.ty().type_params().is_empty()
+                || self.type_substitutes.for_path(path).is_some()
+            {
+                continue
+            }
+            let shape = match ty.ty().type_def() {
+                TypeDef::Composite(composite) => {
+                    Shape::Struct(field_shapes(composite.fields()))
+                }
+                TypeDef::Variant(variant) => {
+                    Shape::Enum(
+                        variant
+                            .variants()
+                            .iter()
+                            .map(|v| {
+                                (v.name().clone(), v.index(), field_shapes(v.fields()))
+                            })
+                            .collect(),
+                    )
+                }
+                _ => continue,
+            };
+            // A type which refers to itself would refer to the wrong type once converted.
+            let fields_refer_to = |id| {
+                match &shape {
+                    Shape::Struct(fields) => fields.iter().any(|f| f.1 == id),
+                    Shape::Enum(variants) => {
+                        variants.iter().any(|v| v.2.iter().any(|f| f.1 == id))
+                    }
+                }
+            };
+            if fields_refer_to(ty.id()) {
+                continue
+            }
+            by_shape.entry((name, shape)).or_default().push(ty.id());
+        }
+
+        let crate_of = |id| self.resolve_type(id).path().segments().first().cloned();
+        let mut impls = TokenStream::new();
+        for ((_, shape), ids) in by_shape {
+            for from in &ids {
+                for to in &ids {
+                    if crate_of(*from) != crate_of(*to) {
+                        impls.extend(self.generate_conversion(&shape, *from, *to));
+                    }
+                }
+            }
+        }
+        impls
+    }
+
+    fn generate_conversion(&self, shape: &Shape, from: u32, to: u32) -> TokenStream {
+        let from_path = self.resolve_type_path(from);
+        let to_path = self.resolve_type_path(to);
+        let body = match shape {
+            Shape::Struct(fields) => {
+                let (pattern, construct) = fields_pattern(fields);
+                quote! {
+                    let #from_path #pattern = value;
+                    #to_path #construct
+                }
+            }
+            Shape::Enum(variants) => {
+                let arms = variants.iter().map(|(name, _, fields)| {
+                    let name = format_ident!("{}", name);
+                    let (pattern, construct) = fields_pattern(fields);
+                    quote! { #from_path::#name #pattern => #to_path::#name #construct, }
+                });
+                quote! {
+                    match value {
+                        #( #arms )*
+                    }
+                }
+            }
+        };
+        quote! {
+            impl ::core::convert::From<#from_path> for #to_path {
+                fn from(value: #from_path) -> Self {
+                    #body
+                }
+            }
+        }
+    }
+}
+
+// The pattern to take the fields given apart with, and the matching expression to put
+// them back together with.
+fn fields_pattern(fields: &[FieldShape]) -> (TokenStream, TokenStream) {
+    if fields.is_empty() {
+        return (quote!(), quote!())
+    }
+    if fields.iter().all(|f| f.0.is_some()) {
+        let names: Vec<_> = fields
+            .iter()
+            .map(|f| format_ident!("{}", f.0.as_ref().expect("checked above; qed")))
+            .collect();
+        (quote!({ #( #names ),* }), quote!({ #( #names ),* }))
+    } else {
+        let names: Vec<_> = (0..fields.len()).map(|i| format_ident!("_{}", i)).collect();
+        (quote!(( #( #names ),* )), quote!(( #( #names ),* )))
+    }
+}
@@ -3,6 +3,7 @@
 // see LICENSE for license details.
 
 mod composite_def;
+mod conversions;
 mod derives;
 mod substitutes;
 #[cfg(test)]
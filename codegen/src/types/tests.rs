@@ -1075,3 +1075,140 @@ fn apply_user_defined_derives_for_specific_types() {
             .to_string()
     )
 }
+
+#[test]
+fn generate_conversions_between_types_of_the_same_shape() {
+    // Derived types are all placed in this crate, so these spell out which crate, and
+    // which module in it, their types are in.
+    macro_rules! types_in {
+        ($module:ident, $path:literal) => {
+            mod $module {
+                use scale_info::{
+                    build::{
+                        Fields,
+                        Variants,
+                    },
+                    Path,
+                    Type,
+                    TypeInfo,
+                };
+
+                pub struct Weight;
+
+                impl TypeInfo for Weight {
+                    type Identity = Self;
+                    fn type_info() -> Type {
+                        Type::builder().path(Path::new("Weight", $path)).composite(
+                            Fields::named().field(|f| {
+                                f.ty::<u64>().name("ref_time").type_name("u64")
+                            }),
+                        )
+                    }
+                }
+
+                pub struct Kind;
+
+                impl TypeInfo for Kind {
+                    type Identity = Self;
+                    fn type_info() -> Type {
+                        Type::builder().path(Path::new("Kind", $path)).variant(
+                            Variants::new().variant("First", |v| v.index(0)).variant(
+                                "Second",
+                                |v| {
+                                    v.index(1).fields(
+                                        Fields::unnamed()
+                                            .field(|f| f.ty::<u8>().type_name("u8")),
+                                    )
+                                },
+                            ),
+                        )
+                    }
+                }
+            }
+        };
+    }
+    types_in!(a, "crate_a");
+    types_in!(b, "crate_b");
+    // Types of the same name in one crate are kept apart, so these are only converted
+    // to and from those in `crate_b`:
+    types_in!(a_v2, "crate_a::v2");
+
+    // Types with the same fields but another name aren't converted to:
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    struct NotWeight {
+        ref_time: u64,
+    }
+
+    #[allow(unused)]
+    #[derive(TypeInfo)]
+    struct S {
+        a: a::Weight,
+        b: b::Weight,
+        c: a_v2::Weight,
+        d: NotWeight,
+        e: a::Kind,
+        f: b::Kind,
+        g: a_v2::Kind,
+    }
+
+    let mut registry = Registry::new();
+    registry.register_type(&meta_type::<S>());
+    let portable_types: PortableRegistry = registry.into();
+
+    let crate_path = "::subxt_path".into();
+    let type_gen = TypeGenerator::new(
+        &portable_types,
+        "root",
+        TypeSubstitutes::new(&crate_path),
+        DerivesRegistry::new(&crate_path),
+        crate_path,
+    );
+    let conversions = type_gen.generate_type_conversions().to_string();
+
+    let weight_conversion = |from: TokenStream, to: TokenStream| {
+        quote! {
+            impl ::core::convert::From<#from> for #to {
+                fn from(value: #from) -> Self {
+                    let #from { ref_time } = value;
+                    #to { ref_time }
+                }
+            }
+        }
+    };
+    let kind_conversion = |from: TokenStream, to: TokenStream| {
+        quote! {
+            impl ::core::convert::From<#from> for #to {
+                fn from(value: #from) -> Self {
+                    match value {
+                        #from::First => #to::First,
+                        #from::Second(_0) => #to::Second(_0),
+                    }
+                }
+            }
+        }
+    };
+    let expected = [
+        weight_conversion(quote!(root::crate_a::Weight), quote!(root::crate_b::Weight)),
+        weight_conversion(quote!(root::crate_b::Weight), quote!(root::crate_a::Weight)),
+        weight_conversion(
+            quote!(root::crate_a::v2::Weight),
+            quote!(root::crate_b::Weight),
+        ),
+        weight_conversion(
+            quote!(root::crate_b::Weight),
+            quote!(root::crate_a::v2::Weight),
+        ),
+        kind_conversion(quote!(root::crate_a::Kind), quote!(root::crate_b::Kind)),
+        kind_conversion(quote!(root::crate_b::Kind), quote!(root::crate_a::Kind)),
+        kind_conversion(quote!(root::crate_a::v2::Kind), quote!(root::crate_b::Kind)),
+        kind_conversion(quote!(root::crate_b::Kind), quote!(root::crate_a::v2::Kind)),
+    ];
+    for conversion in expected {
+        assert!(
+            conversions.contains(&conversion.to_string()),
+            "{conversion} missing from {conversions}"
+        );
+    }
+    assert_eq!(conversions.matches("impl").count(), 8);
+}
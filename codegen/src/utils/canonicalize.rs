@@ -0,0 +1,480 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Canonicalize the type IDs in some metadata.
+//!
+//! The IDs that types are given in a [`PortableRegistry`] depend on the order in which
+//! they were registered, which can differ between metadata sources even when the types
+//! themselves are identical. Here, we sort the types by their path and shape (neither of
+//! which depend on the IDs), and then hand out new IDs in that order, updating every
+//! reference to them in the metadata. Equivalent metadata therefore ends up with identical
+//! type IDs, which keeps code generated from it reproducible.
+
+use codec::{
+    Compact,
+    Decode,
+    Encode,
+};
+use frame_metadata::{
+    v14::RuntimeMetadataV14,
+    PalletMetadata,
+    StorageEntryType,
+};
+use scale_info::{
+    form::PortableForm,
+    interner::UntrackedSymbol,
+    Field,
+    Path,
+    PortableRegistry,
+    Type,
+    TypeDef,
+    TypeDefArray,
+    TypeDefBitSequence,
+    TypeDefCompact,
+    TypeDefComposite,
+    TypeDefSequence,
+    TypeDefTuple,
+    TypeDefVariant,
+    TypeParameter,
+    Variant,
+};
+use std::collections::HashMap;
+
+/// Re-assign the type IDs in the metadata so that they don't depend on the order in which
+/// types were registered. Each type, and every reference to a type in the metadata, is
+/// updated in place.
+pub fn canonicalize_type_ids(metadata: &mut RuntimeMetadataV14) {
+    let registry = &metadata.types;
+
+    // Sort the types by (description, shape), falling back to the original ID only for
+    // types which are otherwise indistinguishable (and so generate identical code).
+    let mut descriptions = HashMap::new();
+    let mut keyed: Vec<_> = registry
+        .types()
+        .iter()
+        .map(|ty| {
+            let id = ty.id();
+            let description = describe(registry, id, &mut descriptions);
+            let shape = shape(registry, ty.ty(), &mut descriptions);
+            ((description, shape), id)
+        })
+        .collect();
+    keyed.sort();
+
+    let id_map: HashMap<u32, u32> = keyed
+        .iter()
+        .enumerate()
+        .map(|(new_id, (_, old_id))| (*old_id, new_id as u32))
+        .collect();
+
+    let types = keyed
+        .iter()
+        .map(|(_, old_id)| {
+            registry
+                .resolve(*old_id)
+                .expect("ID came from the registry; qed")
+        })
+        .map(|ty| remap_type(ty, &id_map));
+    metadata.types = registry_from_types(types);
+
+    remap_metadata(metadata, &id_map);
+}
+
+/// Build a registry containing exactly the types given, in order. Unlike using a
+/// [`scale_info::PortableRegistryBuilder`], this doesn't merge identical types, which
+/// would leave the IDs out of step with those we've handed out.
+fn registry_from_types(
+    types: impl Iterator<Item = Type<PortableForm>>,
+) -> PortableRegistry {
+    let types: Vec<_> = types
+        .enumerate()
+        .map(|(id, ty)| (Compact(id as u32), ty))
+        .collect();
+    // A registry is encoded identically to a list of (compact ID, type) pairs.
+    PortableRegistry::decode(&mut &*types.encode())
+        .expect("registry is encoded as a list of types; qed")
+}
+
+/// A description of a type which doesn't depend on type IDs. Types with a path are
+/// described by that path and their generic parameters only, which prevents any infinite
+/// recursion for recursive types (these must always go via some named type).
+fn describe(
+    registry: &PortableRegistry,
+    id: u32,
+    cache: &mut HashMap<u32, String>,
+) -> String {
+    if let Some(description) = cache.get(&id) {
+        return description.clone()
+    }
+
+    let ty = registry
+        .resolve(id)
+        .unwrap_or_else(|| panic!("No type with id {id} found"));
+    let mut description = String::new();
+
+    if !ty.path().is_empty() {
+        description.push_str(&ty.path().segments().join("::"));
+        let params: Vec<_> = ty
+            .type_params()
+            .iter()
+            .map(|param| {
+                match param.ty() {
+                    Some(ty) => {
+                        format!("{}={}", param.name(), describe(registry, ty.id(), cache))
+                    }
+                    None => param.name().clone(),
+                }
+            })
+            .collect();
+        if !params.is_empty() {
+            description.push_str(&format!("<{}>", params.join(",")));
+        }
+    } else {
+        description = match ty.type_def() {
+            TypeDef::Composite(_) | TypeDef::Variant(_) => String::from("?"),
+            TypeDef::Sequence(seq) => {
+                format!("[{}]", describe(registry, seq.type_param().id(), cache))
+            }
+            TypeDef::Array(arr) => {
+                format!(
+                    "[{};{}]",
+                    describe(registry, arr.type_param().id(), cache),
+                    arr.len()
+                )
+            }
+            TypeDef::Tuple(tuple) => {
+                let fields: Vec<_> = tuple
+                    .fields()
+                    .iter()
+                    .map(|f| describe(registry, f.id(), cache))
+                    .collect();
+                format!("({})", fields.join(","))
+            }
+            TypeDef::Primitive(primitive) => format!("{primitive:?}"),
+            TypeDef::Compact(compact) => {
+                format!(
+                    "Compact<{}>",
+                    describe(registry, compact.type_param().id(), cache)
+                )
+            }
+            TypeDef::BitSequence(bits) => {
+                format!(
+                    "BitSequence<{},{}>",
+                    describe(registry, bits.bit_store_type().id(), cache),
+                    describe(registry, bits.bit_order_type().id(), cache)
+                )
+            }
+        };
+    }
+
+    cache.insert(id, description.clone());
+    description
+}
+
+/// The shape of a type: its fields and variants, described without reference to type IDs.
+/// This distinguishes any named types whose descriptions are the same.
+fn shape(
+    registry: &PortableRegistry,
+    ty: &Type<PortableForm>,
+    cache: &mut HashMap<u32, String>,
+) -> String {
+    let mut describe_fields = |fields: &[Field<PortableForm>]| {
+        fields
+            .iter()
+            .map(|f| {
+                format!(
+                    "{}:{}",
+                    f.name().map(String::as_str).unwrap_or(""),
+                    describe(registry, f.ty().id(), cache)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    match ty.type_def() {
+        TypeDef::Composite(composite) => {
+            format!("{{{}}}", describe_fields(composite.fields()))
+        }
+        TypeDef::Variant(variant) => {
+            let variants: Vec<_> = variant
+                .variants()
+                .iter()
+                .map(|v| {
+                    format!(
+                        "{}@{}{{{}}}",
+                        v.name(),
+                        v.index(),
+                        describe_fields(v.fields())
+                    )
+                })
+                .collect();
+            variants.join("|")
+        }
+        _ => String::new(),
+    }
+}
+
+fn remap_id<T>(
+    ty: &UntrackedSymbol<T>,
+    id_map: &HashMap<u32, u32>,
+) -> UntrackedSymbol<T> {
+    id_map
+        .get(&ty.id())
+        .copied()
+        .unwrap_or_else(|| panic!("No type with id {} found", ty.id()))
+        .into()
+}
+
+fn remap_fields(
+    fields: &[Field<PortableForm>],
+    id_map: &HashMap<u32, u32>,
+) -> Vec<Field<PortableForm>> {
+    fields
+        .iter()
+        .map(|f| {
+            Field::new(
+                f.name().cloned(),
+                remap_id(f.ty(), id_map),
+                f.type_name().cloned(),
+                f.docs().to_vec(),
+            )
+        })
+        .collect()
+}
+
+fn remap_type(ty: &Type<PortableForm>, id_map: &HashMap<u32, u32>) -> Type<PortableForm> {
+    let path = Path::from_segments_unchecked(ty.path().segments().iter().cloned());
+    let type_params = ty.type_params().iter().map(|param| {
+        TypeParameter::new_portable(
+            param.name().clone(),
+            param.ty().map(|ty| remap_id(ty, id_map)),
+        )
+    });
+    let type_def: TypeDef<PortableForm> = match ty.type_def() {
+        TypeDef::Composite(composite) => {
+            TypeDefComposite::new(remap_fields(composite.fields(), id_map)).into()
+        }
+        TypeDef::Variant(variant) => {
+            TypeDefVariant::new(variant.variants().iter().map(|v| {
+                Variant::new(
+                    v.name().clone(),
+                    remap_fields(v.fields(), id_map),
+                    v.index(),
+                    v.docs().to_vec(),
+                )
+            }))
+            .into()
+        }
+        TypeDef::Sequence(seq) => {
+            TypeDefSequence::new(remap_id(seq.type_param(), id_map)).into()
+        }
+        TypeDef::Array(arr) => {
+            TypeDefArray::new(arr.len(), remap_id(arr.type_param(), id_map)).into()
+        }
+        TypeDef::Tuple(tuple) => {
+            TypeDefTuple::new_portable(tuple.fields().iter().map(|f| remap_id(f, id_map)))
+                .into()
+        }
+        TypeDef::Primitive(primitive) => primitive.clone().into(),
+        TypeDef::Compact(compact) => {
+            TypeDefCompact::new(remap_id(compact.type_param(), id_map)).into()
+        }
+        TypeDef::BitSequence(bits) => {
+            TypeDefBitSequence::new_portable(
+                remap_id(bits.bit_store_type(), id_map),
+                remap_id(bits.bit_order_type(), id_map),
+            )
+            .into()
+        }
+    };
+
+    Type::new(path, type_params, type_def, ty.docs().to_vec())
+}
+
+/// Update every type ID referenced from the metadata (but not from the registry itself).
+fn remap_metadata(metadata: &mut RuntimeMetadataV14, id_map: &HashMap<u32, u32>) {
+    metadata.ty = remap_id(&metadata.ty, id_map);
+
+    let extrinsic = &mut metadata.extrinsic;
+    extrinsic.ty = remap_id(&extrinsic.ty, id_map);
+    for ext in &mut extrinsic.signed_extensions {
+        ext.ty = remap_id(&ext.ty, id_map);
+        ext.additional_signed = remap_id(&ext.additional_signed, id_map);
+    }
+
+    for pallet in &mut metadata.pallets {
+        remap_pallet(pallet, id_map);
+    }
+}
+
+fn remap_pallet(pallet: &mut PalletMetadata<PortableForm>, id_map: &HashMap<u32, u32>) {
+    if let Some(storage) = &mut pallet.storage {
+        for entry in &mut storage.entries {
+            entry.ty = match &entry.ty {
+                StorageEntryType::Plain(ty) => {
+                    StorageEntryType::Plain(remap_id(ty, id_map))
+                }
+                StorageEntryType::Map {
+                    hashers,
+                    key,
+                    value,
+                } => {
+                    StorageEntryType::Map {
+                        hashers: hashers.clone(),
+                        key: remap_id(key, id_map),
+                        value: remap_id(value, id_map),
+                    }
+                }
+            };
+        }
+    }
+    if let Some(calls) = &mut pallet.calls {
+        calls.ty = remap_id(&calls.ty, id_map);
+    }
+    if let Some(event) = &mut pallet.event {
+        event.ty = remap_id(&event.ty, id_map);
+    }
+    for constant in &mut pallet.constants {
+        constant.ty = remap_id(&constant.ty, id_map);
+    }
+    if let Some(error) = &mut pallet.error {
+        error.ty = remap_id(&error.ty, id_map);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CratePath,
+        DerivesRegistry,
+        RuntimeGenerator,
+        RuntimeGeneratorOptions,
+        TypeSubstitutes,
+    };
+    use frame_metadata::{
+        v14::{
+            ExtrinsicMetadata,
+            PalletEventMetadata,
+            PalletMetadata,
+            PalletStorageMetadata,
+            StorageEntryMetadata,
+            StorageEntryModifier,
+        },
+        RuntimeMetadataPrefixed,
+    };
+    use scale_info::{
+        meta_type,
+        TypeInfo,
+    };
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Foo<T> {
+        a: T,
+        b: Vec<(u8, bool)>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    enum Event {
+        A(Foo<u32>),
+        B { recursive: Box<Tree> },
+    }
+
+    #[allow(dead_code)]
+    #[derive(TypeInfo)]
+    struct Tree {
+        children: Vec<Tree>,
+        value: Foo<u64>,
+    }
+
+    fn metadata() -> RuntimeMetadataV14 {
+        let pallets = vec![PalletMetadata {
+            name: "Test",
+            storage: Some(PalletStorageMetadata {
+                prefix: "Test",
+                entries: vec![StorageEntryMetadata {
+                    name: "Trees",
+                    modifier: StorageEntryModifier::Optional,
+                    ty: frame_metadata::v14::StorageEntryType::Plain(meta_type::<Tree>()),
+                    default: vec![0],
+                    docs: vec![],
+                }],
+            }),
+            calls: None,
+            event: Some(PalletEventMetadata {
+                ty: meta_type::<Event>(),
+            }),
+            constants: vec![],
+            error: None,
+            index: 0,
+        }];
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<()>(),
+            version: 0,
+            signed_extensions: vec![],
+        };
+        RuntimeMetadataV14::new(pallets, extrinsic, meta_type::<()>())
+    }
+
+    /// Equivalent metadata, but where the type IDs are handed out in reverse.
+    fn reversed_metadata() -> RuntimeMetadataV14 {
+        let mut metadata = metadata();
+        let num_types = metadata.types.types().len() as u32;
+        let id_map: HashMap<u32, u32> =
+            (0..num_types).map(|id| (id, num_types - 1 - id)).collect();
+
+        let mut types: Vec<_> = metadata
+            .types
+            .types()
+            .iter()
+            .map(|ty| (id_map[&ty.id()], remap_type(ty.ty(), &id_map)))
+            .collect();
+        types.sort_by_key(|(id, _)| *id);
+        metadata.types = registry_from_types(types.into_iter().map(|(_, ty)| ty));
+        remap_metadata(&mut metadata, &id_map);
+        metadata
+    }
+
+    fn generate(metadata: RuntimeMetadataV14) -> String {
+        let item_mod = syn::parse_quote!(
+            pub mod api {}
+        );
+        let crate_path = CratePath::default();
+        let generator = RuntimeGenerator::new(RuntimeMetadataPrefixed::from(metadata));
+        generator
+            .generate_runtime_with_options(
+                item_mod,
+                DerivesRegistry::new(&crate_path),
+                TypeSubstitutes::new(&crate_path),
+                crate_path,
+                RuntimeGeneratorOptions {
+                    canonicalize_type_ids: true,
+                    ..Default::default()
+                },
+            )
+            .to_string()
+    }
+
+    #[test]
+    fn equivalent_metadata_is_canonicalized_identically() {
+        let mut a = metadata();
+        let mut b = reversed_metadata();
+        assert!(
+            a.types != b.types,
+            "test metadata should differ before canonicalizing"
+        );
+
+        canonicalize_type_ids(&mut a);
+        canonicalize_type_ids(&mut b);
+        assert!(a == b, "canonicalized metadata should be identical");
+    }
+
+    #[test]
+    fn equivalent_metadata_generates_identical_code() {
+        assert_eq!(generate(metadata()), generate(reversed_metadata()));
+    }
+}
@@ -1,7 +1,12 @@
+use super::ws_transport::{
+    self,
+    ConnectError,
+};
 use jsonrpsee::{
     async_client::ClientBuilder,
     client_transport::ws::{
         Uri,
+        WsHandshakeError,
         WsTransportClientBuilder,
     },
     core::{
@@ -13,6 +18,44 @@ use jsonrpsee::{
 };
 use std::time::Duration;
 
+/// How long to wait for metadata to be fetched, which can be configured separately
+/// for each kind of transport.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FetchMetadataTimeouts {
+    /// The timeout for a metadata request over HTTP(S).
+    pub http: Duration,
+    /// The timeout for a metadata request over WS(S). This also bounds how long
+    /// the WS handshake can take.
+    pub ws: Duration,
+}
+
+impl FetchMetadataTimeouts {
+    /// Use the same timeout for every transport.
+    pub fn all(timeout: Duration) -> Self {
+        FetchMetadataTimeouts {
+            http: timeout,
+            ws: timeout,
+        }
+    }
+}
+
+impl Default for FetchMetadataTimeouts {
+    fn default() -> Self {
+        FetchMetadataTimeouts::all(Duration::from_secs(180))
+    }
+}
+
+/// Options for fetching metadata with [`fetch_metadata_hex_with_options()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchMetadataOptions {
+    /// How long to wait for the metadata to be fetched.
+    pub timeouts: FetchMetadataTimeouts,
+    /// Subprotocols to offer in the `Sec-WebSocket-Protocol` header of the handshake when
+    /// fetching over WS(S). If any are given, the server has to pick one of them, or
+    /// [`FetchMetadataError::SubprotocolRejected`] is handed back.
+    pub ws_subprotocols: Vec<String>,
+}
+
 /// Returns the metadata bytes from the provided URL, blocking the current thread.
 pub fn fetch_metadata_bytes_blocking(url: &Uri) -> Result<Vec<u8>, FetchMetadataError> {
     tokio_block_on(fetch_metadata_bytes(url))
@@ -34,16 +77,61 @@ fn tokio_block_on<T, Fut: std::future::Future<Output = T>>(fut: Fut) -> T {
 
 /// Returns the metadata bytes from the provided URL.
 pub async fn fetch_metadata_bytes(url: &Uri) -> Result<Vec<u8>, FetchMetadataError> {
-    let hex = fetch_metadata_hex(url).await?;
+    fetch_metadata_bytes_with_timeouts(url, FetchMetadataTimeouts::default()).await
+}
+
+/// Returns the metadata bytes from the provided URL, using the given timeouts.
+pub async fn fetch_metadata_bytes_with_timeouts(
+    url: &Uri,
+    timeouts: FetchMetadataTimeouts,
+) -> Result<Vec<u8>, FetchMetadataError> {
+    let options = FetchMetadataOptions {
+        timeouts,
+        ..Default::default()
+    };
+    fetch_metadata_bytes_with_options(url, &options).await
+}
+
+/// Returns the metadata bytes from the provided URL, using the given options.
+pub async fn fetch_metadata_bytes_with_options(
+    url: &Uri,
+    options: &FetchMetadataOptions,
+) -> Result<Vec<u8>, FetchMetadataError> {
+    let hex = fetch_metadata_hex_with_options(url, options).await?;
     let bytes = hex::decode(hex.trim_start_matches("0x"))?;
     Ok(bytes)
 }
 
 /// Returns the raw, 0x prefixed metadata hex from the provided URL.
 pub async fn fetch_metadata_hex(url: &Uri) -> Result<String, FetchMetadataError> {
+    fetch_metadata_hex_with_timeouts(url, FetchMetadataTimeouts::default()).await
+}
+
+/// Returns the raw, 0x prefixed metadata hex from the provided URL, using the given
+/// timeouts.
+pub async fn fetch_metadata_hex_with_timeouts(
+    url: &Uri,
+    timeouts: FetchMetadataTimeouts,
+) -> Result<String, FetchMetadataError> {
+    let options = FetchMetadataOptions {
+        timeouts,
+        ..Default::default()
+    };
+    fetch_metadata_hex_with_options(url, &options).await
+}
+
+/// Returns the raw, 0x prefixed metadata hex from the provided URL, using the given
+/// options.
+pub async fn fetch_metadata_hex_with_options(
+    url: &Uri,
+    options: &FetchMetadataOptions,
+) -> Result<String, FetchMetadataError> {
+    let timeouts = options.timeouts;
     let hex_data = match url.scheme_str() {
-        Some("http") | Some("https") => fetch_metadata_http(url).await,
-        Some("ws") | Some("wss") => fetch_metadata_ws(url).await,
+        Some("http") | Some("https") => fetch_metadata_http(url, timeouts.http).await,
+        Some("ws") | Some("wss") => {
+            fetch_metadata_ws(url, timeouts.ws, &options.ws_subprotocols).await
+        }
         invalid_scheme => {
             let scheme = invalid_scheme.unwrap_or("no scheme");
             Err(FetchMetadataError::InvalidScheme(scheme.to_owned()))
@@ -52,23 +140,51 @@ pub async fn fetch_metadata_hex(url: &Uri) -> Result<String, FetchMetadataError>
     Ok(hex_data)
 }
 
-async fn fetch_metadata_ws(url: &Uri) -> Result<String, FetchMetadataError> {
-    let (sender, receiver) = WsTransportClientBuilder::default()
-        .build(url.to_string().parse::<Uri>().unwrap())
-        .await
-        .map_err(|e| Error::Transport(e.into()))?;
+async fn fetch_metadata_ws(
+    url: &Uri,
+    timeout: Duration,
+    subprotocols: &[String],
+) -> Result<String, FetchMetadataError> {
+    let builder = ClientBuilder::default()
+        .request_timeout(timeout)
+        .max_notifs_per_subscription(4096);
 
-    let client = ClientBuilder::default()
-        .request_timeout(Duration::from_secs(180))
-        .max_notifs_per_subscription(4096)
-        .build_with_tokio(sender, receiver);
+    // The jsonrpsee transport can't ask for subprotocols, so we use our own to do that.
+    let client = if subprotocols.is_empty() {
+        let (sender, receiver) = WsTransportClientBuilder::default()
+            .connection_timeout(timeout)
+            .build(url.to_string().parse::<Uri>().unwrap())
+            .await
+            .map_err(|e| Error::Transport(e.into()))?;
+        builder.build_with_tokio(sender, receiver)
+    } else {
+        let connect =
+            tokio::time::timeout(timeout, ws_transport::connect(url, subprotocols));
+        let (sender, receiver) = connect
+            .await
+            .unwrap_or(Err(WsHandshakeError::Timeout(timeout).into()))
+            .map_err(|e| {
+                match e {
+                    ConnectError::Handshake(e) => {
+                        FetchMetadataError::from(Error::Transport(e.into()))
+                    }
+                    ConnectError::SubprotocolRejected => {
+                        FetchMetadataError::SubprotocolRejected(subprotocols.to_vec())
+                    }
+                }
+            })?;
+        builder.build_with_tokio(sender, receiver)
+    };
 
     Ok(client.request("state_getMetadata", rpc_params![]).await?)
 }
 
-async fn fetch_metadata_http(url: &Uri) -> Result<String, FetchMetadataError> {
+async fn fetch_metadata_http(
+    url: &Uri,
+    timeout: Duration,
+) -> Result<String, FetchMetadataError> {
     let client = HttpClientBuilder::default()
-        .request_timeout(Duration::from_secs(180))
+        .request_timeout(timeout)
         .build(url.to_string())?;
 
     Ok(client.request("state_getMetadata", rpc_params![]).await?)
@@ -79,6 +195,7 @@ pub enum FetchMetadataError {
     DecodeError(hex::FromHexError),
     RequestError(jsonrpsee::core::Error),
     InvalidScheme(String),
+    SubprotocolRejected(Vec<String>),
 }
 
 impl std::fmt::Display for FetchMetadataError {
@@ -94,6 +211,12 @@ impl std::fmt::Display for FetchMetadataError {
                     "'{s}' not supported, supported URI schemes are http, https, ws or wss."
                 )
             }
+            FetchMetadataError::SubprotocolRejected(protocols) => {
+                write!(
+                    f,
+                    "The server did not pick any of the WebSocket subprotocols {protocols:?}"
+                )
+            }
         }
     }
 }
@@ -110,3 +233,97 @@ impl From<jsonrpsee::core::Error> for FetchMetadataError {
         FetchMetadataError::RequestError(e)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::io::{
+        BufReader,
+        BufWriter,
+    };
+    use soketto::handshake::{
+        server::Response,
+        Server,
+    };
+    use tokio::net::TcpListener;
+    use tokio_util::compat::TokioAsyncReadCompatExt;
+
+    // A node which supports the given subprotocol, and answers each request with some
+    // metadata. If `reject` is given, it refuses handshakes with that status code instead.
+    async fn node(subprotocol: &'static str, reject: Option<u16>) -> Uri {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut server = Server::new(BufReader::new(BufWriter::new(socket.compat())));
+            server.add_protocol(subprotocol);
+            let request = server.receive_request().await.unwrap();
+            let key = request.key();
+            let protocol = request.protocols().next().map(str::to_owned);
+            let response = match reject {
+                Some(status_code) => Response::Reject { status_code },
+                None => {
+                    Response::Accept {
+                        key,
+                        protocol: protocol.as_deref(),
+                    }
+                }
+            };
+            server.send_response(&response).await.unwrap();
+
+            let (mut sender, mut receiver) = server.into_builder().finish();
+            let mut message = Vec::new();
+            while receiver.receive_data(&mut message).await.is_ok() {
+                let request: serde_json::Value =
+                    serde_json::from_slice(&message).unwrap();
+                let response = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": "0x1234",
+                });
+                sender.send_text(response.to_string()).await.unwrap();
+                sender.flush().await.unwrap();
+                message.clear();
+            }
+        });
+        url.parse().unwrap()
+    }
+
+    fn options(subprotocol: &str) -> FetchMetadataOptions {
+        FetchMetadataOptions {
+            ws_subprotocols: vec![subprotocol.to_owned()],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn fetches_using_the_subprotocol_that_the_server_picks() {
+        let url = node("route-a", None).await;
+        let hex = fetch_metadata_hex_with_options(&url, &options("route-a"))
+            .await
+            .unwrap();
+        assert_eq!(hex, "0x1234");
+    }
+
+    #[tokio::test]
+    async fn fails_if_the_server_picks_no_subprotocol() {
+        let url = node("route-b", None).await;
+        let err = fetch_metadata_hex_with_options(&url, &options("route-a"))
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&err, FetchMetadataError::SubprotocolRejected(p) if p == &["route-a"]),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_handshakes_are_not_blamed_on_the_subprotocol() {
+        let url = node("route-a", Some(401)).await;
+        let err = fetch_metadata_hex_with_options(&url, &options("route-a"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, FetchMetadataError::RequestError(_)), "{err}");
+        assert!(err.to_string().contains("401"), "{err}");
+    }
+}
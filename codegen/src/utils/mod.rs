@@ -1,12 +1,23 @@
+mod canonicalize;
 mod fetch_metadata;
+mod no_std;
+mod ws_transport;
 
 // easy access to this type needed for fetching metadata:
 pub use jsonrpsee::client_transport::ws::Uri;
 
+pub use canonicalize::canonicalize_type_ids;
 pub use fetch_metadata::{
     fetch_metadata_bytes,
     fetch_metadata_bytes_blocking,
+    fetch_metadata_bytes_with_options,
+    fetch_metadata_bytes_with_timeouts,
     fetch_metadata_hex,
     fetch_metadata_hex_blocking,
+    fetch_metadata_hex_with_options,
+    fetch_metadata_hex_with_timeouts,
     FetchMetadataError,
+    FetchMetadataOptions,
+    FetchMetadataTimeouts,
 };
+pub use no_std::std_paths_to_alloc;
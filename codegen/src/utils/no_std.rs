@@ -0,0 +1,127 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Make generated code usable from `no_std` crates.
+//!
+//! Generated code refers to everything outside of the `subxt` crate by its full path, and
+//! the only things it uses from `std` (`Vec`, `String`, `Box`, `Cow`, `BTreeMap`, `BTreeSet`,
+//! `Borrow` and `vec!`) are re-exports of items in `alloc`, under the same paths. Pointing
+//! the paths at `alloc` instead is therefore all it takes for the code to build without
+//! `std`, so long as the crate that it ends up in declares `extern crate alloc;`.
+
+use proc_macro2::{
+    Group,
+    Ident,
+    Spacing,
+    TokenStream,
+    TokenTree,
+};
+
+/// Rewrite every `::std::` path in the code given into the matching `::alloc::` path.
+///
+/// This is what [`crate::RuntimeGenerator::generate_runtime()`] does to its output in
+/// `no_std` mode, and can be applied in the same way to the output of a
+/// [`crate::TypeGenerator`]. Only the paths that the code generator emits are taken care
+/// of: custom derives and type substitutes have to be `no_std` friendly to begin with.
+pub fn std_paths_to_alloc(tokens: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    tokens
+        .iter()
+        .enumerate()
+        .map(|(i, token)| {
+            match token {
+                TokenTree::Group(group) => {
+                    let mut new_group =
+                        Group::new(group.delimiter(), std_paths_to_alloc(group.stream()));
+                    new_group.set_span(group.span());
+                    TokenTree::Group(new_group)
+                }
+                // Generated code names no module `std`, so any `::std` is the `std` crate.
+                TokenTree::Ident(ident)
+                    if ident == "std"
+                        && i >= 2
+                        && is_path_sep(&tokens[i - 2], &tokens[i - 1]) =>
+                {
+                    TokenTree::Ident(Ident::new("alloc", ident.span()))
+                }
+                token => token.clone(),
+            }
+        })
+        .collect()
+}
+
+fn is_path_sep(first: &TokenTree, second: &TokenTree) -> bool {
+    matches!(
+        (first, second),
+        (TokenTree::Punct(a), TokenTree::Punct(b))
+            if a.as_char() == ':' && a.spacing() == Spacing::Joint && b.as_char() == ':'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        CratePath,
+        DerivesRegistry,
+        RuntimeGenerator,
+        RuntimeGeneratorOptions,
+        TypeSubstitutes,
+    };
+    use codec::Decode;
+    use frame_metadata::RuntimeMetadataPrefixed;
+    use quote::quote;
+
+    #[test]
+    fn std_paths_are_rewritten() {
+        let tokens = quote! {
+            pub struct Foo {
+                pub a: ::std::vec::Vec<::std::string::String>,
+                pub b: ::std::boxed::Box<(::core::primitive::u8, ::std::borrow::Cow<'static, str>)>,
+            }
+            fn keys() -> ::std::vec::Vec<u8> { ::std::vec![] }
+        };
+        let expected = quote! {
+            pub struct Foo {
+                pub a: ::alloc::vec::Vec<::alloc::string::String>,
+                pub b: ::alloc::boxed::Box<(::core::primitive::u8, ::alloc::borrow::Cow<'static, str>)>,
+            }
+            fn keys() -> ::alloc::vec::Vec<u8> { ::alloc::vec![] }
+        };
+        assert_eq!(std_paths_to_alloc(tokens).to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn no_std_runtime_does_not_refer_to_std() {
+        let bytes = include_bytes!("../../../artifacts/polkadot_metadata.scale");
+        let generate = |no_std| {
+            let metadata = RuntimeMetadataPrefixed::decode(&mut &bytes[..]).unwrap();
+            let crate_path = CratePath::default();
+            RuntimeGenerator::new(metadata)
+                .generate_runtime_with_options(
+                    syn::parse_quote!(
+                        pub mod api {}
+                    ),
+                    DerivesRegistry::new(&crate_path),
+                    TypeSubstitutes::new(&crate_path),
+                    crate_path,
+                    RuntimeGeneratorOptions {
+                        no_std,
+                        ..Default::default()
+                    },
+                )
+                .to_string()
+        };
+
+        let with_std = generate(false);
+        assert!(with_std.contains(":: std :: vec :: Vec"));
+        assert!(!with_std.contains(":: alloc ::"));
+
+        let without_std = generate(true);
+        assert!(!without_std.contains(":: std ::"));
+        assert!(without_std.contains(":: alloc :: vec :: Vec"));
+        assert!(without_std.contains(":: alloc :: string :: String"));
+        assert!(without_std.contains(":: alloc :: boxed :: Box"));
+    }
+}
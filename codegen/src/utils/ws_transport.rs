@@ -0,0 +1,204 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! A WebSocket transport which asks the server for subprotocols in its handshake, and
+//! checks that the server picked one of them.
+
+use futures::io::{
+    BufReader,
+    BufWriter,
+};
+use jsonrpsee::{
+    client_transport::ws::{
+        Uri,
+        WsHandshakeError,
+    },
+    core::{
+        async_trait,
+        client::{
+            ReceivedMessage,
+            TransportReceiverT,
+            TransportSenderT,
+        },
+    },
+};
+use soketto::{
+    connection,
+    data::ByteSlice125,
+    handshake::{
+        self,
+        ServerResponse,
+    },
+    Data,
+    Incoming,
+};
+use std::sync::Arc;
+use tokio::{
+    io::{
+        AsyncRead,
+        AsyncWrite,
+    },
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls,
+    TlsConnector,
+};
+use tokio_util::compat::{
+    Compat,
+    TokioAsyncReadCompatExt,
+};
+
+/// Why a connection couldn't be opened.
+pub(crate) enum ConnectError {
+    /// The connection couldn't be opened, or the server refused the handshake.
+    Handshake(WsHandshakeError),
+    /// The server accepted the handshake without picking any of the subprotocols.
+    SubprotocolRejected,
+}
+
+impl From<WsHandshakeError> for ConnectError {
+    fn from(e: WsHandshakeError) -> Self {
+        ConnectError::Handshake(e)
+    }
+}
+
+/// Connect to the `ws://` or `wss://` URL given, asking the server to use one of the
+/// subprotocols given.
+pub(crate) async fn connect(
+    url: &Uri,
+    subprotocols: &[String],
+) -> Result<(Sender, Receiver), ConnectError> {
+    let tls = match url.scheme_str() {
+        Some("ws") => false,
+        Some("wss") => true,
+        _ => return Err(WsHandshakeError::Url("Not a ws:// or wss:// URL".into()).into()),
+    };
+    let host = url
+        .host()
+        .ok_or_else(|| WsHandshakeError::Url("No host in URL".into()))?;
+    let port = url.port_u16().unwrap_or(if tls { 443 } else { 80 });
+    let host_header = match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    };
+    let path = url.path_and_query().map_or("/", |p| p.as_str());
+
+    // IPv6 addresses are bracketed in the URL, but not when connecting.
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let socket = TcpStream::connect((host, port))
+        .await
+        .map_err(WsHandshakeError::Io)?;
+    let stream: Box<dyn Stream> = match tls {
+        false => Box::new(socket),
+        true => {
+            let server_name = rustls::ServerName::try_from(host).map_err(|e| {
+                WsHandshakeError::Url(format!("Invalid host: {e}").into())
+            })?;
+            let stream = tls_connector()
+                .connect(server_name, socket)
+                .await
+                .map_err(WsHandshakeError::Io)?;
+            Box::new(stream)
+        }
+    };
+
+    let mut client = handshake::Client::new(
+        BufReader::new(BufWriter::new(stream.compat())),
+        &host_header,
+        path,
+    );
+    for subprotocol in subprotocols {
+        client.add_protocol(subprotocol);
+    }
+    match client
+        .handshake()
+        .await
+        .map_err(WsHandshakeError::Transport)?
+    {
+        ServerResponse::Accepted { protocol: Some(_) } => {}
+        ServerResponse::Accepted { protocol: None } => {
+            return Err(ConnectError::SubprotocolRejected)
+        }
+        ServerResponse::Rejected { status_code }
+        | ServerResponse::Redirect { status_code, .. } => {
+            return Err(WsHandshakeError::Rejected { status_code }.into())
+        }
+    }
+
+    let (sender, receiver) = client.into_builder().finish();
+    Ok((Sender(sender), Receiver(receiver)))
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+// Anything that a connection can be made over: a TCP stream, with or without TLS.
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+type Socket = BufReader<BufWriter<Compat<Box<dyn Stream>>>>;
+
+/// The sending half of an open connection.
+pub(crate) struct Sender(connection::Sender<Socket>);
+
+/// The receiving half of an open connection.
+pub(crate) struct Receiver(connection::Receiver<Socket>);
+
+#[async_trait]
+impl TransportSenderT for Sender {
+    type Error = connection::Error;
+
+    async fn send(&mut self, body: String) -> Result<(), Self::Error> {
+        self.0.send_text_owned(body).await?;
+        self.0.flush().await
+    }
+
+    async fn send_ping(&mut self) -> Result<(), Self::Error> {
+        let empty = ByteSlice125::try_from(&[][..])
+            .expect("an empty slice is shorter than 125 bytes; qed");
+        self.0.send_ping(empty).await?;
+        self.0.flush().await
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.0.close().await
+    }
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+    type Error = connection::Error;
+
+    async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+        loop {
+            let mut message = Vec::new();
+            match self.0.receive(&mut message).await? {
+                Incoming::Data(Data::Text(_)) => {
+                    let text = String::from_utf8(message)
+                        .map_err(|e| connection::Error::Utf8(e.utf8_error()))?;
+                    return Ok(ReceivedMessage::Text(text))
+                }
+                Incoming::Data(Data::Binary(_)) => {
+                    return Ok(ReceivedMessage::Bytes(message))
+                }
+                Incoming::Pong(_) => return Ok(ReceivedMessage::Pong),
+                Incoming::Closed(_) => continue,
+            }
+        }
+    }
+}
@@ -82,6 +82,76 @@
 //! ```
 //!
 //! By default the path `::subxt` is used.
+//!
+//! ### Item names
+//!
+//! To generate string constants naming each pallet and its calls, events, storage entries and
+//! constants, for use with the dynamic APIs:
+//!
+//! ```ignore
+//! #[subxt::subxt(
+//!     runtime_metadata_path = "polkadot_metadata.scale",
+//!     generate_item_names
+//! )]
+//! pub mod polkadot {}
+//!
+//! let tx = subxt::dynamic::tx(
+//!     polkadot::balances::PALLET,
+//!     polkadot::balances::CALL_TRANSFER,
+//!     vec![/* ... */],
+//! );
+//! ```
+//!
+//! ### Type conversions
+//!
+//! Large runtimes often contain several copies of the same type, defined in different crates
+//! and so generated under different paths. To generate `From` conversions, both ways, between
+//! each pair of generated types from different crates with the same name and exactly the same
+//! fields or variants:
+//!
+//! ```ignore
+//! #[subxt::subxt(
+//!     runtime_metadata_path = "polkadot_metadata.scale",
+//!     generate_type_conversions
+//! )]
+//! pub mod polkadot {}
+//! ```
+//!
+//! Types of the same name within one crate, such as those of the different key types in
+//! `sp_core`, are kept apart on purpose and aren't converted between. Types with type
+//! parameters, and substituted types, are left alone too.
+//!
+//! ### `no_std` friendly paths
+//!
+//! Generated code refers to `Vec`, `String`, `Box` and friends via `::std`. To refer to them
+//! via `::alloc` instead, for use from a `no_std` crate which declares `extern crate alloc;`:
+//!
+//! ```ignore
+//! #[subxt::subxt(
+//!     runtime_metadata_path = "polkadot_metadata.scale",
+//!     crate = "::my_no_std_subxt",
+//!     no_std
+//! )]
+//! pub mod polkadot {}
+//! ```
+//!
+//! `subxt` itself needs `std`, so the `crate` given has to be some `no_std` crate which
+//! provides the items that the generated code uses from it.
+//!
+//! ### Reproducible type IDs
+//!
+//! The IDs that types are given in the metadata depend on the order in which they were
+//! registered, which can differ between metadata sources even when the types are the same. To
+//! canonicalize the IDs before generating code, so that equivalent metadata always generates the
+//! same code:
+//!
+//! ```ignore
+//! #[subxt::subxt(
+//!     runtime_metadata_path = "polkadot_metadata.scale",
+//!     canonicalize_type_ids
+//! )]
+//! pub mod polkadot {}
+//! ```
 
 #![deny(unused_crate_dependencies)]
 
@@ -99,6 +169,7 @@ use proc_macro_error::{
 use subxt_codegen::{
     utils::Uri,
     DerivesRegistry,
+    RuntimeGeneratorOptions,
     TypeSubstitutes,
 };
 use syn::{
@@ -121,6 +192,14 @@ struct RuntimeMetadataArgs {
     substitute_type: Vec<SubstituteType>,
     #[darling(default, rename = "crate")]
     crate_path: Option<String>,
+    #[darling(default)]
+    generate_item_names: darling::util::Flag,
+    #[darling(default)]
+    generate_type_conversions: darling::util::Flag,
+    #[darling(default)]
+    no_std: darling::util::Flag,
+    #[darling(default)]
+    canonicalize_type_ids: darling::util::Flag,
 }
 
 #[derive(Debug, FromMeta)]
@@ -177,17 +256,25 @@ pub fn subxt(args: TokenStream, input: TokenStream) -> TokenStream {
         },
     ));
 
+    let options = RuntimeGeneratorOptions {
+        generate_item_names: args.generate_item_names.is_present(),
+        generate_type_conversions: args.generate_type_conversions.is_present(),
+        no_std: args.no_std.is_present(),
+        canonicalize_type_ids: args.canonicalize_type_ids.is_present(),
+    };
+
     match (args.runtime_metadata_path, args.runtime_metadata_url) {
         (Some(rest_of_path), None) => {
             let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
             let root_path = std::path::Path::new(&root);
             let path = root_path.join(rest_of_path);
-            subxt_codegen::generate_runtime_api_from_path(
+            subxt_codegen::generate_runtime_api_from_path_with_options(
                 item_mod,
                 path,
                 derives_registry,
                 type_substitutes,
                 crate_path,
+                options,
             )
             .into()
         }
@@ -195,12 +282,13 @@ pub fn subxt(args: TokenStream, input: TokenStream) -> TokenStream {
             let url = Uri::from_str(&url_string).unwrap_or_else(|_| {
                 abort_call_site!("Cannot download metadata; invalid url: {}", url_string)
             });
-            subxt_codegen::generate_runtime_api_from_url(
+            subxt_codegen::generate_runtime_api_from_url_with_options(
                 item_mod,
                 &url,
                 derives_registry,
                 type_substitutes,
                 crate_path,
+                options,
             )
             .into()
         }
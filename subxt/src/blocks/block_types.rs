@@ -2,8 +2,16 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+use super::{
+    signed_extensions::{
+        decode_extrinsic_signed_extensions,
+        ExtrinsicSignedExtensions,
+    },
+    ExtrinsicDetails,
+};
 use crate::{
     client::{
+        metadata_at_block,
         OfflineClientT,
         OnlineClientT,
     },
@@ -17,13 +25,35 @@ use crate::{
         Error,
     },
     events,
-    rpc::types::ChainBlockResponse,
+    rpc::types::{
+        ChainBlockResponse,
+        ConsensusEngineId,
+        EncodedJustification,
+        Justification,
+    },
     runtime_api::RuntimeApi,
     storage::Storage,
+    utils::{
+        trie::{
+            ordered_trie_root,
+            StateVersion,
+        },
+        weight::DispatchInfo,
+    },
+};
+use codec::{
+    Decode,
+    Encode,
 };
 use derivative::Derivative;
 use futures::lock::Mutex as AsyncMutex;
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
 
 /// A representation of a block.
 pub struct Block<T: Config, C> {
@@ -79,12 +109,7 @@ where
 
     /// Fetch and return the block body.
     pub async fn body(&self) -> Result<BlockBody<T, C>, Error> {
-        let block_hash = self.header.hash();
-        let block_details = match self.client.rpc().block(Some(block_hash)).await? {
-            Some(block) => block,
-            None => return Err(BlockError::block_hash_not_found(block_hash).into()),
-        };
-
+        let block_details = get_block_details(&self.client, self.header.hash()).await?;
         Ok(BlockBody::new(
             self.client.clone(),
             block_details,
@@ -102,6 +127,109 @@ where
     pub async fn runtime_api(&self) -> Result<RuntimeApi<T, C>, Error> {
         Ok(RuntimeApi::new(self.client.clone(), self.hash()))
     }
+
+    /// Return the time that this block was produced at, in milliseconds since the UNIX epoch.
+    ///
+    /// This reads the `Timestamp::Now` storage entry at this block. The timestamp inherent
+    /// sets this entry while the block is being built, so the value read is the one set for
+    /// this block and not its parent. The genesis block has no timestamp, and so this is 0.
+    pub async fn timestamp(&self) -> Result<u64, Error> {
+        get_timestamp(&self.client, self.hash()).await
+    }
+
+    /// Return the time that this block was produced at.
+    /// See [`Block::timestamp()`] for more details.
+    pub async fn system_time(&self) -> Result<SystemTime, Error> {
+        let millis = self.timestamp().await?;
+        Ok(SystemTime::UNIX_EPOCH + Duration::from_millis(millis))
+    }
+}
+
+/// A block whose body and events have been fetched up front.
+/// See [`crate::blocks::BlocksClient::subscribe_finalized_prefetched()`].
+pub struct PrefetchedBlock<T: Config, C> {
+    block: Block<T, C>,
+    body: BlockBody<T, C>,
+    events: events::Events<T>,
+}
+
+impl<T, C> PrefetchedBlock<T, C>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    // Fetch the body and events of the block given.
+    pub(crate) async fn fetch(block: Block<T, C>) -> Result<Self, Error> {
+        let block_hash = block.hash();
+        let client = block.client.clone();
+        let cached_events = block.cached_events.clone();
+
+        let (details, events) = futures::future::try_join(
+            get_block_details(&client, block_hash),
+            get_events(&client, block_hash, &cached_events),
+        )
+        .await?;
+
+        Ok(PrefetchedBlock {
+            block,
+            body: BlockBody::new(client, details, cached_events),
+            events,
+        })
+    }
+
+    /// Return the block.
+    pub fn block(&self) -> &Block<T, C> {
+        &self.block
+    }
+
+    /// Return the block body.
+    pub fn body(&self) -> &BlockBody<T, C> {
+        &self.body
+    }
+
+    /// Return the events associated with the block.
+    pub fn events(&self) -> &events::Events<T> {
+        &self.events
+    }
+
+    /// Return the block, its body and its events.
+    pub fn into_parts(self) -> (Block<T, C>, BlockBody<T, C>, events::Events<T>) {
+        (self.block, self.body, self.events)
+    }
+}
+
+impl<T, C> PrefetchedBlock<T, C>
+where
+    T: Config,
+    C: OfflineClientT<T>,
+{
+    /// Decode the extrinsic that an event in this block was emitted while applying, given
+    /// the event's phase (see [`events::EventDetails::phase()`]). This is `None` for events
+    /// emitted while initializing or finalizing the block, which no extrinsic caused.
+    pub fn extrinsic_for_phase(
+        &self,
+        phase: &events::Phase,
+    ) -> Result<Option<ExtrinsicDetails<T>>, Error> {
+        let events::Phase::ApplyExtrinsic(index) = *phase else {
+            return Ok(None)
+        };
+        let Some(extrinsic) = self.body.details.block.extrinsics.get(index as usize)
+        else {
+            return Err(Error::Other(format!(
+                "Block 0x{} has no extrinsic at index {index}",
+                hex::encode(self.block.hash())
+            )))
+        };
+        let ext_hash = T::Hasher::hash_of(&extrinsic.0);
+        let events = ExtrinsicEvents::new(ext_hash, index, self.events.clone());
+        ExtrinsicDetails::decode(
+            self.block.number().into(),
+            extrinsic.0.clone(),
+            events,
+            &self.block.client.metadata(),
+        )
+        .map(Some)
+    }
 }
 
 /// The body of a block.
@@ -128,6 +256,36 @@ where
         }
     }
 
+    /// The justifications that the node has for this block, if any. Each one is a
+    /// consensus engine ID (for instance `*b"FRNK"` for GRANDPA) and the encoded
+    /// justification for that engine.
+    pub fn justifications(&self) -> &[Justification] {
+        self.details.justifications.as_deref().unwrap_or_default()
+    }
+
+    /// The encoded justification for this block from the given consensus engine, if the
+    /// node has one.
+    pub fn justification(
+        &self,
+        engine_id: ConsensusEngineId,
+    ) -> Option<&EncodedJustification> {
+        self.justifications()
+            .iter()
+            .find(|(id, _)| *id == engine_id)
+            .map(|(_, justification)| justification)
+    }
+
+    /// Compute the root of the trie containing the extrinsics in this block, given the trie
+    /// layout used by the runtime at this block (see
+    /// [`crate::rpc::types::RuntimeVersion::state_version()`]). For an honest node, this is
+    /// equal to the `extrinsics_root` in the block header.
+    pub fn extrinsics_root(&self, state_version: StateVersion) -> T::Hash {
+        ordered_trie_root::<T::Hasher, _>(
+            self.details.block.extrinsics.iter().map(|e| &e.0),
+            state_version,
+        )
+    }
+
     /// Returns an iterator over the extrinsics in the block body.
     pub fn extrinsics(&self) -> impl Iterator<Item = Extrinsic<'_, T, C>> {
         self.details
@@ -172,6 +330,39 @@ where
     pub fn bytes(&self) -> &'a [u8] {
         self.bytes
     }
+
+    /// Is the extrinsic signed?
+    pub fn is_signed(&self) -> bool {
+        is_signed(self.bytes)
+    }
+
+    /// Was the extrinsic signed by the given account?
+    ///
+    /// This compares the address in the extrinsic with the address that `T::Address`
+    /// produces from the account, so an extrinsic signed using some other form of
+    /// address for the same account (for instance, an account index) won't match.
+    pub fn is_signed_by(&self, account: &T::AccountId) -> bool {
+        let address = T::Address::from(account.clone()).encode();
+        is_signed_by_address(self.bytes, &address)
+    }
+}
+
+/// Is the extrinsic with the given bytes signed?
+fn is_signed(extrinsic_bytes: &[u8]) -> bool {
+    // The first byte is the "is signed" bit followed by the transaction version.
+    extrinsic_bytes
+        .first()
+        .map(|b| b & 0b1000_0000 != 0)
+        .unwrap_or(false)
+}
+
+/// Was the extrinsic with the given bytes signed using the given SCALE encoded address?
+pub(crate) fn is_signed_by_address(
+    extrinsic_bytes: &[u8],
+    encoded_address: &[u8],
+) -> bool {
+    // The signer address immediately follows the version byte.
+    is_signed(extrinsic_bytes) && extrinsic_bytes[1..].starts_with(encoded_address)
 }
 
 impl<'a, T, C> Extrinsic<'a, T, C>
@@ -186,6 +377,68 @@ where
         let ext_hash = T::Hasher::hash_of(&self.bytes);
         Ok(ExtrinsicEvents::new(ext_hash, self.index, events))
     }
+
+    /// Decode the signed extensions of the extrinsic (its nonce, tip, era and so on).
+    /// Returns `None` if the extrinsic isn't signed.
+    ///
+    /// These are decoded using the metadata of the runtime that was in use at this block,
+    /// so this works for blocks produced before a runtime upgrade which changed the signed
+    /// extensions.
+    pub async fn signed_extensions(
+        &self,
+    ) -> Result<Option<ExtrinsicSignedExtensions>, Error> {
+        let metadata = metadata_at_block(&self.client, self.block_hash).await?;
+        decode_extrinsic_signed_extensions(self.bytes, &metadata)
+    }
+}
+
+/// An extrinsic signed by some account, along with the events that it produced. This
+/// is handed back from [`crate::blocks::BlocksClient::subscribe_signed_by()`].
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct SignedExtrinsicDetails<T: Config> {
+    block_number: u64,
+    bytes: Vec<u8>,
+    events: ExtrinsicEvents<T>,
+}
+
+impl<T: Config> SignedExtrinsicDetails<T> {
+    pub(crate) fn new(
+        block_number: u64,
+        bytes: Vec<u8>,
+        events: ExtrinsicEvents<T>,
+    ) -> Self {
+        Self {
+            block_number,
+            bytes,
+            events,
+        }
+    }
+
+    /// The hash of the block that the extrinsic was included in.
+    pub fn block_hash(&self) -> T::Hash {
+        self.events.block_hash()
+    }
+
+    /// The number of the block that the extrinsic was included in.
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// The index of the extrinsic in the block.
+    pub fn index(&self) -> u32 {
+        self.events.extrinsic_index()
+    }
+
+    /// The bytes of the extrinsic.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The events associated with the extrinsic.
+    pub fn events(&self) -> &ExtrinsicEvents<T> {
+        &self.events
+    }
 }
 
 /// The events associated with a given extrinsic.
@@ -227,6 +480,13 @@ impl<T: Config> ExtrinsicEvents<T> {
         self.ext_hash
     }
 
+    /// Configure how errors decoding individual events are reported.
+    /// See [`events::Events::with_decode_error_recovery()`].
+    pub fn with_decode_error_recovery(mut self, recover: bool) -> Self {
+        self.events = self.events.with_decode_error_recovery(recover);
+        self
+    }
+
     /// Return all of the events in the block that the extrinsic is in.
     pub fn all_events_in_block(&self) -> &events::Events<T> {
         &self.events
@@ -252,8 +512,11 @@ impl<T: Config> ExtrinsicEvents<T> {
         &self,
     ) -> impl Iterator<Item = Result<Ev, Error>> + '_ {
         self.iter().filter_map(|ev| {
-            ev.and_then(|ev| ev.as_event::<Ev>().map_err(Into::into))
-                .transpose()
+            ev.and_then(|ev| {
+                ev.as_event::<Ev>()
+                    .map_err(|e| self.events.static_decode_error(&ev, e))
+            })
+            .transpose()
         })
     }
 
@@ -273,6 +536,79 @@ impl<T: Config> ExtrinsicEvents<T> {
     pub fn has<Ev: events::StaticEvent>(&self) -> Result<bool, Error> {
         Ok(self.find::<Ev>().next().transpose()?.is_some())
     }
+
+    /// Return the [`DispatchInfo`] reported in the `System.ExtrinsicSuccess` or
+    /// `System.ExtrinsicFailed` event for this extrinsic, if one was found.
+    ///
+    /// This decodes the weight according to the metadata, and so works for runtimes
+    /// using either "v1" or "v2" weights.
+    pub fn dispatch_info(&self) -> Result<Option<DispatchInfo>, Error> {
+        for ev in self.iter() {
+            let ev = ev?;
+            if ev.pallet_name() != "System"
+                || !matches!(ev.variant_name(), "ExtrinsicSuccess" | "ExtrinsicFailed")
+            {
+                continue
+            }
+
+            // Older runtimes have unnamed fields, where the dispatch info is last.
+            let fields = ev.field_values()?;
+            let info_value = match &fields {
+                scale_value::Composite::Named(vals) => {
+                    vals.iter()
+                        .find(|(name, _)| name == "dispatch_info")
+                        .map(|(_, val)| val)
+                }
+                scale_value::Composite::Unnamed(vals) => vals.last(),
+            };
+            let dispatch_info = info_value
+                .and_then(DispatchInfo::from_value)
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not decode the dispatch info from System.{}",
+                        ev.variant_name()
+                    ))
+                })?;
+            return Ok(Some(dispatch_info))
+        }
+        Ok(None)
+    }
+}
+
+// Fetch the `Timestamp::Now` storage entry at the given block.
+pub(crate) async fn get_timestamp<T, C>(
+    client: &C,
+    block_hash: T::Hash,
+) -> Result<u64, Error>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    // Make sure that the chain actually has a timestamp pallet, so that we don't
+    // misleadingly hand back the default value if it doesn't.
+    client.metadata().pallet("Timestamp")?;
+
+    let mut key = sp_core_hashing::twox_128(b"Timestamp").to_vec();
+    key.extend(sp_core_hashing::twox_128(b"Now"));
+    match client.rpc().storage(&key, Some(block_hash)).await? {
+        Some(data) => Ok(u64::decode(&mut &*data.0)?),
+        None => Ok(0),
+    }
+}
+
+// Fetch the details of the block with the given hash.
+async fn get_block_details<T, C>(
+    client: &C,
+    block_hash: T::Hash,
+) -> Result<ChainBlockResponse<T>, Error>
+where
+    T: Config,
+    C: OnlineClientT<T>,
+{
+    match client.rpc().block(Some(block_hash)).await? {
+        Some(block) => Ok(block),
+        None => Err(BlockError::block_hash_not_found(block_hash).into()),
+    }
 }
 
 // Return Events from the cache, or fetch from the node if needed.
@@ -292,11 +628,84 @@ where
     let events = match &*lock {
         Some(events) => events.clone(),
         None => {
-            events::EventsClient::new(client.clone())
-                .at(Some(block_hash))
-                .await?
+            // Don't hold the client across the await, so that this future remains `Send`.
+            let events = events::EventsClient::new(client.clone()).at(Some(block_hash));
+            events.await?
         }
     };
 
     Ok(events)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::substrate::{
+            BlakeTwo256,
+            SubstrateHeader,
+        },
+        rpc::types::{
+            ChainBlock,
+            ChainBlockExtrinsic,
+        },
+        test_utils::{
+            metadata,
+            offline_client,
+        },
+        tx::TxPayload,
+    };
+
+    #[test]
+    fn finds_the_extrinsic_for_an_event_phase() {
+        let metadata = metadata();
+        let client = offline_client();
+
+        // Two unsigned extrinsics, each a remark:
+        let extrinsics = [[1u8], [2]].map(|remark| {
+            let call = crate::tx::dynamic(
+                "System",
+                "remark",
+                vec![crate::dynamic::Value::from_bytes(remark)],
+            );
+            let mut bytes = vec![4];
+            bytes.extend(call.encode_call_data(&metadata).unwrap());
+            ChainBlockExtrinsic(bytes)
+        });
+        let header = SubstrateHeader::<u32, BlakeTwo256> {
+            parent_hash: Default::default(),
+            number: 7,
+            state_root: Default::default(),
+            extrinsics_root: Default::default(),
+            digest: Default::default(),
+        };
+        let details = ChainBlockResponse {
+            block: ChainBlock {
+                header: header.clone(),
+                extrinsics: extrinsics.to_vec(),
+            },
+            justifications: None,
+        };
+        let block = PrefetchedBlock {
+            block: Block::new(header.clone(), client.clone()),
+            body: BlockBody::new(client, details, Default::default()),
+            events: events::Events::new(metadata, header.hash(), vec![0]),
+        };
+
+        let ext = block
+            .extrinsic_for_phase(&events::Phase::ApplyExtrinsic(1))
+            .unwrap()
+            .unwrap();
+        assert_eq!(ext.index(), 1);
+        assert_eq!(ext.block_number(), 7);
+        assert_eq!(ext.bytes(), &extrinsics[1].0[..]);
+        assert_eq!(ext.call_name(), "remark");
+
+        for phase in [events::Phase::Initialization, events::Phase::Finalization] {
+            assert!(block.extrinsic_for_phase(&phase).unwrap().is_none());
+        }
+        assert!(block
+            .extrinsic_for_phase(&events::Phase::ApplyExtrinsic(2))
+            .is_err());
+    }
+}
@@ -2,19 +2,48 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::Block;
+use super::{
+    block_types::{
+        get_timestamp,
+        is_signed_by_address,
+    },
+    bounded_stream::{
+        BoundedStream,
+        BufferPolicy,
+    },
+    reorgs::{
+        ReorgTracker,
+        TrackedEvent,
+    },
+    BestBlockEvent,
+    Block,
+    ExtrinsicDetails,
+    ExtrinsicEvents,
+    PrefetchedBlock,
+    SignedExtrinsicDetails,
+};
 use crate::{
     client::OnlineClientT,
     config::{
         Config,
+        Hasher,
         Header,
     },
     error::{
         BlockError,
         Error,
+        RpcError,
+    },
+    events::{
+        CandidateEvent,
+        EventInBlock,
+        EventsClient,
     },
+    rpc::types::BlockNumber,
+    tx::TxInBlock,
     utils::PhantomDataSendSync,
 };
+use codec::Encode;
 use derivative::Derivative;
 use futures::{
     future::Either,
@@ -23,13 +52,41 @@ use futures::{
     StreamExt,
 };
 use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
     future::Future,
     pin::Pin,
 };
 
+/// A sensible number of skipped finalized blocks to fill in at once, to hand to
+/// [`BlocksClient::subscribe_finalized_with_max_gap()`].
+pub const DEFAULT_MAX_FINALIZED_GAP: u64 = 1024;
+
 type BlockStream<T> = Pin<Box<dyn Stream<Item = Result<T, Error>> + Send>>;
 type BlockStreamRes<T> = Result<BlockStream<T>, Error>;
 
+/// The numbers of the best and of the latest finalized block, as handed back from
+/// [`BlocksClient::block_numbers()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockNumbers {
+    /// The number of the best block.
+    pub best: u64,
+    /// The number of the latest finalized block.
+    pub finalized: u64,
+}
+
+/// The hashes of some set of blocks, as handed back from
+/// [`BlocksClient::block_hashes_for()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockHashes<Hash> {
+    /// The hash of each block that the node knows about, by number.
+    pub hashes: BTreeMap<u64, Hash>,
+    /// The numbers which the node had no block for, because they're beyond its best block.
+    pub missing: BTreeSet<u64>,
+}
+
 /// A client for working with blocks.
 #[derive(Derivative)]
 #[derivative(Clone(bound = "Client: Clone"))]
@@ -89,6 +146,106 @@ where
         }
     }
 
+    /// Return the time that the block with the provided hash (or the latest block if `None`
+    /// is provided) was produced at, in milliseconds since the UNIX epoch.
+    /// See [`Block::timestamp()`] for more details.
+    pub fn block_timestamp(
+        &self,
+        block_hash: Option<T::Hash>,
+    ) -> impl Future<Output = Result<u64, Error>> + Send + 'static {
+        let client = self.client.clone();
+        async move {
+            let block_hash = match block_hash {
+                Some(hash) => hash,
+                None => {
+                    client
+                        .rpc()
+                        .block_hash(None)
+                        .await?
+                        .expect("didn't pass a block number; qed")
+                }
+            };
+            get_timestamp(&client, block_hash).await
+        }
+    }
+
+    /// Fetch the numbers of the current best block and of the latest finalized block.
+    ///
+    /// The finalized head and the best header are asked for at the same time, and the
+    /// finalized header is then looked up by its hash, so the two numbers are read as close
+    /// together as possible. They aren't read atomically though, and so may not be from
+    /// exactly the same moment; the best block number is never less than the finalized one.
+    pub fn block_numbers(
+        &self,
+    ) -> impl Future<Output = Result<BlockNumbers, Error>> + Send + 'static {
+        let client = self.client.clone();
+        async move {
+            let rpc = client.rpc();
+            let (finalized_hash, best_header) =
+                futures::future::try_join(rpc.finalized_head(), rpc.header(None)).await?;
+            let best_header = best_header.ok_or_else(|| {
+                Error::Other("The node didn't hand back the best header".into())
+            })?;
+            let finalized_header = rpc
+                .header(Some(finalized_hash))
+                .await?
+                .ok_or_else(|| BlockError::block_hash_not_found(finalized_hash))?;
+
+            let finalized = finalized_header.number().into();
+            let best = best_header.number().into();
+            Ok(BlockNumbers {
+                best: best.max(finalized),
+                finalized,
+            })
+        }
+    }
+
+    /// Look up the hashes of the blocks with the given numbers, which needn't be in order
+    /// or next to each other. The distinct numbers are all asked for in a single
+    /// `chain_getBlockHash` call.
+    ///
+    /// Numbers which have no block yet are left out of [`BlockHashes::hashes`] and listed
+    /// in [`BlockHashes::missing`] instead. The hashes are those of the node's best chain,
+    /// so any which aren't finalized yet may change in a reorg.
+    pub fn block_hashes_for(
+        &self,
+        numbers: &[u64],
+    ) -> impl Future<Output = Result<BlockHashes<T::Hash>, Error>> + Send + 'static {
+        let client = self.client.clone();
+        let numbers: BTreeSet<u64> = numbers.iter().copied().collect();
+        async move {
+            let mut block_hashes = BlockHashes {
+                hashes: BTreeMap::new(),
+                missing: BTreeSet::new(),
+            };
+            if numbers.is_empty() {
+                return Ok(block_hashes)
+            }
+
+            let block_numbers: Vec<BlockNumber> =
+                numbers.iter().map(|&n| n.into()).collect();
+            let hashes = client.rpc().block_hashes(&block_numbers).await?;
+            if hashes.len() != numbers.len() {
+                return Err(Error::Other(format!(
+                    "Asked for the hashes of {} blocks but got {} back",
+                    numbers.len(),
+                    hashes.len()
+                )))
+            }
+            for (number, hash) in numbers.into_iter().zip(hashes) {
+                match hash {
+                    Some(hash) => {
+                        block_hashes.hashes.insert(number, hash);
+                    }
+                    None => {
+                        block_hashes.missing.insert(number);
+                    }
+                }
+            }
+            Ok(block_hashes)
+        }
+    }
+
     /// Subscribe to all new blocks imported by the node.
     ///
     /// **Note:** You probably want to use [`Self::subscribe_finalized()`] most of
@@ -123,10 +280,106 @@ where
         })
     }
 
+    /// Subscribe to new best blocks, like [`Self::subscribe_best()`], but also detect when
+    /// the best chain switches to a different fork. When a new best block doesn't build on
+    /// the previous one, a [`BestBlockEvent::Reorg`] is handed back giving the fork point
+    /// and the blocks which are no longer on the best chain, followed by each block on the
+    /// new fork in order. Blocks that the node skipped over are filled in in the same way.
+    ///
+    /// Only the last `max_depth` best blocks are remembered, and so a reorg whose fork point
+    /// is further back than this is reported with no fork point. In that case, everything
+    /// seen so far should be treated as suspect. Finding the fork point also means fetching
+    /// the headers of any new blocks which weren't handed back by the node (however many
+    /// were skipped), so very deep reorgs and long gaps are slower to report.
+    pub fn subscribe_best_with_reorgs(
+        &self,
+        max_depth: usize,
+    ) -> impl Future<Output = Result<BlockStream<BestBlockEvent<T, Client>>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let client = self.client.clone();
+        async move {
+            let sub = client.rpc().subscribe_best_block_headers().await?;
+            let tracker = ReorgTracker::<T>::new(max_depth);
+            let events = stream::unfold(
+                (sub, tracker, client),
+                |(mut sub, mut tracker, client)| {
+                    async move {
+                        let events = match sub.next().await? {
+                            Ok(header) => {
+                                let rpc = client.rpc();
+                                tracker
+                                    .handle(header, |hash| rpc.header(Some(hash)))
+                                    .await
+                            }
+                            Err(e) => Err(e),
+                        };
+                        let events: Vec<Result<_, Error>> = match events {
+                            Ok(events) => {
+                                events
+                                    .into_iter()
+                                    .map(|ev| {
+                                        Ok(match ev {
+                                            TrackedEvent::NewBest(header) => {
+                                                BestBlockEvent::NewBest(Block::new(
+                                                    header,
+                                                    client.clone(),
+                                                ))
+                                            }
+                                            TrackedEvent::Reorg(reorg) => {
+                                                BestBlockEvent::Reorg(reorg)
+                                            }
+                                        })
+                                    })
+                                    .collect()
+                            }
+                            Err(e) => vec![Err(e)],
+                        };
+                        Some((stream::iter(events), (sub, tracker, client)))
+                    }
+                },
+            )
+            .flatten();
+            BlockStreamRes::Ok(Box::pin(events))
+        }
+    }
+
     /// Subscribe to finalized blocks.
     pub fn subscribe_finalized(
         &self,
     ) -> impl Future<Output = Result<BlockStream<Block<T, Client>>, Error>> + Send + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        self.subscribe_finalized_filling_in_gaps(None)
+    }
+
+    /// Subscribe to finalized blocks, filling in at most `max_gap` blocks that the node
+    /// skips over at once.
+    ///
+    /// Larger gaps (for instance after the client has been disconnected for a long time)
+    /// are not filled in, to avoid making a flood of requests to the node. Instead, a
+    /// [`BlockError::LargeGap`] error is handed back giving the blocks that were missed,
+    /// and the subscription carries on from the next finalized block. It's then up to the
+    /// consumer to fetch the missing blocks, resync some other way, or skip over them.
+    /// [`DEFAULT_MAX_FINALIZED_GAP`] is a sensible cap to start with.
+    pub fn subscribe_finalized_with_max_gap(
+        &self,
+        max_gap: u64,
+    ) -> impl Future<Output = Result<BlockStream<Block<T, Client>>, Error>> + Send + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        self.subscribe_finalized_filling_in_gaps(Some(max_gap))
+    }
+
+    fn subscribe_finalized_filling_in_gaps(
+        &self,
+        max_gap: Option<u64>,
+    ) -> impl Future<Output = Result<BlockStream<Block<T, Client>>, Error>> + Send + 'static
     where
         Client: Send + Sync + 'static,
     {
@@ -145,15 +398,407 @@ where
 
             // Adjust the subscription stream to fill in any missing blocks.
             BlockStreamRes::Ok(
-                subscribe_to_block_headers_filling_in_gaps(
-                    client,
-                    last_finalized_block_num,
-                    sub,
-                )
-                .boxed(),
+                fill_in_gaps(client, last_finalized_block_num, max_gap, sub).boxed(),
             )
         })
     }
+
+    /// Subscribe to finalized blocks, buffering at most `capacity` blocks that the consumer
+    /// hasn't yet asked for, and following the given [`BufferPolicy`] once the buffer is full.
+    ///
+    /// Use [`BufferPolicy::DropOldest`] if only the most recent finalized blocks are of
+    /// interest, and a slow consumer should skip over blocks rather than fall behind.
+    pub fn subscribe_finalized_bounded(
+        &self,
+        capacity: usize,
+        policy: BufferPolicy,
+    ) -> impl Future<Output = Result<BoundedStream<BlockStream<Block<T, Client>>>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let sub = self.subscribe_finalized();
+        async move {
+            let sub = sub.await?;
+            Ok(BoundedStream::new(sub, capacity, policy))
+        }
+    }
+
+    /// Subscribe to finalized blocks, fetching the body and events of up to `look_ahead`
+    /// blocks concurrently, ahead of the consumer. Blocks are still handed back in order.
+    ///
+    /// This is useful when processing each block takes a while, or the round trip to the node
+    /// is slow, so that the next blocks are ready to go by the time they are needed. A
+    /// `look_ahead` of 0 is treated as 1, which fetches each block only once it's needed.
+    pub fn subscribe_finalized_prefetched(
+        &self,
+        look_ahead: usize,
+    ) -> impl Future<Output = Result<BlockStream<PrefetchedBlock<T, Client>>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let sub = self.subscribe_finalized();
+        async move {
+            let sub = sub.await?;
+            let prefetched = sub
+                .map(|block| {
+                    async move {
+                        let block = block?;
+                        PrefetchedBlock::fetch(block).await
+                    }
+                })
+                .buffered(look_ahead.max(1));
+            BlockStreamRes::Ok(prefetched.boxed())
+        }
+    }
+
+    /// Subscribe to finalized blocks, handing back each extrinsic signed by the given
+    /// account along with the events it produced. If the account signed more than one
+    /// extrinsic in a block, each of them is handed back in the order they appear in the block.
+    ///
+    /// The subscription ends when the `stop` future resolves (or when it's dropped). Pass
+    /// something like `tokio::time::sleep(duration)` to stop after some timeout, or
+    /// [`futures::future::pending()`] to never stop.
+    ///
+    /// See [`super::Extrinsic::is_signed_by()`] for the caveats on how signers are matched.
+    pub fn subscribe_signed_by<Stop>(
+        &self,
+        account: T::AccountId,
+        stop: Stop,
+    ) -> impl Future<Output = Result<BlockStream<SignedExtrinsicDetails<T>>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+        Stop: Future<Output = ()> + Send + 'static,
+    {
+        let address = T::Address::from(account).encode();
+        let client = self.client.clone();
+        let blocks = self.subscribe_finalized();
+        async move {
+            let sub =
+                blocks.await?.then(move |block| {
+                    let address = address.clone();
+                    let client = client.clone();
+                    async move {
+                        let block = block?;
+                        let block_hash = block.hash();
+                        let block_number = block.number().into();
+
+                        let block_details =
+                            match client.rpc().block(Some(block_hash)).await? {
+                                Some(block) => block,
+                                None => {
+                                    return Err(BlockError::block_hash_not_found(
+                                        block_hash,
+                                    )
+                                    .into())
+                                }
+                            };
+                        let signed: Vec<_> = block_details
+                            .block
+                            .extrinsics
+                            .into_iter()
+                            .enumerate()
+                            .filter(|(_, ext)| is_signed_by_address(&ext.0, &address))
+                            .collect();
+                        if signed.is_empty() {
+                            return Ok(Vec::new())
+                        }
+
+                        // Only fetch the events if there's something to hand them back for.
+                        let events = EventsClient::new(client).at(Some(block_hash));
+                        let events = events.await?;
+                        let found = signed
+                            .into_iter()
+                            .map(|(idx, ext)| {
+                                let ext_hash = T::Hasher::hash_of(&ext.0);
+                                let events = ExtrinsicEvents::new(
+                                    ext_hash,
+                                    idx as u32,
+                                    events.clone(),
+                                );
+                                SignedExtrinsicDetails::new(block_number, ext.0, events)
+                            })
+                            .collect();
+                        Ok::<_, Error>(found)
+                    }
+                });
+
+            let sub = sub
+                .flat_map(|res| {
+                    match res {
+                        Ok(found) => {
+                            Either::Left(stream::iter(found.into_iter().map(Ok)))
+                        }
+                        Err(e) => Either::Right(stream::once(async { Err(e) })),
+                    }
+                })
+                .take_until(stop);
+
+            BlockStreamRes::Ok(Box::pin(sub))
+        }
+    }
+
+    /// Subscribe to finalized blocks, handing back each extrinsic in them decoded according
+    /// to the metadata, along with the events that it produced. Extrinsics are handed back
+    /// in the order they appear in each block.
+    ///
+    /// The client's metadata is used to decode the extrinsics, so the client should be kept
+    /// up to date with runtime upgrades while this subscription is running.
+    pub fn subscribe_finalized_extrinsics(
+        &self,
+    ) -> impl Future<Output = Result<BlockStream<ExtrinsicDetails<T>>, Error>> + Send + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let client = self.client.clone();
+        let blocks = self.subscribe_finalized();
+        async move {
+            let sub = blocks.await?.then(move |block| {
+                let client = client.clone();
+                async move {
+                    let block = block?;
+                    let block_hash = block.hash();
+                    let block_number = block.number().into();
+
+                    let events = EventsClient::new(client.clone()).at(Some(block_hash));
+                    let (block_details, events) = futures::future::try_join(
+                        client.rpc().block(Some(block_hash)),
+                        events,
+                    )
+                    .await?;
+                    let Some(block_details) = block_details else {
+                        return Err(BlockError::block_hash_not_found(block_hash).into())
+                    };
+
+                    let metadata = client.metadata();
+                    block_details
+                        .block
+                        .extrinsics
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, ext)| {
+                            let ext_hash = T::Hasher::hash_of(&ext.0);
+                            let events = ExtrinsicEvents::new(
+                                ext_hash,
+                                idx as u32,
+                                events.clone(),
+                            );
+                            ExtrinsicDetails::decode(
+                                block_number,
+                                ext.0,
+                                events,
+                                &metadata,
+                            )
+                        })
+                        .collect::<Result<Vec<_>, Error>>()
+                }
+            });
+
+            let sub = sub.flat_map(|res| {
+                match res {
+                    Ok(found) => Either::Left(stream::iter(found.into_iter().map(Ok))),
+                    Err(e) => Either::Right(stream::once(async { Err(e) })),
+                }
+            });
+
+            BlockStreamRes::Ok(Box::pin(sub))
+        }
+    }
+
+    /// Subscribe to the candidate events that the parachain inclusion pallet of a relay
+    /// chain emits in finalized blocks, for tracking when the blocks of parachains are
+    /// backed, included or timed out. See [`crate::events::CandidateEvent`].
+    ///
+    /// Blocks without any candidate events are skipped over, so nothing is handed back if
+    /// the chain isn't a relay chain.
+    pub fn subscribe_finalized_candidate_events(
+        &self,
+    ) -> impl Future<Output = Result<BlockStream<EventInBlock<T, CandidateEvent>>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+    {
+        let client = self.client.clone();
+        let blocks = self.subscribe_finalized();
+        async move {
+            let sub = blocks.await?.then(move |block| {
+                let client = client.clone();
+                async move {
+                    let block = block?;
+                    let block_hash = block.hash();
+                    let block_number = block.number().into();
+                    let events = EventsClient::new(client).at(Some(block_hash));
+                    let events = events.await?;
+
+                    let mut found = Vec::new();
+                    for ev in events.iter() {
+                        let ev = ev?;
+                        if let Some(event) = ev.as_candidate_event()? {
+                            found.push(EventInBlock {
+                                block_number,
+                                block_hash,
+                                phase: ev.phase(),
+                                index: ev.index(),
+                                event,
+                            });
+                        }
+                    }
+                    Ok::<_, Error>(found)
+                }
+            });
+
+            let sub = sub.flat_map(|res| {
+                match res {
+                    Ok(found) => Either::Left(stream::iter(found.into_iter().map(Ok))),
+                    Err(e) => Either::Right(stream::once(async { Err(e) })),
+                }
+            });
+
+            BlockStreamRes::Ok(Box::pin(sub))
+        }
+    }
+
+    /// Wait for the extrinsic with the given hash to make it into a finalized block, by
+    /// hashing the extrinsics in each newly finalized block until one matches. This hands
+    /// back the block that the extrinsic made it into along with its index in the block, or
+    /// `None` if the `timeout` future resolves first.
+    ///
+    /// This is useful when the extrinsic was submitted elsewhere, or the subscription used to
+    /// watch it was lost. Only blocks finalized after this is called are looked at, so check
+    /// any earlier blocks separately if the extrinsic may already have been included.
+    /// Pass something like `tokio::time::sleep(duration)` as the `timeout`.
+    pub fn wait_for_inclusion<Timeout>(
+        &self,
+        ext_hash: T::Hash,
+        timeout: Timeout,
+    ) -> impl Future<Output = Result<Option<(TxInBlock<T, Client>, u32)>, Error>>
+           + Send
+           + 'static
+    where
+        Client: Send + Sync + 'static,
+        Timeout: Future<Output = ()> + Send + 'static,
+    {
+        let client = self.client.clone();
+        let blocks = self.subscribe_finalized();
+        async move {
+            let mut blocks = Box::pin(blocks.await?.take_until(timeout));
+            while let Some(block) = blocks.next().await {
+                let block_hash = block?.hash();
+                let block_details = match client.rpc().block(Some(block_hash)).await? {
+                    Some(block) => block,
+                    None => {
+                        return Err(BlockError::block_hash_not_found(block_hash).into())
+                    }
+                };
+                let index = block_details
+                    .block
+                    .extrinsics
+                    .iter()
+                    .position(|ext| T::Hasher::hash_of(&ext.0) == ext_hash);
+                if let Some(index) = index {
+                    let tx = TxInBlock::new(block_hash, ext_hash, client);
+                    return Ok(Some((tx, index as u32)))
+                }
+            }
+            Ok(None)
+        }
+    }
+
+    /// Wait for the block with the given hash, seen on the best chain, to be finalized.
+    /// This hands back the block once it, or one of its descendants, is finalized.
+    ///
+    /// A [`BlockError::Orphaned`] error is handed back if some other block is finalized at
+    /// the same height instead, as the block then never will be, or a
+    /// [`BlockError::FinalityTimeout`] error if the `timeout` future resolves first. Pass
+    /// something like `tokio::time::sleep(duration)` as the `timeout`.
+    pub fn wait_for_finalized<Timeout>(
+        &self,
+        block_hash: T::Hash,
+        timeout: Timeout,
+    ) -> impl Future<Output = Result<Block<T, Client>, Error>> + Send + 'static
+    where
+        Client: Send + Sync + 'static,
+        Timeout: Future<Output = ()> + Send + 'static,
+    {
+        let client = self.client.clone();
+        async move {
+            let header = client
+                .rpc()
+                .header(Some(block_hash))
+                .await?
+                .ok_or_else(|| BlockError::block_hash_not_found(block_hash))?;
+
+            // Subscribe before looking up the current finalized head, so that nothing
+            // finalized in between is missed.
+            let sub = client.rpc().subscribe_finalized_block_headers().await?;
+            let finalized_hash = client.rpc().finalized_head().await?;
+            let finalized_header = client
+                .rpc()
+                .header(Some(finalized_hash))
+                .await?
+                .ok_or_else(|| BlockError::block_hash_not_found(finalized_hash))?;
+            let finalized = stream::once(async { Ok(finalized_header) }).chain(sub);
+
+            let number = header.number().into();
+            wait_until_finalized(&client, block_hash, number, finalized, timeout).await?;
+            Ok(Block::new(header, client))
+        }
+    }
+}
+
+// Wait for a finalized header at or above the height of the block given, and then check
+// that the block is the one finalized at its height.
+async fn wait_until_finalized<T, Client>(
+    client: &Client,
+    hash: T::Hash,
+    number: u64,
+    finalized: impl Stream<Item = Result<T::Header, Error>>,
+    timeout: impl Future<Output = ()>,
+) -> Result<(), Error>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let mut finalized = Box::pin(finalized.take_until(timeout));
+    while let Some(finalized_header) = finalized.next().await {
+        let finalized_header = finalized_header?;
+        let finalized_number: u64 = finalized_header.number().into();
+        if finalized_number < number {
+            continue
+        }
+        // Every block on the best chain up to the finalized head is finalized too, so the
+        // block at our height is the ancestor of the finalized head there.
+        let finalized_hash = if finalized_number == number {
+            finalized_header.hash()
+        } else {
+            client
+                .rpc()
+                .block_hash(Some(number.into()))
+                .await?
+                .ok_or_else(|| {
+                    Error::Other(format!("No block #{number} on the finalized chain"))
+                })?
+        };
+        if finalized_hash == hash {
+            return Ok(())
+        }
+        return Err(BlockError::Orphaned {
+            hash: format!("0x{}", hex::encode(hash)),
+            finalized: format!("0x{}", hex::encode(finalized_hash)),
+        }
+        .into())
+    }
+    if finalized.is_stopped() {
+        Err(BlockError::FinalityTimeout(format!("0x{}", hex::encode(hash))).into())
+    } else {
+        Err(RpcError::SubscriptionDropped.into())
+    }
 }
 
 /// Take a promise that will return a subscription to some block headers,
@@ -185,8 +830,44 @@ where
 /// without notice in a patch release.
 #[doc(hidden)]
 pub fn subscribe_to_block_headers_filling_in_gaps<T, Client, S, E>(
+    client: Client,
+    last_block_num: Option<u64>,
+    sub: S,
+) -> impl Stream<Item = Result<T::Header, Error>> + Send
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+    S: Stream<Item = Result<T::Header, E>> + Send,
+    E: Into<Error> + Send + 'static,
+{
+    fill_in_gaps(client, last_block_num, None, sub)
+}
+
+/// Like [`subscribe_to_block_headers_filling_in_gaps`], but gaps of more than `max_gap`
+/// blocks are not filled in, and a [`BlockError::LargeGap`] is handed back instead.
+///
+/// Note: This is exposed for testing but is not considered stable and may change
+/// without notice in a patch release.
+#[doc(hidden)]
+pub fn subscribe_to_block_headers_filling_in_gaps_with_max_gap<T, Client, S, E>(
+    client: Client,
+    last_block_num: Option<u64>,
+    max_gap: u64,
+    sub: S,
+) -> impl Stream<Item = Result<T::Header, Error>> + Send
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+    S: Stream<Item = Result<T::Header, E>> + Send,
+    E: Into<Error> + Send + 'static,
+{
+    fill_in_gaps(client, last_block_num, Some(max_gap), sub)
+}
+
+fn fill_in_gaps<T, Client, S, E>(
     client: Client,
     mut last_block_num: Option<u64>,
+    max_gap: Option<u64>,
     sub: S,
 ) -> impl Stream<Item = Result<T::Header, Error>> + Send
 where
@@ -201,7 +882,7 @@ where
         // Get the header, or return a stream containing just the error.
         let header = match s {
             Ok(header) => header,
-            Err(e) => return Either::Left(stream::once(async { Err(e.into()) })),
+            Err(e) => return Either::Left(stream::once(async { Err(e.into()) }).boxed()),
         };
 
         // We want all previous details up to, but not including this current block num.
@@ -210,6 +891,19 @@ where
         // This is one after the last block we returned details for last time.
         let start_block_num = last_block_num.map(|n| n + 1).unwrap_or(end_block_num);
 
+        // On the next iteration, we'll get details starting just after this end block.
+        last_block_num = Some(end_block_num);
+
+        // Don't fill in huge gaps; let the consumer decide what to do about them instead.
+        let gap = end_block_num.saturating_sub(start_block_num);
+        if max_gap.map(|max| gap > max).unwrap_or(false) {
+            let err = BlockError::LargeGap {
+                from: start_block_num,
+                to: end_block_num - 1,
+            };
+            return Either::Left(stream::iter([Err(err.into()), Ok(header)]).boxed())
+        }
+
         // Iterate over all of the previous blocks we need headers for, ignoring the current block
         // (which we already have the header info for):
         let previous_headers = stream::iter(start_block_num..end_block_num)
@@ -223,10 +917,236 @@ where
             })
             .filter_map(|h| async { h.transpose() });
 
-        // On the next iteration, we'll get details starting just after this end block.
-        last_block_num = Some(end_block_num);
-
         // Return a combination of any previous headers plus the new header.
         Either::Right(previous_headers.chain(stream::once(async { Ok(header) })))
     })
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::substrate::{
+            BlakeTwo256,
+            SubstrateHeader,
+        },
+        test_utils::{
+            online_client,
+            MockRpc,
+        },
+        OnlineClient,
+        PolkadotConfig,
+    };
+    use futures::future;
+    use std::sync::Arc;
+
+    type TestHeader = SubstrateHeader<u32, BlakeTwo256>;
+
+    // How many blocks the finalized head of a `ChainRpc` is behind its best block.
+    const FINALITY_LAG: usize = 2;
+
+    // A node which knows about a chain of headers.
+    struct ChainRpc {
+        headers: Vec<TestHeader>,
+    }
+
+    impl ChainRpc {
+        fn respond(&self, method: &str, params: serde_json::Value) -> serde_json::Value {
+            let param = params[0].clone();
+            match method {
+                "chain_getBlockHash" => {
+                    let hash = |n: &serde_json::Value| {
+                        match self.headers.get(n.as_u64().unwrap() as usize) {
+                            Some(header) => {
+                                serde_json::json!(format!("{:?}", header.hash()))
+                            }
+                            None => serde_json::Value::Null,
+                        }
+                    };
+                    match param.as_array() {
+                        Some(numbers) => numbers.iter().map(hash).collect(),
+                        None => hash(&param),
+                    }
+                }
+                "chain_getHeader" if param.is_null() => {
+                    serde_json::to_value(self.headers.last()).unwrap()
+                }
+                "chain_getHeader" => {
+                    let header = self
+                        .headers
+                        .iter()
+                        .find(|h| format!("{:?}", h.hash()) == param.as_str().unwrap());
+                    serde_json::to_value(header).unwrap()
+                }
+                "chain_getFinalizedHead" => {
+                    let finalized = &self.headers[self.headers.len() - FINALITY_LAG - 1];
+                    serde_json::json!(format!("{:?}", finalized.hash()))
+                }
+                _ => panic!("unexpected method {method}"),
+            }
+        }
+    }
+
+    // A client for a node which knows about a chain of `len` headers, along with the
+    // headers and the node.
+    fn chain_client(
+        len: u32,
+    ) -> (Vec<TestHeader>, OnlineClient<PolkadotConfig>, Arc<MockRpc>) {
+        let mut headers: Vec<TestHeader> = Vec::new();
+        for number in 0..len {
+            headers.push(TestHeader {
+                parent_hash: headers.last().map(|h| h.hash()).unwrap_or_default(),
+                number,
+                state_root: Default::default(),
+                extrinsics_root: Default::default(),
+                digest: Default::default(),
+            });
+        }
+
+        let node = ChainRpc {
+            headers: headers.clone(),
+        };
+        let rpc = Arc::new(MockRpc::new(move |method, params| {
+            Ok(node.respond(method, params))
+        }));
+        (headers, online_client(rpc.clone()), rpc)
+    }
+
+    #[tokio::test]
+    async fn large_gaps_are_not_filled_in() {
+        let (headers, client, rpc) = chain_client(20);
+
+        // The node hands back blocks 3, 5 and 15, and we fill in gaps of up to 3 blocks:
+        let sub = stream::iter([3, 5, 15].map(|n| Ok::<_, Error>(headers[n].clone())));
+        let results: Vec<_> = subscribe_to_block_headers_filling_in_gaps_with_max_gap(
+            client,
+            Some(2),
+            3,
+            sub,
+        )
+        .map(|res| res.map(|h| h.number))
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().unwrap(), &3);
+        assert_eq!(results[1].as_ref().unwrap(), &4);
+        assert_eq!(results[2].as_ref().unwrap(), &5);
+        assert!(matches!(
+            results[3],
+            Err(Error::Block(BlockError::LargeGap { from: 6, to: 14 }))
+        ));
+        assert_eq!(results[4].as_ref().unwrap(), &15);
+        // Only block 4 was fetched:
+        assert_eq!(rpc.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn gaps_are_filled_in_without_a_max_gap() {
+        let (headers, client, _) = chain_client(20);
+
+        let sub = stream::iter([3, 15].map(|n| Ok::<_, Error>(headers[n].clone())));
+        let numbers: Vec<_> =
+            subscribe_to_block_headers_filling_in_gaps(client, Some(2), sub)
+                .map(|res| res.unwrap().number)
+                .collect()
+                .await;
+
+        assert_eq!(numbers, (3..=15).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn fetches_best_and_finalized_block_numbers() {
+        let (_, client, rpc) = chain_client(10);
+        let numbers = client.blocks().block_numbers().await.unwrap();
+        assert_eq!(
+            numbers,
+            BlockNumbers {
+                best: 9,
+                finalized: 9 - FINALITY_LAG as u64,
+            }
+        );
+        assert_eq!(rpc.requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn fetches_hashes_for_sparse_block_numbers() {
+        let (headers, client, rpc) = chain_client(10);
+        let block_hashes = client
+            .blocks()
+            .block_hashes_for(&[7, 2, 12, 7, 0, 10])
+            .await
+            .unwrap();
+        assert_eq!(
+            block_hashes.hashes,
+            BTreeMap::from([0, 2, 7].map(|n| (n as u64, headers[n].hash())))
+        );
+        assert_eq!(block_hashes.missing, BTreeSet::from([10, 12]));
+        // Every hash was asked for in one go:
+        assert_eq!(rpc.requests(), 1);
+
+        let block_hashes = client.blocks().block_hashes_for(&[]).await.unwrap();
+        assert!(block_hashes.hashes.is_empty() && block_hashes.missing.is_empty());
+        assert_eq!(rpc.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn waits_for_blocks_to_be_finalized_or_orphaned() {
+        let (headers, client, _) = chain_client(10);
+        let finalized = |numbers: &[usize]| {
+            let headers: Vec<_> =
+                numbers.iter().map(|n| Ok(headers[*n].clone())).collect();
+            stream::iter(headers)
+        };
+
+        // Finalized directly, or once a descendant is:
+        let wait = wait_until_finalized(
+            &client,
+            headers[5].hash(),
+            5,
+            finalized(&[3, 5]),
+            future::pending(),
+        );
+        assert!(wait.await.is_ok());
+        let wait = wait_until_finalized(
+            &client,
+            headers[5].hash(),
+            5,
+            finalized(&[4, 8]),
+            future::pending(),
+        );
+        assert!(wait.await.is_ok());
+
+        // A block on another fork at the same height is orphaned once either is finalized:
+        let fork = TestHeader {
+            state_root: BlakeTwo256::hash(b"fork"),
+            ..headers[5].clone()
+        };
+        for numbers in [&[5][..], &[8]] {
+            let wait = wait_until_finalized(
+                &client,
+                fork.hash(),
+                5,
+                finalized(numbers),
+                future::pending(),
+            );
+            assert!(matches!(
+                wait.await,
+                Err(Error::Block(BlockError::Orphaned { .. }))
+            ));
+        }
+
+        // Nothing high enough is finalized in time:
+        let wait = wait_until_finalized(
+            &client,
+            headers[5].hash(),
+            5,
+            finalized(&[3]).chain(stream::pending()),
+            future::ready(()),
+        );
+        assert!(matches!(
+            wait.await,
+            Err(Error::Block(BlockError::FinalityTimeout(_)))
+        ));
+    }
+}
@@ -0,0 +1,129 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use futures::{
+    Stream,
+    StreamExt,
+};
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+/// What a [`BoundedStream`] does when its buffer is full.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BufferPolicy {
+    /// Stop pulling items from the underlying stream until the consumer catches up. No items
+    /// are lost, but the underlying stream (and ultimately the node) has to hold on to them
+    /// instead. Subscriptions from the default RPC client have a buffer of their own, and
+    /// are closed if that fills up.
+    Block,
+    /// Keep pulling items from the underlying stream, dropping the oldest buffered item to
+    /// make room for each new one. The consumer only ever sees the most recent items.
+    DropOldest,
+}
+
+/// A stream which buffers at most some number of items from an underlying stream,
+/// following a [`BufferPolicy`] once the buffer is full.
+///
+/// Items are only pulled from the underlying stream when this stream is polled, so a
+/// consumer which isn't polling won't see any items dropped.
+pub struct BoundedStream<S: Stream> {
+    inner: S,
+    inner_finished: bool,
+    buffer: VecDeque<S::Item>,
+    capacity: usize,
+    policy: BufferPolicy,
+    dropped: u64,
+}
+
+impl<S: Stream + Unpin> BoundedStream<S> {
+    /// Wrap a stream, buffering up to `capacity` items from it. A `capacity` of 0 is
+    /// treated as 1.
+    pub fn new(inner: S, capacity: usize, policy: BufferPolicy) -> Self {
+        BoundedStream {
+            inner,
+            inner_finished: false,
+            buffer: VecDeque::new(),
+            capacity: capacity.max(1),
+            policy,
+            dropped: 0,
+        }
+    }
+
+    /// The policy followed when the buffer is full.
+    pub fn policy(&self) -> BufferPolicy {
+        self.policy
+    }
+
+    /// The number of items that have been dropped so far. This is always 0 for
+    /// [`BufferPolicy::Block`].
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+// Buffered items are never pinned, so this is fine regardless of the item type.
+impl<S: Stream + Unpin> Unpin for BoundedStream<S> {}
+
+impl<S: Stream + Unpin> Stream for BoundedStream<S> {
+    type Item = S::Item;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+
+        // Pull everything that's ready from the underlying stream into the buffer.
+        while !this.inner_finished {
+            if this.policy == BufferPolicy::Block && this.buffer.len() >= this.capacity {
+                break
+            }
+            match this.inner.poll_next_unpin(cx) {
+                Poll::Ready(Some(item)) => {
+                    this.buffer.push_back(item);
+                    if this.buffer.len() > this.capacity {
+                        this.buffer.pop_front();
+                        this.dropped += 1;
+                    }
+                }
+                Poll::Ready(None) => this.inner_finished = true,
+                Poll::Pending => break,
+            }
+        }
+
+        match this.buffer.pop_front() {
+            Some(item) => Poll::Ready(Some(item)),
+            None if this.inner_finished => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::stream;
+
+    #[tokio::test]
+    async fn block_policy_hands_back_everything() {
+        let mut s = BoundedStream::new(stream::iter(0..10), 3, BufferPolicy::Block);
+        let items: Vec<_> = (&mut s).collect().await;
+        assert_eq!(items, (0..10).collect::<Vec<_>>());
+        assert_eq!(s.dropped(), 0);
+    }
+
+    #[tokio::test]
+    async fn drop_oldest_policy_keeps_the_latest_items() {
+        let mut s = BoundedStream::new(stream::iter(0..10), 3, BufferPolicy::DropOldest);
+        let items: Vec<_> = (&mut s).collect().await;
+        assert_eq!(items, vec![7, 8, 9]);
+        assert_eq!(s.dropped(), 7);
+    }
+}
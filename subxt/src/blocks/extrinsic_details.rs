@@ -0,0 +1,454 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    signed_extensions::decode_signed_extensions_from,
+    ExtrinsicEvents,
+    ExtrinsicSignedExtensions,
+};
+use crate::{
+    config::Config,
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        Metadata,
+    },
+};
+use codec::{
+    Decode,
+    DecodeAll,
+};
+use derivative::Derivative;
+use scale_info::TypeDef;
+use scale_value::{
+    scale::TypeId,
+    Composite,
+    ValueDef,
+};
+use std::ops::Range;
+
+/// An extrinsic in a finalized block, decoded according to the metadata, along with the
+/// events that it produced. This is handed back from
+/// [`crate::blocks::BlocksClient::subscribe_finalized_extrinsics()`].
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct ExtrinsicDetails<T: Config> {
+    block_number: u64,
+    bytes: Vec<u8>,
+    address: Option<Range<usize>>,
+    signature: Option<Range<usize>>,
+    signed_extensions: Option<ExtrinsicSignedExtensions>,
+    call_offset: usize,
+    pallet_name: String,
+    call_name: String,
+    fields: Composite<TypeId>,
+    events: ExtrinsicEvents<T>,
+}
+
+impl<T: Config> ExtrinsicDetails<T> {
+    pub(crate) fn decode(
+        block_number: u64,
+        bytes: Vec<u8>,
+        events: ExtrinsicEvents<T>,
+        metadata: &Metadata,
+    ) -> Result<Self, Error> {
+        let parts = decode_extrinsic_parts(&bytes, metadata)?;
+        let (pallet_name, call_name, fields) =
+            decode_call(&bytes[parts.call_offset..], metadata)?;
+        let (address, signature, signed_extensions) = match parts.signed {
+            Some(signed) => {
+                (
+                    Some(signed.address),
+                    Some(signed.signature),
+                    Some(signed.extensions),
+                )
+            }
+            None => (None, None, None),
+        };
+        Ok(ExtrinsicDetails {
+            block_number,
+            bytes,
+            address,
+            signature,
+            signed_extensions,
+            call_offset: parts.call_offset,
+            pallet_name,
+            call_name,
+            fields,
+            events,
+        })
+    }
+
+    /// The hash of the block that the extrinsic was included in.
+    pub fn block_hash(&self) -> T::Hash {
+        self.events.block_hash()
+    }
+
+    /// The number of the block that the extrinsic was included in.
+    pub fn block_number(&self) -> u64 {
+        self.block_number
+    }
+
+    /// The index of the extrinsic in the block.
+    pub fn index(&self) -> u32 {
+        self.events.extrinsic_index()
+    }
+
+    /// The bytes of the extrinsic.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Is the extrinsic signed?
+    pub fn is_signed(&self) -> bool {
+        self.address.is_some()
+    }
+
+    /// The SCALE encoded address of the account that signed the extrinsic, if it's signed.
+    /// This can be decoded into the `T::Address` type that the extrinsic was signed with.
+    pub fn address_bytes(&self) -> Option<&[u8]> {
+        self.address.clone().map(|range| &self.bytes[range])
+    }
+
+    /// Decode the address of the account that signed the extrinsic, if it's signed.
+    ///
+    /// This is usually `T::Address`, which for the default configurations is a
+    /// [`crate::utils::MultiAddress`]: its variant says whether the extrinsic was signed by
+    /// an account ID, an account index or some raw address. Runtimes without account
+    /// indexes (such as Polkadot's) use `()` as the index type, and so `MultiAddress<_, ()>`
+    /// is needed to decode their addresses.
+    pub fn address<Address: Decode>(&self) -> Result<Option<Address>, Error> {
+        decode_signed_part(&self.bytes, self.address.clone())
+    }
+
+    /// The SCALE encoded signature of the extrinsic, if it's signed.
+    pub fn signature_bytes(&self) -> Option<&[u8]> {
+        self.signature.clone().map(|range| &self.bytes[range])
+    }
+
+    /// Decode the signature of the extrinsic, if it's signed.
+    ///
+    /// This is usually `T::Signature`, which for the default configurations is a
+    /// [`crate::utils::MultiSignature`]: its variant says whether the extrinsic was signed
+    /// with an Ed25519, Sr25519 or ECDSA key.
+    pub fn signature<Signature: Decode>(&self) -> Result<Option<Signature>, Error> {
+        decode_signed_part(&self.bytes, self.signature.clone())
+    }
+
+    /// The signed extensions of the extrinsic, if it's signed.
+    pub fn signed_extensions(&self) -> Option<&ExtrinsicSignedExtensions> {
+        self.signed_extensions.as_ref()
+    }
+
+    /// The name of the pallet that the call belongs to.
+    pub fn pallet_name(&self) -> &str {
+        &self.pallet_name
+    }
+
+    /// The name of the call.
+    pub fn call_name(&self) -> &str {
+        &self.call_name
+    }
+
+    /// The arguments given to the call, decoded dynamically.
+    pub fn field_values(&self) -> &Composite<TypeId> {
+        &self.fields
+    }
+
+    /// The SCALE encoded call data, beginning with the pallet and call indexes.
+    pub fn call_bytes(&self) -> &[u8] {
+        &self.bytes[self.call_offset..]
+    }
+
+    /// Decode the call data into some static type. This is usually the `RuntimeCall` type
+    /// generated from the metadata, which can represent any call in the runtime.
+    pub fn as_root_call<Call: Decode>(&self) -> Result<Call, Error> {
+        Ok(Call::decode(&mut self.call_bytes())?)
+    }
+
+    /// The events associated with the extrinsic.
+    pub fn events(&self) -> &ExtrinsicEvents<T> {
+        &self.events
+    }
+}
+
+// The location of the different parts of an extrinsic.
+pub(crate) struct ExtrinsicParts {
+    pub(crate) signed: Option<SignedParts>,
+    pub(crate) call_offset: usize,
+}
+
+pub(crate) struct SignedParts {
+    pub(crate) address: Range<usize>,
+    pub(crate) signature: Range<usize>,
+    pub(crate) extensions: ExtrinsicSignedExtensions,
+}
+
+/// Find the parts of an extrinsic, given its bytes (without the length prefix), decoding
+/// the signed extensions along the way.
+pub(crate) fn decode_extrinsic_parts(
+    extrinsic_bytes: &[u8],
+    metadata: &Metadata,
+) -> Result<ExtrinsicParts, Error> {
+    let Some((&version, mut cursor)) = extrinsic_bytes.split_first() else {
+        return Err(Error::Other("Cannot decode an empty extrinsic".into()))
+    };
+    if version & 0b1000_0000 == 0 {
+        return Ok(ExtrinsicParts {
+            signed: None,
+            call_offset: 1,
+        })
+    }
+
+    let offset = |cursor: &[u8]| extrinsic_bytes.len() - cursor.len();
+    let address_start = offset(cursor);
+    let address_ty = extrinsic_type_param(metadata, "Address")?;
+    if !skip_empty_variant(&mut cursor, address_ty, metadata) {
+        DecodedValue::decode_with_metadata(&mut cursor, address_ty, metadata)?;
+    }
+    let address = address_start..offset(cursor);
+    let signature_start = offset(cursor);
+    DecodedValue::decode_with_metadata(
+        &mut cursor,
+        extrinsic_type_param(metadata, "Signature")?,
+        metadata,
+    )?;
+    let signature = signature_start..offset(cursor);
+    let extensions = decode_signed_extensions_from(&mut cursor, metadata)?;
+
+    Ok(ExtrinsicParts {
+        signed: Some(SignedParts {
+            address,
+            signature,
+            extensions,
+        }),
+        call_offset: offset(cursor),
+    })
+}
+
+// Runtimes without account indexes use `()` for them, so the `Index(Compact<()>)` variant
+// of their `MultiAddress` is encoded as just its variant index. Values can't be decoded
+// into a compact `()`, so variants like this are skipped over here instead, returning
+// whether the bytes held one.
+fn skip_empty_variant(bytes: &mut &[u8], type_id: u32, metadata: &Metadata) -> bool {
+    let is_empty = |type_id| {
+        matches!(
+            metadata.resolve_type(type_id).map(|ty| ty.type_def()),
+            Some(TypeDef::Tuple(tuple)) if tuple.fields().is_empty()
+        )
+    };
+    let is_compact_empty = |type_id| {
+        match metadata.resolve_type(type_id).map(|ty| ty.type_def()) {
+            Some(TypeDef::Compact(compact)) => is_empty(compact.type_param().id()),
+            _ => false,
+        }
+    };
+    let Some(TypeDef::Variant(variants)) =
+        metadata.resolve_type(type_id).map(|ty| ty.type_def())
+    else {
+        return false
+    };
+    let Some(&index) = bytes.first() else {
+        return false
+    };
+    let empty = variants.variants().iter().any(|v| {
+        v.index() == index
+            && !v.fields().is_empty()
+            && v.fields().iter().all(|f| is_compact_empty(f.ty().id()))
+    });
+    if empty {
+        *bytes = &bytes[1..];
+    }
+    empty
+}
+
+// Decode the address or signature of a signed extrinsic, which should use up all of the
+// bytes that the metadata says it has.
+fn decode_signed_part<Part: Decode>(
+    extrinsic_bytes: &[u8],
+    range: Option<Range<usize>>,
+) -> Result<Option<Part>, Error> {
+    range
+        .map(|range| Part::decode_all(&mut &extrinsic_bytes[range]))
+        .transpose()
+        .map_err(Into::into)
+}
+
+// Decode some call data into the pallet name, call name and call arguments.
+fn decode_call(
+    mut call_bytes: &[u8],
+    metadata: &Metadata,
+) -> Result<(String, String, Composite<TypeId>), Error> {
+    let call_ty = extrinsic_type_param(metadata, "Call")?;
+    let value = DecodedValue::decode_with_metadata(&mut call_bytes, call_ty, metadata)?;
+    if !call_bytes.is_empty() {
+        return Err(Error::Other(format!(
+            "{} bytes were left over after decoding the call data of an extrinsic",
+            call_bytes.len()
+        )))
+    }
+
+    // A `RuntimeCall` is an enum of pallets, each holding an enum of calls.
+    let malformed = || Error::Other("The call data of an extrinsic is not a call".into());
+    let ValueDef::Variant(pallet) = value.value else {
+        return Err(malformed())
+    };
+    let Some(call) = pallet.values.into_values().next() else {
+        return Err(malformed())
+    };
+    let ValueDef::Variant(call) = call.value else {
+        return Err(malformed())
+    };
+    Ok((pallet.name, call.name, call.values))
+}
+
+// The address, signature and call types are parameters of the `UncheckedExtrinsic` type
+// given in the metadata.
+fn extrinsic_type_param(metadata: &Metadata, name: &str) -> Result<u32, Error> {
+    let extrinsic_ty_id = metadata.runtime_metadata().extrinsic.ty.id();
+    metadata
+        .resolve_type(extrinsic_ty_id)
+        .and_then(|ty| ty.type_params().iter().find(|param| param.name() == name))
+        .and_then(|param| param.ty())
+        .map(|ty| ty.id())
+        .ok_or_else(|| {
+            Error::Other(format!(
+                "The extrinsic type in the metadata has no {name} type parameter"
+            ))
+        })
+}
+
+#[cfg(all(test, feature = "substrate-compat"))]
+mod test {
+    use super::*;
+    use crate::{
+        config::PolkadotConfig,
+        test_utils::{
+            metadata,
+            offline_client,
+        },
+        tx::{
+            PairSigner,
+            TxPayload,
+        },
+        utils::{
+            AccountId32,
+            MultiAddress,
+            MultiSignature,
+        },
+    };
+    use codec::{
+        Compact,
+        Encode,
+    };
+    use sp_core::Pair;
+
+    fn remark() -> crate::tx::DynamicTxPayload<'static> {
+        crate::tx::dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([1, 2, 3])],
+        )
+    }
+
+    fn signer() -> PairSigner<PolkadotConfig, sp_core::sr25519::Pair> {
+        PairSigner::new(sp_core::sr25519::Pair::from_seed(&[1; 32]))
+    }
+
+    // The Polkadot metadata, and a signed remark (without its length prefix).
+    fn signed_remark() -> (Metadata, Vec<u8>) {
+        let metadata = metadata();
+        let client = offline_client();
+
+        let extrinsic = client
+            .tx()
+            .create_signed_with_nonce(&remark(), &signer(), 0, Default::default())
+            .unwrap();
+        let mut encoded = extrinsic.encoded();
+        let _len = Compact::<u32>::decode(&mut encoded).unwrap();
+        (metadata, encoded.to_vec())
+    }
+
+    #[test]
+    fn decodes_the_parts_of_an_extrinsic() {
+        let (metadata, encoded) = signed_remark();
+
+        let parts = decode_extrinsic_parts(&encoded, &metadata).unwrap();
+        let call_data = remark().encode_call_data(&metadata).unwrap();
+        assert_eq!(&encoded[parts.call_offset..], &call_data[..]);
+        let signed = parts.signed.unwrap();
+        let expected_address =
+            <PolkadotConfig as Config>::Address::from(signer().account_id().clone())
+                .encode();
+        assert_eq!(&encoded[signed.address.clone()], &expected_address[..]);
+        assert_eq!(signed.address.end, signed.signature.start);
+
+        let (pallet_name, call_name, fields) =
+            decode_call(&call_data, &metadata).unwrap();
+        assert_eq!(pallet_name, "System");
+        assert_eq!(call_name, "remark");
+        assert_eq!(fields.len(), 1);
+
+        // Call data with anything left over is rejected:
+        let mut too_long = call_data;
+        too_long.push(0);
+        assert!(decode_call(&too_long, &metadata).is_err());
+    }
+
+    #[test]
+    fn decodes_every_kind_of_address_and_signature() {
+        let (metadata, encoded) = signed_remark();
+        let parts = decode_extrinsic_parts(&encoded, &metadata).unwrap();
+        let signed = parts.signed.unwrap();
+        let rest = &encoded[signed.signature.end..];
+        let call_data = &encoded[parts.call_offset..];
+
+        // Polkadot has no account indexes, so its address type has `()` for them.
+        type Address = MultiAddress<AccountId32, ()>;
+        let addresses = [
+            Address::Id(AccountId32([1; 32])),
+            Address::Index(()),
+            Address::Raw(vec![2; 5]),
+            Address::Address32([3; 32]),
+            Address::Address20([4; 20]),
+        ];
+        let signatures = [
+            MultiSignature::Ed25519([5; 64]),
+            MultiSignature::Sr25519([6; 64]),
+            MultiSignature::Ecdsa([7; 65]),
+        ];
+        for address in &addresses {
+            for signature in &signatures {
+                // Swap in the address and signature, keeping the rest of the extrinsic:
+                let mut bytes = encoded[..signed.address.start].to_vec();
+                bytes.extend(address.encode());
+                bytes.extend(signature.encode());
+                bytes.extend(rest);
+
+                let parts = decode_extrinsic_parts(&bytes, &metadata).unwrap();
+                assert_eq!(&bytes[parts.call_offset..], call_data);
+                let signed = parts.signed.unwrap();
+                assert_eq!(
+                    decode_signed_part::<Address>(&bytes, Some(signed.address)).unwrap(),
+                    Some(address.clone())
+                );
+                assert_eq!(
+                    decode_signed_part::<MultiSignature>(&bytes, Some(signed.signature))
+                        .unwrap(),
+                    Some(signature.clone())
+                );
+            }
+        }
+
+        // Parts which aren't all used up by the type don't decode:
+        let address = Address::Id(AccountId32([1; 32])).encode();
+        let mut too_long = address.clone();
+        too_long.push(0);
+        assert!(
+            decode_signed_part::<Address>(&too_long, Some(0..too_long.len())).is_err()
+        );
+        assert_eq!(decode_signed_part::<Address>(&address, None).unwrap(), None);
+    }
+}
@@ -6,13 +6,38 @@
 
 mod block_types;
 mod blocks_client;
+mod bounded_stream;
+mod extrinsic_details;
+mod reorgs;
+mod signed_extensions;
 
 pub use block_types::{
     Block,
     Extrinsic,
     ExtrinsicEvents,
+    PrefetchedBlock,
+    SignedExtrinsicDetails,
 };
 pub use blocks_client::{
     subscribe_to_block_headers_filling_in_gaps,
+    subscribe_to_block_headers_filling_in_gaps_with_max_gap,
+    BlockHashes,
+    BlockNumbers,
     BlocksClient,
+    DEFAULT_MAX_FINALIZED_GAP,
+};
+pub use bounded_stream::{
+    BoundedStream,
+    BufferPolicy,
+};
+pub use extrinsic_details::ExtrinsicDetails;
+pub use reorgs::{
+    BestBlockEvent,
+    Reorg,
+};
+pub use signed_extensions::{
+    AdditionalSignedContext,
+    ExtrinsicAdditionalSigned,
+    ExtrinsicSignedExtension,
+    ExtrinsicSignedExtensions,
 };
@@ -0,0 +1,299 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::Block;
+use crate::{
+    config::{
+        Config,
+        Header,
+    },
+    error::Error,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use derivative::Derivative;
+use std::{
+    collections::VecDeque,
+    future::Future,
+};
+
+/// Something that happened to the best chain, handed back from
+/// [`crate::blocks::BlocksClient::subscribe_best_with_reorgs()`].
+pub enum BestBlockEvent<T: Config, C> {
+    /// A block became the new best block. Its parent is the previous best block, or the
+    /// fork point of the [`BestBlockEvent::Reorg`] handed back just before it.
+    NewBest(Block<T, C>),
+    /// The best chain switched to a different fork. The blocks from the new fork are
+    /// handed back as [`BestBlockEvent::NewBest`] events immediately after this one.
+    Reorg(Reorg<T>),
+}
+
+/// The details of a reorg of the best chain.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""), PartialEq(bound = ""))]
+pub struct Reorg<T: Config> {
+    /// The number and hash of the last block that the old and new forks have in common.
+    /// Anything seen after this block should be rolled back.
+    ///
+    /// This is `None` if the reorg was deeper than the number of blocks being tracked,
+    /// in which case the fork point couldn't be found, and everything seen so far should
+    /// be treated as suspect.
+    pub fork_point: Option<(u64, T::Hash)>,
+    /// The hashes of the blocks that are no longer on the best chain, newest first.
+    pub retracted: Vec<T::Hash>,
+}
+
+// Something the tracker found, before the headers are turned into blocks.
+pub(crate) enum TrackedEvent<T: Config> {
+    NewBest(T::Header),
+    Reorg(Reorg<T>),
+}
+
+/// Keeps track of the most recent blocks on the best chain, so that we can tell which
+/// of them a new best block builds on.
+pub(crate) struct ReorgTracker<T: Config> {
+    // The most recent best blocks, oldest first. Each is the parent of the next.
+    recent: VecDeque<(u64, T::Hash)>,
+    max_depth: usize,
+}
+
+impl<T: Config> ReorgTracker<T> {
+    pub(crate) fn new(max_depth: usize) -> Self {
+        ReorgTracker {
+            recent: VecDeque::new(),
+            max_depth: max_depth.max(1),
+        }
+    }
+
+    /// Work out what a new best block header means for the best chain. Any ancestors of
+    /// the header that we haven't seen are fetched using `fetch_header`, so that we can
+    /// find where it joins the chain we know about.
+    pub(crate) async fn handle<F, Fut>(
+        &mut self,
+        header: T::Header,
+        fetch_header: F,
+    ) -> Result<Vec<TrackedEvent<T>>, Error>
+    where
+        F: Fn(T::Hash) -> Fut,
+        Fut: Future<Output = Result<Option<T::Header>, Error>>,
+    {
+        if self.recent.is_empty() {
+            self.push(&header);
+            return Ok(vec![TrackedEvent::NewBest(header)])
+        }
+
+        // Walk back from the new header until we reach a block we know about, or until
+        // we're further back than any block we know about, in which case the fork point
+        // is older than the blocks being tracked. `enacted` ends up holding the new
+        // blocks, newest first.
+        let oldest_known = self.recent.front().map(|(n, _)| *n).unwrap_or(0);
+        let mut enacted = Vec::new();
+        let mut cursor = header;
+        let fork = loop {
+            let hash = cursor.hash();
+            if let Some(pos) = self.recent.iter().rposition(|(_, h)| *h == hash) {
+                break Some((pos, cursor))
+            }
+            let number: u64 = cursor.number().into();
+            let parent_hash = parent_hash::<T>(&cursor)?;
+            enacted.push(cursor);
+            if number <= oldest_known {
+                break None
+            }
+            cursor = fetch_header(parent_hash).await?.ok_or_else(|| {
+                Error::Other(format!(
+                    "Cannot find the header of block {parent_hash:?}, which is an ancestor of the best block"
+                ))
+            })?;
+        };
+
+        let mut events = Vec::new();
+        let fork_pos = fork.as_ref().map(|(pos, _)| *pos);
+        let retracted_from = fork_pos.map(|pos| pos + 1).unwrap_or(0);
+        if retracted_from < self.recent.len() {
+            let fork_point = fork_pos.map(|pos| self.recent[pos]);
+            let retracted = self
+                .recent
+                .drain(retracted_from..)
+                .rev()
+                .map(|(_, hash)| hash)
+                .collect();
+            events.push(TrackedEvent::Reorg(Reorg {
+                fork_point,
+                retracted,
+            }));
+            // An older block that we've already seen may have become the best block
+            // again, in which case there's nothing new, but it's still the new best.
+            if enacted.is_empty() {
+                if let Some((_, header)) = fork {
+                    events.push(TrackedEvent::NewBest(header));
+                }
+            }
+        }
+        for header in enacted.into_iter().rev() {
+            self.push(&header);
+            events.push(TrackedEvent::NewBest(header));
+        }
+        Ok(events)
+    }
+
+    fn push(&mut self, header: &T::Header) {
+        self.recent
+            .push_back((header.number().into(), header.hash()));
+        while self.recent.len() > self.max_depth {
+            self.recent.pop_front();
+        }
+    }
+}
+
+// The `Header` trait doesn't expose the parent hash, but Substrate based chains all encode
+// it first.
+fn parent_hash<T: Config>(header: &T::Header) -> Result<T::Hash, Error> {
+    Ok(T::Hash::decode(&mut &*header.encode())?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::substrate::{
+            BlakeTwo256,
+            SubstrateHeader,
+        },
+        SubstrateConfig,
+    };
+    use primitive_types::H256;
+    use std::collections::HashMap;
+
+    type TestHeader = SubstrateHeader<u32, BlakeTwo256>;
+
+    fn genesis() -> TestHeader {
+        TestHeader {
+            parent_hash: Default::default(),
+            number: 0,
+            state_root: Default::default(),
+            extrinsics_root: Default::default(),
+            digest: Default::default(),
+        }
+    }
+
+    // Build a chain of headers on top of the given parent; `fork` makes the headers on
+    // different forks differ.
+    fn chain(parent: &TestHeader, len: u32, fork: u8) -> Vec<TestHeader> {
+        let mut headers: Vec<TestHeader> = Vec::new();
+        for _ in 0..len {
+            let parent = headers.last().unwrap_or(parent);
+            headers.push(TestHeader {
+                parent_hash: parent.hash(),
+                number: parent.number + 1,
+                state_root: H256::repeat_byte(fork),
+                extrinsics_root: Default::default(),
+                digest: Default::default(),
+            });
+        }
+        headers
+    }
+
+    struct Harness {
+        tracker: ReorgTracker<SubstrateConfig>,
+        known: HashMap<H256, TestHeader>,
+    }
+
+    impl Harness {
+        fn new(max_depth: usize, headers: &[&[TestHeader]]) -> Self {
+            let known = headers
+                .iter()
+                .flat_map(|h| h.iter())
+                .map(|h| (h.hash(), h.clone()))
+                .collect();
+            Harness {
+                tracker: ReorgTracker::new(max_depth),
+                known,
+            }
+        }
+
+        // Hand the tracker a header, describing the result as a list of
+        // `(retracted, fork point number)` reorgs and new best block numbers.
+        async fn handle(&mut self, header: &TestHeader) -> Vec<String> {
+            let known = &self.known;
+            let events = self
+                .tracker
+                .handle(header.clone(), |hash| {
+                    let header = known.get(&hash).cloned();
+                    async move { Ok(header) }
+                })
+                .await
+                .unwrap();
+            events
+                .into_iter()
+                .map(|ev| {
+                    match ev {
+                        TrackedEvent::NewBest(h) => format!("best {}", h.number),
+                        TrackedEvent::Reorg(r) => {
+                            format!(
+                                "reorg {} from {:?}",
+                                r.retracted.len(),
+                                r.fork_point.map(|(n, _)| n)
+                            )
+                        }
+                    }
+                })
+                .collect()
+        }
+    }
+
+    #[tokio::test]
+    async fn detects_reorgs_and_their_fork_point() {
+        let genesis = genesis();
+        let fork_a = chain(&genesis, 3, 1);
+        let fork_b = chain(&fork_a[0], 3, 2);
+        let mut h = Harness::new(10, &[std::slice::from_ref(&genesis), &fork_a, &fork_b]);
+
+        assert_eq!(h.handle(&genesis).await, vec!["best 0"]);
+        for header in &fork_a {
+            assert_eq!(h.handle(header).await.len(), 1);
+        }
+        // Blocks 2 and 3 from fork A are rolled back, and fork B is handed back from the
+        // fork point at block 1:
+        assert_eq!(
+            h.handle(&fork_b[1]).await,
+            vec!["reorg 2 from Some(1)", "best 2", "best 3"]
+        );
+        assert_eq!(h.handle(&fork_b[2]).await, vec!["best 4"]);
+        // Seeing an old block again makes it the best block again:
+        assert_eq!(
+            h.handle(&fork_b[0]).await,
+            vec!["reorg 2 from Some(2)", "best 2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn fills_in_skipped_blocks_and_gives_up_on_deep_reorgs() {
+        let genesis = genesis();
+        let fork_a = chain(&genesis, 5, 1);
+        let fork_b = chain(&genesis, 10, 2);
+        let mut h = Harness::new(3, &[std::slice::from_ref(&genesis), &fork_a, &fork_b]);
+
+        assert_eq!(h.handle(&genesis).await, vec!["best 0"]);
+        // Blocks that the subscription skipped over are handed back in order:
+        assert_eq!(
+            h.handle(&fork_a[2]).await,
+            vec!["best 1", "best 2", "best 3"]
+        );
+        assert_eq!(h.handle(&fork_a[4]).await, vec!["best 4", "best 5"]);
+        // Skipping more blocks than are being tracked isn't a reorg:
+        let fork_a = chain(&fork_a[4], 5, 1);
+        h.known.extend(fork_a.iter().map(|h| (h.hash(), h.clone())));
+        assert_eq!(
+            h.handle(&fork_a[4]).await,
+            vec!["best 6", "best 7", "best 8", "best 9", "best 10"]
+        );
+        // The fork point is further back than the 3 blocks being tracked:
+        let events = h.handle(&fork_b[9]).await;
+        assert_eq!(events[0], "reorg 3 from None");
+        assert_eq!(events.last().unwrap(), "best 10");
+    }
+}
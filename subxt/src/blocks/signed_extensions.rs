@@ -0,0 +1,437 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::extrinsic_details::decode_extrinsic_parts;
+use crate::{
+    config::extrinsic_params::Era,
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        Metadata,
+    },
+    rpc::types::RuntimeVersion,
+    storage::utils::value_as_u128,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use scale_value::{
+    Composite,
+    ValueDef,
+};
+
+/// The signed extensions of an extrinsic in a block, decoded according to the metadata.
+/// Obtain these using [`super::Extrinsic::signed_extensions()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtrinsicSignedExtensions {
+    extensions: Vec<ExtrinsicSignedExtension>,
+}
+
+/// The "signed extra" value of one signed extension of an extrinsic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtrinsicSignedExtension {
+    identifier: String,
+    bytes: Vec<u8>,
+    value: DecodedValue,
+}
+
+impl ExtrinsicSignedExtension {
+    /// The name that the runtime gives the signed extension, for example `CheckNonce`.
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// The SCALE encoded bytes of the value given to the signed extension.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The value given to the signed extension, decoded according to the type that the
+    /// metadata gives it.
+    pub fn value(&self) -> &DecodedValue {
+        &self.value
+    }
+}
+
+impl ExtrinsicSignedExtensions {
+    /// Iterate over each of the signed extensions, in the order that the metadata lists them.
+    pub fn iter(&self) -> impl Iterator<Item = &ExtrinsicSignedExtension> {
+        self.extensions.iter()
+    }
+
+    /// Find the signed extension with the given identifier.
+    pub fn find(&self, identifier: &str) -> Option<&ExtrinsicSignedExtension> {
+        self.extensions
+            .iter()
+            .find(|ext| ext.identifier == identifier)
+    }
+
+    /// The nonce of the extrinsic, from the `CheckNonce` signed extension.
+    pub fn nonce(&self) -> Option<u64> {
+        let nonce = value_as_u128(&self.find("CheckNonce")?.value)?;
+        u64::try_from(nonce).ok()
+    }
+
+    /// The tip that was paid for the extrinsic, from the `ChargeTransactionPayment` or
+    /// `ChargeAssetTxPayment` signed extension.
+    pub fn tip(&self) -> Option<u128> {
+        if let Some(ext) = self.find("ChargeTransactionPayment") {
+            return value_as_u128(&ext.value)
+        }
+        let ext = self.find("ChargeAssetTxPayment")?;
+        named_field(&ext.value, "tip").and_then(value_as_u128)
+    }
+
+    /// The ID of the asset that fees were paid in, from the `ChargeAssetTxPayment` signed
+    /// extension. This is `None` if the extension isn't used, or if fees were paid in the
+    /// native token. The type of asset IDs differs between chains, so this is handed back
+    /// as a decoded value.
+    pub fn asset_id(&self) -> Option<&DecodedValue> {
+        let ext = self.find("ChargeAssetTxPayment")?;
+        let asset_id = named_field(&ext.value, "asset_id")?;
+        match &asset_id.value {
+            ValueDef::Variant(v) if v.name == "Some" => v.values.values().next(),
+            _ => None,
+        }
+    }
+
+    /// The era of the extrinsic, from the `CheckMortality` (or, in older runtimes,
+    /// `CheckEra`) signed extension. This says whether the extrinsic is immortal, or the
+    /// period and phase of the blocks that it's valid for if it's mortal.
+    pub fn era(&self) -> Option<Era> {
+        let ext = self
+            .find("CheckMortality")
+            .or_else(|| self.find("CheckEra"))?;
+        Era::decode(&mut ext.bytes()).ok()
+    }
+
+    /// The name of the mode given to the `CheckMetadataHash` signed extension (`Enabled`
+    /// or `Disabled`), if the runtime uses it.
+    pub fn metadata_hash_mode(&self) -> Option<&str> {
+        let ext = self.find("CheckMetadataHash")?;
+        let mode = named_field(&ext.value, "mode").unwrap_or(&ext.value);
+        match &mode.value {
+            ValueDef::Variant(v) => Some(&v.name),
+            _ => None,
+        }
+    }
+}
+
+/// The "additional signed" value of one signed extension, which isn't sent along with an
+/// extrinsic but is signed over. See [`ExtrinsicSignedExtensions::additional_signed()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtrinsicAdditionalSigned {
+    identifier: String,
+    bytes: Vec<u8>,
+    value: DecodedValue,
+}
+
+impl ExtrinsicAdditionalSigned {
+    /// The name that the runtime gives the signed extension, for example `CheckGenesis`.
+    pub fn identifier(&self) -> &str {
+        &self.identifier
+    }
+
+    /// The SCALE encoded bytes that the signed extension adds to the signer payload.
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// The value that the signed extension adds to the signer payload, decoded according
+    /// to the type that the metadata gives it.
+    pub fn value(&self) -> &DecodedValue {
+        &self.value
+    }
+}
+
+/// The things that the runtime knows about when checking an extrinsic, and which the
+/// "additional signed" values of the standard signed extensions are made from. Given to
+/// [`ExtrinsicSignedExtensions::additional_signed()`].
+#[derive(Clone, Debug)]
+pub struct AdditionalSignedContext<Hash> {
+    spec_version: u32,
+    transaction_version: u32,
+    genesis_hash: Hash,
+    mortality_checkpoint: Option<Hash>,
+    metadata_hash: Option<[u8; 32]>,
+}
+
+impl<Hash> AdditionalSignedContext<Hash> {
+    /// The runtime version and genesis hash of the chain that the extrinsic was
+    /// submitted to.
+    pub fn new(runtime_version: &RuntimeVersion, genesis_hash: Hash) -> Self {
+        AdditionalSignedContext {
+            spec_version: runtime_version.spec_version,
+            transaction_version: runtime_version.transaction_version,
+            genesis_hash,
+            mortality_checkpoint: None,
+            metadata_hash: None,
+        }
+    }
+
+    /// The hash of the block that a mortal extrinsic's era begins at. This is needed
+    /// to reconstruct the `CheckMortality` value of mortal extrinsics; immortal ones use
+    /// the genesis hash.
+    pub fn mortality_checkpoint(mut self, hash: Hash) -> Self {
+        self.mortality_checkpoint = Some(hash);
+        self
+    }
+
+    /// The metadata hash that the runtime expects. This is needed to reconstruct the
+    /// `CheckMetadataHash` value of extrinsics which enable that check.
+    pub fn metadata_hash(mut self, hash: [u8; 32]) -> Self {
+        self.metadata_hash = Some(hash);
+        self
+    }
+}
+
+impl ExtrinsicSignedExtensions {
+    /// Reconstruct the "additional signed" value of each signed extension, in the order
+    /// that the metadata lists them, from the "signed extra" values in the extrinsic and
+    /// the context given. This is handy for checking that an extrinsic was signed for the
+    /// expected chain, runtime and metadata; concatenating the bytes of each value gives
+    /// the "additional" part of the payload that was signed.
+    ///
+    /// An error is returned if some extension's value can't be worked out, for instance
+    /// because it's a custom extension which adds something to the signer payload, or
+    /// because the context is missing something that the extrinsic needs.
+    pub fn additional_signed<Hash: Encode>(
+        &self,
+        context: &AdditionalSignedContext<Hash>,
+        metadata: &Metadata,
+    ) -> Result<Vec<ExtrinsicAdditionalSigned>, Error> {
+        let signed_extensions = &metadata.runtime_metadata().extrinsic.signed_extensions;
+        let mut additional = Vec::with_capacity(self.extensions.len());
+        for ext in &self.extensions {
+            let Some(ty) = signed_extensions
+                .iter()
+                .find(|meta| meta.identifier == ext.identifier)
+                .map(|meta| meta.additional_signed.id())
+            else {
+                return Err(Error::Other(format!(
+                    "The signed extension {} is not in the metadata",
+                    ext.identifier
+                )))
+            };
+            let bytes = self.additional_signed_bytes(ext, context)?;
+
+            // Decoding the bytes checks that they line up with what the runtime expects.
+            let mut cursor = &bytes[..];
+            let value = DecodedValue::decode_with_metadata(&mut cursor, ty, metadata)
+                .map_err(|_| reconstruct_error(&ext.identifier))?;
+            if !cursor.is_empty() {
+                return Err(reconstruct_error(&ext.identifier))
+            }
+            additional.push(ExtrinsicAdditionalSigned {
+                identifier: ext.identifier.clone(),
+                bytes,
+                value,
+            });
+        }
+        Ok(additional)
+    }
+
+    fn additional_signed_bytes<Hash: Encode>(
+        &self,
+        ext: &ExtrinsicSignedExtension,
+        context: &AdditionalSignedContext<Hash>,
+    ) -> Result<Vec<u8>, Error> {
+        let bytes = match &*ext.identifier {
+            "CheckSpecVersion" => context.spec_version.encode(),
+            "CheckTxVersion" => context.transaction_version.encode(),
+            "CheckGenesis" => context.genesis_hash.encode(),
+            "CheckMortality" | "CheckEra" => {
+                match Era::decode(&mut ext.bytes())? {
+                    Era::Immortal => context.genesis_hash.encode(),
+                    Era::Mortal(..) => {
+                        let Some(checkpoint) = &context.mortality_checkpoint else {
+                            return Err(Error::Other(
+                                "The extrinsic is mortal, so the hash of the block that its era begins at is needed".into()
+                            ))
+                        };
+                        checkpoint.encode()
+                    }
+                }
+            }
+            "CheckMetadataHash" => {
+                match self.metadata_hash_mode() {
+                    Some("Enabled") => {
+                        let Some(hash) = context.metadata_hash else {
+                            return Err(Error::Other(
+                                "The extrinsic enables the metadata hash check, so the metadata hash is needed".into()
+                            ))
+                        };
+                        Some(hash).encode()
+                    }
+                    _ => None::<[u8; 32]>.encode(),
+                }
+            }
+            // The other standard extensions add nothing, which is checked when the
+            // (empty) bytes are decoded.
+            _ => Vec::new(),
+        };
+        Ok(bytes)
+    }
+}
+
+fn reconstruct_error(identifier: &str) -> Error {
+    Error::Other(format!(
+        "Cannot reconstruct the additional signed value of the {identifier} signed extension"
+    ))
+}
+
+fn named_field<'a>(value: &'a DecodedValue, name: &str) -> Option<&'a DecodedValue> {
+    match &value.value {
+        ValueDef::Composite(Composite::Named(fields)) => {
+            fields.iter().find(|(n, _)| n == name).map(|(_, v)| v)
+        }
+        _ => None,
+    }
+}
+
+/// Decode the signed extensions of an extrinsic, given its bytes (without the length
+/// prefix). Returns `None` if the extrinsic isn't signed.
+pub(crate) fn decode_extrinsic_signed_extensions(
+    extrinsic_bytes: &[u8],
+    metadata: &Metadata,
+) -> Result<Option<ExtrinsicSignedExtensions>, Error> {
+    let parts = decode_extrinsic_parts(extrinsic_bytes, metadata)?;
+    Ok(parts.signed.map(|signed| signed.extensions))
+}
+
+// Decode the signed extensions which appear next in the bytes given.
+pub(crate) fn decode_signed_extensions_from(
+    cursor: &mut &[u8],
+    metadata: &Metadata,
+) -> Result<ExtrinsicSignedExtensions, Error> {
+    let signed_extensions = &metadata.runtime_metadata().extrinsic.signed_extensions;
+    let mut extensions = Vec::with_capacity(signed_extensions.len());
+    for ext in signed_extensions {
+        let start = *cursor;
+        let value = DecodedValue::decode_with_metadata(cursor, ext.ty.id(), metadata)?;
+        let len = start.len() - cursor.len();
+        extensions.push(ExtrinsicSignedExtension {
+            identifier: ext.identifier.clone(),
+            bytes: start[..len].to_vec(),
+            value,
+        });
+    }
+    Ok(ExtrinsicSignedExtensions { extensions })
+}
+
+#[cfg(all(test, feature = "substrate-compat"))]
+mod test {
+    use super::*;
+    use crate::{
+        config::{
+            polkadot::PolkadotExtrinsicParamsBuilder,
+            PolkadotConfig,
+        },
+        test_utils::{
+            metadata,
+            offline_client,
+            runtime_version,
+        },
+        tx::PairSigner,
+        OfflineClient,
+    };
+    use codec::Compact;
+    use primitive_types::H256;
+    use sp_core::Pair;
+
+    #[test]
+    fn decodes_the_signed_extensions_of_an_extrinsic() {
+        let metadata = metadata();
+        let client = offline_client();
+
+        let remark = crate::tx::dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([0; 8])],
+        );
+        let signer = PairSigner::new(sp_core::sr25519::Pair::from_seed(&[1; 32]));
+        let params = PolkadotExtrinsicParamsBuilder::new().tip(1234);
+        let extrinsic = client
+            .tx()
+            .create_signed_with_nonce(&remark, &signer, 7, params)
+            .unwrap();
+
+        // The extrinsic bytes in a block don't have the length prefix:
+        let mut encoded = extrinsic.encoded();
+        let _len = Compact::<u32>::decode(&mut encoded).unwrap();
+
+        let extensions = decode_extrinsic_signed_extensions(encoded, &metadata)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extensions.nonce(), Some(7));
+        assert_eq!(extensions.tip(), Some(1234));
+        assert_eq!(extensions.asset_id(), None);
+        assert_eq!(extensions.era(), Some(Era::Immortal));
+        assert!(extensions
+            .find("CheckSpecVersion")
+            .unwrap()
+            .bytes()
+            .is_empty());
+
+        // Unsigned extrinsics have no signed extensions:
+        let unsigned = [4u8, 0, 0];
+        assert_eq!(
+            decode_extrinsic_signed_extensions(&unsigned, &metadata).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn reconstructs_the_additional_signed_values() {
+        let metadata = metadata();
+        let runtime_version = runtime_version();
+        let genesis_hash = H256::repeat_byte(1);
+        let client = OfflineClient::<PolkadotConfig>::new(
+            genesis_hash,
+            runtime_version.clone(),
+            metadata.clone(),
+        );
+        let remark = crate::tx::dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([0; 8])],
+        );
+        let signer = PairSigner::new(sp_core::sr25519::Pair::from_seed(&[1; 32]));
+        let checkpoint = H256::repeat_byte(2);
+        let params =
+            PolkadotExtrinsicParamsBuilder::new().era(Era::mortal(64, 10), checkpoint);
+        let extrinsic = client
+            .tx()
+            .create_signed_with_nonce(&remark, &signer, 7, params)
+            .unwrap();
+        let mut encoded = extrinsic.encoded();
+        let _len = Compact::<u32>::decode(&mut encoded).unwrap();
+        let extensions = decode_extrinsic_signed_extensions(encoded, &metadata)
+            .unwrap()
+            .unwrap();
+        assert_eq!(extensions.era(), Some(Era::mortal(64, 10)));
+
+        // The era is mortal, so we need to know where it begins:
+        let context = AdditionalSignedContext::new(&runtime_version, genesis_hash);
+        assert!(extensions.additional_signed(&context, &metadata).is_err());
+
+        let context = context.mortality_checkpoint(checkpoint);
+        let additional = extensions.additional_signed(&context, &metadata).unwrap();
+        let additional_bytes: Vec<u8> = additional
+            .iter()
+            .flat_map(|ext| ext.bytes().to_vec())
+            .collect();
+        assert_eq!(
+            additional_bytes,
+            (1u32, 2u32, genesis_hash, checkpoint).encode()
+        );
+        let genesis = additional
+            .iter()
+            .find(|ext| ext.identifier() == "CheckGenesis")
+            .unwrap();
+        assert_eq!(genesis.bytes(), genesis_hash.as_bytes());
+    }
+}
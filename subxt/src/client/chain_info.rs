@@ -0,0 +1,95 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::rpc::types::SystemProperties;
+
+/// Details about the chain and the node that a client is connected to, as returned
+/// from [`super::OnlineClient::chain_info()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainInfo {
+    /// The name of the chain, from `system_chain`, for example "Polkadot".
+    pub chain: String,
+    /// The name of the node implementation, from `system_name`, for example "Parity Polkadot".
+    pub node_name: String,
+    /// The version of the node implementation, from `system_version`.
+    pub node_version: String,
+    /// The properties given in the chain spec, from `system_properties`.
+    pub properties: SystemProperties,
+}
+
+impl ChainInfo {
+    /// The SS58 prefix that addresses on this chain are expected to use, if the chain spec
+    /// gives one.
+    pub fn ss58_prefix(&self) -> Option<u16> {
+        self.properties
+            .get("ss58Format")
+            .and_then(|v| v.as_u64())
+            .and_then(|n| u16::try_from(n).ok())
+    }
+
+    /// The symbols of the chain's tokens, if the chain spec gives them. Chains with more
+    /// than one token list them all; the first is the native token.
+    pub fn token_symbols(&self) -> Vec<String> {
+        one_or_many(self.properties.get("tokenSymbol"), |v| {
+            v.as_str().map(ToOwned::to_owned)
+        })
+    }
+
+    /// The number of decimal places of the chain's tokens, given in the same order as
+    /// [`ChainInfo::token_symbols()`].
+    pub fn token_decimals(&self) -> Vec<u8> {
+        one_or_many(self.properties.get("tokenDecimals"), |v| {
+            v.as_u64().and_then(|n| u8::try_from(n).ok())
+        })
+    }
+}
+
+// Chain spec properties that describe tokens are either a single value or an array of them.
+fn one_or_many<T>(
+    value: Option<&serde_json::Value>,
+    f: impl Fn(&serde_json::Value) -> Option<T>,
+) -> Vec<T> {
+    match value {
+        Some(serde_json::Value::Array(values)) => values.iter().filter_map(f).collect(),
+        Some(value) => f(value).into_iter().collect(),
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn chain_info(properties: serde_json::Value) -> ChainInfo {
+        ChainInfo {
+            chain: "Development".into(),
+            node_name: "Substrate Node".into(),
+            node_version: "1.0.0".into(),
+            properties: serde_json::from_value(properties).unwrap(),
+        }
+    }
+
+    #[test]
+    fn reads_chain_spec_properties() {
+        let info = chain_info(serde_json::json!({
+            "ss58Format": 0,
+            "tokenDecimals": 10,
+            "tokenSymbol": "DOT"
+        }));
+        assert_eq!(info.ss58_prefix(), Some(0));
+        assert_eq!(info.token_symbols(), vec!["DOT".to_string()]);
+        assert_eq!(info.token_decimals(), vec![10]);
+
+        let info = chain_info(serde_json::json!({
+            "tokenDecimals": [12, 12],
+            "tokenSymbol": ["KAR", "KUSD"]
+        }));
+        assert_eq!(info.ss58_prefix(), None);
+        assert_eq!(
+            info.token_symbols(),
+            vec!["KAR".to_string(), "KUSD".to_string()]
+        );
+        assert_eq!(info.token_decimals(), vec![12, 12]);
+    }
+}
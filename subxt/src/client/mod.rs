@@ -8,9 +8,12 @@
 //! require network access. The [`OnlineClient`] requires network
 //! access.
 
+mod chain_info;
 mod offline_client;
 mod online_client;
+mod pinned_client;
 
+pub use chain_info::ChainInfo;
 pub use offline_client::{
     OfflineClient,
     OfflineClientT,
@@ -22,6 +25,13 @@ pub use online_client::{
     RuntimeUpdaterStream,
     Update,
     UpgradeError,
+    DEFAULT_ARCHIVE_PROBE_DEPTH,
+};
+pub use pinned_client::PinnedClient;
+
+pub(crate) use online_client::{
+    metadata_at_block,
+    runtime_at_block,
 };
 
 #[cfg(any(
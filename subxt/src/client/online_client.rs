@@ -3,14 +3,24 @@
 // see LICENSE for license details.
 
 use super::{
+    ChainInfo,
     OfflineClient,
     OfflineClientT,
+    PinnedClient,
 };
 use crate::{
     blocks::BlocksClient,
+    config::{
+        extrinsic_params::MetadataHashMode,
+        Header,
+    },
     constants::ConstantsClient,
-    error::Error,
+    error::{
+        Error,
+        RpcError,
+    },
     events::EventsClient,
+    metadata::MetadataHashInfo,
     rpc::{
         types::{
             RuntimeVersion,
@@ -22,6 +32,7 @@ use crate::{
     runtime_api::RuntimeApiClient,
     storage::StorageClient,
     tx::TxClient,
+    utils::AccountId32,
     Config,
     Metadata,
 };
@@ -33,13 +44,26 @@ use derivative::Derivative;
 use frame_metadata::RuntimeMetadataPrefixed;
 use futures::future;
 use parking_lot::RwLock;
-use std::sync::Arc;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+};
 
 /// A trait representing a client that can perform
 /// online actions.
 pub trait OnlineClientT<T: Config>: OfflineClientT<T> {
     /// Return an RPC client that can be used to communicate with a node.
     fn rpc(&self) -> &Rpc<T>;
+
+    /// Return previously seen [`Metadata`] for the runtime with the given
+    /// spec version, if the client keeps such a cache.
+    fn cached_metadata(&self, _spec_version: u32) -> Option<Metadata> {
+        None
+    }
+
+    /// Hand some [`Metadata`] for the runtime with the given spec version to
+    /// the client, so that it can be reused by future calls to [`OnlineClientT::cached_metadata()`].
+    fn cache_metadata(&self, _spec_version: u32, _metadata: Metadata) {}
 }
 
 /// A client that can be used to perform API calls (that is, either those
@@ -57,6 +81,14 @@ struct Inner<T: Config> {
     genesis_hash: T::Hash,
     runtime_version: RuntimeVersion,
     metadata: Metadata,
+    /// Metadata for runtimes other than the current one, keyed by spec version.
+    versioned_metadata: HashMap<u32, Metadata>,
+    /// Details about the chain, fetched the first time that they're asked for.
+    chain_info: Option<ChainInfo>,
+    /// The probe depth used to check whether the node is an archive node, and the outcome.
+    archive_probe: Option<(u32, bool)>,
+    /// The hashes that the `CheckMetadataHash` extension checks, keyed by spec version.
+    metadata_hashes: HashMap<u32, [u8; 32]>,
 }
 
 impl<T: Config> std::fmt::Debug for OnlineClient<T> {
@@ -94,10 +126,42 @@ impl<T: Config> OnlineClient<T> {
     }
 
     /// Construct a new [`OnlineClient`], providing a URL to connect to.
+    ///
+    /// `http://` and `https://` URLs are connected to using an
+    /// [`HttpClient`](crate::rpc::http_client::HttpClient), which needs the `http` feature
+    /// and can't subscribe to anything. Any other URL is connected to using the
+    /// [`default_rpc_client()`].
     pub async fn from_url(url: impl AsRef<str>) -> Result<OnlineClient<T>, Error> {
+        let url = url.as_ref();
+        if is_http_url(url) {
+            return OnlineClient::from_http_url(url).await
+        }
         let client = default_rpc_client(url).await?;
         OnlineClient::from_rpc_client(Arc::new(client)).await
     }
+
+    #[cfg(feature = "http")]
+    async fn from_http_url(url: &str) -> Result<OnlineClient<T>, Error> {
+        let client = crate::rpc::http_client::HttpClientBuilder::new().build(url)?;
+        OnlineClient::from_rpc_client(Arc::new(client)).await
+    }
+
+    #[cfg(not(feature = "http"))]
+    async fn from_http_url(url: &str) -> Result<OnlineClient<T>, Error> {
+        Err(Error::Other(format!(
+            "Connecting to {url} over HTTP needs the `http` feature of subxt to be enabled"
+        )))
+    }
+}
+
+// Whether the URL is one to connect to over HTTP rather than a WebSocket.
+#[cfg(any(
+    feature = "jsonrpsee-ws",
+    all(feature = "jsonrpsee-web", target_arch = "wasm32")
+))]
+fn is_http_url(url: &str) -> bool {
+    let url = url.to_ascii_lowercase();
+    url.starts_with("http://") || url.starts_with("https://")
 }
 
 impl<T: Config> OnlineClient<T> {
@@ -145,6 +209,10 @@ impl<T: Config> OnlineClient<T> {
                 genesis_hash,
                 runtime_version,
                 metadata,
+                versioned_metadata: HashMap::new(),
+                chain_info: None,
+                archive_probe: None,
+                metadata_hashes: HashMap::new(),
             })),
             rpc: Rpc::new(rpc_client),
         })
@@ -210,6 +278,28 @@ impl<T: Config> OnlineClient<T> {
         inner.metadata.clone()
     }
 
+    /// Fetch the [`Metadata`] that was in use at the given block, which is needed to decode
+    /// blocks produced by older runtimes. Metadata is cached by the runtime spec version,
+    /// so this only fetches metadata once per runtime, and not at all for the current one.
+    pub async fn metadata_at(&self, block_hash: T::Hash) -> Result<Metadata, Error> {
+        metadata_at_block(self, block_hash).await
+    }
+
+    /// Pin a view of this client to the given block, and to the runtime version and metadata
+    /// in use at that block. Storage, events, constants and runtime API calls made through
+    /// the [`PinnedClient`] all look at that block, for a consistent snapshot of the chain
+    /// however far it moves on. Transactions are still submitted to the live chain.
+    pub async fn pinned_at(&self, block_hash: T::Hash) -> Result<PinnedClient<T>, Error> {
+        PinnedClient::new(self.clone(), block_hash).await
+    }
+
+    /// Pin a view of this client to the current finalized block. See
+    /// [`OnlineClient::pinned_at()`].
+    pub async fn pinned_at_finalized(&self) -> Result<PinnedClient<T>, Error> {
+        let block_hash = self.rpc().finalized_head().await?;
+        self.pinned_at(block_hash).await
+    }
+
     /// Change the [`Metadata`] used in this client.
     ///
     /// # Warning
@@ -255,6 +345,171 @@ impl<T: Config> OnlineClient<T> {
         inner.runtime_version = runtime_version;
     }
 
+    /// Return details about the chain and the node that this client is connected to: the
+    /// chain name, the node's name and version, and the chain spec properties (which include
+    /// things like the SS58 prefix and token details).
+    ///
+    /// These are fetched from the node the first time that this is called, and cached after
+    /// that. The node version can change if the node is restarted, so use
+    /// [`Rpc::system_version()`] if an up-to-date version is needed.
+    pub async fn chain_info(&self) -> Result<ChainInfo, Error> {
+        let cached = self.inner.read().chain_info.clone();
+        if let Some(info) = cached {
+            return Ok(info)
+        }
+
+        let (chain, node_name, node_version, properties) = future::try_join4(
+            self.rpc.system_chain(),
+            self.rpc.system_name(),
+            self.rpc.system_version(),
+            self.rpc.system_properties(),
+        )
+        .await?;
+        let info = ChainInfo {
+            chain,
+            node_name,
+            node_version,
+            properties,
+        };
+        self.inner.write().chain_info = Some(info.clone());
+        Ok(info)
+    }
+
+    /// Check whether the node that this client is connected to keeps the state of old
+    /// blocks around (ie it's an archive node), which features like querying storage over
+    /// a range of blocks rely on.
+    ///
+    /// This is a heuristic: it asks for some storage at the finalized block `probe_depth`
+    /// blocks ago (or the genesis block, if the chain is shorter than that), and assumes
+    /// that the node is an archive node if it answers. Nodes which prune state keep 256
+    /// blocks of it by default, so a depth somewhat larger than that, such as
+    /// [`DEFAULT_ARCHIVE_PROBE_DEPTH`], is a good choice. The outcome is cached, and only
+    /// checked again if a different depth is asked for. Errors other than the node saying
+    /// that it has discarded the state are handed back, and nothing is cached for them.
+    pub async fn is_archive_node(&self, probe_depth: u32) -> Result<bool, Error> {
+        let cached = self.inner.read().archive_probe;
+        if let Some((depth, is_archive)) = cached {
+            if depth == probe_depth {
+                return Ok(is_archive)
+            }
+        }
+
+        let finalized = self.rpc.finalized_head().await?;
+        let Some(header) = self.rpc.header(Some(finalized)).await? else {
+            return Err(Error::Other(format!(
+                "The node doesn't have the header of its finalized block {finalized:?}"
+            )))
+        };
+        let probe_number = header.number().into().saturating_sub(probe_depth as u64);
+        let Some(probe_hash) = self.rpc.block_hash(Some(probe_number.into())).await?
+        else {
+            return Err(Error::Other(format!(
+                "The node doesn't have the hash of block {probe_number}"
+            )))
+        };
+
+        let mut key = sp_core_hashing::twox_128(b"System").to_vec();
+        key.extend(sp_core_hashing::twox_128(b"Number"));
+        let is_archive = match self.rpc.storage(&key, Some(probe_hash)).await {
+            Ok(_) => true,
+            Err(Error::Rpc(RpcError::ClientError(e)))
+                if is_discarded_state_error(&*e) =>
+            {
+                false
+            }
+            Err(e) => return Err(e),
+        };
+        self.inner.write().archive_probe = Some((probe_depth, is_archive));
+        Ok(is_archive)
+    }
+
+    /// Work out how the `CheckMetadataHash` signed extension should be handled for
+    /// transactions at the given block, for passing to
+    /// [`crate::config::extrinsic_params::BaseExtrinsicParamsBuilder::metadata_hash()`].
+    ///
+    /// This is [`MetadataHashMode::NotIncluded`] if the runtime at the block has no such
+    /// extension, and [`MetadataHashMode::Enabled`] with the hash of the runtime's metadata
+    /// (see [`Metadata::merkleized_hash()`]) otherwise. Alongside the metadata, the hash
+    /// covers the runtime's spec name and version, its SS58 prefix (found like in
+    /// [`OnlineClient::validate_address()`]) and the native token's symbol and decimals from
+    /// the chain spec properties. Hashes are cached for each spec version.
+    pub async fn metadata_hash_mode_at(
+        &self,
+        block_hash: T::Hash,
+    ) -> Result<MetadataHashMode, Error> {
+        let (runtime_version, metadata) = runtime_at_block(self, block_hash).await?;
+        let has_extension = metadata
+            .runtime_metadata()
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .any(|ext| ext.identifier == "CheckMetadataHash");
+        if !has_extension {
+            return Ok(MetadataHashMode::NotIncluded)
+        }
+
+        let spec_version = runtime_version.spec_version;
+        let cached = self
+            .inner
+            .read()
+            .metadata_hashes
+            .get(&spec_version)
+            .copied();
+        if let Some(hash) = cached {
+            return Ok(MetadataHashMode::Enabled(hash))
+        }
+
+        let Some(spec_name) = runtime_version.spec_name() else {
+            return Err(Error::Other(format!(
+                "The node doesn't report the spec name of the runtime at block {block_hash:?}"
+            )))
+        };
+        let chain_info = self.chain_info().await?;
+        let (Some(token_symbol), Some(&decimals)) = (
+            chain_info.token_symbols().into_iter().next(),
+            chain_info.token_decimals().first(),
+        ) else {
+            return Err(Error::Other(
+                "The chain spec doesn't give the native token's symbol and decimals"
+                    .to_owned(),
+            ))
+        };
+        let base58_prefix = match ss58_prefix_constant(&metadata) {
+            Some(prefix) => prefix,
+            None => chain_info.ss58_prefix().unwrap_or(42),
+        };
+        let info = MetadataHashInfo {
+            spec_version,
+            spec_name: spec_name.to_owned(),
+            base58_prefix,
+            decimals,
+            token_symbol,
+        };
+        let hash = metadata.merkleized_hash(&info)?;
+        self.inner
+            .write()
+            .metadata_hashes
+            .insert(spec_version, hash);
+        Ok(MetadataHashMode::Enabled(hash))
+    }
+
+    /// Decode an SS58 encoded address, checking that it's meant for the chain that this
+    /// client is connected to. An error describing the mismatch is returned if the address
+    /// was encoded with a different SS58 prefix, for instance if it's a Polkadot address
+    /// but this client is connected to Kusama.
+    ///
+    /// The chain's prefix is taken from the `System::SS58Prefix` constant if the runtime
+    /// has one, and from the chain spec properties (see [`OnlineClient::chain_info()`])
+    /// otherwise. Chains which give neither are assumed to use the generic Substrate prefix, 42.
+    pub async fn validate_address(&self, address: &str) -> Result<AccountId32, Error> {
+        let prefix = match ss58_prefix_constant(&self.metadata()) {
+            Some(prefix) => prefix,
+            None => self.chain_info().await?.ss58_prefix().unwrap_or(42),
+        };
+        AccountId32::from_ss58check_for_prefix(address, prefix)
+            .map_err(|e| Error::Other(format!("Invalid address {address}: {e}")))
+    }
+
     /// Return an RPC client to make raw requests with.
     pub fn rpc(&self) -> &Rpc<T> {
         &self.rpc
@@ -320,6 +575,90 @@ impl<T: Config> OnlineClientT<T> for OnlineClient<T> {
     fn rpc(&self) -> &Rpc<T> {
         &self.rpc
     }
+
+    fn cached_metadata(&self, spec_version: u32) -> Option<Metadata> {
+        let inner = self.inner.read();
+        if inner.runtime_version.spec_version == spec_version {
+            return Some(inner.metadata.clone())
+        }
+        inner.versioned_metadata.get(&spec_version).cloned()
+    }
+
+    fn cache_metadata(&self, spec_version: u32, metadata: Metadata) {
+        let mut inner = self.inner.write();
+        if inner.runtime_version.spec_version != spec_version {
+            inner.versioned_metadata.insert(spec_version, metadata);
+        }
+    }
+}
+
+// The version of the metadata format that we can decode.
+const METADATA_FORMAT_VERSION: u32 = 14;
+
+// Nodes which have pruned the state at a block answer requests for it with a client error
+// along the lines of "UnknownBlock: State already discarded for Hash(0x..)". This is only
+// recognisable from its message, which the RPC error carries along. Other `UnknownBlock`
+// errors (for instance for a block the node has never seen) don't mean that the state was
+// pruned, so only this message counts.
+fn is_discarded_state_error(err: &(dyn std::error::Error + Send + Sync)) -> bool {
+    err.to_string().contains("State already discarded")
+}
+
+/// The number of blocks back that [`OnlineClient::is_archive_node()`] is suggested to check
+/// for old state. This is comfortably more than the 256 blocks of state that pruning nodes
+/// keep by default.
+pub const DEFAULT_ARCHIVE_PROBE_DEPTH: u32 = 1024;
+
+// Read the `System::SS58Prefix` constant from the metadata, if there is one.
+fn ss58_prefix_constant(metadata: &Metadata) -> Option<u16> {
+    let constant = metadata
+        .pallet("System")
+        .ok()?
+        .constant("SS58Prefix")
+        .ok()?;
+    u16::decode(&mut &*constant.value).ok()
+}
+
+/// Obtain the [`Metadata`] that was in use at the given block, looking it up by the
+/// runtime spec version at that block and consulting the client's cache first.
+///
+/// Metadata is fetched using the `Metadata_metadata_at_version` runtime API if the runtime
+/// at the block supports it, and `state_getMetadata` otherwise.
+pub(crate) async fn metadata_at_block<T: Config, Client: OnlineClientT<T>>(
+    client: &Client,
+    block_hash: T::Hash,
+) -> Result<Metadata, Error> {
+    let (_, metadata) = runtime_at_block(client, block_hash).await?;
+    Ok(metadata)
+}
+
+/// Like [`metadata_at_block()`], but also hand back the runtime version at the block,
+/// which has to be fetched to look the metadata up anyway.
+pub(crate) async fn runtime_at_block<T: Config, Client: OnlineClientT<T>>(
+    client: &Client,
+    block_hash: T::Hash,
+) -> Result<(RuntimeVersion, Metadata), Error> {
+    let runtime_version = client.rpc().runtime_version(Some(block_hash)).await?;
+    let spec_version = runtime_version.spec_version;
+
+    if let Some(metadata) = client.cached_metadata(spec_version) {
+        return Ok((runtime_version, metadata))
+    }
+
+    let metadata = if runtime_version.has_runtime_api("Metadata", 2) {
+        client
+            .rpc()
+            .metadata_at_version(METADATA_FORMAT_VERSION, Some(block_hash))
+            .await?
+    } else {
+        None
+    };
+    let metadata = match metadata {
+        Some(metadata) => metadata,
+        None => client.rpc().metadata(Some(block_hash)).await?,
+    };
+    client.cache_metadata(spec_version, metadata.clone());
+    Ok((runtime_version, metadata))
 }
 
 /// Client wrapper for performing runtime updates. See [`OnlineClient::updater()`]
@@ -334,8 +673,18 @@ impl<T: Config> ClientRuntimeUpdater<T> {
 
     fn do_update(&self, update: Update) {
         let mut writable = self.0.inner.write();
-        writable.metadata = update.metadata;
+        let new_spec_version = update.runtime_version.spec_version;
+        let old_spec_version = writable.runtime_version.spec_version;
+        let old_metadata = std::mem::replace(&mut writable.metadata, update.metadata);
         writable.runtime_version = update.runtime_version;
+
+        // Keep the outgoing metadata around for decoding historic blocks.
+        writable.versioned_metadata.remove(&new_spec_version);
+        if old_spec_version != new_spec_version {
+            writable
+                .versioned_metadata
+                .insert(old_spec_version, old_metadata);
+        }
     }
 
     /// Tries to apply a new update.
@@ -499,3 +848,266 @@ mod jsonrpsee_helpers {
             .build_with_wasm(sender, receiver))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::RpcError,
+        test_utils::{
+            online_client,
+            MockRpc,
+            METADATA,
+        },
+        PolkadotConfig,
+    };
+    use codec::Encode;
+    use std::sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    };
+
+    // A node whose runtime at every block has spec version 2, and reports supporting the
+    // version of the `Metadata` runtime API given.
+    fn metadata_rpc(metadata_api_version: u32) -> Arc<MockRpc> {
+        Arc::new(MockRpc::new(move |method, _| {
+            let res = match method {
+                "state_getRuntimeVersion" => {
+                    let metadata_api_id = sp_core_hashing::blake2_64(b"Metadata");
+                    serde_json::json!({
+                        "specVersion": 2,
+                        "transactionVersion": 1,
+                        "apis": [[
+                            format!("0x{}", hex::encode(metadata_api_id)),
+                            metadata_api_version
+                        ]],
+                    })
+                }
+                "state_call" => {
+                    let opaque = Some(METADATA.to_vec()).encode();
+                    serde_json::json!(format!("0x{}", hex::encode(opaque)))
+                }
+                "state_getMetadata" => {
+                    serde_json::json!(format!("0x{}", hex::encode(METADATA)))
+                }
+                _ => panic!("unexpected method {method}"),
+            };
+            Ok(res)
+        }))
+    }
+
+    #[tokio::test]
+    async fn metadata_at_blocks_is_fetched_once_per_runtime() {
+        let rpc = metadata_rpc(2);
+        let client = online_client(rpc.clone());
+        for _ in 0..2 {
+            client.metadata_at(Default::default()).await.unwrap();
+        }
+        assert_eq!(rpc.requests_to("state_call"), 1);
+        assert_eq!(rpc.requests_to("state_getMetadata"), 0);
+    }
+
+    #[tokio::test]
+    async fn metadata_at_blocks_falls_back_to_state_get_metadata() {
+        // The first version of the `Metadata` runtime API has no `metadata_at_version`.
+        let rpc = metadata_rpc(1);
+        let client = online_client(rpc.clone());
+        client.metadata_at(Default::default()).await.unwrap();
+        assert_eq!(rpc.requests_to("state_call"), 0);
+        assert_eq!(rpc.requests_to("state_getMetadata"), 1);
+    }
+
+    #[tokio::test]
+    async fn pinned_clients_use_the_runtime_at_their_block() {
+        let rpc = metadata_rpc(2);
+        let client = online_client(rpc.clone());
+        let block_hash = <PolkadotConfig as Config>::Hash::repeat_byte(1);
+        let pinned = client.pinned_at(block_hash).await.unwrap();
+
+        assert_eq!(pinned.block_hash(), block_hash);
+        assert_eq!(pinned.storage().block_hash(), block_hash);
+        // The live client is on spec version 1, and the pinned block on 2:
+        assert_eq!(pinned.runtime_version().spec_version, 2);
+        assert_eq!(pinned.live().runtime_version().spec_version, 1);
+        assert_eq!(OfflineClientT::runtime_version(&pinned).spec_version, 2);
+        assert!(pinned
+            .constants()
+            .at(&crate::dynamic::constant("System", "SS58Prefix"))
+            .is_ok());
+        // Pinning again reuses the metadata fetched the first time:
+        client.pinned_at(block_hash).await.unwrap();
+        assert_eq!(rpc.requests_to("state_call"), 1);
+        assert_eq!(rpc.requests_to("state_getRuntimeVersion"), 2);
+    }
+
+    // A node at block 2000, which has either kept or pruned the state of old blocks, and
+    // whose connection may drop the first time that storage is asked for.
+    fn archive_rpc(pruned: bool, drop_first_request: bool) -> Arc<MockRpc> {
+        let storage_requests = AtomicUsize::new(0);
+        Arc::new(MockRpc::new(move |method, _| {
+            let hash = format!("{:?}", <PolkadotConfig as Config>::Hash::zero());
+            let res = match method {
+                "chain_getFinalizedHead" | "chain_getBlockHash" => {
+                    serde_json::json!(hash)
+                }
+                "chain_getHeader" => {
+                    serde_json::json!({
+                        "parentHash": hash,
+                        "number": "0x7d0",
+                        "stateRoot": hash,
+                        "extrinsicsRoot": hash,
+                        "digest": { "logs": [] },
+                    })
+                }
+                "state_getStorage" => {
+                    let n = storage_requests.fetch_add(1, Ordering::SeqCst);
+                    if drop_first_request && n == 0 {
+                        return Err(RpcError::ClientError(
+                            "The background task closed".into(),
+                        ))
+                    }
+                    if pruned {
+                        return Err(RpcError::ClientError(
+                            "Client error: UnknownBlock: State already discarded for Hash(0x00)"
+                                .into(),
+                        ))
+                    }
+                    serde_json::json!("0x00")
+                }
+                _ => panic!("unexpected method {method}"),
+            };
+            Ok(res)
+        }))
+    }
+
+    #[tokio::test]
+    async fn archive_nodes_are_detected_and_cached() {
+        for (pruned, drop_first_request) in
+            [(false, false), (true, false), (false, true), (true, true)]
+        {
+            let rpc = archive_rpc(pruned, drop_first_request);
+            let client = online_client(rpc.clone());
+
+            // Other errors are handed back rather than taken to mean that the state was
+            // pruned, and aren't cached:
+            if drop_first_request {
+                assert!(client
+                    .is_archive_node(DEFAULT_ARCHIVE_PROBE_DEPTH)
+                    .await
+                    .is_err());
+            }
+            for _ in 0..2 {
+                let is_archive = client
+                    .is_archive_node(DEFAULT_ARCHIVE_PROBE_DEPTH)
+                    .await
+                    .unwrap();
+                assert_eq!(is_archive, !pruned);
+            }
+            let expected_requests = if drop_first_request { 2 } else { 1 };
+            assert_eq!(rpc.requests_to("state_getStorage"), expected_requests);
+        }
+    }
+
+    // A node on a Polkadot runtime which has a `CheckMetadataHash` extension, along with the
+    // metadata of that runtime.
+    fn metadata_hash_rpc() -> (Arc<MockRpc>, Metadata) {
+        let mut metadata = RuntimeMetadataPrefixed::decode(&mut &*METADATA).unwrap();
+        let frame_metadata::RuntimeMetadata::V14(v14) = &mut metadata.1 else {
+            panic!("the test metadata is V14")
+        };
+        let mut extension = v14.extrinsic.signed_extensions[0].clone();
+        extension.identifier = "CheckMetadataHash".to_owned();
+        v14.extrinsic.signed_extensions.push(extension);
+        let bytes = metadata.encode();
+
+        let rpc = Arc::new(MockRpc::new(move |method, _| {
+            let res = match method {
+                "state_getRuntimeVersion" => {
+                    serde_json::json!({
+                        "specName": "polkadot",
+                        "specVersion": 9370,
+                        "transactionVersion": 1,
+                    })
+                }
+                "state_getMetadata" => {
+                    serde_json::json!(format!("0x{}", hex::encode(&bytes)))
+                }
+                "system_chain" | "system_name" | "system_version" => {
+                    serde_json::json!("Polkadot")
+                }
+                "system_properties" => {
+                    serde_json::json!({
+                        "ss58Format": 0,
+                        "tokenDecimals": 10,
+                        "tokenSymbol": "DOT",
+                    })
+                }
+                _ => panic!("unexpected method {method}"),
+            };
+            Ok(res)
+        }));
+        (rpc, metadata.try_into().unwrap())
+    }
+
+    #[tokio::test]
+    async fn metadata_hashes_are_computed_and_cached() {
+        let (rpc, metadata) = metadata_hash_rpc();
+        let client = online_client(rpc.clone());
+        let info = MetadataHashInfo {
+            spec_version: 9370,
+            spec_name: "polkadot".to_owned(),
+            base58_prefix: 0,
+            decimals: 10,
+            token_symbol: "DOT".to_owned(),
+        };
+        let expected =
+            MetadataHashMode::Enabled(metadata.merkleized_hash(&info).unwrap());
+
+        for _ in 0..2 {
+            let mode = client
+                .metadata_hash_mode_at(Default::default())
+                .await
+                .unwrap();
+            assert_eq!(mode, expected);
+        }
+        assert_eq!(client.inner.read().metadata_hashes.len(), 1);
+        assert_eq!(rpc.requests_to("state_getMetadata"), 1);
+    }
+
+    #[tokio::test]
+    async fn metadata_hashes_are_not_included_without_the_extension() {
+        let client = online_client(metadata_rpc(1));
+        let mode = client
+            .metadata_hash_mode_at(Default::default())
+            .await
+            .unwrap();
+        assert_eq!(mode, MetadataHashMode::NotIncluded);
+    }
+
+    #[cfg(feature = "jsonrpsee-ws")]
+    #[test]
+    fn http_urls_are_recognised() {
+        assert!(is_http_url("http://127.0.0.1:9933"));
+        assert!(is_http_url("HTTPS://rpc.polkadot.io"));
+        assert!(!is_http_url("ws://127.0.0.1:9944"));
+        assert!(!is_http_url("wss://rpc.polkadot.io"));
+    }
+
+    #[test]
+    fn only_discarded_state_errors_mean_the_state_was_pruned() {
+        let is_discarded = |message: &str| {
+            let err: Box<dyn std::error::Error + Send + Sync> = message.into();
+            is_discarded_state_error(&*err)
+        };
+        assert!(is_discarded(
+            "Client error: UnknownBlock: State already discarded for Hash(0x00)"
+        ));
+        assert!(is_discarded("State already discarded for 0x1234"));
+
+        assert!(!is_discarded(
+            "Client error: UnknownBlock: Header was not found"
+        ));
+        assert!(!is_discarded("UnknownBlock: Block 0x1234 not found"));
+        assert!(!is_discarded("Networking or low-level protocol error"));
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    runtime_at_block,
+    OfflineClientT,
+    OnlineClient,
+    OnlineClientT,
+};
+use crate::{
+    blocks::Block,
+    constants::ConstantsClient,
+    error::Error,
+    events::{
+        Events,
+        EventsClient,
+    },
+    rpc::{
+        types::RuntimeVersion,
+        Rpc,
+    },
+    runtime_api::RuntimeApi,
+    storage::Storage,
+    tx::TxClient,
+    Config,
+    Metadata,
+};
+use derivative::Derivative;
+use std::future::Future;
+
+/// A view of an [`OnlineClient`] which is pinned to one block, and to the runtime version
+/// and metadata in use at that block. Obtain one with [`OnlineClient::pinned_at()`].
+///
+/// Storage, events, constants and runtime API calls all look at the pinned block and are
+/// encoded and decoded using its metadata, however far the chain has moved on since, so a
+/// batch job run against a pinned client sees one consistent snapshot of the chain. The
+/// pinned block's state has to still be available, which for old blocks means talking to
+/// an archive node.
+///
+/// Transactions can't be pinned: [`PinnedClient::tx()`] submits them to the live chain,
+/// using the latest runtime version and metadata.
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""))]
+pub struct PinnedClient<T: Config> {
+    client: OnlineClient<T>,
+    block_hash: T::Hash,
+    runtime_version: RuntimeVersion,
+    metadata: Metadata,
+}
+
+impl<T: Config> PinnedClient<T> {
+    pub(crate) async fn new(
+        client: OnlineClient<T>,
+        block_hash: T::Hash,
+    ) -> Result<Self, Error> {
+        let (runtime_version, metadata) = runtime_at_block(&client, block_hash).await?;
+        Ok(PinnedClient {
+            client,
+            block_hash,
+            runtime_version,
+            metadata,
+        })
+    }
+
+    /// The hash of the block that this client is pinned to.
+    pub fn block_hash(&self) -> T::Hash {
+        self.block_hash
+    }
+
+    /// The [`Metadata`] in use at the pinned block.
+    pub fn metadata(&self) -> Metadata {
+        self.metadata.clone()
+    }
+
+    /// The runtime version in use at the pinned block.
+    pub fn runtime_version(&self) -> RuntimeVersion {
+        self.runtime_version.clone()
+    }
+
+    /// The live client that this was pinned from.
+    pub fn live(&self) -> &OnlineClient<T> {
+        &self.client
+    }
+
+    /// Return an RPC client to make raw requests with. These aren't pinned to any block.
+    pub fn rpc(&self) -> &Rpc<T> {
+        self.client.rpc()
+    }
+
+    /// Read storage at the pinned block.
+    pub fn storage(&self) -> Storage<T, Self> {
+        Storage::new(self.clone(), self.block_hash)
+    }
+
+    /// Fetch the events of the pinned block.
+    pub fn events(
+        &self,
+    ) -> impl Future<Output = Result<Events<T>, Error>> + Send + 'static {
+        EventsClient::new(self.clone()).at(Some(self.block_hash))
+    }
+
+    /// Access the constants of the runtime in use at the pinned block.
+    pub fn constants(&self) -> ConstantsClient<T, Self> {
+        ConstantsClient::new(self.clone())
+    }
+
+    /// Call runtime APIs at the pinned block.
+    pub fn runtime_api(&self) -> RuntimeApi<T, Self> {
+        RuntimeApi::new(self.clone(), self.block_hash)
+    }
+
+    /// Fetch the pinned block.
+    pub fn block(
+        &self,
+    ) -> impl Future<Output = Result<Block<T, Self>, Error>> + Send + 'static {
+        <Self as OfflineClientT<T>>::blocks(self).at(Some(self.block_hash))
+    }
+
+    /// Work with transactions. These are built for, and submitted to, the live chain rather
+    /// than the pinned block.
+    pub fn tx(&self) -> TxClient<T, OnlineClient<T>> {
+        self.client.tx()
+    }
+}
+
+impl<T: Config> OfflineClientT<T> for PinnedClient<T> {
+    fn metadata(&self) -> Metadata {
+        self.metadata()
+    }
+    fn genesis_hash(&self) -> T::Hash {
+        self.client.genesis_hash()
+    }
+    fn runtime_version(&self) -> RuntimeVersion {
+        self.runtime_version()
+    }
+}
+
+impl<T: Config> OnlineClientT<T> for PinnedClient<T> {
+    fn rpc(&self) -> &Rpc<T> {
+        self.client.rpc()
+    }
+
+    fn cached_metadata(&self, spec_version: u32) -> Option<Metadata> {
+        self.client.cached_metadata(spec_version)
+    }
+
+    fn cache_metadata(&self, spec_version: u32, metadata: Metadata) {
+        self.client.cache_metadata(spec_version, metadata)
+    }
+}
@@ -8,6 +8,10 @@
 //! used by the provided Substrate and Polkadot configuration.
 
 use crate::{
+    config::{
+        polkadot::PlainTip,
+        substrate::AssetTip,
+    },
     utils::Encoded,
     Config,
 };
@@ -16,7 +20,10 @@ use codec::{
     Decode,
     Encode,
 };
-use core::fmt::Debug;
+use core::{
+    any::TypeId,
+    fmt::Debug,
+};
 use derivative::Derivative;
 use serde::{
     Deserialize,
@@ -53,6 +60,16 @@ pub trait ExtrinsicParams<Index, Hash>: Debug + 'static {
     /// sent along with the transaction, but are taken into account when
     /// signing it, meaning the client and node must agree on their values.
     fn encode_additional_to(&self, v: &mut Vec<u8>);
+
+    /// The identifiers of the signed extensions that these parameters provide values for,
+    /// in the order that they're encoded, or `None` if that isn't known. Extensions which
+    /// have no "signed extra" or "additional" values can be left out.
+    ///
+    /// This is used by [`crate::tx::TxClient::check_signed_extensions()`] to find out whether
+    /// these parameters line up with what the runtime expects.
+    fn signed_extensions(&self) -> Option<Vec<&'static str>> {
+        None
+    }
 }
 
 /// An implementation of [`ExtrinsicParams`] that is suitable for constructing
@@ -61,15 +78,19 @@ pub trait ExtrinsicParams<Index, Hash>: Debug + 'static {
 /// differs between Substrate and Polkadot nodes, and so we are generic over that in
 /// order to support both here with relative ease.
 ///
-/// If your node differs in the "signed extra" and "additional" parameters expected
-/// to be sent/signed with a transaction, then you can define your own type which
-/// implements the [`ExtrinsicParams`] trait.
+/// Chains which have signed extensions of their own on top of the usual ones can provide
+/// the parameters for them via `Ext` (see [`CustomExtensionParams`]). If your node differs
+/// more than that in the "signed extra" and "additional" parameters expected to be
+/// sent/signed with a transaction, then you can define your own type which implements the
+/// [`ExtrinsicParams`] trait.
 #[derive(Derivative)]
-#[derivative(Debug(bound = "Tip: Debug"))]
-pub struct BaseExtrinsicParams<T: Config, Tip: Debug> {
+#[derivative(Debug(bound = "Tip: Debug, Ext: Debug"))]
+pub struct BaseExtrinsicParams<T: Config, Tip: Debug, Ext = ()> {
     era: Era,
     nonce: T::Index,
     tip: Tip,
+    metadata_hash: MetadataHashMode,
+    extensions: Ext,
     spec_version: u32,
     transaction_version: u32,
     genesis_hash: T::Hash,
@@ -77,6 +98,80 @@ pub struct BaseExtrinsicParams<T: Config, Tip: Debug> {
     marker: std::marker::PhantomData<T>,
 }
 
+/// The parameters for any signed extensions which come after the ones that
+/// [`BaseExtrinsicParams`] knows about. These are provided using
+/// [`BaseExtrinsicParamsBuilder::extensions()`], and encoded after the usual "signed extra"
+/// and "additional" parameters, so they must be given in the order that the runtime expects.
+pub trait CustomExtensionParams: Debug + 'static {
+    /// SCALE encode the "signed extra" parameters of the extensions, which are sent
+    /// along with the transaction.
+    fn encode_extra_to(&self, v: &mut Vec<u8>);
+
+    /// SCALE encode the "additional" parameters of the extensions, which are signed but
+    /// not sent along with the transaction.
+    fn encode_additional_to(&self, v: &mut Vec<u8>);
+
+    /// The identifiers of the extensions that these are the parameters of, in order.
+    fn identifiers(&self) -> Vec<&'static str>;
+}
+
+/// No custom signed extensions.
+impl CustomExtensionParams for () {
+    fn encode_extra_to(&self, _v: &mut Vec<u8>) {}
+    fn encode_additional_to(&self, _v: &mut Vec<u8>) {}
+    fn identifiers(&self) -> Vec<&'static str> {
+        Vec::new()
+    }
+}
+
+/// Tips are paid by a signed extension, and which one it is depends on how the tip is
+/// given. This hands back the identifier of that extension for the tip types provided
+/// here, and `None` for any other tip type, since we can't know which extension takes it.
+fn tip_extension<Tip: 'static>() -> Option<&'static str> {
+    let tip = TypeId::of::<Tip>();
+    if tip == TypeId::of::<PlainTip>() {
+        Some("ChargeTransactionPayment")
+    } else if tip == TypeId::of::<AssetTip>() {
+        Some("ChargeAssetTxPayment")
+    } else {
+        None
+    }
+}
+
+/// How to handle the `CheckMetadataHash` signed extension, which lets a runtime check that
+/// a transaction was signed by a wallet which was shown the correct metadata.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum MetadataHashMode {
+    /// The runtime has no `CheckMetadataHash` extension, and so nothing is encoded for it.
+    /// This is the default.
+    #[default]
+    NotIncluded,
+    /// The runtime has a `CheckMetadataHash` extension, but we don't ask for the metadata
+    /// hash to be checked.
+    Disabled,
+    /// The runtime has a `CheckMetadataHash` extension, and should check that its metadata
+    /// has the hash given.
+    Enabled([u8; 32]),
+}
+
+impl MetadataHashMode {
+    fn encode_extra_to(&self, v: &mut Vec<u8>) {
+        match self {
+            MetadataHashMode::NotIncluded => {}
+            MetadataHashMode::Disabled => 0u8.encode_to(v),
+            MetadataHashMode::Enabled(_) => 1u8.encode_to(v),
+        }
+    }
+
+    fn encode_additional_to(&self, v: &mut Vec<u8>) {
+        match self {
+            MetadataHashMode::NotIncluded => {}
+            MetadataHashMode::Disabled => None::<[u8; 32]>.encode_to(v),
+            MetadataHashMode::Enabled(hash) => Some(hash).encode_to(v),
+        }
+    }
+}
+
 /// This builder allows you to provide the parameters that can be configured in order to
 /// construct a [`BaseExtrinsicParams`] value. This implements [`Default`], which allows
 /// [`BaseExtrinsicParams`] to be used with convenience methods like `sign_and_submit_default()`.
@@ -86,23 +181,27 @@ pub struct BaseExtrinsicParams<T: Config, Tip: Debug> {
 /// version tailored to Polkadot.
 #[derive(Derivative)]
 #[derivative(
-    Debug(bound = "Tip: Debug"),
-    Clone(bound = "Tip: Clone"),
-    Copy(bound = "Tip: Copy"),
-    PartialEq(bound = "Tip: PartialEq")
+    Debug(bound = "Tip: Debug, Ext: Debug"),
+    Clone(bound = "Tip: Clone, Ext: Clone"),
+    Copy(bound = "Tip: Copy, Ext: Copy"),
+    PartialEq(bound = "Tip: PartialEq, Ext: PartialEq")
 )]
-pub struct BaseExtrinsicParamsBuilder<T: Config, Tip> {
+pub struct BaseExtrinsicParamsBuilder<T: Config, Tip, Ext = ()> {
     era: Era,
     mortality_checkpoint: Option<T::Hash>,
     tip: Tip,
+    metadata_hash: MetadataHashMode,
+    extensions: Ext,
 }
 
-impl<T: Config, Tip: Default> BaseExtrinsicParamsBuilder<T, Tip> {
+impl<T: Config, Tip: Default, Ext: Default> BaseExtrinsicParamsBuilder<T, Tip, Ext> {
     /// Instantiate the default set of [`BaseExtrinsicParamsBuilder`]
     pub fn new() -> Self {
         Self::default()
     }
+}
 
+impl<T: Config, Tip, Ext> BaseExtrinsicParamsBuilder<T, Tip, Ext> {
     /// Set the [`Era`], which defines how long the transaction will be valid for
     /// (it can be either immortal, or it can be mortal and expire after a certain amount
     /// of time). The second argument is the block hash after which the transaction
@@ -114,28 +213,60 @@ impl<T: Config, Tip: Default> BaseExtrinsicParamsBuilder<T, Tip> {
         self
     }
 
+    /// Make the transaction mortal, so that it's valid for `period` blocks from the block
+    /// with the number and hash given (usually the current best or finalized block). See
+    /// [`Era::mortal()`] for how the period is rounded.
+    ///
+    /// Periods longer than 4096 blocks may begin a little before the block given, in which
+    /// case [`BaseExtrinsicParamsBuilder::era()`] must be used with the hash of the block
+    /// that the era begins at instead.
+    pub fn mortal(self, period: u64, block_number: u64, block_hash: T::Hash) -> Self {
+        self.era(Era::mortal(period, block_number), block_hash)
+    }
+
     /// Set the tip you'd like to give to the block author
     /// for this transaction.
     pub fn tip(mut self, tip: impl Into<Tip>) -> Self {
         self.tip = tip.into();
         self
     }
+
+    /// Set how the `CheckMetadataHash` signed extension is handled. This must be set to
+    /// something other than [`MetadataHashMode::NotIncluded`] for runtimes which have the
+    /// extension, since transactions are otherwise rejected as invalid.
+    pub fn metadata_hash(mut self, mode: MetadataHashMode) -> Self {
+        self.metadata_hash = mode;
+        self
+    }
+
+    /// Set the parameters for any custom signed extensions of the chain.
+    pub fn extensions(mut self, extensions: Ext) -> Self {
+        self.extensions = extensions;
+        self
+    }
 }
 
-impl<T: Config, Tip: Default> Default for BaseExtrinsicParamsBuilder<T, Tip> {
+impl<T: Config, Tip: Default, Ext: Default> Default
+    for BaseExtrinsicParamsBuilder<T, Tip, Ext>
+{
     fn default() -> Self {
         Self {
             era: Era::Immortal,
             mortality_checkpoint: None,
             tip: Tip::default(),
+            metadata_hash: MetadataHashMode::default(),
+            extensions: Ext::default(),
         }
     }
 }
 
-impl<T: Config, Tip: Debug + Encode + 'static> ExtrinsicParams<T::Index, T::Hash>
-    for BaseExtrinsicParams<T, Tip>
+impl<T, Tip, Ext> ExtrinsicParams<T::Index, T::Hash> for BaseExtrinsicParams<T, Tip, Ext>
+where
+    T: Config,
+    Tip: Debug + Encode + 'static,
+    Ext: CustomExtensionParams,
 {
-    type OtherParams = BaseExtrinsicParamsBuilder<T, Tip>;
+    type OtherParams = BaseExtrinsicParamsBuilder<T, Tip, Ext>;
 
     fn new(
         // Provided from subxt client:
@@ -152,6 +283,8 @@ impl<T: Config, Tip: Debug + Encode + 'static> ExtrinsicParams<T::Index, T::Hash
                 .mortality_checkpoint
                 .unwrap_or(genesis_hash),
             tip: other_params.tip,
+            metadata_hash: other_params.metadata_hash,
+            extensions: other_params.extensions,
             nonce,
             spec_version,
             transaction_version,
@@ -164,6 +297,8 @@ impl<T: Config, Tip: Debug + Encode + 'static> ExtrinsicParams<T::Index, T::Hash
         let nonce: u64 = self.nonce.into();
         let tip = Encoded(self.tip.encode());
         (self.era, Compact(nonce), tip).encode_to(v);
+        self.metadata_hash.encode_extra_to(v);
+        self.extensions.encode_extra_to(v);
     }
 
     fn encode_additional_to(&self, v: &mut Vec<u8>) {
@@ -174,6 +309,24 @@ impl<T: Config, Tip: Debug + Encode + 'static> ExtrinsicParams<T::Index, T::Hash
             self.mortality_checkpoint,
         )
             .encode_to(v);
+        self.metadata_hash.encode_additional_to(v);
+        self.extensions.encode_additional_to(v);
+    }
+
+    fn signed_extensions(&self) -> Option<Vec<&'static str>> {
+        let mut identifiers = vec![
+            "CheckSpecVersion",
+            "CheckTxVersion",
+            "CheckGenesis",
+            "CheckMortality",
+            "CheckNonce",
+            tip_extension::<Tip>()?,
+        ];
+        if self.metadata_hash != MetadataHashMode::NotIncluded {
+            identifiers.push("CheckMetadataHash");
+        }
+        identifiers.extend(self.extensions.identifiers());
+        Some(identifiers)
     }
 }
 
@@ -268,3 +421,174 @@ impl Decode for Era {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{
+        polkadot::PlainTip,
+        substrate::{
+            BlakeTwo256,
+            SubstrateHeader,
+            H256,
+        },
+        SubstrateConfig,
+    };
+
+    // A config whose nonces and block numbers are 64 rather than 32 bits wide.
+    enum WideConfig {}
+
+    impl Config for WideConfig {
+        type Index = u64;
+        type Hash = H256;
+        type AccountId = <SubstrateConfig as Config>::AccountId;
+        type Address = <SubstrateConfig as Config>::Address;
+        type Signature = <SubstrateConfig as Config>::Signature;
+        type Hasher = BlakeTwo256;
+        type Header = SubstrateHeader<u64, BlakeTwo256>;
+        type ExtrinsicParams = BaseExtrinsicParams<Self, PlainTip>;
+    }
+
+    fn extra<T: Config<Hash = H256>>(nonce: T::Index) -> Vec<u8> {
+        let params = <BaseExtrinsicParams<T, PlainTip>>::new(
+            0,
+            0,
+            nonce,
+            Default::default(),
+            Default::default(),
+        );
+        let mut extra = Vec::new();
+        params.encode_extra_to(&mut extra);
+        extra
+    }
+
+    #[test]
+    fn nonces_encode_according_to_the_configured_index_type() {
+        let expected = (Era::Immortal, Compact(5u32), PlainTip::new(0)).encode();
+        assert_eq!(extra::<SubstrateConfig>(5), expected);
+        // Compact encodings don't depend on the width of the type:
+        assert_eq!(extra::<WideConfig>(5), expected);
+
+        // Nonces which don't fit in a u32 are encoded in full:
+        let nonce = u32::MAX as u64 + 1;
+        let extra = extra::<WideConfig>(nonce);
+        let (era, decoded) = <(Era, Compact<u64>)>::decode(&mut &*extra).unwrap();
+        assert_eq!(era, Era::Immortal);
+        assert_eq!(decoded.0, nonce);
+        assert!(<(Era, Compact<u32>)>::decode(&mut &*extra).is_err());
+    }
+
+    #[test]
+    fn block_numbers_encode_according_to_the_configured_number_type() {
+        let header = |number| {
+            SubstrateHeader::<u64, BlakeTwo256> {
+                parent_hash: H256::zero(),
+                number,
+                state_root: H256::zero(),
+                extrinsics_root: H256::zero(),
+                digest: Default::default(),
+            }
+        };
+
+        let narrow = SubstrateHeader::<u32, BlakeTwo256> {
+            parent_hash: H256::zero(),
+            number: 100,
+            state_root: H256::zero(),
+            extrinsics_root: H256::zero(),
+            digest: Default::default(),
+        };
+        assert_eq!(header(100).encode(), narrow.encode());
+
+        let wide = header(u32::MAX as u64 + 1);
+        let decoded =
+            SubstrateHeader::<u64, BlakeTwo256>::decode(&mut &*wide.encode()).unwrap();
+        assert_eq!(decoded, wide);
+        assert!(
+            SubstrateHeader::<u32, BlakeTwo256>::decode(&mut &*wide.encode()).is_err()
+        );
+    }
+
+    // A tip type that subxt knows nothing about.
+    #[derive(Clone, Copy, Debug, Default, Encode)]
+    struct OtherTip(u64);
+
+    #[test]
+    fn other_tip_types_can_be_used() {
+        let params = <BaseExtrinsicParams<SubstrateConfig, OtherTip>>::new(
+            0,
+            0,
+            5,
+            Default::default(),
+            BaseExtrinsicParamsBuilder::new().tip(OtherTip(3)),
+        );
+        let mut extra = Vec::new();
+        params.encode_extra_to(&mut extra);
+        assert_eq!(extra, (Era::Immortal, Compact(5u32), OtherTip(3)).encode());
+
+        // We don't know which signed extension takes this tip:
+        assert_eq!(params.signed_extensions(), None);
+        let params = <BaseExtrinsicParams<SubstrateConfig, PlainTip>>::new(
+            0,
+            0,
+            5,
+            Default::default(),
+            Default::default(),
+        );
+        assert!(params
+            .signed_extensions()
+            .unwrap()
+            .contains(&"ChargeTransactionPayment"));
+    }
+
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    struct ChargeSomething(u8);
+
+    impl CustomExtensionParams for ChargeSomething {
+        fn encode_extra_to(&self, v: &mut Vec<u8>) {
+            self.0.encode_to(v);
+        }
+        fn encode_additional_to(&self, v: &mut Vec<u8>) {
+            (self.0 as u16).encode_to(v);
+        }
+        fn identifiers(&self) -> Vec<&'static str> {
+            vec!["ChargeSomething"]
+        }
+    }
+
+    #[test]
+    fn metadata_hash_and_custom_extensions_come_last() {
+        let params =
+            |builder| {
+                let params = <BaseExtrinsicParams<
+                    SubstrateConfig,
+                    PlainTip,
+                    ChargeSomething,
+                >>::new(1, 2, 3, H256::zero(), builder);
+                let (mut extra, mut additional) = (Vec::new(), Vec::new());
+                params.encode_extra_to(&mut extra);
+                params.encode_additional_to(&mut additional);
+                (extra, additional)
+            };
+        let base_extra = (Era::Immortal, Compact(3u32), PlainTip::new(5)).encode();
+        let base_additional = (1u32, 2u32, H256::zero(), H256::zero()).encode();
+        let builder = BaseExtrinsicParamsBuilder::new()
+            .tip(5)
+            .extensions(ChargeSomething(9));
+
+        // By default, there's no `CheckMetadataHash` extension:
+        let (extra, additional) = params(builder);
+        assert_eq!(extra, [&base_extra[..], &[9]].concat());
+        assert_eq!(additional, [&base_additional[..], &[9, 0]].concat());
+
+        let (extra, additional) =
+            params(builder.metadata_hash(MetadataHashMode::Disabled));
+        assert_eq!(extra, [&base_extra[..], &[0, 9]].concat());
+        assert_eq!(additional, [&base_additional[..], &[0, 9, 0]].concat());
+
+        let (extra, additional) =
+            params(builder.metadata_hash(MetadataHashMode::Enabled([7; 32])));
+        assert_eq!(extra, [&base_extra[..], &[1, 9]].concat());
+        let hash = Some([7u8; 32]).encode();
+        assert_eq!(additional, [&base_additional[..], &hash, &[9, 0]].concat());
+    }
+}
@@ -35,7 +35,10 @@ pub trait Config: 'static {
     /// transactions associated with a sender account.
     type Index: Debug + Copy + DeserializeOwned + Into<u64>;
 
-    /// The output of the `Hashing` function.
+    /// The output of the `Hashing` function. This is always sent to and received from the
+    /// node as a hex string when it's an RPC param or result, using the `AsRef<[u8]>` and
+    /// `Decode` impls. Hashes nested in the types in [`crate::rpc::types`] use the serde
+    /// impls, which should do the same.
     type Hash: Debug
         + Copy
         + Send
@@ -96,6 +99,16 @@ pub trait Header: Sized + Encode {
     fn hash(&self) -> <Self::Hasher as Hasher>::Output {
         Self::Hasher::hash_of(self)
     }
+
+    /// Hash this header and check that the result is the expected block hash. This
+    /// lets a header obtained from an untrusted source be checked against a block
+    /// hash that is already trusted.
+    fn has_hash(&self, expected: &<Self::Hasher as Hasher>::Output) -> bool
+    where
+        <Self::Hasher as Hasher>::Output: PartialEq,
+    {
+        &self.hash() == expected
+    }
 }
 
 /// Take a type implementing [`Config`] (eg [`SubstrateConfig`]), and some type which describes the
@@ -84,6 +84,14 @@ impl AssetTip {
     }
 }
 
+impl<T: Config, Ext> BaseExtrinsicParamsBuilder<T, AssetTip, Ext> {
+    /// Set the tip you'd like to give to the block author for this transaction, paid
+    /// in the asset class given rather than the native currency.
+    pub fn tip_of_asset(self, amount: u128, asset: u32) -> Self {
+        self.tip(AssetTip::new(amount).of_asset(asset))
+    }
+}
+
 impl From<u128> for AssetTip {
     fn from(n: u128) -> Self {
         AssetTip::new(n)
@@ -285,3 +293,31 @@ where
     let u256: U256 = serde::Deserialize::deserialize(d)?;
     TryFrom::try_from(u256).map_err(|_| serde::de::Error::custom("Try from failed"))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hashes_a_known_header() {
+        // The Polkadot genesis header, as given back from `chain_getHeader`.
+        let header: SubstrateHeader<u32, BlakeTwo256> = serde_json::from_str(
+            r#"{
+                "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "number": "0x0",
+                "stateRoot": "0x29d0d972cd27cbc511e9589fcb7a4506d5eb6a9e8df205f00472e5ab354a4e17",
+                "extrinsicsRoot": "0x03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314",
+                "digest": { "logs": [] }
+            }"#,
+        )
+        .unwrap();
+        let expected: H256 =
+            "0x91b171bb158e2d3848fa23a9f1c25182fb8e20313b2c1eb49219da7a70ce90c3"
+                .parse()
+                .unwrap();
+
+        assert_eq!(header.hash(), expected);
+        assert!(header.has_hash(&expected));
+        assert!(!header.has_hash(&H256::zero()));
+    }
+}
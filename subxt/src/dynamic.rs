@@ -9,6 +9,7 @@ use crate::{
     error::Error,
     metadata::{
         DecodeWithMetadata,
+        EncodeWithMetadata,
         Metadata,
     },
 };
@@ -33,6 +34,26 @@ pub use crate::storage::{
     dynamic_root as storage_root,
 };
 
+/// SCALE encode a [`Value`] as the type in the metadata with the ID given, checking along the
+/// way that the value has the right shape for the type. Values decoded from a node, such as a
+/// [`DecodedValue`], can be modified and encoded again this way.
+///
+/// If the value doesn't match the type, an [`Error::EncodeValue`] is handed back, which
+/// contains the part of the value that didn't match and the ID of the type that it should
+/// have matched.
+pub fn encode_value<Ctx: Clone>(
+    value: &Value<Ctx>,
+    type_id: u32,
+    metadata: &Metadata,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    value
+        .clone()
+        .remove_context()
+        .encode_with_metadata(type_id, metadata, &mut bytes)?;
+    Ok(bytes)
+}
+
 /// This is the result of making a dynamic request to a node. From this,
 /// we can return the raw SCALE bytes that we were handed back, or we can
 /// complete the decoding of the bytes into a [`DecodedValue`] type.
@@ -79,3 +100,56 @@ impl DecodedValueThunk {
         )
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+    use codec::Encode;
+    use frame_metadata::StorageEntryType;
+    use scale_value::{
+        scale::EncodeError,
+        At,
+        Composite,
+        ValueDef,
+    };
+
+    #[test]
+    fn decoded_values_can_be_modified_and_encoded_again() {
+        let metadata = metadata();
+        let entry = metadata
+            .pallet("System")
+            .unwrap()
+            .storage("Account")
+            .unwrap();
+        let StorageEntryType::Map { value: ty, .. } = &entry.ty else {
+            panic!("System::Account should be a map")
+        };
+
+        // An `AccountInfo`, with a nonce of 1 and a free balance of 10:
+        let account_info = (1u32, 0u32, 1u32, 0u32, 10u128, 0u128, 0u128, 0u128).encode();
+        let mut value =
+            DecodedValue::decode_with_metadata(&mut &*account_info, ty.id(), &metadata)
+                .unwrap();
+        assert_eq!(
+            encode_value(&value, ty.id(), &metadata).unwrap(),
+            account_info
+        );
+
+        // Bump the nonce:
+        let ValueDef::Composite(Composite::Named(fields)) = &mut value.value else {
+            panic!("AccountInfo should be a struct")
+        };
+        fields[0].1 = Value::u128(2).map_context(|_| fields[0].1.context);
+        assert_eq!(value.at("nonce").unwrap().as_u128(), Some(2));
+        let expected = (2u32, 0u32, 1u32, 0u32, 10u128, 0u128, 0u128, 0u128).encode();
+        assert_eq!(encode_value(&value, ty.id(), &metadata).unwrap(), expected);
+
+        // Values of the wrong shape are rejected:
+        let err = encode_value(&Value::bool(true), ty.id(), &metadata).unwrap_err();
+        assert!(
+            matches!(err, Error::EncodeValue(EncodeError::WrongShape { .. })),
+            "{err:?}"
+        );
+    }
+}
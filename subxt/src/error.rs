@@ -7,7 +7,12 @@
 use crate::metadata::Metadata;
 use codec::Decode;
 use core::fmt::Debug;
+use frame_metadata::StorageHasher;
 use scale_info::TypeDef;
+use scale_value::{
+    Value,
+    ValueDef,
+};
 use std::borrow::Cow;
 
 // Re-expose the errors we use from other crates here:
@@ -61,6 +66,9 @@ pub enum Error {
     /// An error encoding a storage address.
     #[error("Error encoding storage address: {0}")]
     StorageAddress(#[from] StorageAddressError),
+    /// An event could not be decoded.
+    #[error("Event decode error: {0}")]
+    EventDecode(#[from] EventDecodeError),
     /// Other error.
     #[error("Other error: {0}")]
     Other(String),
@@ -99,23 +107,27 @@ pub enum RpcError {
 }
 
 /// This is our attempt to decode a runtime DispatchError. We either
-/// successfully decode it into a [`ModuleError`], or we fail and keep
-/// hold of the bytes, which we can attempt to decode if we have an
-/// appropriate static type to hand.
+/// successfully decode it into a [`ModuleError`] or a [`RuntimeError`], or
+/// we fail and keep hold of the bytes, which we can attempt to decode if
+/// we have an appropriate static type to hand.
 #[derive(Debug, thiserror::Error)]
 pub enum DispatchError {
     /// An error was emitted from a specific pallet/module.
     #[error("Module error: {0}")]
     Module(ModuleError),
+    /// One of the other kinds of error that the runtime can dispatch with, such as
+    /// `BadOrigin` or `Token(FundsUnavailable)`.
+    #[error("Runtime error: {0}")]
+    Runtime(RuntimeError),
     /// Some other error was emitted.
     #[error("Undecoded dispatch error: {0:?}")]
     Other(Vec<u8>),
 }
 
 impl DispatchError {
-    /// Attempt to decode a runtime DispatchError, returning either the [`ModuleError`] it decodes
-    /// to, along with additional details on the error, or returning the raw bytes if it could not
-    /// be decoded.
+    /// Attempt to decode a runtime DispatchError, returning either the [`ModuleError`] or
+    /// [`RuntimeError`] it decodes to, along with additional details on the error, or returning
+    /// the raw bytes if it could not be decoded.
     pub fn decode_from<'a>(bytes: impl Into<Cow<'a, [u8]>>, metadata: &Metadata) -> Self {
         let bytes = bytes.into();
 
@@ -160,10 +172,14 @@ impl DispatchError {
             }
         };
 
-        // If the error bytes don't correspond to a ModuleError, just return the bytes.
-        // This is perfectly reasonable and expected, so no logging.
-        if bytes[0] != module_variant_idx {
-            return DispatchError::Other(bytes.into_owned())
+        // If the error bytes don't correspond to a ModuleError, decode whichever
+        // other variant they are, or just return the bytes if we can't.
+        if bytes.first() != Some(&module_variant_idx) {
+            return match RuntimeError::decode_from(&bytes, dispatch_error_ty_id, metadata)
+            {
+                Some(err) => DispatchError::Runtime(err),
+                None => DispatchError::Other(bytes.into_owned()),
+            }
         }
 
         // The remaining bytes are the module error, all being well:
@@ -221,6 +237,70 @@ impl DispatchError {
     }
 }
 
+/// A dispatch error which did not come from a pallet, such as `BadOrigin`, or one of the
+/// token, arithmetic or transactional errors (eg `Token(FundsUnavailable)`).
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind}{}", .reason.as_ref().map(|r| format!("::{r}")).unwrap_or_default())]
+pub struct RuntimeError {
+    /// The name of the `DispatchError` variant, for example `Token` or `BadOrigin`.
+    pub kind: String,
+    /// The name of the more specific error that the variant holds, if it holds one,
+    /// for example `FundsUnavailable` for a `Token` error.
+    pub reason: Option<String>,
+}
+
+impl RuntimeError {
+    /// A human readable name for the error, for example `Token::FundsUnavailable` or
+    /// `BadOrigin`.
+    pub fn name(&self) -> String {
+        match &self.reason {
+            Some(reason) => format!("{}::{reason}", self.kind),
+            None => self.kind.clone(),
+        }
+    }
+
+    // Decode the given bytes into the `DispatchError` type, handing back the names of the
+    // variant and of the error that it holds (if any).
+    fn decode_from(
+        bytes: &[u8],
+        type_id: u32,
+        metadata: &Metadata,
+    ) -> Option<RuntimeError> {
+        let value =
+            scale_value::scale::decode_as_type(&mut &*bytes, type_id, metadata.types())
+                .ok()?;
+        let ValueDef::Variant(variant) = value.value else {
+            return None
+        };
+        // The errors that variants hold, such as `TokenError`, are themselves plain enums.
+        let reason = match variant.values.values().collect::<Vec<_>>()[..] {
+            [Value {
+                value: ValueDef::Variant(inner),
+                ..
+            }] => Some(inner.name.clone()),
+            _ => None,
+        };
+        Some(RuntimeError {
+            kind: variant.name,
+            reason,
+        })
+    }
+}
+
+/// An event which could not be decoded. This is only handed back when decode error
+/// recovery has been enabled via [`crate::events::Events::with_decode_error_recovery()`].
+#[derive(Debug, thiserror::Error)]
+#[error("Could not decode event {index}: {error}")]
+pub struct EventDecodeError {
+    /// The index of the event that could not be decoded.
+    pub index: u32,
+    /// The raw bytes of the event that could not be decoded. Events are not length prefixed,
+    /// so if we could not find where the event ends, this contains every remaining byte.
+    pub bytes: Vec<u8>,
+    /// The reason that the event could not be decoded.
+    pub error: Box<Error>,
+}
+
 /// Block error
 #[derive(Clone, Debug, Eq, thiserror::Error, PartialEq)]
 pub enum BlockError {
@@ -229,6 +309,31 @@ pub enum BlockError {
         "Could not find a block with hash {0} (perhaps it was on a non-finalized fork?)"
     )]
     BlockHashNotFound(String),
+    /// There is no block with the given number, most likely because the chain hasn't
+    /// reached it yet.
+    #[error("Could not find a block with number {0}")]
+    BlockNumberNotFound(u64),
+    /// The node skipped over more finalized blocks than the subscription was willing to
+    /// fill in. The blocks from `from` to `to` (inclusive) were not handed back.
+    #[error("Finalized blocks #{from} to #{to} were skipped, which is more than the maximum gap that will be filled in")]
+    LargeGap {
+        /// The first block number that was skipped.
+        from: u64,
+        /// The last block number that was skipped.
+        to: u64,
+    },
+    /// Some other block was finalized at the height of the block that was being waited on,
+    /// so it will never be finalized.
+    #[error("Block {hash} was orphaned; block {finalized} was finalized in its place")]
+    Orphaned {
+        /// The hash of the orphaned block.
+        hash: String,
+        /// The hash of the block that was finalized at the same height.
+        finalized: String,
+    },
+    /// The block being waited on wasn't finalized before the timeout.
+    #[error("Block {0} was not finalized before the timeout")]
+    FinalityTimeout(String),
 }
 
 impl BlockError {
@@ -315,4 +420,97 @@ pub enum StorageAddressError {
         /// The number of fields in the metadata for this storage entry.
         fields: usize,
     },
+    /// The keys of a storage address were built with hashers or key types which don't
+    /// match the storage entry in the metadata, and so would point at the wrong place.
+    #[error(
+        "Storage address for {pallet_name}::{entry_name} does not match the metadata: {}",
+        join_mismatches(.mismatches)
+    )]
+    KeysDoNotMatch {
+        /// The name of the pallet that the entry lives under.
+        pallet_name: String,
+        /// The name of the storage entry.
+        entry_name: String,
+        /// Each way in which the keys don't match the metadata.
+        mismatches: Vec<StorageKeyMismatch>,
+    },
+}
+
+/// A way in which the keys of a storage address don't match the storage entry in the
+/// metadata. Keys are counted from 0, in the order in which they're appended to the
+/// address.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum StorageKeyMismatch {
+    /// The key is hashed with a different hasher to the one in the metadata.
+    #[error("key {index} is hashed with {actual:?}, but the metadata uses {expected:?}")]
+    Hasher {
+        /// The position of the key.
+        index: usize,
+        /// The hasher in the metadata.
+        expected: StorageHasher,
+        /// The hasher that the address hashes the key with.
+        actual: StorageHasher,
+    },
+    /// The encoded key can't be decoded as the key type in the metadata.
+    #[error("key {index} does not decode as the key type {type_id} in the metadata")]
+    KeyType {
+        /// The position of the key.
+        index: usize,
+        /// The ID of the key type in the metadata.
+        type_id: u32,
+    },
+    /// The address has more keys than the storage entry has hashers for.
+    #[error("the address has {actual} keys, but the metadata only has {expected}")]
+    NumberOfKeys {
+        /// The number of keys in the metadata.
+        expected: usize,
+        /// The number of keys in the address.
+        actual: usize,
+    },
+}
+
+fn join_mismatches(mismatches: &[StorageKeyMismatch]) -> String {
+    mismatches
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+
+    fn runtime_error(bytes: &[u8]) -> RuntimeError {
+        match DispatchError::decode_from(bytes, &metadata()) {
+            DispatchError::Runtime(err) => err,
+            other => panic!("expected a runtime error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn decodes_non_module_dispatch_errors() {
+        // `DispatchError::BadOrigin`:
+        let err = runtime_error(&[2]);
+        assert_eq!(err.kind, "BadOrigin");
+        assert_eq!(err.reason, None);
+        assert_eq!(err.to_string(), "BadOrigin");
+
+        // `DispatchError::Arithmetic(ArithmeticError::Overflow)`:
+        let err = runtime_error(&[8, 1]);
+        assert_eq!(err.name(), "Arithmetic::Overflow");
+        assert_eq!(err.to_string(), "Arithmetic::Overflow");
+
+        // `DispatchError::Token(TokenError::NoFunds)`:
+        let err = runtime_error(&[7, 0]);
+        assert_eq!(err.kind, "Token");
+        assert_eq!(err.reason.as_deref(), Some("NoFunds"));
+
+        // Bytes which aren't a valid `DispatchError` are handed back:
+        assert!(matches!(
+            DispatchError::decode_from(&[200u8][..], &metadata()),
+            DispatchError::Other(bytes) if bytes == vec![200]
+        ));
+    }
 }
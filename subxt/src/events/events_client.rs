@@ -3,14 +3,49 @@
 // see LICENSE for license details.
 
 use crate::{
-    client::OnlineClientT,
-    error::Error,
-    events::Events,
+    client::{
+        metadata_at_block,
+        OnlineClientT,
+    },
+    error::{
+        BlockError,
+        Error,
+    },
+    events::{
+        Events,
+        Phase,
+        StaticEvent,
+    },
     rpc::types::StorageKey,
     Config,
 };
 use derivative::Derivative;
-use std::future::Future;
+use futures::{
+    stream,
+    Stream,
+    StreamExt,
+};
+use std::{
+    future::Future,
+    ops::Range,
+};
+
+/// An event found by [`EventsClient::find_in_range()`], along with details of where it was
+/// found.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "Ev: Clone"), Debug(bound = "Ev: std::fmt::Debug"))]
+pub struct EventInBlock<T: Config, Ev> {
+    /// The number of the block that the event was emitted in.
+    pub block_number: u64,
+    /// The hash of the block that the event was emitted in.
+    pub block_hash: T::Hash,
+    /// The phase of the block's execution that the event was emitted in.
+    pub phase: Phase,
+    /// The index of the event in the block.
+    pub index: u32,
+    /// The event itself.
+    pub event: Ev,
+}
 
 /// A client for working with events.
 #[derive(Derivative)]
@@ -69,6 +104,83 @@ where
     }
 }
 
+impl<T, Client> EventsClient<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T> + Send + Sync + 'static,
+{
+    /// Find every event of type `Ev` in the given range of block numbers, handing them back
+    /// in the order they were emitted. The events of up to `look_ahead` blocks are fetched
+    /// concurrently, ahead of the consumer; a `look_ahead` of 0 is treated as 1.
+    ///
+    /// The events in each block are decoded using the metadata of the runtime that was in
+    /// use at that block, so the range can span runtime upgrades, provided that `Ev` decodes
+    /// the same way either side of them. Events aren't length prefixed, so every event in
+    /// a block is still decoded dynamically to find where it ends, but only those of type
+    /// `Ev` are decoded into an `Ev`.
+    pub fn find_in_range<Ev>(
+        &self,
+        block_numbers: Range<u64>,
+        look_ahead: usize,
+    ) -> impl Stream<Item = Result<EventInBlock<T, Ev>, Error>> + Send + 'static
+    where
+        Ev: StaticEvent + Send + 'static,
+    {
+        let client = self.client.clone();
+        stream::iter(block_numbers)
+            .map(move |block_number| {
+                let client = client.clone();
+                async move { find_in_block::<T, Client, Ev>(&client, block_number).await }
+            })
+            .buffered(look_ahead.max(1))
+            .flat_map(|events| {
+                let events = match events {
+                    Ok(events) => events.into_iter().map(Ok).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                stream::iter(events)
+            })
+    }
+}
+
+// Find the events of type `Ev` in the block with the given number.
+async fn find_in_block<T, Client, Ev>(
+    client: &Client,
+    block_number: u64,
+) -> Result<Vec<EventInBlock<T, Ev>>, Error>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+    Ev: StaticEvent,
+{
+    let block_hash = client
+        .rpc()
+        .block_hash(Some(block_number.into()))
+        .await?
+        .ok_or(BlockError::BlockNumberNotFound(block_number))?;
+    let metadata = metadata_at_block(client, block_hash).await?;
+    let event_bytes = get_event_bytes(client, Some(block_hash)).await?;
+    let events = Events::<T>::new(metadata, block_hash, event_bytes);
+
+    let mut found = Vec::new();
+    for ev in events.iter() {
+        let ev = ev?;
+        if !Ev::is_event(ev.pallet_name(), ev.variant_name()) {
+            continue
+        }
+        if let Some(event) = ev.as_event::<Ev>()? {
+            found.push(EventInBlock {
+                block_number,
+                block_hash,
+                phase: ev.phase(),
+                index: ev.index(),
+                event,
+            });
+        }
+    }
+    Ok(found)
+}
+
 // The storage key needed to access events.
 fn system_events_key() -> StorageKey {
     let mut storage_key = sp_core_hashing::twox_128(b"System").to_vec();
@@ -10,7 +10,10 @@ use super::{
 };
 use crate::{
     client::OnlineClientT,
-    error::Error,
+    error::{
+        Error,
+        EventDecodeError,
+    },
     events::events_client::get_event_bytes,
     metadata::EventMetadata,
     Config,
@@ -37,6 +40,7 @@ pub struct Events<T: Config> {
     event_bytes: Arc<[u8]>,
     start_idx: usize,
     num_events: u32,
+    recover_decode_errors: bool,
 }
 
 impl<T: Config> Events<T> {
@@ -63,9 +67,25 @@ impl<T: Config> Events<T> {
             event_bytes: event_bytes.into(),
             start_idx,
             num_events,
+            recover_decode_errors: false,
         }
     }
 
+    /// Configure how errors decoding individual events are reported. By default, the
+    /// underlying decode error is handed back. When recovery is enabled, errors are instead
+    /// handed back as an [`Error::EventDecode`], which contains the index and raw bytes of
+    /// the offending event, so that it can be recorded and dealt with later.
+    ///
+    /// Errors decoding an event into some static type (see [`Events::find()`]) don't affect
+    /// any other events. However, events are not length prefixed, and so if we can't work out
+    /// where an event ends (for instance because the metadata doesn't know about it), we still
+    /// can't decode any of the events that follow it. In this case, the error contains all of
+    /// the remaining event bytes.
+    pub fn with_decode_error_recovery(mut self, recover: bool) -> Self {
+        self.recover_decode_errors = recover;
+        self
+    }
+
     /// Obtain the events from a block hash given custom metadata and a client.
     ///
     /// This method gives users the ability to inspect the events of older blocks,
@@ -138,6 +158,7 @@ impl<T: Config> Events<T> {
         let event_bytes = self.event_bytes.clone();
         let metadata = self.metadata.clone();
         let num_events = self.num_events;
+        let recover_decode_errors = self.recover_decode_errors;
 
         let mut pos = self.start_idx;
         let mut index = 0;
@@ -160,6 +181,16 @@ impl<T: Config> Events<T> {
                         Some(Ok(event_details))
                     }
                     Err(e) => {
+                        let e = if recover_decode_errors {
+                            EventDecodeError {
+                                index,
+                                bytes: event_bytes[pos..].to_vec(),
+                                error: Box::new(e),
+                            }
+                            .into()
+                        } else {
+                            e
+                        };
                         // By setting the position to the "end" of the event bytes,
                         // the cursor len will become 0 and the iterator will return `None`
                         // from now on:
@@ -171,13 +202,69 @@ impl<T: Config> Events<T> {
         })
     }
 
+    /// Decode all of the events up front, handing them back in a `Vec` so that they can be
+    /// indexed into or iterated over in any order. Each [`EventDetails`] keeps track of its
+    /// own phase and index, so these are unaffected by the order that they're looked at in.
+    ///
+    /// Events must be decoded in order, so if any event fails to decode, the error is handed
+    /// back instead.
+    pub fn all(&self) -> Result<Vec<EventDetails>, Error> {
+        self.iter().collect()
+    }
+
+    /// Iterate over the events in reverse order, so the most recent event comes first.
+    /// All of the events are decoded up front; see [`Events::all()`].
+    pub fn iter_rev(
+        &self,
+    ) -> Result<
+        impl DoubleEndedIterator<Item = EventDetails> + Send + Sync + 'static,
+        Error,
+    > {
+        Ok(self.all()?.into_iter().rev())
+    }
+
     /// Iterate through the events using metadata to dynamically decode and skip
     /// them, and return only those which should decode to the provided `Ev` type.
     /// If an error occurs, all subsequent iterations return `None`.
     pub fn find<Ev: StaticEvent>(&self) -> impl Iterator<Item = Result<Ev, Error>> + '_ {
         self.iter().filter_map(|ev| {
-            ev.and_then(|ev| ev.as_event::<Ev>().map_err(Into::into))
-                .transpose()
+            ev.and_then(|ev| {
+                ev.as_event::<Ev>()
+                    .map_err(|e| self.static_decode_error(&ev, e))
+            })
+            .transpose()
+        })
+    }
+
+    // Turn an error decoding some event into a static type into an `Error`, taking
+    // into account whether we want to recover from decode errors.
+    pub(crate) fn static_decode_error(&self, ev: &EventDetails, e: CodecError) -> Error {
+        if self.recover_decode_errors {
+            EventDecodeError {
+                index: ev.index(),
+                bytes: ev.bytes().to_vec(),
+                error: Box::new(e.into()),
+            }
+            .into()
+        } else {
+            e.into()
+        }
+    }
+
+    /// Iterate through the events, returning only those which were deposited with the
+    /// topic given. If an error occurs, all subsequent iterations return `None`.
+    pub fn find_by_topic(
+        &self,
+        topic: T::Hash,
+    ) -> impl Iterator<Item = Result<EventDetails, Error>> + '_ {
+        self.iter().filter_map(move |ev| {
+            ev.and_then(|ev| {
+                let has_topic = ev
+                    .has_topic(&topic)
+                    .map_err(|e| self.static_decode_error(&ev, e))?;
+                Ok(has_topic.then_some(ev))
+            })
+            .transpose()
         })
     }
 
@@ -253,8 +340,8 @@ impl EventDetails {
         // the end of the field bytes.
         let event_fields_end_idx = all_bytes.len() - input.len();
 
-        // topics come after the event data in EventRecord. They aren't used for
-        // anything at the moment, so just decode and throw them away.
+        // topics come after the event data in EventRecord. Decode them to find out
+        // where they end; they can be decoded again via `EventDetails::topics()`.
         let _topics = Vec::<T::Hash>::decode(input)?;
 
         // what bytes did we skip over in total, including topics.
@@ -392,6 +479,108 @@ impl EventDetails {
     pub fn as_root_event<E: Decode>(&self) -> Result<E, CodecError> {
         E::decode(&mut &self.all_bytes[self.event_start_idx..self.event_fields_end_idx])
     }
+
+    /// The SCALE encoded topics that the event was deposited with. Topics are hashes that
+    /// can be used to find events of interest without decoding every event.
+    pub fn topic_bytes(&self) -> &[u8] {
+        &self.all_bytes[self.event_fields_end_idx..self.end_idx]
+    }
+
+    /// Decode the topics that the event was deposited with. `Hash` is usually `T::Hash`.
+    pub fn topics<Hash: Decode>(&self) -> Result<Vec<Hash>, CodecError> {
+        Vec::<Hash>::decode(&mut self.topic_bytes())
+    }
+
+    /// Was the event deposited with the topic given?
+    pub fn has_topic<Hash: Decode + PartialEq>(
+        &self,
+        topic: &Hash,
+    ) -> Result<bool, CodecError> {
+        Ok(self.topics::<Hash>()?.contains(topic))
+    }
+}
+
+/// An event as it's stored in `System::Events`, along with the phase of the block that it
+/// was produced in and the topics that it was deposited with.
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""), Clone(bound = ""))]
+pub struct EventRecord<T: Config> {
+    /// When the event was produced.
+    pub phase: Phase,
+    /// The event itself.
+    pub event: EventDetails,
+    /// The topics that the event was deposited with.
+    pub topics: Vec<T::Hash>,
+}
+
+/// Decode the raw, SCALE encoded `Vec<EventRecord>` found in `System::Events` storage
+/// according to the metadata given. Unlike [`Events`], this doesn't need a client or a block
+/// hash, and so it can be used to decode events from storage proofs, snapshots and the like.
+///
+/// An error is handed back if any of the records can't be decoded, or if there are any bytes
+/// left over once all of them have been.
+pub fn decode_event_records<T: Config>(
+    event_bytes: &[u8],
+    metadata: &Metadata,
+) -> Result<Vec<EventRecord<T>>, Error> {
+    let all_bytes: Arc<[u8]> = event_bytes.into();
+    let cursor = &mut &*all_bytes;
+    let num_events = <Compact<u32>>::decode(cursor)?.0;
+
+    let mut pos = all_bytes.len() - cursor.len();
+    let mut records = Vec::new();
+    for index in 0..num_events {
+        let event = EventDetails::decode_from::<T>(
+            metadata.clone(),
+            all_bytes.clone(),
+            pos,
+            index,
+        )?;
+        pos += event.bytes().len();
+        records.push(EventRecord {
+            phase: event.phase(),
+            topics: event.topics::<T::Hash>()?,
+            event,
+        });
+    }
+
+    if pos != all_bytes.len() {
+        return Err(Error::Other(format!(
+            "{} bytes were left over after decoding {num_events} event records",
+            all_bytes.len() - pos
+        )))
+    }
+    Ok(records)
+}
+
+/// Decode the raw `System::Events` bytes of many blocks in parallel, handing back the
+/// event records of each block (or the error decoding them) in the order that the blocks
+/// were given. Each block comes with its hash and the metadata to decode its events with,
+/// as blocks either side of a runtime upgrade need different metadata.
+///
+/// This is [`decode_event_records()`] spread over the threads of the current rayon thread
+/// pool, and so doesn't fetch anything itself; fetch the event bytes however suits you.
+/// To control how many threads are used, call this from within
+/// [`rayon::ThreadPool::install()`].
+#[cfg(feature = "rayon")]
+pub fn decode_event_records_par<T, B>(
+    blocks: &[(T::Hash, B, Metadata)],
+) -> Vec<(T::Hash, Result<Vec<EventRecord<T>>, Error>)>
+where
+    T: Config,
+    B: AsRef<[u8]> + Sync,
+{
+    use rayon::prelude::*;
+
+    blocks
+        .par_iter()
+        .map(|(block_hash, event_bytes, metadata)| {
+            (
+                *block_hash,
+                decode_event_records::<T>(event_bytes.as_ref(), metadata),
+            )
+        })
+        .collect()
 }
 
 /// Event related test utilities used outside this module.
@@ -443,11 +632,29 @@ pub(crate) mod test_utils {
         }
     }
 
+    impl<E: Encode> EventRecord<E> {
+        /// Set the topics that the event is deposited with.
+        pub fn with_topics(
+            mut self,
+            topics: Vec<<SubstrateConfig as Config>::Hash>,
+        ) -> Self {
+            self.topics = topics;
+            self
+        }
+    }
+
     /// Build fake metadata consisting of a single pallet that knows
     /// about the event type provided.
     pub fn metadata<E: TypeInfo + 'static>() -> Metadata {
+        metadata_with_pallet::<E>("Test")
+    }
+
+    /// Much like [`metadata`], but the pallet is given the name provided.
+    pub fn metadata_with_pallet<E: TypeInfo + 'static>(
+        pallet_name: &'static str,
+    ) -> Metadata {
         let pallets = vec![PalletMetadata {
-            name: "Test",
+            name: pallet_name,
             storage: None,
             calls: None,
             event: Some(PalletEventMetadata {
@@ -513,6 +720,7 @@ mod tests {
         },
         *,
     };
+    use crate::SubstrateConfig;
     use codec::Encode;
     use scale_info::TypeInfo;
     use scale_value::Value;
@@ -721,6 +929,39 @@ mod tests {
         assert!(event_details.next().is_none());
     }
 
+    #[test]
+    fn events_can_be_iterated_in_reverse() {
+        #[derive(Clone, Copy, Debug, PartialEq, Decode, Encode, TypeInfo)]
+        enum Event {
+            A(u8),
+        }
+
+        let metadata = metadata::<Event>();
+        let events = events::<Event>(
+            metadata,
+            vec![
+                event_record(Phase::Initialization, Event::A(1)),
+                event_record(Phase::ApplyExtrinsic(123), Event::A(2)),
+                event_record(Phase::Finalization, Event::A(3)),
+            ],
+        );
+
+        let reversed: Vec<_> = events
+            .iter_rev()
+            .unwrap()
+            .map(|ev| (ev.index(), ev.phase(), ev.field_bytes().to_vec()))
+            .collect();
+        assert_eq!(
+            reversed,
+            vec![
+                (2, Phase::Finalization, vec![3]),
+                (1, Phase::ApplyExtrinsic(123), vec![2]),
+                (0, Phase::Initialization, vec![1]),
+            ]
+        );
+        assert_eq!(events.all().unwrap()[1].index(), 1);
+    }
+
     #[test]
     fn dynamically_decode_multiple_events_until_error() {
         #[derive(Clone, Debug, PartialEq, Decode, Encode, TypeInfo)]
@@ -784,6 +1025,60 @@ mod tests {
         assert!(events_iter.next().is_none());
     }
 
+    #[test]
+    fn decode_errors_can_be_recovered() {
+        #[derive(Clone, Debug, PartialEq, Decode, Encode, TypeInfo)]
+        enum Event {
+            A(u8),
+            B(bool),
+        }
+
+        // This doesn't line up with `Event::B`, and so will fail to decode:
+        #[derive(Debug, Decode)]
+        struct StaticB {
+            _value: u32,
+        }
+        impl StaticEvent for StaticB {
+            const PALLET: &'static str = "Test";
+            const EVENT: &'static str = "B";
+        }
+
+        let metadata = metadata::<Event>();
+
+        let mut event_bytes = vec![];
+        event_record(Phase::Initialization, Event::B(true)).encode_to(&mut event_bytes);
+        event_record(Phase::Initialization, Event::A(1)).encode_to(&mut event_bytes);
+        event_record(Phase::Finalization, Event::B(false)).encode_to(&mut event_bytes);
+        event_bytes.extend_from_slice(&[3, 127, 45, 0, 2]);
+
+        let events =
+            events_raw(metadata, event_bytes, 4).with_decode_error_recovery(true);
+
+        // Each event which can't be statically decoded is handed back with its bytes,
+        // followed by the error from the broken final event:
+        let found: Vec<_> = events.find::<StaticB>().collect();
+        assert_eq!(found.len(), 3);
+        let mut expected_bytes = vec![];
+        event_record(Phase::Finalization, Event::B(false)).encode_to(&mut expected_bytes);
+        match &found[1] {
+            Err(Error::EventDecode(e)) => {
+                assert_eq!(e.index, 2);
+                assert_eq!(e.bytes, expected_bytes);
+            }
+            other => panic!("expected an EventDecode error, got {other:?}"),
+        }
+
+        // Events we can't find the end of hand back all of the remaining bytes:
+        match events.iter().nth(3) {
+            Some(Err(Error::EventDecode(e))) => {
+                assert_eq!(e.index, 3);
+                assert_eq!(e.bytes, vec![3, 127, 45, 0, 2]);
+            }
+            other => panic!("expected an EventDecode error, got {other:?}"),
+        }
+        assert_eq!(events.iter().filter(|ev| ev.is_ok()).count(), 3);
+    }
+
     #[test]
     fn compact_event_field() {
         #[derive(Clone, Debug, PartialEq, Encode, Decode, TypeInfo)]
@@ -901,4 +1196,116 @@ mod tests {
         );
         assert!(event_details.next().is_none());
     }
+
+    #[test]
+    fn event_records_can_be_decoded_from_raw_bytes() {
+        #[derive(Clone, Debug, PartialEq, Decode, Encode, TypeInfo)]
+        enum Event {
+            A(u8),
+            B(bool),
+        }
+
+        let metadata = metadata::<Event>();
+        let topic = <SubstrateConfig as Config>::Hash::repeat_byte(7);
+        let mut event_bytes = Compact(2u32).encode();
+        event_record(Phase::Initialization, Event::A(1)).encode_to(&mut event_bytes);
+        event_record(Phase::ApplyExtrinsic(2), Event::B(true))
+            .with_topics(vec![topic])
+            .encode_to(&mut event_bytes);
+
+        let records =
+            decode_event_records::<SubstrateConfig>(&event_bytes, &metadata).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].phase, Phase::Initialization);
+        assert!(records[0].topics.is_empty());
+        assert_eq!(records[1].phase, Phase::ApplyExtrinsic(2));
+        assert_eq!(records[1].topics, vec![topic]);
+        assert_eq!(records[1].event.index(), 1);
+        assert_eq!(
+            records[1]
+                .event
+                .as_root_event::<AllEvents<Event>>()
+                .unwrap(),
+            AllEvents::Test(Event::B(true))
+        );
+
+        // Bytes left over after the last record are an error:
+        event_bytes.push(0);
+        assert!(
+            decode_event_records::<SubstrateConfig>(&event_bytes, &metadata).is_err()
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn event_records_of_many_blocks_can_be_decoded_in_parallel() {
+        #[derive(Clone, Debug, PartialEq, Decode, Encode, TypeInfo)]
+        enum Event {
+            A(u8),
+        }
+
+        let metadata = metadata::<Event>();
+        let blocks: Vec<_> = (0..20u8)
+            .map(|n| {
+                let mut event_bytes = Compact(u32::from(n)).encode();
+                for _ in 0..n {
+                    event_record(Phase::Finalization, Event::A(n))
+                        .encode_to(&mut event_bytes);
+                }
+                // One block's events can't be decoded:
+                if n == 5 {
+                    event_bytes.push(0);
+                }
+                let block_hash = <SubstrateConfig as Config>::Hash::repeat_byte(n);
+                (block_hash, event_bytes, metadata.clone())
+            })
+            .collect();
+
+        let decoded = decode_event_records_par::<SubstrateConfig, _>(&blocks);
+        assert_eq!(decoded.len(), blocks.len());
+        for (n, (block_hash, records)) in decoded.into_iter().enumerate() {
+            assert_eq!(block_hash, blocks[n].0);
+            if n == 5 {
+                assert!(records.is_err());
+            } else {
+                assert_eq!(records.unwrap().len(), n);
+            }
+        }
+    }
+
+    #[test]
+    fn events_can_be_found_by_topic() {
+        #[derive(Clone, Debug, PartialEq, Decode, Encode, TypeInfo)]
+        enum Event {
+            A(u8),
+        }
+
+        let topic = <SubstrateConfig as Config>::Hash::repeat_byte(1);
+        let other_topic = <SubstrateConfig as Config>::Hash::repeat_byte(2);
+        let events = events::<Event>(
+            metadata::<Event>(),
+            vec![
+                event_record(Phase::Initialization, Event::A(1))
+                    .with_topics(vec![other_topic, topic]),
+                event_record(Phase::Initialization, Event::A(2)),
+                event_record(Phase::Finalization, Event::A(3)).with_topics(vec![topic]),
+            ],
+        );
+
+        let found: Vec<_> = events
+            .find_by_topic(topic)
+            .map(|ev| ev.unwrap().index())
+            .collect();
+        assert_eq!(found, vec![0, 2]);
+        assert_eq!(events.find_by_topic(other_topic).count(), 1);
+
+        let first = events.iter().next().unwrap().unwrap();
+        assert_eq!(
+            first.topics::<<SubstrateConfig as Config>::Hash>().unwrap(),
+            vec![other_topic, topic]
+        );
+        assert!(!first
+            .has_topic(&<SubstrateConfig as Config>::Hash::zero())
+            .unwrap());
+    }
 }
@@ -7,13 +7,34 @@
 //! and calls like [crate::tx::TxProgress::wait_for_finalized_success()].
 
 mod events_client;
-mod events_type;
+pub(crate) mod events_type;
+mod para_inclusion;
+mod xcm;
 
-pub use events_client::EventsClient;
+pub use events_client::{
+    EventInBlock,
+    EventsClient,
+};
+#[cfg(feature = "rayon")]
+pub use events_type::decode_event_records_par;
 pub use events_type::{
+    decode_event_records,
     EventDetails,
+    EventRecord,
     Events,
 };
+pub use para_inclusion::{
+    para_inclusion_pallet_name,
+    CandidateEvent,
+    CandidateEventKind,
+    PARA_INCLUSION_PALLET_NAMES,
+};
+pub use xcm::{
+    xcm_pallet_name,
+    XcmEvent,
+    XcmMessage,
+    XCM_PALLET_NAMES,
+};
 
 use codec::{
     Decode,
@@ -0,0 +1,226 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Dynamically decode the candidate events that the parachain inclusion pallet of a relay
+//! chain emits as parachain blocks are backed, included and timed out.
+
+use super::EventDetails;
+use crate::{
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::Metadata,
+    storage::utils::{
+        value_as_bytes,
+        value_as_u32,
+    },
+};
+use scale_value::At;
+
+/// The names that the parachain inclusion pallet has been given in relay chain runtimes.
+pub const PARA_INCLUSION_PALLET_NAMES: [&str; 2] = ["ParaInclusion", "ParasInclusion"];
+
+/// Return the name of the parachain inclusion pallet in the given metadata, if there is
+/// one.
+pub fn para_inclusion_pallet_name(metadata: &Metadata) -> Option<&'static str> {
+    PARA_INCLUSION_PALLET_NAMES
+        .into_iter()
+        .find(|name| metadata.pallet(name).is_ok())
+}
+
+/// What happened to a parachain block candidate.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CandidateEventKind {
+    /// The candidate was backed by validators, and is waiting to be included.
+    Backed,
+    /// The candidate was included in the relay chain.
+    Included,
+    /// The candidate wasn't made available in time, and was dropped.
+    TimedOut,
+}
+
+/// A `CandidateBacked`, `CandidateIncluded` or `CandidateTimedOut` event, emitted by the
+/// parachain inclusion pallet of a relay chain.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CandidateEvent {
+    /// What happened to the candidate.
+    pub kind: CandidateEventKind,
+    /// The ID of the parachain that the candidate is a block of.
+    pub para_id: u32,
+    /// The head data of the candidate, which for most parachains is the SCALE encoded
+    /// header of the parachain block.
+    pub head_data: Vec<u8>,
+    /// The index of the availability core that the candidate occupies.
+    pub core_index: u32,
+    /// The index of the validator group responsible for the candidate. Timed out
+    /// candidates don't have one.
+    pub group_index: Option<u32>,
+    /// The whole candidate receipt, which also holds the relay parent, collator and
+    /// hashes of the candidate.
+    pub candidate_receipt: DecodedValue,
+}
+
+impl EventDetails {
+    /// Attempt to decode these [`EventDetails`] into a [`CandidateEvent`]. This returns
+    /// `Ok(None)` if the event isn't a candidate event from the parachain inclusion pallet.
+    pub fn as_candidate_event(&self) -> Result<Option<CandidateEvent>, Error> {
+        if !PARA_INCLUSION_PALLET_NAMES.contains(&self.pallet_name()) {
+            return Ok(None)
+        }
+
+        let event_name = self.variant_name();
+        let kind = match event_name {
+            "CandidateBacked" => CandidateEventKind::Backed,
+            "CandidateIncluded" => CandidateEventKind::Included,
+            "CandidateTimedOut" => CandidateEventKind::TimedOut,
+            _ => return Ok(None),
+        };
+
+        // The fields of these events are unnamed, so we go by position.
+        let mut fields = self.field_values()?.into_values();
+        let mut next_field = |name: &str| {
+            fields.next().ok_or_else(|| {
+                Error::Other(format!(
+                    "Could not find the {name} of the {}::{event_name} event",
+                    self.pallet_name()
+                ))
+            })
+        };
+        let malformed = |name: &str, value: &DecodedValue| {
+            Error::Other(format!(
+                "Could not interpret {value} as the {name} of the {}::{event_name} event",
+                self.pallet_name()
+            ))
+        };
+
+        let candidate_receipt = next_field("candidate receipt")?;
+        let head_data = next_field("head data")?;
+        let core_index = next_field("core index")?;
+        let group_index = match kind {
+            CandidateEventKind::TimedOut => None,
+            _ => Some(next_field("group index")?),
+        };
+
+        let para_id = candidate_receipt
+            .at("descriptor")
+            .and_then(|descriptor| descriptor.at("para_id"))
+            .and_then(value_as_u32)
+            .ok_or_else(|| malformed("candidate receipt", &candidate_receipt))?;
+        let head_data = value_as_bytes(&head_data)
+            .ok_or_else(|| malformed("head data", &head_data))?;
+        let core_index = value_as_u32(&core_index)
+            .ok_or_else(|| malformed("core index", &core_index))?;
+        let group_index = group_index
+            .map(|index| {
+                value_as_u32(&index).ok_or_else(|| malformed("group index", &index))
+            })
+            .transpose()?;
+
+        Ok(Some(CandidateEvent {
+            kind,
+            para_id,
+            head_data,
+            core_index,
+            group_index,
+            candidate_receipt,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{
+        events_type::test_utils,
+        Phase,
+    };
+    use codec::{
+        Decode,
+        Encode,
+    };
+    use scale_info::TypeInfo;
+
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    struct Id(u32);
+
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    struct HeadData(Vec<u8>);
+
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    struct CandidateDescriptor {
+        para_id: Id,
+        relay_parent: [u8; 32],
+    }
+
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    struct CandidateReceipt {
+        descriptor: CandidateDescriptor,
+        commitments_hash: [u8; 32],
+    }
+
+    #[allow(dead_code)]
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    enum Event {
+        CandidateBacked(CandidateReceipt, HeadData, u32, u32),
+        CandidateIncluded(CandidateReceipt, HeadData, u32, u32),
+        CandidateTimedOut(CandidateReceipt, HeadData, u32),
+        UpwardMessagesReceived(Id, u32, u32),
+    }
+
+    fn receipt(para_id: u32) -> CandidateReceipt {
+        CandidateReceipt {
+            descriptor: CandidateDescriptor {
+                para_id: Id(para_id),
+                relay_parent: [1; 32],
+            },
+            commitments_hash: [2; 32],
+        }
+    }
+
+    #[test]
+    fn decodes_candidate_events() {
+        let metadata = test_utils::metadata_with_pallet::<Event>("ParaInclusion");
+        let events = test_utils::events(
+            metadata,
+            vec![
+                test_utils::event_record(
+                    Phase::Initialization,
+                    Event::CandidateIncluded(
+                        receipt(1000),
+                        HeadData(vec![1, 2, 3]),
+                        4,
+                        5,
+                    ),
+                ),
+                test_utils::event_record(
+                    Phase::Initialization,
+                    Event::CandidateTimedOut(receipt(2000), HeadData(vec![]), 6),
+                ),
+                test_utils::event_record(
+                    Phase::Initialization,
+                    Event::UpwardMessagesReceived(Id(1000), 1, 1),
+                ),
+            ],
+        );
+        let found: Vec<_> = events
+            .iter()
+            .map(|ev| ev.unwrap().as_candidate_event().unwrap())
+            .collect();
+
+        let included = found[0].as_ref().unwrap();
+        assert_eq!(included.kind, CandidateEventKind::Included);
+        assert_eq!(included.para_id, 1000);
+        assert_eq!(included.head_data, vec![1, 2, 3]);
+        assert_eq!(included.core_index, 4);
+        assert_eq!(included.group_index, Some(5));
+
+        let timed_out = found[1].as_ref().unwrap();
+        assert_eq!(timed_out.kind, CandidateEventKind::TimedOut);
+        assert_eq!(timed_out.para_id, 2000);
+        assert!(timed_out.head_data.is_empty());
+        assert_eq!(timed_out.group_index, None);
+
+        // Other events from the pallet are ignored:
+        assert_eq!(found[2], None);
+    }
+}
@@ -0,0 +1,263 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Dynamically decode the events of the XCM pallet. XCM types change shape between
+//! versions and runtimes, so rather than decoding them into static types, this surfaces
+//! the structure that is common to them and hands back everything else as [`DecodedValue`]s.
+
+use super::EventDetails;
+use crate::{
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::Metadata,
+};
+use scale_value::{
+    Composite,
+    Value,
+    ValueDef,
+};
+
+/// The names that the XCM pallet is given in relay chain and parachain runtimes.
+pub const XCM_PALLET_NAMES: [&str; 2] = ["XcmPallet", "PolkadotXcm"];
+
+/// Return the name of the XCM pallet in the given metadata, if there is one.
+pub fn xcm_pallet_name(metadata: &Metadata) -> Option<&'static str> {
+    XCM_PALLET_NAMES
+        .into_iter()
+        .find(|name| metadata.pallet(name).is_ok())
+}
+
+/// An XCM message, which is a list of instructions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XcmMessage {
+    /// The XCM version that the message is expressed in, if it was wrapped in a
+    /// `VersionedXcm`.
+    pub version: Option<u32>,
+    /// The instructions in the message. Messages from before XCM v2 consist of a single
+    /// instruction, which is handed back on its own.
+    pub instructions: Vec<DecodedValue>,
+}
+
+impl XcmMessage {
+    /// Attempt to interpret a dynamically decoded `Xcm` or `VersionedXcm` as an
+    /// [`XcmMessage`], returning `None` if it doesn't have the expected shape.
+    pub fn from_value(value: DecodedValue) -> Option<XcmMessage> {
+        let (version, message) = match value.value {
+            ValueDef::Variant(v) => {
+                match xcm_version(&v.name) {
+                    Some(version) => (Some(version), single_value(v.values)?),
+                    // An unversioned, pre-v2 message is a single instruction.
+                    None => {
+                        return Some(XcmMessage {
+                            version: None,
+                            instructions: vec![Value {
+                                value: ValueDef::Variant(v),
+                                context: value.context,
+                            }],
+                        })
+                    }
+                }
+            }
+            value_def => {
+                (
+                    None,
+                    Value {
+                        value: value_def,
+                        context: value.context,
+                    },
+                )
+            }
+        };
+
+        match message.value {
+            // Pre-v2 versioned messages are a single instruction.
+            ValueDef::Variant(_) => {
+                Some(XcmMessage {
+                    version,
+                    instructions: vec![message],
+                })
+            }
+            // Later messages are a newtype around a list of instructions.
+            ValueDef::Composite(Composite::Unnamed(vals)) => {
+                let instructions = match <[_; 1]>::try_from(vals) {
+                    Ok(
+                        [Value {
+                            value: ValueDef::Composite(Composite::Unnamed(inner)),
+                            ..
+                        }],
+                    ) => inner,
+                    Ok([val]) => vec![val],
+                    Err(vals) => vals,
+                };
+                Some(XcmMessage {
+                    version,
+                    instructions,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// An event emitted by the XCM pallet which relates to executing or sending a message.
+#[derive(Clone, Debug, PartialEq)]
+pub enum XcmEvent {
+    /// A message was executed locally, with the given outcome.
+    Attempted {
+        /// The `Outcome` of executing the message.
+        outcome: DecodedValue,
+    },
+    /// A message was sent to another location.
+    Sent {
+        /// The location that the message was sent from.
+        origin: DecodedValue,
+        /// The location that the message was sent to.
+        destination: DecodedValue,
+        /// The message that was sent.
+        message: XcmMessage,
+    },
+}
+
+impl EventDetails {
+    /// Attempt to decode these [`EventDetails`] into an [`XcmEvent`]. This returns
+    /// `Ok(None)` if the event isn't an `Attempted` or `Sent` event from the XCM pallet.
+    pub fn as_xcm_event(&self) -> Result<Option<XcmEvent>, Error> {
+        if !XCM_PALLET_NAMES.contains(&self.pallet_name()) {
+            return Ok(None)
+        }
+
+        let event_name = self.variant_name();
+        if event_name != "Attempted" && event_name != "Sent" {
+            return Ok(None)
+        }
+
+        // Older runtimes use unnamed fields, so we go by position.
+        let mut fields = self.field_values()?.into_values();
+        let mut next_field = |name: &str| {
+            fields.next().ok_or_else(|| {
+                Error::Other(format!(
+                    "Could not find the {name} of the {}::{event_name} event",
+                    self.pallet_name()
+                ))
+            })
+        };
+
+        let event = if event_name == "Attempted" {
+            XcmEvent::Attempted {
+                outcome: next_field("outcome")?,
+            }
+        } else {
+            let origin = next_field("origin")?;
+            let destination = next_field("destination")?;
+            let message = next_field("message")?;
+            let message = XcmMessage::from_value(message.clone()).ok_or_else(|| {
+                Error::Other(format!("Could not interpret {message} as an XCM message"))
+            })?;
+            XcmEvent::Sent {
+                origin,
+                destination,
+                message,
+            }
+        };
+        Ok(Some(event))
+    }
+}
+
+// Versioned XCM types have variants named like "V3".
+fn xcm_version(variant_name: &str) -> Option<u32> {
+    variant_name.strip_prefix('V')?.parse().ok()
+}
+
+fn single_value<T>(composite: Composite<T>) -> Option<Value<T>> {
+    let mut values = composite.into_values();
+    let value = values.next()?;
+    values.next().is_none().then_some(value)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{
+        events_type::test_utils,
+        Phase,
+    };
+    use codec::{
+        Decode,
+        Encode,
+    };
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    enum Instruction {
+        ClearOrigin,
+        Trap(u64),
+    }
+
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    struct Xcm(Vec<Instruction>);
+
+    #[allow(dead_code)]
+    #[derive(Clone, Encode, TypeInfo)]
+    enum VersionedXcm {
+        V1(Instruction),
+        V3(Xcm),
+    }
+
+    fn decode(message: VersionedXcm) -> Option<XcmMessage> {
+        let value = crate::test_utils::decode(message);
+        XcmMessage::from_value(value)
+    }
+
+    #[test]
+    fn decodes_versioned_messages() {
+        let v3 = decode(VersionedXcm::V3(Xcm(vec![
+            Instruction::ClearOrigin,
+            Instruction::Trap(1),
+        ])))
+        .unwrap();
+        assert_eq!(v3.version, Some(3));
+        assert_eq!(v3.instructions.len(), 2);
+        assert!(
+            matches!(&v3.instructions[1].value, ValueDef::Variant(v) if v.name == "Trap")
+        );
+
+        let v1 = decode(VersionedXcm::V1(Instruction::ClearOrigin)).unwrap();
+        assert_eq!(v1.version, Some(1));
+        assert_eq!(v1.instructions.len(), 1);
+    }
+
+    #[test]
+    fn decodes_xcm_events() {
+        #[allow(dead_code)]
+        #[derive(Clone, Decode, Encode, TypeInfo)]
+        enum Event {
+            Attempted(u8),
+            Sent(u8, u8, Xcm),
+        }
+
+        let event = |metadata| {
+            test_utils::events(
+                metadata,
+                vec![test_utils::event_record(
+                    Phase::Finalization,
+                    Event::Sent(1, 2, Xcm(vec![Instruction::Trap(3)])),
+                )],
+            )
+        };
+
+        let events = event(test_utils::metadata_with_pallet::<Event>("PolkadotXcm"));
+        let ev = events.iter().next().unwrap().unwrap();
+        let Some(XcmEvent::Sent { message, .. }) = ev.as_xcm_event().unwrap() else {
+            panic!("expected a Sent event")
+        };
+        assert_eq!(message.version, None);
+        assert_eq!(message.instructions.len(), 1);
+
+        // Events from other pallets are ignored:
+        let events = event(test_utils::metadata::<Event>());
+        let ev = events.iter().next().unwrap().unwrap();
+        assert_eq!(ev.as_xcm_event().unwrap(), None);
+    }
+}
@@ -160,6 +160,9 @@ pub mod storage;
 pub mod tx;
 pub mod utils;
 
+#[cfg(test)]
+mod test_utils;
+
 // Expose a few of the most common types at root,
 // but leave most types behind their respective modules.
 pub use crate::{
@@ -176,6 +179,17 @@ pub use crate::{
     metadata::Metadata,
 };
 
+/// The version of the interface between this crate and the code generated from metadata by
+/// `subxt-codegen`, via the [`subxt`] macro or the `subxt codegen` CLI command.
+///
+/// Generated code relies on the types and traits exposed here, so code generated by one
+/// release of `subxt-codegen` may not compile against another release of this crate. To
+/// catch that early, generated code checks this version at compile time and fails with a
+/// message asking for the code to be regenerated if it doesn't match the version that it
+/// was generated for. The version is only bumped when generated code needs to change, so
+/// code generated by an older `subxt-codegen` keeps working across releases which don't.
+pub const GENERATED_CODE_VERSION: u32 = 1;
+
 /// Re-export external crates that are made use of in the subxt API.
 pub mod ext {
     pub use codec;
@@ -0,0 +1,251 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    Metadata,
+    MetadataError,
+};
+use scale_info::TypeDef;
+use std::fmt;
+
+/// An origin that a call can be dispatched from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CallOrigin {
+    /// The root origin, usually reached through sudo or governance.
+    Root,
+    /// An origin signed by some account.
+    Signed,
+    /// No origin, as used by unsigned transactions and inherents.
+    None,
+    /// An origin given by an item of the pallet's configuration, for example
+    /// `ForceOrigin`. Which origins this allows is decided by the runtime.
+    Custom(String),
+}
+
+impl fmt::Display for CallOrigin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CallOrigin::Root => f.write_str("Root"),
+            CallOrigin::Signed => f.write_str("Signed"),
+            CallOrigin::None => f.write_str("None"),
+            CallOrigin::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+/// What the documentation of a call says about the origin that it must be dispatched
+/// from. Obtain this from [`Metadata::call_origin()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CallOriginHint {
+    /// The origins that are mentioned, any one of which is likely to be accepted. Calls
+    /// often have further requirements, such as the signer being a member of some set,
+    /// which are only described in [`CallOriginHint::sentence`].
+    pub origins: Vec<CallOrigin>,
+    /// The sentence of the documentation that the origins were found in.
+    pub sentence: String,
+}
+
+impl Metadata {
+    /// Make a best-effort attempt at working out which origin the given call must be
+    /// dispatched from.
+    ///
+    /// The metadata doesn't record this itself, so it's found by looking through the
+    /// documentation of the call for a sentence describing the origin, as in "The dispatch
+    /// origin for this call must be _Signed_" or "May only be called from
+    /// `T::ForceOrigin`". This returns `Ok(None)` if there's no such sentence, which is
+    /// always the case for metadata with the documentation stripped out.
+    pub fn call_origin(
+        &self,
+        pallet: &str,
+        call: &str,
+    ) -> Result<Option<CallOriginHint>, MetadataError> {
+        let pallet = self.pallet(pallet)?;
+        let call_ty_id = pallet.call_ty_id().ok_or(MetadataError::CallNotFound)?;
+        let ty = self
+            .resolve_type(call_ty_id)
+            .ok_or(MetadataError::TypeNotFound(call_ty_id))?;
+        let TypeDef::Variant(variant) = ty.type_def() else {
+            return Err(MetadataError::CallNotFound)
+        };
+        let call = variant
+            .variants()
+            .iter()
+            .find(|v| v.name() == call)
+            .ok_or(MetadataError::CallNotFound)?;
+        Ok(origin_from_docs(call.docs()))
+    }
+}
+
+// Words which suggest that a sentence mentioning an origin is saying what it must be.
+const REQUIREMENT_WORDS: [&str; 4] = ["must", "only", "called", "require"];
+
+fn origin_from_docs(docs: &[String]) -> Option<CallOriginHint> {
+    sentences(docs)
+        .into_iter()
+        .filter(|sentence| {
+            let lower = sentence.to_lowercase();
+            lower.contains("origin")
+                && REQUIREMENT_WORDS.iter().any(|word| lower.contains(word))
+        })
+        .find_map(|sentence| {
+            let origins = origins_in_sentence(&sentence);
+            (!origins.is_empty()).then(|| {
+                CallOriginHint {
+                    origins,
+                    sentence: sentence.trim_end_matches('.').to_owned(),
+                }
+            })
+        })
+}
+
+// Split documentation into sentences. Sentences are often wrapped over several lines, but
+// never run across a paragraph, heading or list item, each of which starts a new line.
+// Within a paragraph, a sentence ends with a word ending in `.` that's followed by a
+// capitalised word, unless it's an abbreviation such as "e.g." or "i.e.".
+fn sentences(docs: &[String]) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut sentence: Vec<&str> = Vec::new();
+    for line in docs {
+        let line = line.trim();
+        let is_fence = line.starts_with("```");
+        let marker = ["#", "- ", "* "].into_iter().find(|m| line.starts_with(m));
+        if (line.is_empty() || is_fence || marker.is_some()) && !sentence.is_empty() {
+            sentences.push(sentence.join(" "));
+            sentence.clear();
+        }
+        if is_fence {
+            continue
+        }
+        // List markers and headings aren't part of the sentence.
+        let line = match marker {
+            Some(marker) => line.trim_start_matches(marker),
+            None => line,
+        };
+        let mut words = line.split_whitespace().peekable();
+        while let Some(word) = words.next() {
+            sentence.push(word);
+            let is_abbreviation = word.trim_end_matches('.').contains('.');
+            let next_is_capitalised = match words.peek() {
+                Some(next) => next.starts_with(|c: char| c.is_uppercase()),
+                None => true,
+            };
+            if word.ends_with('.') && !is_abbreviation && next_is_capitalised {
+                sentences.push(sentence.join(" "));
+                sentence.clear();
+            }
+        }
+    }
+    if !sentence.is_empty() {
+        sentences.push(sentence.join(" "));
+    }
+    sentences
+}
+
+fn origins_in_sentence(sentence: &str) -> Vec<CallOrigin> {
+    let mut origins = Vec::new();
+    for raw in sentence.split_whitespace() {
+        let word = raw.trim_matches(|c: char| !c.is_alphanumeric() && c != ':');
+        let word = word.trim_end_matches(':');
+        // Emphasis marks that the word is naming an origin, rather than just being
+        // part of the sentence.
+        let emphasised = raw.contains('_') || raw.contains('`');
+        let origin = match word.to_lowercase().as_str() {
+            "root" => Some(CallOrigin::Root),
+            "signed" => Some(CallOrigin::Signed),
+            "unsigned" => Some(CallOrigin::None),
+            "none" if emphasised || word == "None" => Some(CallOrigin::None),
+            _ => custom_origin(word),
+        };
+        if let Some(origin) = origin {
+            if !origins.contains(&origin) {
+                origins.push(origin);
+            }
+        }
+    }
+    origins
+}
+
+// Origins taken from the pallet's configuration, such as `T::ForceOrigin`.
+fn custom_origin(word: &str) -> Option<CallOrigin> {
+    let name = word.rsplit("::").next().unwrap_or(word);
+    let is_config_item = name.len() > "Origin".len()
+        && name.ends_with("Origin")
+        && name.starts_with(|c: char| c.is_ascii_uppercase())
+        && name != "RuntimeOrigin";
+    is_config_item.then(|| CallOrigin::Custom(name.to_owned()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+
+    fn docs(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_origins_in_docs() {
+        let hint = origin_from_docs(&docs(&[
+            "Set the thing.",
+            "",
+            "The dispatch origin for this call must be _Signed_ or",
+            "_Root_. Signed callers pay a fee.",
+        ]))
+        .unwrap();
+        assert_eq!(hint.origins, vec![CallOrigin::Signed, CallOrigin::Root]);
+        assert_eq!(
+            hint.sentence,
+            "The dispatch origin for this call must be _Signed_ or _Root_"
+        );
+
+        let hint =
+            origin_from_docs(&docs(&["May only be called from `T::ForceOrigin`."]))
+                .unwrap();
+        assert_eq!(hint.origins, vec![CallOrigin::Custom("ForceOrigin".into())]);
+
+        // Abbreviations don't end a sentence, and paragraphs do:
+        let hint = origin_from_docs(&docs(&[
+            "The origin must be privileged, e.g. _Root_. Anyone may call this",
+            "",
+            "if the origin is _Signed_ by the owner.",
+        ]))
+        .unwrap();
+        assert_eq!(hint.origins, vec![CallOrigin::Root]);
+        assert_eq!(hint.sentence, "The origin must be privileged, e.g. _Root_");
+        let hint = origin_from_docs(&docs(&[
+            "- The origin must be _Signed_, i.e. the",
+            "  sender pays the fee",
+            "- Whoever calls this is made its owner.",
+        ]))
+        .unwrap();
+        assert_eq!(
+            hint.sentence,
+            "The origin must be _Signed_, i.e. the sender pays the fee"
+        );
+
+        // Sentences which mention an origin without saying what it must be are skipped:
+        assert_eq!(
+            origin_from_docs(&docs(&["The calls are dispatched from the same origin."])),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_origins_of_real_calls() {
+        let metadata = metadata();
+        let origins = |pallet, call| {
+            metadata
+                .call_origin(pallet, call)
+                .unwrap()
+                .map(|hint| hint.origins)
+        };
+        assert_eq!(
+            origins("Balances", "force_transfer"),
+            Some(vec![CallOrigin::Root])
+        );
+        assert_eq!(origins("Staking", "bond"), Some(vec![CallOrigin::Signed]));
+        assert!(metadata.call_origin("Balances", "not_a_call").is_err());
+    }
+}
@@ -0,0 +1,593 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Computing the metadata hash which the `CheckMetadataHash` signed extension checks,
+//! as described in [RFC-78](https://polkadot-fellows.github.io/RFCs/approved/0078-merkleized-metadata.html).
+//!
+//! Each of the types that extrinsics can be made of is put into a simpler form (with one
+//! entry per enum variant), and these are the leaves of a Merkle tree. The metadata hash
+//! is the hash of the tree root, the hash of the extrinsic format, and some details about
+//! the chain which wallets need to display transactions.
+
+use super::{
+    Metadata,
+    MetadataError,
+};
+use codec::{
+    Compact,
+    Encode,
+};
+use scale_info::{
+    form::PortableForm,
+    PortableRegistry,
+    Type,
+    TypeDef,
+    TypeDefPrimitive,
+};
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+    VecDeque,
+};
+
+/// Details about the chain which are hashed alongside the metadata to give the hash that
+/// the `CheckMetadataHash` signed extension checks. See [`Metadata::merkleized_hash()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MetadataHashInfo {
+    /// The spec version of the runtime.
+    pub spec_version: u32,
+    /// The spec name of the runtime, for example "polkadot".
+    pub spec_name: String,
+    /// The SS58 prefix that addresses on the chain use.
+    pub base58_prefix: u16,
+    /// The number of decimal places of the chain's native token.
+    pub decimals: u8,
+    /// The symbol of the chain's native token, for example "DOT".
+    pub token_symbol: String,
+}
+
+impl Metadata {
+    /// Compute the hash of this metadata which the `CheckMetadataHash` signed extension
+    /// checks transactions against, to be handed to it with
+    /// [`crate::config::extrinsic_params::MetadataHashMode::Enabled`].
+    ///
+    /// This is not the same as [`Metadata::metadata_hash()`], which is used to check that
+    /// the metadata is compatible with the code generated from it.
+    pub fn merkleized_hash(
+        &self,
+        info: &MetadataHashInfo,
+    ) -> Result<[u8; 32], MetadataError> {
+        let extrinsic = &self.runtime_metadata().extrinsic;
+        let extrinsic_ty_id = extrinsic.ty.id();
+        let type_param = |name: &str| {
+            self.resolve_type(extrinsic_ty_id)
+                .ok_or(MetadataError::TypeNotFound(extrinsic_ty_id))?
+                .type_params()
+                .iter()
+                .find(|param| param.name() == name)
+                .and_then(|param| param.ty())
+                .map(|ty| ty.id())
+                .ok_or_else(|| {
+                    MetadataError::MetadataHashError(format!(
+                        "the extrinsic type has no {name} type parameter"
+                    ))
+                })
+        };
+        let address_ty = type_param("Address")?;
+        let call_ty = type_param("Call")?;
+        let signature_ty = type_param("Signature")?;
+
+        // Only the types which extrinsics can be made of are hashed.
+        let registry = self.types();
+        let mut reachable = BTreeSet::new();
+        let extension_tys = extrinsic
+            .signed_extensions
+            .iter()
+            .flat_map(|ext| [ext.ty.id(), ext.additional_signed.id()]);
+        for id in [call_ty, address_ty, signature_ty]
+            .into_iter()
+            .chain(extension_tys)
+        {
+            collect_reachable(registry, id, &mut reachable)?;
+        }
+
+        // Primitive, compact and empty types are referred to inline rather than being
+        // leaves, and the rest are given new IDs in the order of their old ones.
+        let mut leaf_ids = BTreeMap::new();
+        for id in reachable {
+            if is_leaf(resolve(registry, id)?) {
+                let leaf_id = leaf_ids.len() as u32;
+                leaf_ids.insert(id, leaf_id);
+            }
+        }
+        let types = Types {
+            registry,
+            leaf_ids: &leaf_ids,
+        };
+
+        let mut leaves = Vec::new();
+        for (&id, &leaf_id) in &leaf_ids {
+            for leaf in types.leaves(id, leaf_id)? {
+                leaves.push(blake3::hash(&leaf.encode()).into());
+            }
+        }
+
+        let signed_extensions = extrinsic
+            .signed_extensions
+            .iter()
+            .map(|ext| {
+                Ok(SignedExtension {
+                    identifier: ext.identifier.clone(),
+                    included_in_extrinsic: types.type_ref(ext.ty.id())?,
+                    included_in_signed_data: types
+                        .type_ref(ext.additional_signed.id())?,
+                })
+            })
+            .collect::<Result<_, MetadataError>>()?;
+        let extrinsic_metadata = ExtrinsicMetadata {
+            version: extrinsic.version,
+            address_ty: types.type_ref(address_ty)?,
+            call_ty: types.type_ref(call_ty)?,
+            signature_ty: types.type_ref(signature_ty)?,
+            signed_extensions,
+        };
+
+        let digest = MetadataDigest::V1 {
+            types_tree_root: merkle_root(leaves),
+            extrinsic_metadata_hash: blake3::hash(&extrinsic_metadata.encode()).into(),
+            spec_version: info.spec_version,
+            spec_name: info.spec_name.clone(),
+            base58_prefix: info.base58_prefix,
+            decimals: info.decimals,
+            token_symbol: info.token_symbol.clone(),
+        };
+        Ok(blake3::hash(&digest.encode()).into())
+    }
+}
+
+// The root of a tree whose leaves are given from left to right. Pairs of nodes are taken
+// from the end and their parent put at the front until there's just the root left.
+fn merkle_root(leaves: Vec<[u8; 32]>) -> [u8; 32] {
+    let mut nodes = VecDeque::from(leaves);
+    while nodes.len() > 1 {
+        let right = nodes.pop_back().expect("there are at least 2 nodes; qed");
+        let left = nodes.pop_back().expect("there are at least 2 nodes; qed");
+        nodes.push_front(blake3::hash(&(left, right).encode()).into());
+    }
+    nodes.pop_back().unwrap_or_default()
+}
+
+fn resolve(
+    registry: &PortableRegistry,
+    id: u32,
+) -> Result<&Type<PortableForm>, MetadataError> {
+    registry.resolve(id).ok_or(MetadataError::TypeNotFound(id))
+}
+
+fn is_leaf(ty: &Type<PortableForm>) -> bool {
+    match ty.type_def() {
+        TypeDef::Primitive(_) | TypeDef::Compact(_) => false,
+        TypeDef::Composite(c) => !c.fields().is_empty(),
+        TypeDef::Variant(v) => !v.variants().is_empty(),
+        TypeDef::Tuple(t) => !t.fields().is_empty(),
+        TypeDef::Sequence(_) | TypeDef::Array(_) | TypeDef::BitSequence(_) => true,
+    }
+}
+
+// Gather the IDs of the type given and the types that it's made of. The types inside
+// compact and bit sequence types aren't needed, since those are described inline.
+fn collect_reachable(
+    registry: &PortableRegistry,
+    id: u32,
+    reachable: &mut BTreeSet<u32>,
+) -> Result<(), MetadataError> {
+    if !reachable.insert(id) {
+        return Ok(())
+    }
+    let inner_ids: Vec<u32> = match resolve(registry, id)?.type_def() {
+        TypeDef::Composite(c) => c.fields().iter().map(|f| f.ty().id()).collect(),
+        TypeDef::Variant(v) => {
+            v.variants()
+                .iter()
+                .flat_map(|v| v.fields())
+                .map(|f| f.ty().id())
+                .collect()
+        }
+        TypeDef::Sequence(s) => vec![s.type_param().id()],
+        TypeDef::Array(a) => vec![a.type_param().id()],
+        TypeDef::Tuple(t) => t.fields().iter().map(|f| f.id()).collect(),
+        TypeDef::Primitive(_) | TypeDef::Compact(_) | TypeDef::BitSequence(_) => {
+            Vec::new()
+        }
+    };
+    for id in inner_ids {
+        collect_reachable(registry, id, reachable)?;
+    }
+    Ok(())
+}
+
+// Gather the primitive types that the type given is made of.
+fn collect_primitives(
+    registry: &PortableRegistry,
+    id: u32,
+    visited: &mut BTreeSet<u32>,
+    found: &mut Vec<TypeDefPrimitive>,
+) -> Result<(), MetadataError> {
+    if !visited.insert(id) {
+        return Ok(())
+    }
+    let inner_ids: Vec<u32> = match resolve(registry, id)?.type_def() {
+        TypeDef::Primitive(p) => {
+            found.push(p.clone());
+            Vec::new()
+        }
+        TypeDef::Composite(c) => c.fields().iter().map(|f| f.ty().id()).collect(),
+        TypeDef::Variant(v) => {
+            v.variants()
+                .iter()
+                .flat_map(|v| v.fields())
+                .map(|f| f.ty().id())
+                .collect()
+        }
+        TypeDef::Sequence(s) => vec![s.type_param().id()],
+        TypeDef::Array(a) => vec![a.type_param().id()],
+        TypeDef::Tuple(t) => t.fields().iter().map(|f| f.id()).collect(),
+        TypeDef::Compact(c) => vec![c.type_param().id()],
+        TypeDef::BitSequence(b) => {
+            vec![b.bit_order_type().id(), b.bit_store_type().id()]
+        }
+    };
+    for id in inner_ids {
+        collect_primitives(registry, id, visited, found)?;
+    }
+    Ok(())
+}
+
+// The only primitive type that the type given is made of, if there is one.
+fn single_primitive(
+    registry: &PortableRegistry,
+    id: u32,
+) -> Result<Option<TypeDefPrimitive>, MetadataError> {
+    let mut found = Vec::new();
+    collect_primitives(registry, id, &mut BTreeSet::new(), &mut found)?;
+    if found.len() > 1 {
+        return Err(MetadataError::MetadataHashError(format!(
+            "type {id} is made of more than one primitive type"
+        )))
+    }
+    Ok(found.pop())
+}
+
+// The registry, along with the IDs of the types in it which are leaves of the tree.
+#[derive(Clone, Copy)]
+struct Types<'a> {
+    registry: &'a PortableRegistry,
+    leaf_ids: &'a BTreeMap<u32, u32>,
+}
+
+impl<'a> Types<'a> {
+    fn type_ref(&self, id: u32) -> Result<TypeRef, MetadataError> {
+        let ty = resolve(self.registry, id)?;
+        let type_ref = match ty.type_def() {
+            TypeDef::Primitive(p) => {
+                match p {
+                    TypeDefPrimitive::Bool => TypeRef::Bool,
+                    TypeDefPrimitive::Char => TypeRef::Char,
+                    TypeDefPrimitive::Str => TypeRef::Str,
+                    TypeDefPrimitive::U8 => TypeRef::U8,
+                    TypeDefPrimitive::U16 => TypeRef::U16,
+                    TypeDefPrimitive::U32 => TypeRef::U32,
+                    TypeDefPrimitive::U64 => TypeRef::U64,
+                    TypeDefPrimitive::U128 => TypeRef::U128,
+                    TypeDefPrimitive::U256 => TypeRef::U256,
+                    TypeDefPrimitive::I8 => TypeRef::I8,
+                    TypeDefPrimitive::I16 => TypeRef::I16,
+                    TypeDefPrimitive::I32 => TypeRef::I32,
+                    TypeDefPrimitive::I64 => TypeRef::I64,
+                    TypeDefPrimitive::I128 => TypeRef::I128,
+                    TypeDefPrimitive::I256 => TypeRef::I256,
+                }
+            }
+            TypeDef::Compact(_) => {
+                match single_primitive(self.registry, id)? {
+                    None => TypeRef::Void,
+                    Some(TypeDefPrimitive::U8) => TypeRef::CompactU8,
+                    Some(TypeDefPrimitive::U16) => TypeRef::CompactU16,
+                    Some(TypeDefPrimitive::U32) => TypeRef::CompactU32,
+                    Some(TypeDefPrimitive::U64) => TypeRef::CompactU64,
+                    Some(TypeDefPrimitive::U128) => TypeRef::CompactU128,
+                    Some(p) => {
+                        return Err(MetadataError::MetadataHashError(format!(
+                            "type {id} is a compact {p:?}, which can't be compact encoded"
+                        )))
+                    }
+                }
+            }
+            _ if !is_leaf(ty) => TypeRef::Void,
+            _ => {
+                let leaf_id = self
+                    .leaf_ids
+                    .get(&id)
+                    .ok_or(MetadataError::TypeNotFound(id))?;
+                TypeRef::ById(Compact(*leaf_id))
+            }
+        };
+        Ok(type_ref)
+    }
+
+    fn field(
+        &self,
+        field: &scale_info::Field<PortableForm>,
+    ) -> Result<Field, MetadataError> {
+        Ok(Field {
+            name: field.name().cloned(),
+            ty: self.type_ref(field.ty().id())?,
+            type_name: field.type_name().cloned(),
+        })
+    }
+
+    fn fields(
+        &self,
+        fields: &[scale_info::Field<PortableForm>],
+    ) -> Result<Vec<Field>, MetadataError> {
+        fields.iter().map(|f| self.field(f)).collect()
+    }
+
+    // The leaves for the type given: one for each variant of an enum (in the order of
+    // their indices), and just the one for any other type.
+    fn leaves(&self, id: u32, leaf_id: u32) -> Result<Vec<LeafType>, MetadataError> {
+        let ty = resolve(self.registry, id)?;
+        let leaf = |type_def| {
+            LeafType {
+                path: ty.path().segments().to_vec(),
+                type_def,
+                type_id: Compact(leaf_id),
+            }
+        };
+        let type_def = match ty.type_def() {
+            TypeDef::Variant(v) => {
+                let mut variants: Vec<_> = v.variants().iter().collect();
+                variants.sort_by_key(|v| v.index());
+                return variants
+                    .into_iter()
+                    .map(|v| {
+                        Ok(leaf(LeafTypeDef::Enumeration(EnumerationVariant {
+                            name: v.name().clone(),
+                            fields: self.fields(v.fields())?,
+                            index: Compact(v.index() as u32),
+                        })))
+                    })
+                    .collect()
+            }
+            TypeDef::Composite(c) => LeafTypeDef::Composite(self.fields(c.fields())?),
+            TypeDef::Sequence(s) => {
+                LeafTypeDef::Sequence(self.type_ref(s.type_param().id())?)
+            }
+            TypeDef::Array(a) => {
+                LeafTypeDef::Array {
+                    len: a.len(),
+                    type_param: self.type_ref(a.type_param().id())?,
+                }
+            }
+            TypeDef::Tuple(t) => {
+                LeafTypeDef::Tuple(
+                    t.fields()
+                        .iter()
+                        .map(|f| self.type_ref(f.id()))
+                        .collect::<Result<_, _>>()?,
+                )
+            }
+            TypeDef::BitSequence(b) => {
+                let num_bytes =
+                    match single_primitive(self.registry, b.bit_store_type().id())? {
+                        Some(TypeDefPrimitive::U8) => 1,
+                        Some(TypeDefPrimitive::U16) => 2,
+                        Some(TypeDefPrimitive::U32) => 4,
+                        Some(TypeDefPrimitive::U64) => 8,
+                        _ => {
+                            return Err(MetadataError::MetadataHashError(format!(
+                                "bit sequence type {id} has an unsupported store type"
+                            )))
+                        }
+                    };
+                let order_id = b.bit_order_type().id();
+                let least_significant_bit_first = match resolve(self.registry, order_id)?
+                    .path()
+                    .segments()
+                    .iter()
+                    .find(|s| *s == "Lsb0" || *s == "Msb0")
+                {
+                    Some(order) => order == "Lsb0",
+                    None => {
+                        return Err(MetadataError::MetadataHashError(format!(
+                            "bit sequence type {id} has an order other than Lsb0 or Msb0"
+                        )))
+                    }
+                };
+                LeafTypeDef::BitSequence {
+                    num_bytes,
+                    least_significant_bit_first,
+                }
+            }
+            TypeDef::Primitive(_) | TypeDef::Compact(_) => return Ok(Vec::new()),
+        };
+        Ok(vec![leaf(type_def)])
+    }
+}
+
+// The forms that types take when they're hashed, as given in RFC-78.
+
+#[derive(Encode)]
+enum TypeRef {
+    #[codec(index = 0)]
+    Bool,
+    #[codec(index = 1)]
+    Char,
+    #[codec(index = 2)]
+    Str,
+    #[codec(index = 3)]
+    U8,
+    #[codec(index = 4)]
+    U16,
+    #[codec(index = 5)]
+    U32,
+    #[codec(index = 6)]
+    U64,
+    #[codec(index = 7)]
+    U128,
+    #[codec(index = 8)]
+    U256,
+    #[codec(index = 9)]
+    I8,
+    #[codec(index = 10)]
+    I16,
+    #[codec(index = 11)]
+    I32,
+    #[codec(index = 12)]
+    I64,
+    #[codec(index = 13)]
+    I128,
+    #[codec(index = 14)]
+    I256,
+    #[codec(index = 15)]
+    CompactU8,
+    #[codec(index = 16)]
+    CompactU16,
+    #[codec(index = 17)]
+    CompactU32,
+    #[codec(index = 18)]
+    CompactU64,
+    #[codec(index = 19)]
+    CompactU128,
+    #[codec(index = 20)]
+    // Never produced, since `TypeDefPrimitive` has no compactable 256 bit type, but
+    // kept so that the indices above line up with the RFC.
+    #[allow(dead_code)]
+    CompactU256,
+    #[codec(index = 21)]
+    Void,
+    #[codec(index = 22)]
+    ById(Compact<u32>),
+}
+
+#[derive(Encode)]
+struct LeafType {
+    path: Vec<String>,
+    type_def: LeafTypeDef,
+    type_id: Compact<u32>,
+}
+
+#[derive(Encode)]
+enum LeafTypeDef {
+    #[codec(index = 0)]
+    Composite(Vec<Field>),
+    #[codec(index = 1)]
+    Enumeration(EnumerationVariant),
+    #[codec(index = 2)]
+    Sequence(TypeRef),
+    #[codec(index = 3)]
+    Array { len: u32, type_param: TypeRef },
+    #[codec(index = 4)]
+    Tuple(Vec<TypeRef>),
+    #[codec(index = 5)]
+    BitSequence {
+        num_bytes: u8,
+        least_significant_bit_first: bool,
+    },
+}
+
+#[derive(Encode)]
+struct Field {
+    name: Option<String>,
+    ty: TypeRef,
+    type_name: Option<String>,
+}
+
+#[derive(Encode)]
+struct EnumerationVariant {
+    name: String,
+    fields: Vec<Field>,
+    index: Compact<u32>,
+}
+
+#[derive(Encode)]
+struct ExtrinsicMetadata {
+    version: u8,
+    address_ty: TypeRef,
+    call_ty: TypeRef,
+    signature_ty: TypeRef,
+    signed_extensions: Vec<SignedExtension>,
+}
+
+#[derive(Encode)]
+struct SignedExtension {
+    identifier: String,
+    included_in_extrinsic: TypeRef,
+    included_in_signed_data: TypeRef,
+}
+
+#[derive(Encode)]
+enum MetadataDigest {
+    // A digest which says that the metadata hash is disabled; we never need to hash it.
+    #[codec(index = 0)]
+    #[allow(dead_code)]
+    Disabled,
+    #[codec(index = 1)]
+    V1 {
+        types_tree_root: [u8; 32],
+        extrinsic_metadata_hash: [u8; 32],
+        spec_version: u32,
+        spec_name: String,
+        base58_prefix: u16,
+        decimals: u8,
+        token_symbol: String,
+    },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+
+    fn polkadot_info() -> MetadataHashInfo {
+        MetadataHashInfo {
+            spec_version: 9370,
+            spec_name: "polkadot".to_owned(),
+            base58_prefix: 0,
+            decimals: 10,
+            token_symbol: "DOT".to_owned(),
+        }
+    }
+
+    #[test]
+    fn polkadot_metadata_has_the_known_hash() {
+        // Computed with the `merkleized-metadata` crate, which the runtime uses:
+        let expected = "597964ffe5e24f52cafda0676f5ab5d5560ca4da4ea7b2edd82dc201d8eb7768";
+        let hash = metadata().merkleized_hash(&polkadot_info()).unwrap();
+        assert_eq!(hex::encode(hash), expected);
+    }
+
+    #[test]
+    fn chain_details_are_part_of_the_hash() {
+        let metadata = metadata();
+        let hash = metadata.merkleized_hash(&polkadot_info()).unwrap();
+        let info = MetadataHashInfo {
+            spec_version: 9380,
+            ..polkadot_info()
+        };
+        assert_ne!(metadata.merkleized_hash(&info).unwrap(), hash);
+    }
+
+    #[test]
+    fn merkle_roots_pair_nodes_from_the_end() {
+        let hash = |left: [u8; 32], right: [u8; 32]| -> [u8; 32] {
+            blake3::hash(&[left, right].concat()).into()
+        };
+        let (a, b, c) = ([1; 32], [2; 32], [3; 32]);
+        assert_eq!(merkle_root(vec![]), [0; 32]);
+        assert_eq!(merkle_root(vec![a]), a);
+        // `b` and `c` are paired first, and their parent is then paired with `a`:
+        assert_eq!(merkle_root(vec![a, b, c]), hash(hash(b, c), a));
+    }
+}
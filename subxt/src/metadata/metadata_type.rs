@@ -72,6 +72,9 @@ pub enum MetadataError {
     /// Runtime metadata is not fully compatible with the static one.
     #[error("Node metadata is not fully compatible")]
     IncompatibleMetadata,
+    /// The metadata hash checked by the `CheckMetadataHash` extension can't be computed.
+    #[error("Cannot compute the metadata hash: {0}")]
+    MetadataHashError(String),
 }
 
 // We hide the innards behind an Arc so that it's easy to clone and share.
@@ -142,6 +145,18 @@ impl Metadata {
         self.inner.dispatch_error_ty
     }
 
+    /// Return the ID of the outer `RuntimeCall` type if it can be found. This is taken from
+    /// the `Call` type parameter of the extrinsic type.
+    pub fn runtime_call_ty(&self) -> Option<u32> {
+        let extrinsic_ty = self.resolve_type(self.inner.metadata.extrinsic.ty.id())?;
+        extrinsic_ty
+            .type_params()
+            .iter()
+            .find(|param| param.name() == "Call")
+            .and_then(|param| param.ty())
+            .map(|ty| ty.id())
+    }
+
     /// Return the type registry embedded within the metadata.
     pub fn types(&self) -> &PortableRegistry {
         &self.inner.metadata.types
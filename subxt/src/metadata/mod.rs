@@ -4,12 +4,19 @@
 
 //! Types representing the metadata obtained from a node.
 
+mod call_origin;
 mod decode_with_metadata;
 mod encode_with_metadata;
 mod hash_cache;
+mod merkleized_hash;
 mod metadata_location;
 mod metadata_type;
 
+pub use call_origin::{
+    CallOrigin,
+    CallOriginHint,
+};
+pub use merkleized_hash::MetadataHashInfo;
 pub use metadata_location::MetadataLocation;
 
 pub use metadata_type::{
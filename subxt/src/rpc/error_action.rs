@@ -0,0 +1,136 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::error::{
+    Error,
+    RpcError,
+};
+
+/// What should be done in response to an error from an RPC call.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ErrorAction {
+    /// The connection is broken; reconnect before trying the call again. See
+    /// [`super::ReconnectingClient`].
+    Reconnect,
+    /// The connection is fine, but the call may succeed if it's tried again.
+    Retry,
+    /// Trying again won't help; hand the error back to the caller.
+    Propagate,
+}
+
+/// Decide what should be done in response to an error, so that code which retries or
+/// reconnects can leave that decision up to its user. This is implemented for closures
+/// of the form `Fn(&Error) -> ErrorAction`, and [`DefaultErrorClassifier`] provides a
+/// sensible default.
+pub trait ErrorClassifier: Send + Sync {
+    /// Decide what should be done in response to the given error.
+    fn classify(&self, error: &Error) -> ErrorAction;
+}
+
+impl<F> ErrorClassifier for F
+where
+    F: Fn(&Error) -> ErrorAction + Send + Sync,
+{
+    fn classify(&self, error: &Error) -> ErrorAction {
+        self(error)
+    }
+}
+
+/// The default [`ErrorClassifier`]:
+///
+/// - Dropped subscriptions, IO errors and errors from the transport underlying the RPC
+///   client call for a reconnect.
+/// - Requests which timed out or which were rejected because too many were in flight
+///   are retried.
+/// - Everything else, including errors that the node responds with (such as the method
+///   not being found), is propagated.
+///
+/// Errors from RPC clients other than the `jsonrpsee` one are only known to be
+/// [`RpcError::ClientError`]s, and so are propagated.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct DefaultErrorClassifier;
+
+impl ErrorClassifier for DefaultErrorClassifier {
+    fn classify(&self, error: &Error) -> ErrorAction {
+        match error {
+            Error::Io(_) => ErrorAction::Reconnect,
+            Error::Rpc(RpcError::SubscriptionDropped) => ErrorAction::Reconnect,
+            Error::Rpc(RpcError::ClientError(e)) => client_error_action(&**e),
+            _ => ErrorAction::Propagate,
+        }
+    }
+}
+
+#[cfg(feature = "jsonrpsee")]
+fn client_error_action(
+    error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> ErrorAction {
+    use jsonrpsee::core::Error as JsonRpseeError;
+
+    match error.downcast_ref::<JsonRpseeError>() {
+        Some(
+            JsonRpseeError::Transport(_)
+            | JsonRpseeError::RestartNeeded(_)
+            | JsonRpseeError::Internal(_),
+        ) => ErrorAction::Reconnect,
+        Some(JsonRpseeError::RequestTimeout | JsonRpseeError::MaxSlotsExceeded) => {
+            ErrorAction::Retry
+        }
+        _ => ErrorAction::Propagate,
+    }
+}
+
+#[cfg(not(feature = "jsonrpsee"))]
+fn client_error_action(
+    _error: &(dyn std::error::Error + Send + Sync + 'static),
+) -> ErrorAction {
+    ErrorAction::Propagate
+}
+
+#[cfg(all(test, feature = "jsonrpsee"))]
+mod test {
+    use super::*;
+    use jsonrpsee::{
+        core::Error as JsonRpseeError,
+        types::error::{
+            CallError,
+            ErrorObject,
+        },
+    };
+
+    fn classify(error: JsonRpseeError) -> ErrorAction {
+        DefaultErrorClassifier.classify(&RpcError::ClientError(Box::new(error)).into())
+    }
+
+    #[test]
+    fn default_classifier_only_reconnects_for_connection_errors() {
+        assert_eq!(
+            classify(JsonRpseeError::RestartNeeded("closed".into())),
+            ErrorAction::Reconnect
+        );
+        assert_eq!(
+            DefaultErrorClassifier.classify(&RpcError::SubscriptionDropped.into()),
+            ErrorAction::Reconnect
+        );
+        assert_eq!(classify(JsonRpseeError::RequestTimeout), ErrorAction::Retry);
+        let method_not_found = ErrorObject::owned(-32601, "Method not found", None::<()>);
+        assert_eq!(
+            classify(JsonRpseeError::Call(CallError::Custom(method_not_found))),
+            ErrorAction::Propagate
+        );
+        assert_eq!(
+            DefaultErrorClassifier.classify(&Error::Other("bad".into())),
+            ErrorAction::Propagate
+        );
+    }
+
+    #[test]
+    fn closures_can_classify_errors() {
+        let retry_all = |_: &Error| ErrorAction::Retry;
+        assert_eq!(
+            retry_all.classify(&RpcError::SubscriptionDropped.into()),
+            ErrorAction::Retry
+        );
+    }
+}
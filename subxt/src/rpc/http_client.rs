@@ -0,0 +1,435 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An [`RpcClientT`] which sends each request to the node as an HTTP POST, and can attach
+//! headers that are worked out afresh for every request.
+//!
+//! This is handy behind gateways which authenticate each request, for instance with a
+//! token which is rotated every so often. A header provider closure is called just
+//! before each request is sent, and the headers that it hands back are added to it.
+//!
+//! Headers can only be set per request over HTTP. A WebSocket connection sends headers
+//! once, in the handshake that opens it, so the WebSocket client can't do this; and as
+//! HTTP has no way for the node to push notifications, this client can't subscribe to
+//! anything.
+//!
+//! Requests which the node hasn't answered within a minute fail with
+//! [`HttpClientError::Timeout`]; see [`HttpClientBuilder::with_request_timeout()`]. Like
+//! the rest of `hyper`, this needs to run inside a `tokio` runtime (with its timers
+//! enabled).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use subxt::{
+//!     rpc::http_client::{ HeaderMap, HeaderValue, HttpClientBuilder },
+//!     OnlineClient,
+//!     PolkadotConfig,
+//! };
+//!
+//! # fn current_token() -> String { String::new() }
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), subxt::Error> {
+//! let rpc_client = HttpClientBuilder::new()
+//!     .with_header_provider(|| {
+//!         let mut headers = HeaderMap::new();
+//!         let token = format!("Bearer {}", current_token());
+//!         headers.insert("authorization", HeaderValue::from_str(&token).unwrap());
+//!         headers
+//!     })
+//!     .build("https://rpc.example.com")?;
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(rpc_client)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    RawValue,
+    RpcClientT,
+    RpcFuture,
+    RpcSubscription,
+};
+use crate::error::{
+    Error,
+    RpcError,
+};
+use hyper::{
+    client::HttpConnector,
+    header::{
+        ACCEPT,
+        CONTENT_TYPE,
+    },
+    Body,
+    Client,
+    Request,
+    Uri,
+};
+use hyper_rustls::HttpsConnector;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{
+        atomic::{
+            AtomicU64,
+            Ordering,
+        },
+        Arc,
+    },
+    time::Duration,
+};
+
+pub use hyper::{
+    header::{
+        HeaderName,
+        HeaderValue,
+    },
+    HeaderMap,
+    StatusCode,
+};
+
+/// Something which is called before every request, and hands back the headers to add
+/// to it. This is implemented for closures of the form `Fn() -> HeaderMap`.
+pub trait HeaderProvider: Send + Sync + 'static {
+    /// The headers to add to the next request.
+    fn headers(&self) -> HeaderMap;
+}
+
+impl<F> HeaderProvider for F
+where
+    F: Fn() -> HeaderMap + Send + Sync + 'static,
+{
+    fn headers(&self) -> HeaderMap {
+        self()
+    }
+}
+
+/// An error from an [`HttpClient`]. These are handed back inside an
+/// [`RpcError::ClientError`].
+#[derive(Debug, thiserror::Error)]
+pub enum HttpClientError {
+    /// The request couldn't be sent, or the response couldn't be read.
+    #[error("HTTP transport error: {0}")]
+    Transport(#[from] hyper::Error),
+    /// The node responded with an HTTP status other than 200.
+    #[error("HTTP request failed with status {0}")]
+    Status(StatusCode),
+    /// The node responded with a JSON-RPC error.
+    #[error("JSON-RPC error {code}: {message}")]
+    Rpc {
+        /// The error code.
+        code: i64,
+        /// The error message.
+        message: String,
+    },
+    /// The response wasn't a valid JSON-RPC response.
+    #[error("Invalid JSON-RPC response: {0}")]
+    InvalidResponse(String),
+    /// Subscriptions need the node to push notifications, which it can't do over HTTP.
+    #[error("Subscriptions are not supported over HTTP")]
+    SubscriptionsUnsupported,
+    /// The node didn't respond within the request timeout.
+    #[error("The request timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+/// How long requests are given to complete by default.
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Configures and builds an [`HttpClient`].
+pub struct HttpClientBuilder {
+    headers: HeaderMap,
+    header_provider: Option<Arc<dyn HeaderProvider>>,
+    request_timeout: Duration,
+}
+
+impl Default for HttpClientBuilder {
+    fn default() -> Self {
+        HttpClientBuilder {
+            headers: HeaderMap::new(),
+            header_provider: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+}
+
+impl HttpClientBuilder {
+    /// Start building a client with no custom headers, and the
+    /// [`DEFAULT_REQUEST_TIMEOUT`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fail requests which haven't been answered in full within the given time, counting
+    /// from when they're sent.
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Add a header which is sent with every request.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Call `provider` before each request is sent, and add the headers it hands back to
+    /// the request. These take precedence over headers given to
+    /// [`HttpClientBuilder::with_header()`] with the same name.
+    pub fn with_header_provider(mut self, provider: impl HeaderProvider) -> Self {
+        self.header_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Build a client which sends requests to the `http://` or `https://` URL given.
+    pub fn build(self, url: impl AsRef<str>) -> Result<HttpClient, Error> {
+        let url = url.as_ref();
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| Error::Other(format!("Invalid URL {url}: {e}")))?;
+        if !matches!(uri.scheme_str(), Some("http" | "https")) {
+            return Err(Error::Other(format!(
+                "The URL {url} is not an http:// or https:// URL"
+            )))
+        }
+
+        let connector = hyper_rustls::HttpsConnectorBuilder::new()
+            .with_webpki_roots()
+            .https_or_http()
+            .enable_http1()
+            .build();
+        let mut headers = self.headers;
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        Ok(HttpClient {
+            client: Client::builder().build(connector),
+            uri,
+            headers,
+            header_provider: self.header_provider,
+            request_timeout: self.request_timeout,
+            next_id: AtomicU64::new(0),
+        })
+    }
+}
+
+/// An [`RpcClientT`] which talks to a node over HTTP. Construct one using an
+/// [`HttpClientBuilder`]. See [the module docs](self) for more.
+pub struct HttpClient {
+    client: Client<HttpsConnector<HttpConnector>>,
+    uri: Uri,
+    headers: HeaderMap,
+    header_provider: Option<Arc<dyn HeaderProvider>>,
+    request_timeout: Duration,
+    next_id: AtomicU64,
+}
+
+impl fmt::Debug for HttpClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HttpClient")
+            .field("uri", &self.uri)
+            .finish()
+    }
+}
+
+impl HttpClient {
+    // The headers to send with the next request.
+    fn request_headers(&self) -> HeaderMap {
+        let mut headers = self.headers.clone();
+        if let Some(provider) = &self.header_provider {
+            let provided = provider.headers();
+            for name in provided.keys() {
+                headers.remove(name);
+            }
+            for (name, value) in &provided {
+                headers.append(name, value.clone());
+            }
+        }
+        headers
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Box<RawValue>, HttpClientError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let body = serde_json::to_vec(&JsonRpcRequest {
+            jsonrpc: "2.0",
+            id,
+            method,
+            params,
+        })
+        .expect("JSON-RPC requests can be serialized; qed");
+
+        let mut request = Request::post(self.uri.clone())
+            .body(Body::from(body))
+            .expect("the URI is valid; qed");
+        *request.headers_mut() = self.request_headers();
+
+        let response = async {
+            let response = self.client.request(request).await?;
+            if response.status() != StatusCode::OK {
+                return Err(HttpClientError::Status(response.status()))
+            }
+            Ok(hyper::body::to_bytes(response.into_body()).await?)
+        };
+        let body = tokio::time::timeout(self.request_timeout, response)
+            .await
+            .map_err(|_| HttpClientError::Timeout(self.request_timeout))??;
+        response_result(&body, id)
+    }
+}
+
+impl RpcClientT for HttpClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        Box::pin(async move {
+            self.send(method, params)
+                .await
+                .map_err(|e| RpcError::ClientError(Box::new(e)))
+        })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        _sub: &'a str,
+        _params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        Box::pin(async {
+            Err(RpcError::ClientError(Box::new(
+                HttpClientError::SubscriptionsUnsupported,
+            )))
+        })
+    }
+}
+
+#[derive(Serialize)]
+struct JsonRpcRequest<'a> {
+    jsonrpc: &'static str,
+    id: u64,
+    method: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    params: Option<Box<RawValue>>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    code: i64,
+    message: String,
+}
+
+// Pull the result, or the error, out of the response to the request with the ID given.
+fn response_result(body: &[u8], id: u64) -> Result<Box<RawValue>, HttpClientError> {
+    let invalid = |e: serde_json::Error| HttpClientError::InvalidResponse(e.to_string());
+    // The fields are kept raw, as a `null` result would otherwise look like a missing one.
+    let mut response: HashMap<String, Box<RawValue>> =
+        serde_json::from_slice(body).map_err(invalid)?;
+    if let Some(error) = response.get("error") {
+        let error: JsonRpcError = serde_json::from_str(error.get()).map_err(invalid)?;
+        return Err(HttpClientError::Rpc {
+            code: error.code,
+            message: error.message,
+        })
+    }
+    let response_id = response
+        .get("id")
+        .and_then(|response_id| serde_json::from_str::<u64>(response_id.get()).ok());
+    if response_id != Some(id) {
+        return Err(HttpClientError::InvalidResponse(format!(
+            "expected a response to request {id}, got one to {response_id:?}"
+        )))
+    }
+    response.remove("result").ok_or_else(|| {
+        HttpClientError::InvalidResponse("the response has no result".into())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    #[test]
+    fn header_provider_is_called_for_each_request() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let provider_calls = calls.clone();
+        let client = HttpClientBuilder::new()
+            .with_header(
+                HeaderName::from_static("x-static"),
+                HeaderValue::from_static("1"),
+            )
+            .with_header_provider(move || {
+                let n = provider_calls.fetch_add(1, Ordering::SeqCst);
+                let mut headers = HeaderMap::new();
+                headers.insert("x-token", HeaderValue::from(n));
+                headers
+            })
+            .build("http://127.0.0.1:9933")
+            .unwrap();
+
+        let first = client.request_headers();
+        let second = client.request_headers();
+        assert_eq!(first["x-token"], "0");
+        assert_eq!(second["x-token"], "1");
+        assert_eq!(second["x-static"], "1");
+        assert_eq!(second[CONTENT_TYPE], "application/json");
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+
+        assert!(HttpClientBuilder::new()
+            .build("ws://127.0.0.1:9944")
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn requests_time_out() {
+        // A node which accepts connections, but never responds.
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let _accept = tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((connection, _)) = listener.accept().await {
+                connections.push(connection);
+            }
+        });
+
+        let client = HttpClientBuilder::new()
+            .with_request_timeout(Duration::from_millis(50))
+            .build(url)
+            .unwrap();
+        let err = client.send("system_name", None).await.unwrap_err();
+        assert!(matches!(err, HttpClientError::Timeout(_)), "{err}");
+    }
+
+    #[test]
+    fn reads_json_rpc_responses() {
+        let result =
+            response_result(br#"{"jsonrpc":"2.0","id":3,"result":[1,2]}"#, 3).unwrap();
+        assert_eq!(result.get(), "[1,2]");
+
+        let err = response_result(
+            br#"{"jsonrpc":"2.0","id":3,"error":{"code":-32601,"message":"Method not found"}}"#,
+            3,
+        )
+        .unwrap_err();
+        assert!(matches!(err, HttpClientError::Rpc { code: -32601, .. }));
+
+        // A `null` result is still a result:
+        let result =
+            response_result(br#"{"jsonrpc":"2.0","id":3,"result":null}"#, 3).unwrap();
+        assert_eq!(result.get(), "null");
+
+        // A response to some other request:
+        assert!(
+            response_result(br#"{"jsonrpc":"2.0","id":4,"result":null}"#, 3).is_err()
+        );
+    }
+}
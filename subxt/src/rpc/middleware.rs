@@ -0,0 +1,314 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Stack middleware over an [`RpcClientT`], to look at and change the requests going out
+//! to the node and the responses coming back.
+//!
+//! Each [`RpcMiddleware`] is handed a request along with the [`Next`] layer down, which it
+//! can pass the request on to (changing the method or params first if it likes), and then
+//! look at or change the result that comes back. A middleware can also answer a request
+//! itself, or refuse it, without passing it on at all. This makes for things like signing
+//! requests, routing them between tenants, rewriting methods, metering requests, or only
+//! letting some methods through.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use subxt::{
+//!     client::default_rpc_client,
+//!     rpc::{
+//!         middleware::{ Next, RpcMiddleware, RpcMiddlewareClient },
+//!         RawValue,
+//!         RpcFuture,
+//!     },
+//!     OnlineClient,
+//!     PolkadotConfig,
+//! };
+//!
+//! // Log every request, and whether it succeeded.
+//! struct Log;
+//!
+//! impl RpcMiddleware for Log {
+//!     fn request<'a>(
+//!         &'a self,
+//!         method: &'a str,
+//!         params: Option<Box<RawValue>>,
+//!         next: Next<'a>,
+//!     ) -> RpcFuture<'a, Box<RawValue>> {
+//!         Box::pin(async move {
+//!             let res = next.request(method, params).await;
+//!             println!("{method}: {}", if res.is_ok() { "ok" } else { "failed" });
+//!             res
+//!         })
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), subxt::Error> {
+//! let rpc_client = default_rpc_client("ws://127.0.0.1:9944").await?;
+//! let rpc_client = RpcMiddlewareClient::new(rpc_client).with_middleware(Log);
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(rpc_client)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    RawValue,
+    RpcClientT,
+    RpcFuture,
+    RpcSubscription,
+};
+use std::sync::Arc;
+
+/// Something which sits between an [`RpcMiddlewareClient`] and the client that it wraps,
+/// and can look at or change the requests and subscriptions that go through it. Both
+/// methods pass everything straight on to the next layer by default.
+pub trait RpcMiddleware: Send + Sync + 'static {
+    /// Handle a request, usually by passing it on to `next` and handing back the result.
+    fn request<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+        next: Next<'a>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        next.request(method, params)
+    }
+
+    /// Handle a subscription, usually by passing it on to `next` and handing back the
+    /// subscription.
+    fn subscribe<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+        next: Next<'a>,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        next.subscribe(sub, params, unsub)
+    }
+}
+
+/// The rest of the middleware below some [`RpcMiddleware`], ending with the client that
+/// they're stacked over.
+#[derive(Clone, Copy)]
+pub struct Next<'a> {
+    middleware: &'a [Arc<dyn RpcMiddleware>],
+    client: &'a dyn RpcClientT,
+}
+
+impl<'a> Next<'a> {
+    /// Pass a request on to the next layer. The method given doesn't have to be the one
+    /// that was handed to the middleware.
+    pub fn request<'b>(
+        self,
+        method: &'b str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'b, Box<RawValue>>
+    where
+        'a: 'b,
+    {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                first.request(
+                    method,
+                    params,
+                    Next {
+                        middleware: rest,
+                        client: self.client,
+                    },
+                )
+            }
+            None => self.client.request_raw(method, params),
+        }
+    }
+
+    /// Pass a subscription on to the next layer.
+    pub fn subscribe<'b>(
+        self,
+        sub: &'b str,
+        params: Option<Box<RawValue>>,
+        unsub: &'b str,
+    ) -> RpcFuture<'b, RpcSubscription>
+    where
+        'a: 'b,
+    {
+        match self.middleware.split_first() {
+            Some((first, rest)) => {
+                first.subscribe(
+                    sub,
+                    params,
+                    unsub,
+                    Next {
+                        middleware: rest,
+                        client: self.client,
+                    },
+                )
+            }
+            None => self.client.subscribe_raw(sub, params, unsub),
+        }
+    }
+}
+
+/// An [`RpcClientT`] which sends every request and subscription through a stack of
+/// [`RpcMiddleware`] before it reaches the client that it wraps. See
+/// [the module docs](self) for more.
+pub struct RpcMiddlewareClient<R> {
+    inner: R,
+    middleware: Vec<Arc<dyn RpcMiddleware>>,
+}
+
+impl<R: RpcClientT> RpcMiddlewareClient<R> {
+    /// Wrap an RPC client, with no middleware to begin with.
+    pub fn new(inner: R) -> Self {
+        RpcMiddlewareClient {
+            inner,
+            middleware: Vec::new(),
+        }
+    }
+
+    /// Add some middleware below any that's already been added. The middleware added first
+    /// sees each request first, and its result last.
+    pub fn with_middleware(mut self, middleware: impl RpcMiddleware) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// The client that this wraps.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    fn next(&self) -> Next<'_> {
+        Next {
+            middleware: &self.middleware,
+            client: &self.inner,
+        }
+    }
+}
+
+impl<R: RpcClientT> RpcClientT for RpcMiddlewareClient<R> {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        self.next().request(method, params)
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        self.next().subscribe(sub, params, unsub)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        error::RpcError,
+        test_utils::MockRpc,
+    };
+    use std::sync::Mutex;
+
+    // Responds to each request with the method and params it was sent.
+    fn echo() -> MockRpc {
+        MockRpc::new(|method, params| Ok(serde_json::json!([method, params])))
+    }
+
+    // Renames methods with a prefix.
+    struct Rename(&'static str);
+
+    impl RpcMiddleware for Rename {
+        fn request<'a>(
+            &'a self,
+            method: &'a str,
+            params: Option<Box<RawValue>>,
+            next: Next<'a>,
+        ) -> RpcFuture<'a, Box<RawValue>> {
+            Box::pin(async move {
+                let method = format!("{}{method}", self.0);
+                next.request(&method, params).await
+            })
+        }
+    }
+
+    // Records the method and result of each request that it sees.
+    #[derive(Default)]
+    struct Record(Arc<Mutex<Vec<(String, String)>>>);
+
+    impl RpcMiddleware for Record {
+        fn request<'a>(
+            &'a self,
+            method: &'a str,
+            params: Option<Box<RawValue>>,
+            next: Next<'a>,
+        ) -> RpcFuture<'a, Box<RawValue>> {
+            Box::pin(async move {
+                let res = next.request(method, params).await?;
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push((method.to_owned(), res.get().to_owned()));
+                Ok(res)
+            })
+        }
+    }
+
+    // Overrides nothing.
+    struct Passthrough;
+
+    impl RpcMiddleware for Passthrough {}
+
+    // Refuses every request without passing it on.
+    struct Deny;
+
+    impl RpcMiddleware for Deny {
+        fn request<'a>(
+            &'a self,
+            method: &'a str,
+            _params: Option<Box<RawValue>>,
+            _next: Next<'a>,
+        ) -> RpcFuture<'a, Box<RawValue>> {
+            let err = RpcError::ClientError(format!("{method} is not allowed").into());
+            Box::pin(async move { Err(err) })
+        }
+    }
+
+    #[tokio::test]
+    async fn requests_go_through_each_middleware_in_turn() {
+        let outer = Record::default();
+        let inner = Record::default();
+        let (outer_seen, inner_seen) = (outer.0.clone(), inner.0.clone());
+        let client = RpcMiddlewareClient::new(echo())
+            .with_middleware(outer)
+            .with_middleware(Rename("a_"))
+            .with_middleware(inner)
+            .with_middleware(Rename("b_"))
+            // Middleware that doesn't override anything passes everything on:
+            .with_middleware(Passthrough);
+
+        let params = RawValue::from_string("[1]".into()).unwrap();
+        let res = client.request_raw("method", Some(params)).await.unwrap();
+        assert_eq!(res.get(), r#"["b_a_method",[1]]"#);
+        assert_eq!(
+            *outer_seen.lock().unwrap(),
+            [("method".to_owned(), res.get().to_owned())]
+        );
+        assert_eq!(
+            *inner_seen.lock().unwrap(),
+            [("a_method".to_owned(), res.get().to_owned())]
+        );
+
+        // Middleware can answer requests itself:
+        let client = RpcMiddlewareClient::new(echo()).with_middleware(Deny);
+        assert!(matches!(
+            client.request_raw("method", None).await,
+            Err(RpcError::ClientError(_))
+        ));
+    }
+}
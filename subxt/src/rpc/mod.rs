@@ -52,12 +52,20 @@
 // with other file names for their types.
 #![allow(clippy::module_inception)]
 
+mod error_action;
+#[cfg(feature = "http")]
+pub mod http_client;
 #[cfg(feature = "jsonrpsee")]
 mod jsonrpsee_impl;
+pub mod middleware;
 
+mod reconnecting;
 mod rpc;
 mod rpc_client;
 mod rpc_client_t;
+mod subscription_limit;
+#[cfg(feature = "jsonrpsee-ws")]
+pub mod ws_client;
 
 // Expose our RPC types here.
 pub mod types;
@@ -65,6 +73,13 @@ pub mod types;
 // Expose the `Rpc` struct.
 pub use rpc::*;
 
+// Expose the types used to decide how to respond to RPC errors.
+pub use error_action::{
+    DefaultErrorClassifier,
+    ErrorAction,
+    ErrorClassifier,
+};
+
 pub use rpc_client_t::{
     RawValue,
     RpcClientT,
@@ -80,3 +95,6 @@ pub use rpc_client::{
     RpcParams,
     Subscription,
 };
+
+pub use reconnecting::ReconnectingClient;
+pub use subscription_limit::LimitSubscriptions;
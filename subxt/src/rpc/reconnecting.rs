@@ -0,0 +1,239 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    DefaultErrorClassifier,
+    ErrorAction,
+    ErrorClassifier,
+    RawValue,
+    RpcClientT,
+    RpcFuture,
+    RpcSubscription,
+};
+use crate::error::{
+    Error,
+    RpcError,
+};
+use futures::lock::Mutex;
+use std::{
+    future::Future,
+    sync::Arc,
+};
+
+type Connect = Box<dyn Fn() -> RpcFuture<'static, Arc<dyn RpcClientT>> + Send + Sync>;
+
+/// An [`RpcClientT`] which opens a new connection when a request or subscription fails
+/// with an error that an [`ErrorClassifier`] says calls for a reconnect, and then tries
+/// it again over the new connection. By default the [`DefaultErrorClassifier`] is used,
+/// so a broken connection is replaced, but an error that the node responds with (such as
+/// a method not being found) is handed straight back.
+///
+/// Errors which are classified as [`ErrorAction::Retry`] are handed back too, since the
+/// connection is fine; [`crate::utils::retry::retry()`] can be used to try those again.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use subxt::{
+///     client::default_rpc_client,
+///     rpc::ReconnectingClient,
+///     OnlineClient,
+///     PolkadotConfig,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), subxt::Error> {
+/// let rpc_client = ReconnectingClient::new(|| default_rpc_client("ws://127.0.0.1:9944")).await?;
+/// let api = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(rpc_client)).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Subscriptions
+///
+/// Subscriptions which were open on the old connection aren't opened again; their streams
+/// end with an error, and it's up to their users to subscribe again (which then goes over
+/// the new connection).
+pub struct ReconnectingClient {
+    connect: Connect,
+    classifier: Arc<dyn ErrorClassifier>,
+    max_reconnects: usize,
+    // The current connection, and how many connections have been made before it, so that
+    // calls which failed on an old connection don't replace a newer one.
+    current: Mutex<(usize, Arc<dyn RpcClientT>)>,
+}
+
+impl ReconnectingClient {
+    /// Connect using the function given, which is called again each time a new connection
+    /// is needed.
+    pub async fn new<F, Fut, R>(connect: F) -> Result<Self, Error>
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, Error>> + Send + 'static,
+        R: RpcClientT,
+    {
+        let connect: Connect = Box::new(move || {
+            let connection = connect();
+            Box::pin(async move {
+                let client: Arc<dyn RpcClientT> = match connection.await {
+                    Ok(client) => Arc::new(client),
+                    Err(Error::Rpc(e)) => return Err(e),
+                    Err(e) => return Err(RpcError::ClientError(Box::new(e))),
+                };
+                Ok(client)
+            })
+        });
+        let client = connect().await?;
+        Ok(ReconnectingClient {
+            connect,
+            classifier: Arc::new(DefaultErrorClassifier),
+            max_reconnects: 3,
+            current: Mutex::new((0, client)),
+        })
+    }
+
+    /// Decide which errors call for a reconnect using the given [`ErrorClassifier`] rather
+    /// than the [`DefaultErrorClassifier`].
+    pub fn with_classifier(mut self, classifier: impl ErrorClassifier + 'static) -> Self {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    /// Reconnect at most this many times for any one call before handing its error back.
+    /// This is 3 by default.
+    pub fn with_max_reconnects(mut self, max_reconnects: usize) -> Self {
+        self.max_reconnects = max_reconnects;
+        self
+    }
+
+    async fn connection(&self) -> (usize, Arc<dyn RpcClientT>) {
+        let current = self.current.lock().await;
+        (current.0, current.1.clone())
+    }
+
+    // Replace the given connection with a new one, unless somebody else already has.
+    async fn reconnect(&self, connection: usize) -> Result<(), RpcError> {
+        let mut current = self.current.lock().await;
+        if current.0 == connection {
+            tracing::debug!("Reconnecting to the node");
+            *current = (connection + 1, (self.connect)().await?);
+        }
+        Ok(())
+    }
+
+    // Run the call given, reconnecting and running it again for as long as it fails with
+    // an error that calls for a reconnect.
+    async fn call<T, F>(&self, call: F) -> Result<T, RpcError>
+    where
+        F: Fn(Arc<dyn RpcClientT>) -> RpcFuture<'static, T>,
+    {
+        let mut reconnects = 0;
+        loop {
+            let (connection, client) = self.connection().await;
+            let err = match call(client).await {
+                Ok(res) => return Ok(res),
+                Err(err) => Error::Rpc(err),
+            };
+            let action = self.classifier.classify(&err);
+            let Error::Rpc(err) = err else {
+                unreachable!("the error was made from an RpcError above; qed")
+            };
+            if action != ErrorAction::Reconnect || reconnects >= self.max_reconnects {
+                return Err(err)
+            }
+            tracing::debug!("Reconnecting after error: {err}");
+            self.reconnect(connection).await?;
+            reconnects += 1;
+        }
+    }
+}
+
+impl RpcClientT for ReconnectingClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        Box::pin(self.call(move |client| {
+            let method = method.to_owned();
+            let params = params.clone();
+            Box::pin(async move { client.request_raw(&method, params).await })
+        }))
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        Box::pin(self.call(move |client| {
+            let (sub, unsub) = (sub.to_owned(), unsub.to_owned());
+            let params = params.clone();
+            Box::pin(async move { client.subscribe_raw(&sub, params, &unsub).await })
+        }))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::MockRpc;
+    use std::sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    };
+
+    // A client whose first `broken` connections fail every request with the given error.
+    async fn flaky_client(
+        broken: usize,
+        error: fn() -> RpcError,
+    ) -> (ReconnectingClient, Arc<AtomicUsize>) {
+        let connections = Arc::new(AtomicUsize::new(0));
+        let counter = connections.clone();
+        let client = ReconnectingClient::new(move || {
+            let connection = counter.fetch_add(1, Ordering::SeqCst);
+            async move {
+                Ok(MockRpc::new(move |_, _| {
+                    match connection < broken {
+                        true => Err(error()),
+                        false => Ok(serde_json::json!(42)),
+                    }
+                }))
+            }
+        })
+        .await
+        .unwrap();
+        (client, connections)
+    }
+
+    #[tokio::test]
+    async fn reconnects_when_the_connection_breaks() {
+        let (client, connections) =
+            flaky_client(2, || RpcError::SubscriptionDropped).await;
+        let res = client.request_raw("method", None).await.unwrap();
+        assert_eq!(res.get(), "42");
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+
+        // The new connection is kept:
+        client.request_raw("method", None).await.unwrap();
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn other_errors_are_handed_back() {
+        let (client, connections) =
+            flaky_client(1, || RpcError::ClientError("Method not found".into())).await;
+        assert!(client.request_raw("method", None).await.is_err());
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+
+        // Nor do we keep reconnecting forever:
+        let (client, connections) =
+            flaky_client(10, || RpcError::SubscriptionDropped).await;
+        let client = client.with_max_reconnects(2);
+        assert!(client.request_raw("method", None).await.is_err());
+        assert_eq!(connections.load(Ordering::SeqCst), 3);
+    }
+}
@@ -58,6 +58,7 @@ use crate::{
 };
 use codec::{
     Decode,
+    DecodeAll,
     Encode,
 };
 use frame_metadata::RuntimeMetadataPrefixed;
@@ -103,11 +104,23 @@ impl<T: Config> Rpc<T> {
         key: &[u8],
         hash: Option<T::Hash>,
     ) -> Result<Option<types::StorageData>, Error> {
-        let params = rpc_params![to_hex(key), hash];
+        let params = rpc_params![to_hex(key), hash.map(to_hex)];
         let data = self.client.request("state_getStorage", params).await?;
         Ok(data)
     }
 
+    /// Fetch the length in bytes of the value at the given storage key, without fetching
+    /// the value itself. Returns `None` if there is no value at the key.
+    pub async fn storage_size(
+        &self,
+        key: &[u8],
+        hash: Option<T::Hash>,
+    ) -> Result<Option<u64>, Error> {
+        let params = rpc_params![to_hex(key), hash.map(to_hex)];
+        let size = self.client.request("state_getStorageSize", params).await?;
+        Ok(size)
+    }
+
     /// Returns the keys with prefix with pagination support.
     /// Up to `count` keys will be returned.
     /// If `start_key` is passed, return next keys in storage in lexicographic order.
@@ -119,11 +132,67 @@ impl<T: Config> Rpc<T> {
         hash: Option<T::Hash>,
     ) -> Result<Vec<types::StorageKey>, Error> {
         let start_key = start_key.map(to_hex);
-        let params = rpc_params![to_hex(key), count, start_key, hash];
+        let params = rpc_params![to_hex(key), count, start_key, hash.map(to_hex)];
         let data = self.client.request("state_getKeysPaged", params).await?;
         Ok(data)
     }
 
+    /// Fetch the raw bytes at a key in a child trie. `child_key` is the prefixed key of the
+    /// child trie, which for default child tries is `:child_storage:default:` followed by
+    /// the trie's ID.
+    pub async fn child_storage(
+        &self,
+        child_key: &[u8],
+        key: &[u8],
+        hash: Option<T::Hash>,
+    ) -> Result<Option<types::StorageData>, Error> {
+        let params = rpc_params![to_hex(child_key), to_hex(key), hash.map(to_hex)];
+        let data = self.client.request("childstate_getStorage", params).await?;
+        Ok(data)
+    }
+
+    /// Returns up to `count` keys with the given prefix from a child trie, like
+    /// [`Rpc::storage_keys_paged()`] does for the main trie. See [`Rpc::child_storage()`]
+    /// for what `child_key` is.
+    pub async fn child_storage_keys_paged(
+        &self,
+        child_key: &[u8],
+        prefix: &[u8],
+        count: u32,
+        start_key: Option<&[u8]>,
+        hash: Option<T::Hash>,
+    ) -> Result<Vec<types::StorageKey>, Error> {
+        let start_key = start_key.map(to_hex);
+        let params = rpc_params![
+            to_hex(child_key),
+            to_hex(prefix),
+            count,
+            start_key,
+            hash.map(to_hex)
+        ];
+        let data = self
+            .client
+            .request("childstate_getKeysPaged", params)
+            .await?;
+        Ok(data)
+    }
+
+    /// Fetch every key with the given prefix, along with its value, in one call.
+    ///
+    /// The node has to read every matching entry to answer, so `state_getPairs` is marked
+    /// unsafe, and public nodes won't answer it unless they're run with
+    /// `--rpc-methods=unsafe`. It's handy on dev chains, but prefer
+    /// [`Rpc::storage_keys_paged()`] and [`Rpc::query_storage_at()`] otherwise.
+    pub async fn storage_pairs(
+        &self,
+        key: &[u8],
+        hash: Option<T::Hash>,
+    ) -> Result<Vec<(types::StorageKey, types::StorageData)>, Error> {
+        let params = rpc_params![to_hex(key), hash.map(to_hex)];
+        let data = self.client.request("state_getPairs", params).await?;
+        Ok(data)
+    }
+
     /// Query historical storage entries
     pub async fn query_storage(
         &self,
@@ -132,7 +201,7 @@ impl<T: Config> Rpc<T> {
         to: Option<T::Hash>,
     ) -> Result<Vec<types::StorageChangeSet<T::Hash>>, Error> {
         let keys: Vec<String> = keys.into_iter().map(to_hex).collect();
-        let params = rpc_params![keys, from, to];
+        let params = rpc_params![keys, to_hex(from), to.map(to_hex)];
         self.client
             .request("state_queryStorage", params)
             .await
@@ -146,7 +215,7 @@ impl<T: Config> Rpc<T> {
         at: Option<T::Hash>,
     ) -> Result<Vec<types::StorageChangeSet<T::Hash>>, Error> {
         let keys: Vec<String> = keys.into_iter().map(to_hex).collect();
-        let params = rpc_params![keys, at];
+        let params = rpc_params![keys, at.map(to_hex)];
         self.client
             .request("state_queryStorageAt", params)
             .await
@@ -157,22 +226,45 @@ impl<T: Config> Rpc<T> {
     pub async fn genesis_hash(&self) -> Result<T::Hash, Error> {
         let block_zero = 0u32;
         let params = rpc_params![block_zero];
-        let genesis_hash: Option<T::Hash> =
+        let genesis_hash: Option<String> =
             self.client.request("chain_getBlockHash", params).await?;
-        genesis_hash.ok_or_else(|| "Genesis hash not found".into())
+        match genesis_hash {
+            Some(hash) => hash_from_hex(&hash),
+            None => Err("Genesis hash not found".into()),
+        }
     }
 
     /// Fetch the metadata
     pub async fn metadata(&self, at: Option<T::Hash>) -> Result<Metadata, Error> {
         let bytes: types::Bytes = self
             .client
-            .request("state_getMetadata", rpc_params![at])
+            .request("state_getMetadata", rpc_params![at.map(to_hex)])
             .await?;
         let meta: RuntimeMetadataPrefixed = Decode::decode(&mut &bytes[..])?;
         let metadata: Metadata = meta.try_into()?;
         Ok(metadata)
     }
 
+    /// Fetch the metadata in the format given by `version` using the
+    /// `Metadata_metadata_at_version` runtime API. This is `None` if the runtime can't
+    /// provide metadata in that format. Runtimes reporting version 2 or later of the
+    /// `Metadata` runtime API support this call.
+    pub async fn metadata_at_version(
+        &self,
+        version: u32,
+        at: Option<T::Hash>,
+    ) -> Result<Option<Metadata>, Error> {
+        let bytes = self
+            .state_call("Metadata_metadata_at_version", Some(&version.encode()), at)
+            .await?;
+        let Some(opaque) = Option::<Vec<u8>>::decode(&mut &bytes[..])? else {
+            return Ok(None)
+        };
+        let meta: RuntimeMetadataPrefixed = Decode::decode(&mut &opaque[..])?;
+        let metadata: Metadata = meta.try_into()?;
+        Ok(Some(metadata))
+    }
+
     /// Execute a runtime API call.
     pub async fn call(
         &self,
@@ -186,12 +278,89 @@ impl<T: Config> Rpc<T> {
             .client
             .request(
                 "state_call",
-                rpc_params![function, to_hex(call_parameters), at],
+                rpc_params![function, to_hex(call_parameters), at.map(to_hex)],
             )
             .await?;
         Ok(bytes)
     }
 
+    /// Fetch the names of the RPC methods that the node exposes.
+    pub async fn rpc_methods(&self) -> Result<types::RpcMethods, Error> {
+        self.client.request("rpc_methods", rpc_params![]).await
+    }
+
+    /// Check the RPC methods that subxt relies on against those that the node exposes,
+    /// returning a report of any that are missing. The report also lists the methods
+    /// which are known to be deprecated alongside the method from the new JSON-RPC spec
+    /// that takes over from each; subxt still uses these, so they're only informational.
+    ///
+    /// A warning is logged for each missing method, so this can be called once at startup
+    /// to catch problems with a node before they show up as failed calls.
+    pub async fn check_rpc_methods(&self) -> Result<types::RpcMethodsReport, Error> {
+        let available = self.rpc_methods().await?;
+        let report = rpc_methods_report(&available.methods);
+        for info in &report.missing {
+            tracing::warn!("The node does not support the {} RPC method", info.method);
+        }
+        Ok(report)
+    }
+
+    /// Work out whether the chain spec can be fetched using the `chainSpec_v1_*` methods
+    /// from the new JSON-RPC spec, or whether the legacy methods are needed, by looking at
+    /// the methods the node exposes.
+    pub async fn chain_spec_methods(&self) -> Result<types::ChainSpecMethods, Error> {
+        let available = self.rpc_methods().await?;
+        Ok(chain_spec_methods_from(&available.methods))
+    }
+
+    /// Fetch the genesis hash, name and properties of the chain. These come from the
+    /// `chainSpec_v1_*` methods if the node has them, and from the legacy methods
+    /// otherwise; see [`Rpc::chain_spec_methods()`].
+    pub async fn chain_spec(&self) -> Result<types::ChainSpec<T::Hash>, Error> {
+        let chain_spec = match self.chain_spec_methods().await? {
+            types::ChainSpecMethods::V1 => {
+                types::ChainSpec {
+                    genesis_hash: self.chainspec_v1_genesis_hash().await?,
+                    chain_name: self.chainspec_v1_chain_name().await?,
+                    properties: self.chainspec_v1_properties().await?,
+                }
+            }
+            types::ChainSpecMethods::Legacy => {
+                types::ChainSpec {
+                    genesis_hash: self.genesis_hash().await?,
+                    chain_name: self.system_chain().await?,
+                    properties: self.system_properties().await?,
+                }
+            }
+        };
+        Ok(chain_spec)
+    }
+
+    /// Fetch the genesis hash using the `chainSpec_v1_genesisHash` method.
+    pub async fn chainspec_v1_genesis_hash(&self) -> Result<T::Hash, Error> {
+        let hash: String = self
+            .client
+            .request("chainSpec_v1_genesisHash", rpc_params![])
+            .await?;
+        hash_from_hex(&hash)
+    }
+
+    /// Fetch the name of the chain using the `chainSpec_v1_chainName` method.
+    pub async fn chainspec_v1_chain_name(&self) -> Result<String, Error> {
+        self.client
+            .request("chainSpec_v1_chainName", rpc_params![])
+            .await
+    }
+
+    /// Fetch the properties of the chain using the `chainSpec_v1_properties` method.
+    pub async fn chainspec_v1_properties(
+        &self,
+    ) -> Result<types::SystemProperties, Error> {
+        self.client
+            .request("chainSpec_v1_properties", rpc_params![])
+            .await
+    }
+
     /// Fetch system properties
     pub async fn system_properties(&self) -> Result<types::SystemProperties, Error> {
         self.client
@@ -234,7 +403,7 @@ impl<T: Config> Rpc<T> {
         &self,
         hash: Option<T::Hash>,
     ) -> Result<Option<T::Header>, Error> {
-        let params = rpc_params![hash];
+        let params = rpc_params![hash.map(to_hex)];
         let header = self.client.request("chain_getHeader", params).await?;
         Ok(header)
     }
@@ -245,17 +414,33 @@ impl<T: Config> Rpc<T> {
         block_number: Option<types::BlockNumber>,
     ) -> Result<Option<T::Hash>, Error> {
         let params = rpc_params![block_number];
-        let block_hash = self.client.request("chain_getBlockHash", params).await?;
-        Ok(block_hash)
+        let block_hash: Option<String> =
+            self.client.request("chain_getBlockHash", params).await?;
+        block_hash.map(|hash| hash_from_hex(&hash)).transpose()
+    }
+
+    /// Get the hashes of the blocks with the given numbers in a single call, in the same
+    /// order as the numbers. Numbers which have no block yet have no hash.
+    pub async fn block_hashes(
+        &self,
+        block_numbers: &[types::BlockNumber],
+    ) -> Result<Vec<Option<T::Hash>>, Error> {
+        let params = rpc_params![block_numbers];
+        let block_hashes: Vec<Option<String>> =
+            self.client.request("chain_getBlockHash", params).await?;
+        block_hashes
+            .into_iter()
+            .map(|hash| hash.map(|hash| hash_from_hex(&hash)).transpose())
+            .collect()
     }
 
     /// Get a block hash of the latest finalized block
     pub async fn finalized_head(&self) -> Result<T::Hash, Error> {
-        let hash = self
+        let hash: String = self
             .client
             .request("chain_getFinalizedHead", rpc_params![])
             .await?;
-        Ok(hash)
+        hash_from_hex(&hash)
     }
 
     /// Get a Block
@@ -263,7 +448,7 @@ impl<T: Config> Rpc<T> {
         &self,
         hash: Option<T::Hash>,
     ) -> Result<Option<types::ChainBlockResponse<T>>, Error> {
-        let params = rpc_params![hash];
+        let params = rpc_params![hash.map(to_hex)];
         let block = self.client.request("chain_getBlock", params).await?;
         Ok(block)
     }
@@ -277,19 +462,22 @@ impl<T: Config> Rpc<T> {
         &self,
         block_hash: T::Hash,
     ) -> Result<Option<types::BlockStats>, Error> {
-        let params = rpc_params![block_hash];
+        let params = rpc_params![to_hex(block_hash)];
         let stats = self.client.request("dev_getBlockStats", params).await?;
         Ok(stats)
     }
 
     /// Get proof of storage entries at a specific block's state.
+    ///
+    /// The proof can be checked against the state root of the block's header using
+    /// [`crate::storage::verify_read_proof()`].
     pub async fn read_proof(
         &self,
         keys: impl IntoIterator<Item = &[u8]>,
         hash: Option<T::Hash>,
     ) -> Result<types::ReadProof<T::Hash>, Error> {
         let keys: Vec<String> = keys.into_iter().map(to_hex).collect();
-        let params = rpc_params![keys, hash];
+        let params = rpc_params![keys, hash.map(to_hex)];
         let proof = self.client.request("state_getReadProof", params).await?;
         Ok(proof)
     }
@@ -299,7 +487,7 @@ impl<T: Config> Rpc<T> {
         &self,
         at: Option<T::Hash>,
     ) -> Result<types::RuntimeVersion, Error> {
-        let params = rpc_params![at];
+        let params = rpc_params![at.map(to_hex)];
         let version = self
             .client
             .request("state_getRuntimeVersion", params)
@@ -365,6 +553,26 @@ impl<T: Config> Rpc<T> {
         Ok(subscription)
     }
 
+    /// Subscribe to GRANDPA justifications, which are produced as blocks are finalized. Each
+    /// item is a SCALE encoded justification, which can be decoded into a
+    /// [`types::GrandpaJustification`] if the details are needed.
+    ///
+    /// This requires a node which exposes the GRANDPA RPC methods; chains using some other
+    /// finality mechanism won't support this.
+    pub async fn subscribe_grandpa_justifications(
+        &self,
+    ) -> Result<Subscription<types::Bytes>, Error> {
+        let subscription = self
+            .client
+            .subscribe(
+                "grandpa_subscribeJustifications",
+                rpc_params![],
+                "grandpa_unsubscribeJustifications",
+            )
+            .await?;
+        Ok(subscription)
+    }
+
     /// Subscribe to runtime version updates that produce changes in the metadata.
     pub async fn subscribe_runtime_version(
         &self,
@@ -387,11 +595,11 @@ impl<T: Config> Rpc<T> {
     ) -> Result<T::Hash, Error> {
         let bytes: types::Bytes = extrinsic.encode().into();
         let params = rpc_params![bytes];
-        let xt_hash = self
+        let xt_hash: String = self
             .client
             .request("author_submitExtrinsic", params)
             .await?;
-        Ok(xt_hash)
+        hash_from_hex(&xt_hash)
     }
 
     /// Execute a runtime API call.
@@ -407,7 +615,7 @@ impl<T: Config> Rpc<T> {
             .client
             .request(
                 "state_call",
-                rpc_params![function, to_hex(call_parameters), at],
+                rpc_params![function, to_hex(call_parameters), at.map(to_hex)],
             )
             .await?;
         Ok(bytes)
@@ -455,6 +663,10 @@ impl<T: Config> Rpc<T> {
     /// `session_keys` is the SCALE encoded session keys object from the runtime.
     ///
     /// Returns `true` iff all private keys could be found.
+    ///
+    /// This is an unsafe RPC method, so the node needs to be run with `--rpc-methods unsafe`
+    /// (or be connected to locally) for this to succeed. Validators usually expose it only
+    /// locally, to check that the keys set for them on chain are in their keystore.
     pub async fn has_session_keys(
         &self,
         session_keys: types::Bytes,
@@ -465,7 +677,11 @@ impl<T: Config> Rpc<T> {
 
     /// Checks if the keystore has private keys for the given public key and key type.
     ///
+    /// The key type is the four character ID of the key, such as `"babe"` or `"gran"`.
     /// Returns `true` if a private key could be found.
+    ///
+    /// This is an unsafe RPC method, so the node needs to be run with `--rpc-methods unsafe`
+    /// (or be connected to locally) for this to succeed.
     pub async fn has_key(
         &self,
         public_key: types::Bytes,
@@ -475,6 +691,37 @@ impl<T: Config> Rpc<T> {
         self.client.request("author_hasKey", params).await
     }
 
+    /// Fetch the value at some key in the node's offchain storage, if there is one.
+    ///
+    /// This is an unsafe RPC method, so the node needs to be run with `--rpc-methods unsafe`
+    /// (or be connected to locally) for this to succeed.
+    pub async fn offchain_local_storage_get(
+        &self,
+        kind: types::OffchainStorageKind,
+        key: &[u8],
+    ) -> Result<Option<types::Bytes>, Error> {
+        let params = rpc_params![kind, to_hex(key)];
+        self.client
+            .request("offchain_localStorageGet", params)
+            .await
+    }
+
+    /// Set the value at some key in the node's offchain storage.
+    ///
+    /// This is an unsafe RPC method, so the node needs to be run with `--rpc-methods unsafe`
+    /// (or be connected to locally) for this to succeed.
+    pub async fn offchain_local_storage_set(
+        &self,
+        kind: types::OffchainStorageKind,
+        key: &[u8],
+        value: &[u8],
+    ) -> Result<(), Error> {
+        let params = rpc_params![kind, to_hex(key), to_hex(value)];
+        self.client
+            .request("offchain_localStorageSet", params)
+            .await
+    }
+
     /// Submits the extrinsic to the dry_run RPC, to test if it would succeed.
     ///
     /// Returns a [`types::DryRunResult`], which is the result of performing the dry run.
@@ -483,12 +730,47 @@ impl<T: Config> Rpc<T> {
         encoded_signed: &[u8],
         at: Option<T::Hash>,
     ) -> Result<types::DryRunResult, Error> {
-        let params = rpc_params![to_hex(encoded_signed), at];
-        let result_bytes: types::Bytes =
-            self.client.request("system_dryRun", params).await?;
+        let result_bytes = self.dry_run_raw(encoded_signed, at).await?;
         Ok(types::decode_dry_run_result(&mut &*result_bytes.0)?)
     }
 
+    /// Submits the extrinsic to the dry_run RPC, to test if it would succeed.
+    ///
+    /// Returns the SCALE encoded `ApplyExtrinsicResult` handed back from the node, leaving
+    /// it up to the caller to decode it.
+    pub async fn dry_run_raw(
+        &self,
+        encoded_signed: &[u8],
+        at: Option<T::Hash>,
+    ) -> Result<types::Bytes, Error> {
+        let params = rpc_params![to_hex(encoded_signed), at.map(to_hex)];
+        self.client.request("system_dryRun", params).await
+    }
+
+    /// Fetch the weight, class and fee of an extrinsic using the `payment_queryInfo` RPC
+    /// method. Newer nodes may not expose this, in which case the
+    /// `TransactionPaymentApi_query_info` runtime API can be called instead.
+    pub async fn payment_query_info(
+        &self,
+        encoded_signed: &[u8],
+        at: Option<T::Hash>,
+    ) -> Result<types::RuntimeDispatchInfo, Error> {
+        let params = rpc_params![to_hex(encoded_signed), at.map(to_hex)];
+        self.client.request("payment_queryInfo", params).await
+    }
+
+    /// Fetch a breakdown of the fee of an extrinsic using the `payment_queryFeeDetails`
+    /// RPC method. Newer nodes may not expose this, in which case the
+    /// `TransactionPaymentApi_query_fee_details` runtime API can be called instead.
+    pub async fn payment_query_fee_details(
+        &self,
+        encoded_signed: &[u8],
+        at: Option<T::Hash>,
+    ) -> Result<types::FeeDetails, Error> {
+        let params = rpc_params![to_hex(encoded_signed), at.map(to_hex)];
+        self.client.request("payment_queryFeeDetails", params).await
+    }
+
     /// Subscribe to `chainHead_unstable_follow` to obtain all reported blocks by the chain.
     ///
     /// The subscription ID can be used to make queries for the
@@ -533,7 +815,7 @@ impl<T: Config> Rpc<T> {
             .client
             .subscribe(
                 "chainHead_unstable_body",
-                rpc_params![subscription_id, hash],
+                rpc_params![subscription_id, to_hex(hash)],
                 "chainHead_unstable_stopBody",
             )
             .await?;
@@ -556,7 +838,7 @@ impl<T: Config> Rpc<T> {
             .client
             .request(
                 "chainHead_unstable_header",
-                rpc_params![subscription_id, hash],
+                rpc_params![subscription_id, to_hex(hash)],
             )
             .await?;
 
@@ -581,7 +863,12 @@ impl<T: Config> Rpc<T> {
             .client
             .subscribe(
                 "chainHead_unstable_storage",
-                rpc_params![subscription_id, hash, to_hex(key), child_key.map(to_hex)],
+                rpc_params![
+                    subscription_id,
+                    to_hex(hash),
+                    to_hex(key),
+                    child_key.map(to_hex)
+                ],
                 "chainHead_unstable_stopStorage",
             )
             .await?;
@@ -607,7 +894,12 @@ impl<T: Config> Rpc<T> {
             .client
             .subscribe(
                 "chainHead_unstable_call",
-                rpc_params![subscription_id, hash, function, to_hex(call_parameters)],
+                rpc_params![
+                    subscription_id,
+                    to_hex(hash),
+                    function,
+                    to_hex(call_parameters)
+                ],
                 "chainHead_unstable_stopCall",
             )
             .await?;
@@ -629,7 +921,7 @@ impl<T: Config> Rpc<T> {
         self.client
             .request(
                 "chainHead_unstable_unpin",
-                rpc_params![subscription_id, hash],
+                rpc_params![subscription_id, to_hex(hash)],
             )
             .await?;
 
@@ -638,15 +930,197 @@ impl<T: Config> Rpc<T> {
 
     /// Get genesis hash obtained from the `chainHead_genesisHash` method.
     pub async fn chainhead_unstable_genesishash(&self) -> Result<T::Hash, Error> {
-        let hash = self
+        let hash: String = self
             .client
             .request("chainHead_unstable_genesisHash", rpc_params![])
             .await?;
 
-        Ok(hash)
+        hash_from_hex(&hash)
+    }
+}
+
+/// The legacy RPC methods relied on by subxt's higher level APIs.
+const USED_RPC_METHODS: &[&str] = &[
+    "author_submitAndWatchExtrinsic",
+    "author_submitExtrinsic",
+    "chain_getBlock",
+    "chain_getBlockHash",
+    "chain_getFinalizedHead",
+    "chain_getHeader",
+    "chain_subscribeAllHeads",
+    "chain_subscribeFinalizedHeads",
+    "chain_subscribeNewHeads",
+    "state_call",
+    "state_getKeysPaged",
+    "state_getMetadata",
+    "state_getRuntimeVersion",
+    "state_getStorage",
+    "state_queryStorageAt",
+    "state_subscribeRuntimeVersion",
+    "system_accountNextIndex",
+];
+
+/// Legacy RPC methods which are deprecated, and the new JSON-RPC spec methods which
+/// replace them (if there's a direct replacement).
+const DEPRECATED_RPC_METHODS: &[(&str, Option<&str>)] = &[
+    ("chain_subscribeNewHeads", Some("chainHead_unstable_follow")),
+    ("chain_subscribeAllHeads", Some("chainHead_unstable_follow")),
+    (
+        "chain_subscribeFinalizedHeads",
+        Some("chainHead_unstable_follow"),
+    ),
+    ("chain_getBlock", Some("chainHead_unstable_body")),
+    ("chain_getHeader", Some("chainHead_unstable_header")),
+    ("state_getStorage", Some("chainHead_unstable_storage")),
+    ("state_call", Some("chainHead_unstable_call")),
+    (
+        "author_submitAndWatchExtrinsic",
+        Some("transaction_unstable_submitAndWatch"),
+    ),
+    (
+        "state_subscribeRuntimeVersion",
+        Some("chainHead_unstable_follow"),
+    ),
+];
+
+fn rpc_methods_report(available: &[String]) -> types::RpcMethodsReport {
+    let is_available = |method: &str| available.iter().any(|m| m == method);
+    let missing = USED_RPC_METHODS
+        .iter()
+        .filter(|method| !is_available(method))
+        .map(|&method| {
+            let replacement = DEPRECATED_RPC_METHODS
+                .iter()
+                .find(|(m, _)| *m == method)
+                .and_then(|(_, replacement)| *replacement);
+            types::RpcMethodInfo {
+                method,
+                replacement,
+            }
+        })
+        .collect();
+    let deprecated = DEPRECATED_RPC_METHODS
+        .iter()
+        .filter(|(method, _)| is_available(method))
+        .map(|&(method, replacement)| {
+            types::RpcMethodInfo {
+                method,
+                replacement,
+            }
+        })
+        .collect();
+    types::RpcMethodsReport {
+        missing,
+        deprecated,
     }
 }
 
+/// The `chainSpec_v1_*` methods, all of which need to be available to use them.
+const CHAIN_SPEC_V1_METHODS: &[&str] = &[
+    "chainSpec_v1_chainName",
+    "chainSpec_v1_genesisHash",
+    "chainSpec_v1_properties",
+];
+
+fn chain_spec_methods_from(available: &[String]) -> types::ChainSpecMethods {
+    let has_v1 = CHAIN_SPEC_V1_METHODS
+        .iter()
+        .all(|method| available.iter().any(|m| m == method));
+    if has_v1 {
+        types::ChainSpecMethods::V1
+    } else {
+        types::ChainSpecMethods::Legacy
+    }
+}
+
+// Hashes are always sent and received as hex strings, so that this works regardless of
+// how some custom `Config::Hash` type would otherwise be serialized.
 fn to_hex(bytes: impl AsRef<[u8]>) -> String {
     format!("0x{}", hex::encode(bytes.as_ref()))
 }
+
+fn hash_from_hex<Hash: Decode>(hash: &str) -> Result<Hash, Error> {
+    let bytes = hex::decode(hash.trim_start_matches("0x"))
+        .map_err(|e| Error::Other(format!("Invalid hex encoded hash {hash}: {e}")))?;
+    Ok(Hash::decode_all(&mut &*bytes)?)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // A hash type whose serde impls don't use hex, unlike `H256`.
+    #[derive(Debug, Clone, Copy, PartialEq, Encode, Decode, Serialize)]
+    struct CustomHash([u8; 4]);
+
+    impl AsRef<[u8]> for CustomHash {
+        fn as_ref(&self) -> &[u8] {
+            &self.0
+        }
+    }
+
+    #[test]
+    fn custom_hashes_round_trip_as_hex() {
+        let hash = CustomHash([1, 2, 3, 255]);
+        assert_eq!(serde_json::to_string(&hash).unwrap(), "[1,2,3,255]");
+
+        let hex = to_hex(hash);
+        assert_eq!(hex, "0x010203ff");
+        assert_eq!(hash_from_hex::<CustomHash>(&hex).unwrap(), hash);
+    }
+
+    #[test]
+    fn hashes_of_the_wrong_length_are_rejected() {
+        assert!(hash_from_hex::<CustomHash>("0x010203").is_err());
+        assert!(hash_from_hex::<CustomHash>("0x0102030405").is_err());
+        assert!(hash_from_hex::<CustomHash>("not hex").is_err());
+    }
+
+    #[test]
+    fn reports_missing_and_deprecated_methods() {
+        let mut available: Vec<String> =
+            USED_RPC_METHODS.iter().map(|m| m.to_string()).collect();
+        available.retain(|m| m != "state_getKeysPaged");
+
+        let report = rpc_methods_report(&available);
+        assert_eq!(
+            report.missing,
+            vec![types::RpcMethodInfo {
+                method: "state_getKeysPaged",
+                replacement: None,
+            }]
+        );
+        assert!(report.deprecated.contains(&types::RpcMethodInfo {
+            method: "state_getStorage",
+            replacement: Some("chainHead_unstable_storage"),
+        }));
+        assert!(!report.is_ok());
+
+        // Deprecated methods that are still there are fine:
+        available.push("state_getKeysPaged".into());
+        let report = rpc_methods_report(&available);
+        assert!(report.missing.is_empty());
+        assert!(!report.deprecated.is_empty());
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn picks_chain_spec_methods() {
+        let mut available: Vec<String> = USED_RPC_METHODS
+            .iter()
+            .chain(CHAIN_SPEC_V1_METHODS)
+            .map(|m| m.to_string())
+            .collect();
+        assert_eq!(
+            chain_spec_methods_from(&available),
+            types::ChainSpecMethods::V1
+        );
+
+        // All of the methods are needed to use the new ones:
+        available.retain(|m| m != "chainSpec_v1_properties");
+        assert_eq!(
+            chain_spec_methods_from(&available),
+            types::ChainSpecMethods::Legacy
+        );
+    }
+}
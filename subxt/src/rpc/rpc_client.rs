@@ -18,6 +18,7 @@ use serde::{
 };
 use serde_json::value::RawValue;
 use std::{
+    any::Any,
     pin::Pin,
     sync::Arc,
     task::Poll,
@@ -29,11 +30,48 @@ use std::{
 /// Wrapping [`RpcClientT`] in this way is simply a way to expose this additional functionality
 /// without getting into issues with non-object-safe methods or no `async` in traits.
 #[derive(Clone)]
-pub struct RpcClient(Arc<dyn RpcClientT>);
+pub struct RpcClient {
+    client: Arc<dyn RpcClientT>,
+    // The same client as above, kept around so that it can be downcast back to
+    // the concrete type it was constructed from.
+    client_any: Arc<dyn Any + Send + Sync>,
+}
 
 impl RpcClient {
     pub(crate) fn new<R: RpcClientT>(client: Arc<R>) -> Self {
-        RpcClient(client)
+        RpcClient {
+            client: client.clone(),
+            client_any: client,
+        }
+    }
+
+    /// Attempt to obtain the concrete [`RpcClientT`] implementation that this was constructed
+    /// from. This is an escape hatch for accessing functionality specific to some client which
+    /// isn't exposed via [`RpcClientT`], without needing to open a second connection.
+    ///
+    /// This returns `None` unless `R` is exactly the type that was handed to
+    /// [`crate::OnlineClient::from_rpc_client()`] (or similar); it can't see through any
+    /// wrappers around the client. When the client was constructed using
+    /// [`crate::OnlineClient::from_url()`], `R` is `jsonrpsee::core::client::Client`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use subxt::{ PolkadotConfig, OnlineClient };
+    /// use jsonrpsee::core::client::Client;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let api = OnlineClient::<PolkadotConfig>::new().await.unwrap();
+    ///
+    /// let client: std::sync::Arc<Client> = api
+    ///     .rpc()
+    ///     .downcast()
+    ///     .expect("client was constructed from a jsonrpsee Client");
+    /// # }
+    /// ```
+    pub fn downcast<R: RpcClientT>(&self) -> Option<Arc<R>> {
+        self.client_any.clone().downcast().ok()
     }
 
     /// Make an RPC request, given a method name and some parameters.
@@ -45,7 +83,7 @@ impl RpcClient {
         method: &str,
         params: RpcParams,
     ) -> Result<Res, Error> {
-        let res = self.0.request_raw(method, params.build()).await?;
+        let res = self.client.request_raw(method, params.build()).await?;
         let val = serde_json::from_str(res.get())?;
         Ok(val)
     }
@@ -61,7 +99,10 @@ impl RpcClient {
         params: RpcParams,
         unsub: &str,
     ) -> Result<Subscription<Res>, Error> {
-        let sub = self.0.subscribe_raw(sub, params.build(), unsub).await?;
+        let sub = self
+            .client
+            .subscribe_raw(sub, params.build(), unsub)
+            .await?;
         Ok(Subscription::new(sub))
     }
 }
@@ -75,7 +116,7 @@ impl std::fmt::Debug for RpcClient {
 impl std::ops::Deref for RpcClient {
     type Target = dyn RpcClientT;
     fn deref(&self) -> &Self::Target {
-        &*self.0
+        &*self.client
     }
 }
 
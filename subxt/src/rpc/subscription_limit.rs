@@ -0,0 +1,199 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    RawValue,
+    RpcClientT,
+    RpcFuture,
+    RpcSubscription,
+    RpcSubscriptionStream,
+};
+use crate::error::RpcError;
+use futures::{
+    channel::mpsc,
+    lock::Mutex,
+    Stream,
+    StreamExt,
+};
+use std::{
+    pin::Pin,
+    task::{
+        Context,
+        Poll,
+    },
+};
+
+/// An [`RpcClientT`] which wraps some other client, and caps the number of subscriptions
+/// that can be open through it at once. Nodes limit the number of subscriptions that each
+/// connection can have, and once that limit is hit new subscriptions fail; with this in
+/// place, new subscriptions instead wait until an open one is dropped, and are then
+/// started in the order that they were asked for.
+///
+/// Requests that don't open a subscription aren't limited.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use subxt::{
+///     client::default_rpc_client,
+///     rpc::LimitSubscriptions,
+///     OnlineClient,
+///     PolkadotConfig,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), subxt::Error> {
+/// let rpc_client = default_rpc_client("ws://127.0.0.1:9944").await?;
+/// let limited = LimitSubscriptions::new(rpc_client, 64);
+/// let api = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(limited)).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// # Buffered and resumed subscriptions
+///
+/// A slot is taken for as long as the subscription stream handed back is alive, however
+/// far behind its consumer is. Wrapping a subscription in a
+/// [`crate::blocks::BoundedStream`] doesn't free up its slot; only dropping the stream does.
+///
+/// When a subscription is dropped by the node and then opened again (for instance by
+/// [`crate::utils::retry::retry()`] after an [`super::ErrorAction::Reconnect`]), the old
+/// stream must be dropped first. Otherwise it keeps its slot, and if every slot is taken,
+/// opening the new subscription waits forever.
+pub struct LimitSubscriptions<R> {
+    inner: R,
+    limit: usize,
+    // Each open subscription holds one of `limit` permits. Waiting for the lock on the
+    // receiver queues up those waiting for a permit.
+    permits: Mutex<mpsc::UnboundedReceiver<()>>,
+    release: mpsc::UnboundedSender<()>,
+}
+
+impl<R: RpcClientT> LimitSubscriptions<R> {
+    /// Wrap an RPC client, allowing at most `limit` subscriptions to be open through it at
+    /// once. A `limit` of 0 is treated as 1.
+    pub fn new(inner: R, limit: usize) -> Self {
+        let limit = limit.max(1);
+        let (release, permits) = mpsc::unbounded();
+        for _ in 0..limit {
+            release
+                .unbounded_send(())
+                .expect("the receiver is alive; qed");
+        }
+        LimitSubscriptions {
+            inner,
+            limit,
+            permits: Mutex::new(permits),
+            release,
+        }
+    }
+
+    /// The most subscriptions that can be open at once.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// The client that this wraps.
+    pub fn inner(&self) -> &R {
+        &self.inner
+    }
+
+    async fn acquire(&self) -> Permit {
+        // We hold on to the sender, so the stream of permits never ends.
+        self.permits.lock().await.next().await;
+        Permit(self.release.clone())
+    }
+}
+
+impl<R: RpcClientT> RpcClientT for LimitSubscriptions<R> {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        self.inner.request_raw(method, params)
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        Box::pin(async move {
+            let permit = self.acquire().await;
+            // If subscribing fails, the permit is dropped and handed straight back.
+            let subscription = self.inner.subscribe_raw(sub, params, unsub).await?;
+            let stream: RpcSubscriptionStream = Box::pin(PermitStream {
+                stream: subscription.stream,
+                _permit: permit,
+            });
+            Ok(RpcSubscription {
+                stream,
+                id: subscription.id,
+            })
+        })
+    }
+}
+
+// Hands the permit for a subscription back when dropped.
+struct Permit(mpsc::UnboundedSender<()>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        // This only fails if the client, and so everybody waiting for a permit, is gone.
+        let _ = self.0.unbounded_send(());
+    }
+}
+
+// A subscription stream which holds a permit for as long as it's alive.
+struct PermitStream {
+    stream: RpcSubscriptionStream,
+    _permit: Permit,
+}
+
+impl Stream for PermitStream {
+    type Item = Result<Box<RawValue>, RpcError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.stream.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::MockRpc;
+    use futures::FutureExt;
+
+    // A node whose subscriptions never hand anything back.
+    fn pending_subscriptions() -> MockRpc {
+        MockRpc::new(|_, _| Ok(serde_json::Value::Null)).with_subscriptions(|_, _| {
+            Ok(RpcSubscription {
+                stream: Box::pin(futures::stream::pending()),
+                id: None,
+            })
+        })
+    }
+
+    #[tokio::test]
+    async fn subscriptions_wait_for_a_free_slot() {
+        let client = LimitSubscriptions::new(pending_subscriptions(), 1);
+        let first = client.subscribe_raw("sub", None, "unsub").await.unwrap();
+
+        // Requests aren't limited:
+        client.request_raw("method", None).await.unwrap();
+
+        let mut second = client.subscribe_raw("sub", None, "unsub");
+        assert!((&mut second).now_or_never().is_none());
+
+        // Dropping the first subscription lets the second one through:
+        drop(first);
+        assert!(second.now_or_never().unwrap().is_ok());
+    }
+}
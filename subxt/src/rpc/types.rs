@@ -4,13 +4,21 @@
 
 //! Types sent to/from the Substrate RPC interface.
 
-use crate::Config;
+use crate::{
+    utils::{
+        trie::StateVersion,
+        weight::DispatchClass,
+        Weight,
+    },
+    Config,
+};
 use codec::{
     Decode,
     Encode,
 };
 use primitive_types::U256;
 use serde::{
+    de::DeserializeOwned,
     Deserialize,
     Serialize,
 };
@@ -113,6 +121,54 @@ pub type ConsensusEngineId = [u8; 4];
 /// The encoded justification specific to a consensus engine.
 pub type EncodedJustification = Vec<u8>;
 
+/// A GRANDPA justification, proving that some block has been finalized. This can be decoded
+/// from the [`EncodedJustification`] bytes of a justification whose [`ConsensusEngineId`] is
+/// `*b"FRNK"`, or from the items handed back by
+/// [`crate::rpc::Rpc::subscribe_grandpa_justifications()`].
+///
+/// The `Header` type must SCALE decode in the same way as the chain's block headers (for
+/// instance [`crate::config::substrate::SubstrateHeader`]).
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct GrandpaJustification<Hash, Number, Header> {
+    /// The round in which the block was finalized.
+    pub round: u64,
+    /// The commit message finalizing the block.
+    pub commit: GrandpaCommit<Hash, Number>,
+    /// The headers needed to prove that the precommits are for descendants of the target.
+    pub votes_ancestries: Vec<Header>,
+}
+
+/// A GRANDPA commit message, made up of precommits for the target block.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct GrandpaCommit<Hash, Number> {
+    /// The hash of the block being finalized.
+    pub target_hash: Hash,
+    /// The number of the block being finalized.
+    pub target_number: Number,
+    /// The signed precommits of the authorities.
+    pub precommits: Vec<GrandpaSignedPrecommit<Hash, Number>>,
+}
+
+/// A GRANDPA precommit, signed by some authority.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct GrandpaSignedPrecommit<Hash, Number> {
+    /// The precommit message.
+    pub precommit: GrandpaPrecommit<Hash, Number>,
+    /// The ed25519 signature of the authority.
+    pub signature: [u8; 64],
+    /// The ed25519 public key of the authority.
+    pub id: [u8; 32],
+}
+
+/// A GRANDPA precommit message for some block.
+#[derive(Clone, Debug, PartialEq, Eq, Encode, Decode)]
+pub struct GrandpaPrecommit<Hash, Number> {
+    /// The hash of the block voted for.
+    pub target_hash: Hash,
+    /// The number of the block voted for.
+    pub target_number: Number,
+}
+
 /// Bytes representing an extrinsic in a [`ChainBlock`].
 #[derive(Clone, Debug)]
 pub struct ChainBlockExtrinsic(pub Vec<u8>);
@@ -245,6 +301,9 @@ pub type SystemProperties = serde_json::Map<String, serde_json::Value>;
 /// must be kept compatible with that type from the target substrate version.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[serde(
+    bound = "Hash: Serialize + DeserializeOwned, BlockHash: Serialize + DeserializeOwned"
+)]
 pub enum SubstrateTxStatus<Hash, BlockHash> {
     /// Transaction is part of the future queue.
     Future,
@@ -297,6 +356,53 @@ pub struct RuntimeVersion {
     pub other: HashMap<String, serde_json::Value>,
 }
 
+impl RuntimeVersion {
+    /// Return the version of the runtime API with the given name (for example, `"Core"` or
+    /// `"TransactionPaymentApi"`), or `None` if the runtime does not report supporting it.
+    pub fn runtime_api_version(&self, name: &str) -> Option<u32> {
+        // Runtime APIs are identified by the blake2_64 hash of their name, and reported
+        // by the node as a list of `[hex_encoded_id, version]` pairs.
+        let id = sp_core_hashing::blake2_64(name.as_bytes());
+        let id = format!("0x{}", hex::encode(id));
+
+        self.other
+            .get("apis")?
+            .as_array()?
+            .iter()
+            .filter_map(|api| {
+                let api = api.as_array()?;
+                let api_id = api.first()?.as_str()?;
+                let version = api.get(1)?.as_u64()?;
+                Some((api_id, version))
+            })
+            .find(|(api_id, _)| api_id.eq_ignore_ascii_case(&id))
+            .and_then(|(_, version)| u32::try_from(version).ok())
+    }
+
+    /// The name of the runtime, for example `"polkadot"`, or `None` if the node doesn't
+    /// report it.
+    pub fn spec_name(&self) -> Option<&str> {
+        self.other.get("specName")?.as_str()
+    }
+
+    /// The version of the trie layout used by the runtime, or `None` if the node doesn't
+    /// report it (which is the case for runtimes predating [`StateVersion::V1`]).
+    pub fn state_version(&self) -> Option<StateVersion> {
+        match self.other.get("stateVersion")?.as_u64()? {
+            0 => Some(StateVersion::V0),
+            1 => Some(StateVersion::V1),
+            _ => None,
+        }
+    }
+
+    /// Does the runtime support the runtime API with the given name, at `min_version` or above?
+    pub fn has_runtime_api(&self, name: &str, min_version: u32) -> bool {
+        self.runtime_api_version(name)
+            .map(|version| version >= min_version)
+            .unwrap_or(false)
+    }
+}
+
 /// ReadProof struct returned by the RPC
 ///
 /// # Note
@@ -305,6 +411,7 @@ pub struct RuntimeVersion {
 /// must be kept compatible with that type from the target substrate version.
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
+#[serde(bound = "Hash: Serialize + DeserializeOwned")]
 pub struct ReadProof<Hash> {
     /// Block hash used to generate the proof
     pub at: Hash,
@@ -374,9 +481,22 @@ impl AsRef<[u8]> for StorageData {
     }
 }
 
+/// The kind of offchain storage to access in the `offchain_localStorage*` RPC methods.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OffchainStorageKind {
+    /// Storage which isn't fork aware; values set at some block persist even if that block
+    /// is reverted.
+    #[serde(rename = "PERSISTENT")]
+    Persistent,
+    /// Storage which is fork aware; values set at some block are reverted along with it.
+    #[serde(rename = "LOCAL")]
+    Local,
+}
+
 /// Storage change set
 #[derive(Serialize, Deserialize, PartialEq, Eq, Debug)]
 #[serde(rename_all = "camelCase")]
+#[serde(bound = "Hash: Serialize + DeserializeOwned")]
 pub struct StorageChangeSet<Hash> {
     /// Block hash
     pub block: Hash,
@@ -384,6 +504,63 @@ pub struct StorageChangeSet<Hash> {
     pub changes: Vec<(StorageKey, Option<StorageData>)>,
 }
 
+/// The methods that a node exposes, as returned by the `rpc_methods` RPC call.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcMethods {
+    /// The names of the methods.
+    pub methods: Vec<String>,
+}
+
+/// How the node compares to the RPC methods that subxt uses. Obtain one of these
+/// from [`crate::rpc::Rpc::check_rpc_methods()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RpcMethodsReport {
+    /// Methods that subxt uses which the node doesn't expose. The subxt APIs which
+    /// rely on these will fail.
+    pub missing: Vec<RpcMethodInfo>,
+    /// Methods which the node exposes, but which are deprecated and may be removed in
+    /// the future. subxt still relies on some of these, so this is for information only.
+    pub deprecated: Vec<RpcMethodInfo>,
+}
+
+impl RpcMethodsReport {
+    /// Returns true if none of the methods used by subxt are missing.
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+/// An RPC method named in an [`RpcMethodsReport`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RpcMethodInfo {
+    /// The name of the method.
+    pub method: &'static str,
+    /// The method from the new JSON-RPC spec which takes over from this one, if any.
+    pub replacement: Option<&'static str>,
+}
+
+/// Which group of RPC methods the chain spec is fetched with. See
+/// [`crate::rpc::Rpc::chain_spec_methods()`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ChainSpecMethods {
+    /// The `chainSpec_v1_*` methods from the new JSON-RPC spec.
+    V1,
+    /// The legacy `chain_getBlockHash`, `system_chain` and `system_properties` methods.
+    Legacy,
+}
+
+/// The details of a chain given by its chain spec. Obtain these from
+/// [`crate::rpc::Rpc::chain_spec()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainSpec<Hash> {
+    /// The hash of the genesis block.
+    pub genesis_hash: Hash,
+    /// The human readable name of the chain, for example `Polkadot`.
+    pub chain_name: String,
+    /// The properties of the chain, such as its token symbol and decimals.
+    pub properties: SystemProperties,
+}
+
 /// Health struct returned by the RPC
 #[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -768,6 +945,92 @@ impl<Hash> From<TransactionEventIR<Hash>> for TransactionEvent<Hash> {
     }
 }
 
+/// Information about an extrinsic that the `TransactionPayment` pallet works its fee out
+/// from, as handed back from `payment_queryInfo` or the `TransactionPaymentApi_query_info`
+/// runtime API.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeDispatchInfo {
+    /// The weight of the extrinsic.
+    pub weight: Weight,
+    /// The class of the extrinsic.
+    pub class: DispatchClass,
+    /// The fee that would be charged for the extrinsic, not including any tip.
+    #[serde(with = "balance")]
+    pub partial_fee: u128,
+}
+
+/// A breakdown of the fee that would be charged for an extrinsic, as handed back from
+/// `payment_queryFeeDetails` or the `TransactionPaymentApi_query_fee_details` runtime API.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Decode, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeeDetails {
+    /// The fee for including the extrinsic in a block. This is `None` for unsigned
+    /// extrinsics, which pay no fees.
+    pub inclusion_fee: Option<InclusionFee>,
+    /// The tip given with the extrinsic. The `payment_queryFeeDetails` RPC method doesn't
+    /// hand this back, in which case it's 0.
+    #[serde(default, with = "balance")]
+    pub tip: u128,
+}
+
+impl FeeDetails {
+    /// The fee that would be charged for the extrinsic, not including any tip.
+    pub fn partial_fee(&self) -> u128 {
+        self.inclusion_fee
+            .as_ref()
+            .map(|fee| {
+                fee.base_fee
+                    .saturating_add(fee.len_fee)
+                    .saturating_add(fee.adjusted_weight_fee)
+            })
+            .unwrap_or(0)
+    }
+}
+
+/// The parts of the fee for including an extrinsic in a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Decode, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionFee {
+    /// The minimum fee that every extrinsic pays.
+    #[serde(with = "balance")]
+    pub base_fee: u128,
+    /// The fee for the length of the extrinsic in bytes.
+    #[serde(with = "balance")]
+    pub len_fee: u128,
+    /// The fee for the weight of the extrinsic, adjusted for how busy the chain is.
+    #[serde(with = "balance")]
+    pub adjusted_weight_fee: u128,
+}
+
+/// Deserialize helper for balances, which nodes hand back as numbers, decimal strings or
+/// hex strings depending on the RPC method and node version.
+mod balance {
+    use super::*;
+    use serde::Deserializer;
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Balance {
+        Number(u64),
+        String(String),
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<u128, D::Error> {
+        let s = match Balance::deserialize(deserializer)? {
+            Balance::Number(n) => return Ok(n.into()),
+            Balance::String(s) => s,
+        };
+        let res = match s.strip_prefix("0x") {
+            Some(hex) => u128::from_str_radix(hex, 16),
+            None => s.parse(),
+        };
+        res.map_err(|e| serde::de::Error::custom(format!("Invalid balance {s}: {e}")))
+    }
+}
+
 /// Serialize and deserialize helper as string.
 mod as_string {
     use super::*;
@@ -810,6 +1073,51 @@ mod test {
         assert_eq!(b, &new_b);
     }
 
+    #[test]
+    fn signed_block_is_substrate_compatible() {
+        use sp_runtime::{
+            generic::{
+                Block,
+                Header,
+                SignedBlock,
+            },
+            traits::BlakeTwo256,
+            Justifications,
+            OpaqueExtrinsic,
+        };
+
+        let signed_block: SignedBlock<Block<Header<u32, BlakeTwo256>, OpaqueExtrinsic>> =
+            SignedBlock {
+                block: Block {
+                    header: Header {
+                        parent_hash: Default::default(),
+                        number: 1,
+                        state_root: Default::default(),
+                        extrinsics_root: Default::default(),
+                        digest: Default::default(),
+                    },
+                    extrinsics: vec![],
+                },
+                justifications: Some(Justifications::from((*b"FRNK", vec![1, 2, 3]))),
+            };
+
+        let json = serde_json::to_string(&signed_block).expect("serializing failed");
+        let response: ChainBlockResponse<crate::SubstrateConfig> =
+            serde_json::from_str(&json).expect("deserializing failed");
+        assert_eq!(
+            response.justifications,
+            Some(vec![(*b"FRNK", vec![1, 2, 3])])
+        );
+    }
+
+    #[test]
+    fn offchain_storage_kind_is_substrate_compatible() {
+        use sp_core::offchain::StorageKind;
+
+        assert_ser_deser(&StorageKind::PERSISTENT, &OffchainStorageKind::Persistent);
+        assert_ser_deser(&StorageKind::LOCAL, &OffchainStorageKind::Local);
+    }
+
     #[test]
     fn runtime_version_is_substrate_compatible() {
         use sp_version::RuntimeVersion as SpRuntimeVersion;
@@ -866,6 +1174,33 @@ mod test {
         assert_deser(r#"1000000000000"#, NumberOrHex::Number(1000000000000));
     }
 
+    #[test]
+    fn runtime_api_versions_can_be_looked_up() {
+        use sp_version::RuntimeVersion as SpRuntimeVersion;
+
+        let sp_version = SpRuntimeVersion {
+            spec_version: 1,
+            transaction_version: 2,
+            // The IDs of the "Core" and "Metadata" runtime APIs:
+            apis: std::borrow::Cow::Owned(vec![
+                ([0xdf, 0x6a, 0xcb, 0x68, 0x99, 0x07, 0x60, 0x9b], 4),
+                ([0x37, 0xe3, 0x97, 0xfc, 0x7c, 0x91, 0xf5, 0xe4], 1),
+            ]),
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&sp_version).expect("serializing failed");
+        let version: RuntimeVersion =
+            serde_json::from_str(&json).expect("deserializing failed");
+
+        assert_eq!(version.runtime_api_version("Core"), Some(4));
+        assert_eq!(version.runtime_api_version("Metadata"), Some(1));
+        assert_eq!(version.runtime_api_version("NotAnApi"), None);
+        assert!(version.has_runtime_api("Core", 3));
+        assert!(version.has_runtime_api("Core", 4));
+        assert!(!version.has_runtime_api("Core", 5));
+        assert!(!version.has_runtime_api("NotAnApi", 0));
+    }
+
     #[test]
     fn dry_run_result_is_substrate_compatible() {
         use sp_runtime::{
@@ -939,4 +1274,30 @@ mod test {
             },
         );
     }
+
+    #[test]
+    fn fee_info_can_be_deserialized() {
+        // Older nodes hand back "v1" weights and decimal string balances:
+        let info: RuntimeDispatchInfo = serde_json::from_str(
+            r#"{"weight":1234,"class":"normal","partialFee":"1000000000000"}"#,
+        )
+        .unwrap();
+        assert_eq!(info.weight, Weight::from_ref_time(1234));
+        assert_eq!(info.class, DispatchClass::Normal);
+        assert_eq!(info.partial_fee, 1_000_000_000_000);
+
+        let info: RuntimeDispatchInfo = serde_json::from_str(
+            r#"{"weight":{"refTime":1234,"proofSize":56},"class":"mandatory","partialFee":0}"#,
+        )
+        .unwrap();
+        assert_eq!(info.weight, Weight::from_parts(1234, 56));
+        assert_eq!(info.class, DispatchClass::Mandatory);
+
+        let details: FeeDetails = serde_json::from_str(
+            r#"{"inclusionFee":{"baseFee":"0x10","lenFee":2,"adjustedWeightFee":"0x3"}}"#,
+        )
+        .unwrap();
+        assert_eq!(details.tip, 0);
+        assert_eq!(details.partial_fee(), 21);
+    }
 }
@@ -0,0 +1,455 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! An [`RpcClientT`] which talks to a node over a WebSocket connection that it opens
+//! asking for one or more subprotocols.
+//!
+//! Some gateways route or authenticate connections based on the subprotocols listed in
+//! the `Sec-WebSocket-Protocol` header of the handshake. The subprotocols given to
+//! [`WsClientBuilder::with_subprotocol()`] are offered in that header, and the server
+//! has to pick one of them (by echoing it back in its response) for the connection to be
+//! opened. If it doesn't pick any of them, [`WsClientError::SubprotocolRejected`] is
+//! handed back; if it refuses the handshake altogether, [`WsClientError::Rejected`] is.
+//!
+//! Redirects aren't followed. With no subprotocols to ask for, the
+//! [`default_rpc_client()`](crate::client::default_rpc_client) does the same job.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use std::sync::Arc;
+//! use subxt::{
+//!     rpc::ws_client::WsClientBuilder,
+//!     OnlineClient,
+//!     PolkadotConfig,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), subxt::Error> {
+//! let rpc_client = WsClientBuilder::new()
+//!     .with_subprotocol("my-gateway-route")
+//!     .build("wss://rpc.example.com")
+//!     .await?;
+//! let api = OnlineClient::<PolkadotConfig>::from_rpc_client(Arc::new(rpc_client)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::{
+    RawValue,
+    RpcClientT,
+    RpcFuture,
+    RpcSubscription,
+};
+use crate::error::{
+    Error,
+    RpcError,
+};
+use futures::io::{
+    BufReader,
+    BufWriter,
+};
+use jsonrpsee::{
+    client_transport::ws::Uri,
+    core::{
+        async_trait,
+        client::{
+            Client,
+            ClientBuilder,
+            ReceivedMessage,
+            TransportReceiverT,
+            TransportSenderT,
+        },
+    },
+};
+use soketto::{
+    connection,
+    data::ByteSlice125,
+    handshake::{
+        self,
+        ServerResponse,
+    },
+    Data,
+    Incoming,
+};
+use std::{
+    fmt,
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{
+    io::{
+        AsyncRead,
+        AsyncWrite,
+    },
+    net::TcpStream,
+};
+use tokio_rustls::{
+    rustls,
+    TlsConnector,
+};
+use tokio_util::compat::{
+    Compat,
+    TokioAsyncReadCompatExt,
+};
+
+/// An error from opening a WebSocket connection with a [`WsClientBuilder`]. These are
+/// handed back inside an [`RpcError::ClientError`].
+#[derive(Debug, thiserror::Error)]
+pub enum WsClientError {
+    /// The URL wasn't a valid `ws://` or `wss://` URL.
+    #[error("Invalid URL: {0}")]
+    InvalidUrl(String),
+    /// The TCP connection couldn't be opened.
+    #[error("Error when opening the TCP socket: {0}")]
+    Io(#[from] std::io::Error),
+    /// The connection wasn't opened within the connection timeout.
+    #[error("Connection timeout exceeded: {0:?}")]
+    Timeout(Duration),
+    /// Something went wrong in the WebSocket handshake.
+    #[error("Error in the WebSocket handshake: {0}")]
+    Handshake(#[from] handshake::Error),
+    /// The server refused the handshake, or responded with a redirect.
+    #[error("Connection rejected with status code: {status_code}")]
+    Rejected {
+        /// The HTTP status code that the server responded with.
+        status_code: u16,
+    },
+    /// The server accepted the handshake without picking any of the subprotocols that
+    /// it was asked to use.
+    #[error("The server did not pick any of the WebSocket subprotocols {0:?}")]
+    SubprotocolRejected(Vec<String>),
+}
+
+/// How long opening a connection is given to complete by default.
+pub const DEFAULT_CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configures and builds a [`WsClient`].
+pub struct WsClientBuilder {
+    subprotocols: Vec<String>,
+    connection_timeout: Duration,
+}
+
+impl Default for WsClientBuilder {
+    fn default() -> Self {
+        WsClientBuilder {
+            subprotocols: Vec::new(),
+            connection_timeout: DEFAULT_CONNECTION_TIMEOUT,
+        }
+    }
+}
+
+impl WsClientBuilder {
+    /// Start building a client which asks for no subprotocols, and uses the
+    /// [`DEFAULT_CONNECTION_TIMEOUT`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the server to use this subprotocol. This can be called more than once to offer
+    /// several, in order of preference; the server has to pick one of them.
+    pub fn with_subprotocol(mut self, subprotocol: impl Into<String>) -> Self {
+        self.subprotocols.push(subprotocol.into());
+        self
+    }
+
+    /// Fail if the connection hasn't been opened within the given time. This covers
+    /// connecting to the server as well as the TLS and WebSocket handshakes.
+    pub fn with_connection_timeout(mut self, timeout: Duration) -> Self {
+        self.connection_timeout = timeout;
+        self
+    }
+
+    /// Open a connection to the `ws://` or `wss://` URL given.
+    pub async fn build(self, url: impl AsRef<str>) -> Result<WsClient, Error> {
+        let timeout = self.connection_timeout;
+        let connect = tokio::time::timeout(timeout, self.connect(url.as_ref()));
+        let (sender, receiver, subprotocol) = connect
+            .await
+            .map_err(|_| WsClientError::Timeout(timeout))
+            .and_then(|res| res)
+            .map_err(|e| RpcError::ClientError(Box::new(e)))?;
+        let client = ClientBuilder::default()
+            .max_notifs_per_subscription(4096)
+            .build_with_tokio(sender, receiver);
+        Ok(WsClient {
+            client,
+            subprotocol,
+        })
+    }
+
+    async fn connect(
+        &self,
+        url: &str,
+    ) -> Result<(Sender, Receiver, Option<String>), WsClientError> {
+        let uri: Uri = url
+            .parse()
+            .map_err(|e| WsClientError::InvalidUrl(format!("{url}: {e}")))?;
+        let tls = match uri.scheme_str() {
+            Some("ws") => false,
+            Some("wss") => true,
+            _ => {
+                return Err(WsClientError::InvalidUrl(format!(
+                    "{url} is not a ws:// or wss:// URL"
+                )))
+            }
+        };
+        let host = uri
+            .host()
+            .ok_or_else(|| WsClientError::InvalidUrl(format!("{url} has no host")))?;
+        let port = uri.port_u16().unwrap_or(if tls { 443 } else { 80 });
+        let host_header = match uri.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_owned(),
+        };
+        let path = uri.path_and_query().map_or("/", |p| p.as_str());
+
+        // IPv6 addresses are bracketed in the URL, but not when connecting.
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        let socket = TcpStream::connect((host, port)).await?;
+        if let Err(e) = socket.set_nodelay(true) {
+            tracing::warn!("Failed to set TCP_NODELAY: {e}");
+        }
+        let stream: Box<dyn Stream> = match tls {
+            false => Box::new(socket),
+            true => {
+                let server_name = rustls::ServerName::try_from(host).map_err(|e| {
+                    WsClientError::InvalidUrl(format!("Invalid host {host}: {e}"))
+                })?;
+                Box::new(tls_connector().connect(server_name, socket).await?)
+            }
+        };
+
+        let mut client = handshake::Client::new(
+            BufReader::new(BufWriter::new(stream.compat())),
+            &host_header,
+            path,
+        );
+        for subprotocol in &self.subprotocols {
+            client.add_protocol(subprotocol);
+        }
+        let subprotocol = match client.handshake().await? {
+            ServerResponse::Accepted { protocol } => protocol,
+            ServerResponse::Rejected { status_code }
+            | ServerResponse::Redirect { status_code, .. } => {
+                return Err(WsClientError::Rejected { status_code })
+            }
+        };
+        // The server doesn't have to pick a subprotocol, but we need it to if we asked.
+        if subprotocol.is_none() && !self.subprotocols.is_empty() {
+            return Err(WsClientError::SubprotocolRejected(
+                self.subprotocols.clone(),
+            ))
+        }
+
+        let (sender, receiver) = client.into_builder().finish();
+        Ok((Sender(sender), Receiver(receiver), subprotocol))
+    }
+}
+
+fn tls_connector() -> TlsConnector {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.add_server_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.0.iter().map(|ta| {
+        rustls::OwnedTrustAnchor::from_subject_spki_name_constraints(
+            ta.subject,
+            ta.spki,
+            ta.name_constraints,
+        )
+    }));
+    let config = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    TlsConnector::from(Arc::new(config))
+}
+
+/// An [`RpcClientT`] which talks to a node over a WebSocket connection. Construct one
+/// using a [`WsClientBuilder`]. See [the module docs](self) for more.
+pub struct WsClient {
+    client: Client,
+    subprotocol: Option<String>,
+}
+
+impl fmt::Debug for WsClient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WsClient")
+            .field("subprotocol", &self.subprotocol)
+            .finish()
+    }
+}
+
+impl WsClient {
+    /// The subprotocol that the server picked, if it was asked to pick one.
+    pub fn subprotocol(&self) -> Option<&str> {
+        self.subprotocol.as_deref()
+    }
+}
+
+impl RpcClientT for WsClient {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        self.client.request_raw(method, params)
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        self.client.subscribe_raw(sub, params, unsub)
+    }
+}
+
+// Anything that a connection can be made over: a TCP stream, with or without TLS.
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Stream for T {}
+
+type Socket = BufReader<BufWriter<Compat<Box<dyn Stream>>>>;
+
+// The halves of an open connection, for the jsonrpsee client to send and receive
+// messages over.
+struct Sender(connection::Sender<Socket>);
+struct Receiver(connection::Receiver<Socket>);
+
+#[async_trait]
+impl TransportSenderT for Sender {
+    type Error = connection::Error;
+
+    async fn send(&mut self, body: String) -> Result<(), Self::Error> {
+        self.0.send_text_owned(body).await?;
+        self.0.flush().await
+    }
+
+    async fn send_ping(&mut self) -> Result<(), Self::Error> {
+        let empty = ByteSlice125::try_from(&[][..])
+            .expect("an empty slice is shorter than 125 bytes; qed");
+        self.0.send_ping(empty).await?;
+        self.0.flush().await
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.0.close().await
+    }
+}
+
+#[async_trait]
+impl TransportReceiverT for Receiver {
+    type Error = connection::Error;
+
+    async fn receive(&mut self) -> Result<ReceivedMessage, Self::Error> {
+        loop {
+            let mut message = Vec::new();
+            match self.0.receive(&mut message).await? {
+                Incoming::Data(Data::Text(_)) => {
+                    let text = String::from_utf8(message)
+                        .map_err(|e| connection::Error::Utf8(e.utf8_error()))?;
+                    return Ok(ReceivedMessage::Text(text))
+                }
+                Incoming::Data(Data::Binary(_)) => {
+                    return Ok(ReceivedMessage::Bytes(message))
+                }
+                Incoming::Pong(_) => return Ok(ReceivedMessage::Pong),
+                Incoming::Closed(_) => continue,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use soketto::handshake::server::Response;
+    use tokio::net::TcpListener;
+
+    // A node which supports the given subprotocol, and answers each request with 42. If
+    // `reject` is given, it refuses handshakes with that status code instead.
+    async fn node(subprotocol: &'static str, reject: Option<u16>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut server =
+                handshake::Server::new(BufReader::new(BufWriter::new(socket.compat())));
+            server.add_protocol(subprotocol);
+            let request = server.receive_request().await.unwrap();
+            let key = request.key();
+            let protocol = request.protocols().next().map(str::to_owned);
+            let response = match reject {
+                Some(status_code) => Response::Reject { status_code },
+                None => {
+                    Response::Accept {
+                        key,
+                        protocol: protocol.as_deref(),
+                    }
+                }
+            };
+            server.send_response(&response).await.unwrap();
+
+            let (mut sender, mut receiver) = server.into_builder().finish();
+            let mut message = Vec::new();
+            while receiver.receive_data(&mut message).await.is_ok() {
+                let request: serde_json::Value =
+                    serde_json::from_slice(&message).unwrap();
+                let response = serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": 42,
+                });
+                sender.send_text(response.to_string()).await.unwrap();
+                sender.flush().await.unwrap();
+                message.clear();
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn connects_using_the_subprotocol_that_the_server_picks() {
+        let url = node("route-a", None).await;
+        let client = WsClientBuilder::new()
+            .with_subprotocol("route-a")
+            .build(url)
+            .await
+            .unwrap();
+        assert_eq!(client.subprotocol(), Some("route-a"));
+        let res = client.request_raw("system_name", None).await.unwrap();
+        assert_eq!(res.get(), "42");
+    }
+
+    #[tokio::test]
+    async fn fails_if_the_server_picks_no_subprotocol() {
+        let url = node("route-b", None).await;
+        let Err(err) = WsClientBuilder::new()
+            .with_subprotocol("route-a")
+            .connect(&url)
+            .await
+        else {
+            panic!("the connection should not have been opened")
+        };
+        assert!(
+            matches!(&err, WsClientError::SubprotocolRejected(p) if p == &["route-a"]),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn refused_handshakes_are_not_blamed_on_the_subprotocol() {
+        let url = node("route-a", Some(401)).await;
+        let Err(err) = WsClientBuilder::new()
+            .with_subprotocol("route-a")
+            .connect(&url)
+            .await
+        else {
+            panic!("the connection should not have been opened")
+        };
+        assert!(
+            matches!(err, WsClientError::Rejected { status_code: 401 }),
+            "{err}"
+        );
+    }
+}
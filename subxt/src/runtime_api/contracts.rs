@@ -0,0 +1,377 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Call `pallet-contracts` contracts using the `ContractsApi` runtime API, without
+//! submitting a transaction, and work out the addresses that contracts are given.
+
+use crate::{
+    config::Hasher,
+    dynamic::DecodedValue,
+    error::{
+        DispatchError,
+        Error,
+    },
+    metadata::{
+        DecodeWithMetadata,
+        Metadata,
+    },
+    utils::Weight,
+    Config,
+};
+use codec::{
+    Compact,
+    Decode,
+    Encode,
+    Input,
+};
+
+/// A call to a contract, to be executed using [`super::RuntimeApi::contract_call()`].
+/// Construct one using [`contract_call()`].
+#[derive(Clone, Debug)]
+pub struct ContractCall {
+    // The account IDs are stored encoded so that this can be held across await points
+    // regardless of whether `T::AccountId` is `Send`.
+    origin: Vec<u8>,
+    dest: Vec<u8>,
+    value: u128,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<u128>,
+    input_data: Vec<u8>,
+}
+
+/// Call the contract at `dest` from the account `origin`, handing it the `input_data`
+/// given. This is usually a selector followed by the SCALE encoded arguments of the
+/// message, as given by the contract ABI.
+pub fn contract_call<T: Config>(
+    origin: &T::AccountId,
+    dest: &T::AccountId,
+    input_data: Vec<u8>,
+) -> ContractCall
+where
+    T::AccountId: Encode,
+{
+    ContractCall {
+        origin: origin.encode(),
+        dest: dest.encode(),
+        value: 0,
+        gas_limit: None,
+        storage_deposit_limit: None,
+        input_data,
+    }
+}
+
+impl ContractCall {
+    /// The balance to transfer to the contract along with the call. Defaults to 0.
+    pub fn value(mut self, value: u128) -> Self {
+        self.value = value;
+        self
+    }
+
+    /// The most gas that the call can use. If this isn't given, the most that a block
+    /// allows is used, which is handy for working out how much gas a call needs before
+    /// submitting it as a transaction.
+    pub fn gas_limit(mut self, gas_limit: Weight) -> Self {
+        self.gas_limit = Some(gas_limit);
+        self
+    }
+
+    /// The most balance that can be taken from the origin to pay for storage. If this
+    /// isn't given, there's no limit.
+    pub fn storage_deposit_limit(mut self, limit: u128) -> Self {
+        self.storage_deposit_limit = Some(limit);
+        self
+    }
+
+    /// Encode the arguments given to `ContractsApi_call`. Version 1 of the runtime API
+    /// takes a "v1" gas limit, and later versions an optional "v2" one.
+    pub(crate) fn encode_params(&self, api_version: u32) -> Result<Vec<u8>, Error> {
+        let mut out = Vec::new();
+        out.extend(&self.origin);
+        out.extend(&self.dest);
+        self.value.encode_to(&mut out);
+        if api_version < 2 {
+            // There's no way to ask for the block limit here, so the caller has to say.
+            let Some(gas_limit) = self.gas_limit else {
+                return Err(Error::Other(
+                    "This runtime's ContractsApi needs a gas limit to be given".into(),
+                ))
+            };
+            gas_limit.ref_time().encode_to(&mut out);
+        } else {
+            self.gas_limit
+                .map(|w| (Compact(w.ref_time()), Compact(w.proof_size())))
+                .encode_to(&mut out);
+        }
+        self.storage_deposit_limit.encode_to(&mut out);
+        self.input_data.encode_to(&mut out);
+        Ok(out)
+    }
+}
+
+/// The outcome of calling a contract, as handed back from `ContractsApi_call`. Balances
+/// are assumed to be `u128`.
+#[derive(Debug)]
+pub struct ContractExecResult {
+    /// The gas that the call used.
+    pub gas_consumed: Weight,
+    /// The gas that the call needs to be given to succeed. This can be more than
+    /// [`ContractExecResult::gas_consumed`], since gas consumed by nested calls to other
+    /// contracts may be refunded.
+    pub gas_required: Weight,
+    /// The balance that the call would charge or refund for storage.
+    pub storage_deposit: StorageDeposit,
+    /// Any debug messages that the contract printed. These are only recorded by nodes
+    /// with debug output enabled.
+    pub debug_message: Vec<u8>,
+    /// The value returned from the contract, or the reason that the call failed.
+    pub result: Result<ContractReturnValue, DispatchError>,
+}
+
+/// The balance charged or refunded for the storage that a contract call uses.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Decode)]
+pub enum StorageDeposit {
+    /// The balance refunded to the origin, because the call freed up storage.
+    Refund(u128),
+    /// The balance charged to the origin.
+    Charge(u128),
+}
+
+/// The value returned from a contract call.
+#[derive(Clone, Debug, PartialEq, Eq, Decode)]
+pub struct ContractReturnValue {
+    flags: u32,
+    data: Vec<u8>,
+}
+
+impl ContractReturnValue {
+    /// Did the contract revert? The call still succeeds in this case, but any changes
+    /// that the contract made are rolled back, and the data is usually an error.
+    pub fn did_revert(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    /// The bytes that the contract returned. These can be decoded according to the
+    /// return type of the message given by the contract ABI.
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+/// The ways in which `pallet-contracts` has derived the address of a new contract from the
+/// account deploying it, the hash of its code and the salt given. All of them hash some
+/// preimage with the runtime's hasher, and turn the hash into an account ID.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ContractAddressScheme {
+    /// The hash of the deployer, code hash and salt, each as raw bytes, one after the other.
+    /// This is what older versions of the pallet do.
+    Concatenated,
+    /// The hash of the SCALE encoded tuple `(b"contract_addr_v1", deployer, code_hash,
+    /// input_data, salt)`, as the pallet's `DefaultAddressGenerator` does. Unlike the
+    /// older scheme, the constructor's input data is a part of the address.
+    #[default]
+    InputData,
+}
+
+/// Work out the address that a contract instantiated by `deployer` from the code with
+/// hash `code_hash` is given, with the `input_data` (the constructor's selector and
+/// arguments) and `salt` handed to the instantiation. The address doesn't depend on
+/// whether the code is uploaded at the same time as it's instantiated.
+///
+/// Which scheme the pallet uses depends on its version; each runtime using the pallet can
+/// also configure its own, in which case this doesn't apply. `input_data` is ignored by
+/// [`ContractAddressScheme::Concatenated`].
+pub fn contract_address<T: Config>(
+    scheme: ContractAddressScheme,
+    deployer: &T::AccountId,
+    code_hash: &T::Hash,
+    input_data: &[u8],
+    salt: &[u8],
+) -> Result<T::AccountId, Error>
+where
+    T::AccountId: Encode + Decode,
+{
+    let hash = match scheme {
+        ContractAddressScheme::Concatenated => {
+            let mut preimage = deployer.encode();
+            preimage.extend_from_slice(code_hash.as_ref());
+            preimage.extend_from_slice(salt);
+            T::Hasher::hash(&preimage)
+        }
+        ContractAddressScheme::InputData => {
+            let preimage =
+                (b"contract_addr_v1", deployer, code_hash, input_data, salt).encode();
+            T::Hasher::hash(&preimage)
+        }
+    };
+    // Account IDs longer than the hash are padded with zeros, as the pallet does.
+    let id = T::AccountId::decode(&mut TrailingZeros(hash.as_ref()))?;
+    Ok(id)
+}
+
+// Hands back the bytes given, and then zeros for ever.
+struct TrailingZeros<'a>(&'a [u8]);
+
+impl Input for TrailingZeros<'_> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, codec::Error> {
+        Ok(None)
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), codec::Error> {
+        let len = into.len().min(self.0.len());
+        into[..len].copy_from_slice(&self.0[..len]);
+        into[len..].fill(0);
+        self.0 = &self.0[len..];
+        Ok(())
+    }
+}
+
+/// Decode the `ContractExecResult` returned from `ContractsApi_call`. Anything after the
+/// result, such as the events that newer runtimes hand back, is ignored.
+pub(crate) fn decode_contract_exec_result(
+    mut bytes: &[u8],
+    api_version: u32,
+    metadata: &Metadata,
+) -> Result<ContractExecResult, Error> {
+    let cursor = &mut bytes;
+    let mut decode_weight = || -> Result<Weight, Error> {
+        if api_version < 2 {
+            return Ok(Weight::from_ref_time(u64::decode(cursor)?))
+        }
+        let ref_time = Compact::<u64>::decode(cursor)?.0;
+        let proof_size = Compact::<u64>::decode(cursor)?.0;
+        Ok(Weight::from_parts(ref_time, proof_size))
+    };
+    let gas_consumed = decode_weight()?;
+    let gas_required = decode_weight()?;
+    let storage_deposit = StorageDeposit::decode(cursor)?;
+    let debug_message = Vec::<u8>::decode(cursor)?;
+    let result = match u8::decode(cursor)? {
+        0 => Ok(ContractReturnValue::decode(cursor)?),
+        1 => {
+            let Some(ty) = metadata.dispatch_error_ty() else {
+                return Err(Error::Other(
+                    "Cannot find the DispatchError type in the metadata".into(),
+                ))
+            };
+            // Decode the error first to find out how long it is.
+            let start = *cursor;
+            DecodedValue::decode_with_metadata(cursor, ty, metadata)?;
+            let len = start.len() - cursor.len();
+            Err(DispatchError::decode_from(&start[..len], metadata))
+        }
+        n => {
+            return Err(Error::Other(format!(
+                "Invalid variant {n} for the result of a contract call"
+            )))
+        }
+    };
+
+    Ok(ContractExecResult {
+        gas_consumed,
+        gas_required,
+        storage_deposit,
+        debug_message,
+        result,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::metadata,
+        utils::AccountId32,
+        PolkadotConfig,
+    };
+
+    #[test]
+    fn encodes_contract_calls() {
+        let origin = AccountId32([1; 32]);
+        let dest = AccountId32([2; 32]);
+        let call = contract_call::<PolkadotConfig>(&origin, &dest, vec![9, 9])
+            .value(10)
+            .storage_deposit_limit(20);
+
+        let mut expected = vec![1; 32];
+        expected.extend([2; 32]);
+        (10u128, None::<()>, Some(20u128), vec![9u8, 9]).encode_to(&mut expected);
+        assert_eq!(call.encode_params(2).unwrap(), expected);
+
+        // The first version of the runtime API needs a gas limit:
+        assert!(call.encode_params(1).is_err());
+        let call = call.gas_limit(Weight::from_ref_time(5));
+        let mut expected = vec![1; 32];
+        expected.extend([2; 32]);
+        (10u128, 5u64, Some(20u128), vec![9u8, 9]).encode_to(&mut expected);
+        assert_eq!(call.encode_params(1).unwrap(), expected);
+    }
+
+    #[test]
+    fn decodes_contract_call_results() {
+        let mut bytes = (
+            Compact(100u64),
+            Compact(10u64),
+            Compact(200u64),
+            Compact(20u64),
+        )
+            .encode();
+        // `StorageDeposit::Charge(5)`, no debug message, and `Ok` with some data.
+        (1u8, 5u128, Vec::<u8>::new(), 0u8, 1u32, vec![1u8, 2, 3]).encode_to(&mut bytes);
+        // Some events, which are ignored:
+        bytes.extend([1, 0]);
+
+        let res = decode_contract_exec_result(&bytes, 2, &metadata()).unwrap();
+        assert_eq!(res.gas_consumed, Weight::from_parts(100, 10));
+        assert_eq!(res.gas_required, Weight::from_parts(200, 20));
+        assert_eq!(res.storage_deposit, StorageDeposit::Charge(5));
+        let value = res.result.unwrap();
+        assert!(value.did_revert());
+        assert_eq!(value.data(), &[1, 2, 3]);
+
+        // A failed call, with a `DispatchError::BadOrigin`, using "v1" weights:
+        let mut bytes = (100u64, 200u64).encode();
+        (0u8, 5u128, Vec::<u8>::new(), 1u8, 2u8).encode_to(&mut bytes);
+        let res = decode_contract_exec_result(&bytes, 1, &metadata()).unwrap();
+        assert_eq!(res.gas_consumed, Weight::from_ref_time(100));
+        assert_eq!(res.storage_deposit, StorageDeposit::Refund(5));
+        match res.result {
+            Err(DispatchError::Runtime(err)) => assert_eq!(err.kind, "BadOrigin"),
+            other => panic!("expected a BadOrigin error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn derives_contract_addresses() {
+        let deployer = AccountId32([1; 32]);
+        let code_hash = [2; 32].into();
+        let address = |scheme| {
+            contract_address::<PolkadotConfig>(
+                scheme,
+                &deployer,
+                &code_hash,
+                &[3, 3],
+                &[4],
+            )
+            .unwrap()
+        };
+
+        let mut preimage = vec![1; 32];
+        preimage.extend([2; 32]);
+        preimage.push(4);
+        assert_eq!(
+            address(ContractAddressScheme::Concatenated),
+            AccountId32(sp_core_hashing::blake2_256(&preimage))
+        );
+
+        let mut preimage = b"contract_addr_v1".to_vec();
+        preimage.extend([1; 32]);
+        preimage.extend([2; 32]);
+        // The input data and salt are prefixed with their compact encoded lengths.
+        preimage.extend([2 << 2, 3, 3, 1 << 2, 4]);
+        assert_eq!(
+            address(ContractAddressScheme::InputData),
+            AccountId32(sp_core_hashing::blake2_256(&preimage))
+        );
+    }
+}
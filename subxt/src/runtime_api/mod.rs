@@ -4,8 +4,18 @@
 
 //! Types associated with executing runtime API calls.
 
+mod contracts;
 mod runtime_client;
 mod runtime_types;
 
+pub use contracts::{
+    contract_address,
+    contract_call,
+    ContractAddressScheme,
+    ContractCall,
+    ContractExecResult,
+    ContractReturnValue,
+    StorageDeposit,
+};
 pub use runtime_client::RuntimeApiClient;
 pub use runtime_types::RuntimeApi;
@@ -2,6 +2,11 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
+use super::contracts::{
+    decode_contract_exec_result,
+    ContractCall,
+    ContractExecResult,
+};
 use crate::{
     client::OnlineClientT,
     error::Error,
@@ -56,4 +61,33 @@ where
             Ok(data.0)
         }
     }
+
+    /// Call a `pallet-contracts` contract using the `ContractsApi_call` runtime API,
+    /// without submitting a transaction, and decode the result. See
+    /// [`super::contract_call()`] for constructing the call.
+    ///
+    /// The version of the runtime API that the current runtime reports is used to work
+    /// out how to encode the call and decode its result.
+    pub fn contract_call(
+        &self,
+        call: ContractCall,
+    ) -> impl Future<Output = Result<ContractExecResult, Error>> + Send + 'static {
+        let client = self.client.clone();
+        let block_hash = self.block_hash;
+        async move {
+            let Some(api_version) =
+                client.runtime_version().runtime_api_version("ContractsApi")
+            else {
+                return Err(Error::Other(
+                    "The runtime does not support the ContractsApi runtime API".into(),
+                ))
+            };
+            let params = call.encode_params(api_version)?;
+            let data = client
+                .rpc()
+                .state_call("ContractsApi_call", Some(&params), Some(block_hash))
+                .await?;
+            decode_contract_exec_result(&data, api_version, &client.metadata())
+        }
+    }
 }
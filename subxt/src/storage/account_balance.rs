@@ -0,0 +1,478 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    storage_address::DynamicStorageAddress,
+    utils::value_as_u128,
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::{
+        EncodeStaticType,
+        Metadata,
+    },
+    Config,
+};
+use codec::Encode;
+use scale_value::{
+    At,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// The balance of some account, as returned from [`account_balance()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AccountBalance {
+    /// The balance that is not reserved. Some of this may be frozen.
+    pub free: u128,
+    /// The balance that has been reserved, and can't be used for anything else until
+    /// it's unreserved.
+    pub reserved: u128,
+    /// The amount of the free balance which can't be transferred or used to pay fees.
+    pub frozen: u128,
+}
+
+impl AccountBalance {
+    /// Attempt to interpret a dynamically decoded `AccountData` as an [`AccountBalance`],
+    /// returning `None` if it doesn't have the expected shape.
+    ///
+    /// Newer runtimes have a single `frozen` field. Older runtimes instead have separate
+    /// `misc_frozen` and `fee_frozen` fields, in which case `frozen` is the larger of the two.
+    ///
+    /// Some chains use their own `AccountData` type, which holds the standard balances
+    /// alongside other things. If the value doesn't itself look like balances, the values
+    /// that it's composed of are searched for one that does.
+    pub fn from_value<T>(value: &Value<T>) -> Option<AccountBalance> {
+        if let Some(balance) = AccountBalance::from_balances_value(value) {
+            return Some(balance)
+        }
+        match &value.value {
+            ValueDef::Composite(composite) => {
+                composite.values().find_map(AccountBalance::from_value)
+            }
+            _ => None,
+        }
+    }
+
+    fn from_balances_value<T>(value: &Value<T>) -> Option<AccountBalance> {
+        let free = value.at("free").and_then(value_as_u128)?;
+        let reserved = value.at("reserved").and_then(value_as_u128)?;
+        let frozen = match value.at("frozen") {
+            Some(frozen) => value_as_u128(frozen)?,
+            None => {
+                let misc_frozen = value.at("misc_frozen").and_then(value_as_u128)?;
+                let fee_frozen = value.at("fee_frozen").and_then(value_as_u128)?;
+                misc_frozen.max(fee_frozen)
+            }
+        };
+        Some(AccountBalance {
+            free,
+            reserved,
+            frozen,
+        })
+    }
+}
+
+/// Everything that `System::Account` stores about an account, as returned from
+/// [`account_info()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AccountInfo {
+    /// The number of transactions that the account has sent.
+    pub nonce: u64,
+    /// The number of other modules that currently depend on this account's existence.
+    /// The account can't be reaped until this is zero.
+    pub consumers: u32,
+    /// The number of other modules that allow this account to exist. The account may be
+    /// reaped once this and `sufficients` are zero.
+    pub providers: u32,
+    /// The number of modules that allow this account to exist for their own purposes only.
+    pub sufficients: u32,
+    /// The balance of the account.
+    pub balance: AccountBalance,
+}
+
+impl AccountInfo {
+    /// Attempt to interpret a dynamically decoded `AccountInfo` as an [`AccountInfo`],
+    /// returning `None` if it doesn't have the expected shape.
+    ///
+    /// Older runtimes have a single `refcount` in place of `consumers`, and may not have
+    /// `providers` or `sufficients` at all, in which case these are 0. The balance is read
+    /// from the `data` field as per [`AccountBalance::from_value()`].
+    pub fn from_value<T>(value: &Value<T>) -> Option<AccountInfo> {
+        let balance = value.at("data").and_then(AccountBalance::from_value)?;
+        AccountInfo::from_value_with_balance(value, balance)
+    }
+
+    // Interpret the reference counts and nonce of an `AccountInfo`, whose balance has
+    // been found elsewhere.
+    pub(crate) fn from_value_with_balance<T>(
+        value: &Value<T>,
+        balance: AccountBalance,
+    ) -> Option<AccountInfo> {
+        let nonce = account_nonce_from_value(value)?;
+        let consumers = value.at("consumers").or_else(|| value.at("refcount"));
+        let ref_count = |value: Option<&Value<T>>| -> Option<u32> {
+            match value {
+                Some(value) => value_as_u128(value).and_then(|n| u32::try_from(n).ok()),
+                None => Some(0),
+            }
+        };
+        Some(AccountInfo {
+            nonce,
+            consumers: ref_count(consumers)?,
+            providers: ref_count(value.at("providers"))?,
+            sufficients: ref_count(value.at("sufficients"))?,
+            balance,
+        })
+    }
+
+    /// Returns true if the account no longer has anything keeping it alive, and so
+    /// doesn't exist as far as the runtime is concerned.
+    pub fn is_dead(&self) -> bool {
+        self.providers == 0 && self.sufficients == 0
+    }
+}
+
+/// Interpret the `AccountInfo` stored in `System::Account`, handing back its nonce.
+pub(crate) fn account_nonce_from_value<T>(value: &Value<T>) -> Option<u64> {
+    value
+        .at("nonce")
+        .and_then(value_as_u128)
+        .and_then(|n| u64::try_from(n).ok())
+}
+
+// The pallet and entry names of the storage items that an account's balance may be found in.
+#[derive(Copy, Clone)]
+pub(crate) enum BalanceLocation {
+    // `System::Account`, which holds an `AccountInfo` with the `AccountData` in `data`.
+    System,
+    // `Balances::Account`, which holds an `AccountData`.
+    Balances,
+}
+
+impl BalanceLocation {
+    pub(crate) fn pallet_and_entry(&self) -> (&'static str, &'static str) {
+        match self {
+            BalanceLocation::System => ("System", "Account"),
+            BalanceLocation::Balances => ("Balances", "Account"),
+        }
+    }
+
+    // Whether the runtime has the storage entry of this location.
+    pub(crate) fn is_in(&self, metadata: &Metadata) -> bool {
+        let (pallet, entry) = self.pallet_and_entry();
+        metadata
+            .pallet(pallet)
+            .and_then(|pallet| pallet.storage(entry))
+            .is_ok()
+    }
+
+    // Build the address to look up the given account at this location.
+    pub(crate) fn address<Account: Encode>(
+        &self,
+        account: Account,
+    ) -> DynamicStorageAddress<'static, EncodeStaticType<Account>> {
+        let (pallet, entry) = self.pallet_and_entry();
+        crate::dynamic::storage(pallet, entry, vec![EncodeStaticType(account)])
+    }
+
+    // Pluck the `AccountData` from the value stored at this location.
+    pub(crate) fn account_data<'a, T>(
+        &self,
+        value: &'a Value<T>,
+    ) -> Option<&'a Value<T>> {
+        match self {
+            BalanceLocation::System => value.at("data"),
+            BalanceLocation::Balances => Some(value),
+        }
+    }
+}
+
+/// Fetch the balance of some account.
+///
+/// Most chains store the balance of each account in the `data` field of `System::Account`,
+/// and this is where we look first. If the `AccountData` isn't stored there, then we look
+/// in `Balances::Account` instead. Accounts that don't exist have a balance of 0. See
+/// [`AccountBalance::from_value()`] for how the different shapes of `AccountData` found in
+/// different runtime versions are handled.
+pub fn account_balance<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<AccountBalance, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        for location in [BalanceLocation::System, BalanceLocation::Balances] {
+            // Not every chain has both of these storage entries.
+            if !location.is_in(&metadata) {
+                continue
+            }
+            let address = location.address(&account);
+            let value = storage.fetch_or_default(&address).await?.to_value()?;
+            let balance = location
+                .account_data(&value)
+                .and_then(AccountBalance::from_value);
+            if let Some(balance) = balance {
+                return Ok(balance)
+            }
+        }
+        Err(Error::Other(
+            "Could not find the account balance in System::Account or Balances::Account storage".into(),
+        ))
+    }
+}
+
+/// Fetch the nonce of some account from `System::Account`. This is the number of
+/// transactions that the account has submitted, and so the nonce that its next
+/// transaction should use. Accounts that don't exist have a nonce of 0.
+///
+/// The `AccountInfo` is decoded according to the type given in the metadata, so this
+/// works however the chain defines its `AccountData`.
+pub fn account_nonce<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<u64, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = BalanceLocation::System.address(account);
+        let value = storage.fetch_or_default(&address).await?.to_value()?;
+        account_nonce_from_value(&value).ok_or_else(|| {
+            Error::Other(format!("Could not find the account nonce in {value}"))
+        })
+    }
+}
+
+/// Fetch everything that `System::Account` stores about some account: its nonce, its
+/// reference counts and its balance. Accounts that don't exist have a nonce, reference
+/// counts and balance of 0.
+///
+/// The `AccountInfo` is decoded according to the type given in the metadata. If the
+/// balance isn't stored in `System::Account`, it's read from `Balances::Account`
+/// instead, as with [`account_balance()`].
+pub fn account_info<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<AccountInfo, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = BalanceLocation::System.address(&account);
+        let value = storage.fetch_or_default(&address).await?.to_value()?;
+        let balance = match BalanceLocation::System
+            .account_data(&value)
+            .and_then(AccountBalance::from_value)
+        {
+            Some(balance) => balance,
+            None => account_balance(&storage, &account).await?,
+        };
+        AccountInfo::from_value_with_balance(&value, balance).ok_or_else(|| {
+            Error::Other(format!("Could not interpret {value} as an AccountInfo"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::decode;
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    fn decode_as<T: TypeInfo + 'static>(bytes: &[u8]) -> Option<AccountBalance> {
+        let value = crate::test_utils::decode_as::<T>(bytes)?;
+        AccountBalance::from_value(&value)
+    }
+
+    #[test]
+    fn decodes_account_data() {
+        #[derive(Encode, TypeInfo)]
+        struct AccountData {
+            free: u128,
+            reserved: u128,
+            frozen: u128,
+            flags: u128,
+        }
+
+        let data = AccountData {
+            free: 1,
+            reserved: 2,
+            frozen: 3,
+            flags: 4,
+        };
+        assert_eq!(
+            decode_as::<AccountData>(&data.encode()),
+            Some(AccountBalance {
+                free: 1,
+                reserved: 2,
+                frozen: 3
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_legacy_account_data() {
+        #[derive(Encode, TypeInfo)]
+        struct AccountData {
+            free: u128,
+            reserved: u128,
+            misc_frozen: u128,
+            fee_frozen: u128,
+        }
+
+        let data = AccountData {
+            free: 1,
+            reserved: 2,
+            misc_frozen: 5,
+            fee_frozen: 3,
+        };
+        assert_eq!(
+            decode_as::<AccountData>(&data.encode()),
+            Some(AccountBalance {
+                free: 1,
+                reserved: 2,
+                frozen: 5
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_non_standard_account_info() {
+        #[derive(Encode, TypeInfo)]
+        struct Balance(u64);
+
+        #[derive(Encode, TypeInfo)]
+        struct BalancesData {
+            free: Balance,
+            reserved: Balance,
+            frozen: Balance,
+        }
+
+        #[derive(Encode, TypeInfo)]
+        struct AccountData {
+            flags: u8,
+            balances: BalancesData,
+        }
+
+        #[derive(Encode, TypeInfo)]
+        struct AccountInfo {
+            #[codec(compact)]
+            nonce: u64,
+            providers: u32,
+            data: AccountData,
+        }
+
+        let info = AccountInfo {
+            nonce: 7,
+            providers: 1,
+            data: AccountData {
+                flags: 0,
+                balances: BalancesData {
+                    free: Balance(1),
+                    reserved: Balance(2),
+                    frozen: Balance(3),
+                },
+            },
+        };
+
+        let value = decode(info);
+
+        let balance = BalanceLocation::System
+            .account_data(&value)
+            .and_then(AccountBalance::from_value);
+        assert_eq!(
+            balance,
+            Some(AccountBalance {
+                free: 1,
+                reserved: 2,
+                frozen: 3
+            })
+        );
+        assert_eq!(account_nonce_from_value(&value), Some(7));
+        assert_eq!(
+            super::AccountInfo::from_value(&value),
+            Some(super::AccountInfo {
+                nonce: 7,
+                consumers: 0,
+                providers: 1,
+                sufficients: 0,
+                balance: AccountBalance {
+                    free: 1,
+                    reserved: 2,
+                    frozen: 3
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_account_info() {
+        #[derive(Encode, TypeInfo)]
+        struct AccountData {
+            free: u128,
+            reserved: u128,
+            frozen: u128,
+            flags: u128,
+        }
+
+        #[derive(Encode, TypeInfo)]
+        struct AccountInfo {
+            nonce: u32,
+            consumers: u32,
+            providers: u32,
+            sufficients: u32,
+            data: AccountData,
+        }
+
+        let info = AccountInfo {
+            nonce: 3,
+            consumers: 1,
+            providers: 2,
+            sufficients: 0,
+            data: AccountData {
+                free: 100,
+                reserved: 10,
+                frozen: 5,
+                flags: 0,
+            },
+        };
+
+        let value = decode(info);
+
+        let info = super::AccountInfo::from_value(&value).unwrap();
+        assert_eq!(info.nonce, 3);
+        assert_eq!(info.consumers, 1);
+        assert_eq!(info.providers, 2);
+        assert_eq!(info.sufficients, 0);
+        assert_eq!(info.balance.free, 100);
+        assert!(!info.is_dead());
+    }
+
+    #[test]
+    fn rejects_other_shapes() {
+        #[derive(Encode, TypeInfo)]
+        struct NotAccountData {
+            free: u128,
+        }
+
+        assert_eq!(
+            decode_as::<NotAccountData>(&NotAccountData { free: 1 }.encode()),
+            None
+        );
+        assert_eq!(decode_as::<()>(&().encode()), None);
+    }
+}
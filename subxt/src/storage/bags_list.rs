@@ -0,0 +1,313 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Read the voter list that `pallet-bags-list` keeps in order of stake, for staking
+//! election analysis.
+//!
+//! The list is split into bags, each holding the voters whose score (their stake) is at
+//! most the upper threshold of the bag and more than that of the bag below it. Bags are
+//! stored in `ListBags`, keyed by their upper threshold, and only know the first (`head`)
+//! and last (`tail`) voter in them. The voters themselves are stored in `ListNodes`, and
+//! each one knows the voters before (`prev`) and after (`next`) it in the same bag. So to
+//! put the voters in order, each bag is walked from its head, following the `next` link
+//! of every node until the tail is reached, and bags are taken from the highest threshold
+//! to the lowest. Voters within a bag aren't sorted by score; they're in the order that the
+//! election would take them.
+
+use super::{
+    staking::concat_hash_len,
+    storage_type::{
+        map_prefix,
+        single_key_map,
+    },
+    KeyIter,
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::{
+        DecodeStaticType,
+        Metadata,
+    },
+    Config,
+};
+use codec::{
+    Decode,
+    DecodeAll,
+    Input,
+};
+use std::{
+    collections::BTreeMap,
+    future::Future,
+};
+
+/// The names that the bags list pallet holding staking voters has been given in runtimes.
+pub const VOTER_LIST_PALLET_NAMES: [&str; 3] = ["VoterList", "BagsList", "VoterBagsList"];
+
+/// A voter in the bags list, as stored in `ListNodes`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListNode<AccountId> {
+    /// The voter.
+    pub id: AccountId,
+    /// The voter before this one in its bag, if it's not at the head.
+    pub prev: Option<AccountId>,
+    /// The voter after this one in its bag, if it's not at the tail.
+    pub next: Option<AccountId>,
+    /// The upper threshold of the bag that the voter is in.
+    pub bag_upper: u64,
+    /// The score of the voter. Older runtimes don't store this in the node.
+    pub score: Option<u64>,
+}
+
+impl<AccountId: Decode> Decode for ListNode<AccountId> {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, codec::Error> {
+        let id = AccountId::decode(input)?;
+        let prev = Option::<AccountId>::decode(input)?;
+        let next = Option::<AccountId>::decode(input)?;
+        let bag_upper = u64::decode(input)?;
+        let score = match input.remaining_len()? {
+            Some(0) => None,
+            _ => Some(u64::decode(input)?),
+        };
+        Ok(ListNode {
+            id,
+            prev,
+            next,
+            bag_upper,
+            score,
+        })
+    }
+}
+
+/// The ends of a bag, as stored in `ListBags`. The upper threshold of the bag is the key.
+#[derive(Clone, Debug, PartialEq, Eq, Decode)]
+pub(super) struct BagEnds<AccountId> {
+    pub(super) head: Option<AccountId>,
+    pub(super) tail: Option<AccountId>,
+}
+
+/// A bag of voters, and the voters in it in order. Obtain these from
+/// [`voter_list()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoterBag<AccountId> {
+    /// The upper threshold of the bag; every voter in it has at most this score.
+    pub upper: u64,
+    /// The voters in the bag, from the head to the tail.
+    pub voters: Vec<ListNode<AccountId>>,
+}
+
+/// Walk each bag from its head to its tail, and hand the bags back from the highest
+/// threshold to the lowest. An error is returned if the links between the nodes and bags
+/// don't line up, or if some node isn't reachable from any bag.
+pub(super) fn order_voters<AccountId>(
+    mut bags: Vec<(u64, BagEnds<AccountId>)>,
+    mut nodes: BTreeMap<AccountId, ListNode<AccountId>>,
+) -> Result<Vec<VoterBag<AccountId>>, Error>
+where
+    AccountId: Ord + Clone + std::fmt::Debug,
+{
+    bags.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    let mut ordered = Vec::with_capacity(bags.len());
+    for (upper, ends) in bags {
+        let mut voters = Vec::new();
+        let mut prev: Option<AccountId> = None;
+        let mut next = ends.head.clone();
+        while let Some(id) = next {
+            // Taking each node out as it's visited also stops us going round in circles.
+            let node = nodes.remove(&id).ok_or_else(|| {
+                Error::Other(format!(
+                    "The bag with upper threshold {upper} links to the voter {id:?}, which is missing or in another bag"
+                ))
+            })?;
+            if node.bag_upper != upper || node.prev != prev {
+                return Err(Error::Other(format!(
+                    "The voter {id:?} is not linked into the bag with upper threshold {upper} correctly"
+                )))
+            }
+            next = node.next.clone();
+            prev = Some(id);
+            voters.push(node);
+        }
+        if prev != ends.tail {
+            return Err(Error::Other(format!(
+                "The bag with upper threshold {upper} ends at {prev:?} rather than its tail {:?}",
+                ends.tail
+            )))
+        }
+        ordered.push(VoterBag { upper, voters });
+    }
+
+    if !nodes.is_empty() {
+        return Err(Error::Other(format!(
+            "{} voters in the bags list are not in any bag",
+            nodes.len()
+        )))
+    }
+    Ok(ordered)
+}
+
+/// Iterate over every voter in the bags list of the staking pallet (see
+/// [`VOTER_LIST_PALLET_NAMES`]), in no particular order. Nodes are fetched `page_size`
+/// at a time.
+pub fn voter_list_nodes<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<
+    Output = Result<KeyIter<T, Client, DecodeStaticType<ListNode<T::AccountId>>>, Error>,
+> + 'static
+where
+    T: Config,
+    T::AccountId: Decode,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let pallet_name = voter_list_pallet_name(&metadata)?;
+        let (_, return_type_id) = single_key_map(&metadata, pallet_name, "ListNodes")?;
+        let prefix = map_prefix(pallet_name, "ListNodes");
+        Ok(KeyIter::new(
+            storage,
+            prefix,
+            page_size,
+            metadata,
+            return_type_id,
+        ))
+    }
+}
+
+/// Fetch the whole voter list of the staking pallet (see [`VOTER_LIST_PALLET_NAMES`]),
+/// in the order that the election takes voters in: bags from the highest upper
+/// threshold to the lowest, and the voters in each bag from its head to its tail.
+/// Bags and nodes are fetched `page_size` at a time.
+///
+/// This needs every node in the list, which is tens of thousands on Polkadot and
+/// Kusama; see the [module docs](self) for how the order is worked out.
+pub fn voter_list<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<VoterBag<T::AccountId>>, Error>> + 'static
+where
+    T: Config,
+    T::AccountId: Decode + Ord,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let pallet_name = voter_list_pallet_name(&metadata)?;
+        let (hasher, return_type_id) =
+            single_key_map(&metadata, pallet_name, "ListBags")?;
+        let upper_offset = 32 + concat_hash_len(&hasher).ok_or_else(|| {
+            Error::Other(format!(
+                "Cannot find the bag thresholds in {pallet_name}::ListBags keys hashed with {hasher:?}"
+            ))
+        })?;
+
+        let mut bag_iter =
+            KeyIter::<T, Client, DecodeStaticType<BagEnds<T::AccountId>>>::new(
+                storage.clone(),
+                map_prefix(pallet_name, "ListBags"),
+                page_size,
+                metadata,
+                return_type_id,
+            );
+        let mut bags = Vec::new();
+        while let Some((key, ends)) = bag_iter.next().await? {
+            let upper = key
+                .0
+                .get(upper_offset..)
+                .and_then(|mut bytes| u64::decode_all(&mut bytes).ok())
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not find a bag threshold in the storage key 0x{}",
+                        hex::encode(&key.0)
+                    ))
+                })?;
+            bags.push((upper, ends));
+        }
+
+        let mut node_iter = voter_list_nodes(&storage, page_size).await?;
+        let mut nodes = BTreeMap::new();
+        while let Some((_, node)) = node_iter.next().await? {
+            nodes.insert(node.id.clone(), node);
+        }
+        order_voters(bags, nodes)
+    }
+}
+
+// The name of the first bags list pallet in `VOTER_LIST_PALLET_NAMES` that the runtime has.
+fn voter_list_pallet_name(metadata: &Metadata) -> Result<&'static str, Error> {
+    VOTER_LIST_PALLET_NAMES
+        .into_iter()
+        .find(|name| metadata.pallet(name).is_ok())
+        .ok_or_else(|| {
+            Error::Other("Could not find a bags list pallet in the metadata".into())
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use codec::Encode;
+
+    fn node(id: u8, prev: Option<u8>, next: Option<u8>, bag_upper: u64) -> ListNode<u8> {
+        ListNode {
+            id,
+            prev,
+            next,
+            bag_upper,
+            score: None,
+        }
+    }
+
+    fn ends(head: Option<u8>, tail: Option<u8>) -> BagEnds<u8> {
+        BagEnds { head, tail }
+    }
+
+    #[test]
+    fn decodes_nodes_with_and_without_scores() {
+        let bytes = (1u8, None::<u8>, Some(2u8), 100u64).encode();
+        assert_eq!(
+            ListNode::<u8>::decode(&mut &bytes[..]).unwrap(),
+            node(1, None, Some(2), 100)
+        );
+
+        let bytes = (1u8, None::<u8>, Some(2u8), 100u64, 50u64).encode();
+        let decoded = ListNode::<u8>::decode(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded.score, Some(50));
+    }
+
+    #[test]
+    fn orders_voters_by_bag_and_links() {
+        let bags = vec![(10, ends(Some(3), Some(3))), (100, ends(Some(2), Some(1)))];
+        let nodes: BTreeMap<_, _> = [
+            node(1, Some(2), None, 100),
+            node(2, None, Some(1), 100),
+            node(3, None, None, 10),
+        ]
+        .into_iter()
+        .map(|n| (n.id, n))
+        .collect();
+
+        let ordered = order_voters(bags.clone(), nodes.clone()).unwrap();
+        let ids: Vec<(u64, Vec<u8>)> = ordered
+            .iter()
+            .map(|bag| (bag.upper, bag.voters.iter().map(|n| n.id).collect()))
+            .collect();
+        assert_eq!(ids, vec![(100, vec![2, 1]), (10, vec![3])]);
+
+        // A node which the bags don't lead to is an error:
+        let mut extra = nodes.clone();
+        extra.insert(4, node(4, None, None, 10));
+        assert!(order_voters(bags.clone(), extra).is_err());
+
+        // As is a bag whose links lead somewhere else:
+        let mut broken = nodes;
+        broken.insert(1, node(1, Some(2), Some(3), 100));
+        assert!(order_voters(bags, broken).is_err());
+    }
+}
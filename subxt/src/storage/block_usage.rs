@@ -0,0 +1,259 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    utils::value_as_u32,
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    utils::Weight,
+    Config,
+};
+use codec::{
+    Compact,
+    Encode,
+};
+use scale_value::{
+    At,
+    Value,
+};
+use std::future::Future;
+
+/// Something which is tracked separately for each dispatch class.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PerDispatchClass<T> {
+    /// The value for normal dispatches, which includes most transactions.
+    pub normal: T,
+    /// The value for operational dispatches.
+    pub operational: T,
+    /// The value for mandatory dispatches, such as inherents.
+    pub mandatory: T,
+}
+
+impl<T> PerDispatchClass<T> {
+    fn from_value<Ctx>(
+        value: &Value<Ctx>,
+        f: impl Fn(&Value<Ctx>) -> Option<T>,
+    ) -> Option<PerDispatchClass<T>> {
+        Some(PerDispatchClass {
+            normal: value.at("normal").and_then(&f)?,
+            operational: value.at("operational").and_then(&f)?,
+            mandatory: value.at("mandatory").and_then(&f)?,
+        })
+    }
+}
+
+/// How much of a block's weight and length has been used up, as returned from
+/// [`block_usage()`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BlockUsage {
+    /// The weight used so far in the block, from `System::BlockWeight`.
+    pub weight: PerDispatchClass<Weight>,
+    /// The maximum weight of a block, from the `max_block` field of the
+    /// `System::BlockWeights` constant.
+    pub max_weight: Weight,
+    /// The length in bytes of the extrinsics in the block so far, from
+    /// `System::AllExtrinsicsLen`.
+    pub length: u32,
+    /// The maximum length of a block in bytes, which is the largest of the per-class
+    /// limits in the `System::BlockLength` constant.
+    pub max_length: u32,
+}
+
+impl BlockUsage {
+    /// The weight used so far across all dispatch classes.
+    pub fn total_weight(&self) -> Weight {
+        self.weight
+            .normal
+            .saturating_add(self.weight.operational)
+            .saturating_add(self.weight.mandatory)
+    }
+
+    /// The fraction of the maximum block weight that has been used. Where the maximum
+    /// proof size is known, this is the larger of the time and proof size fractions.
+    pub fn weight_utilization(&self) -> f64 {
+        let total = self.total_weight();
+        let ref_time = fraction(total.ref_time(), self.max_weight.ref_time());
+        let proof_size = fraction(total.proof_size(), self.max_weight.proof_size());
+        ref_time.max(proof_size)
+    }
+
+    /// The fraction of the maximum block length that has been used.
+    pub fn length_utilization(&self) -> f64 {
+        fraction(self.length as u64, self.max_length as u64)
+    }
+
+    /// The fraction of the block that has been used, which is the larger of
+    /// [`BlockUsage::weight_utilization()`] and [`BlockUsage::length_utilization()`].
+    /// This is typically between 0 and 1, although mandatory dispatches can push it
+    /// over 1.
+    pub fn utilization(&self) -> f64 {
+        self.weight_utilization().max(self.length_utilization())
+    }
+}
+
+fn fraction(used: u64, max: u64) -> f64 {
+    if max == 0 {
+        0.0
+    } else {
+        used as f64 / max as f64
+    }
+}
+
+/// Interpret the value stored in `System::BlockWeight`.
+pub(crate) fn block_weight_from_value<T>(
+    value: &Value<T>,
+) -> Option<PerDispatchClass<Weight>> {
+    PerDispatchClass::from_value(value, Weight::from_value)
+}
+
+/// Interpret the `System::BlockWeights` constant, handing back the maximum block weight.
+pub(crate) fn max_block_weight_from_value<T>(value: &Value<T>) -> Option<Weight> {
+    value.at("max_block").and_then(Weight::from_value)
+}
+
+/// Interpret the `System::BlockLength` constant, handing back the largest of the
+/// per-class length limits.
+pub(crate) fn max_block_length_from_value<T>(value: &Value<T>) -> Option<u32> {
+    let max = PerDispatchClass::from_value(value.at("max")?, value_as_u32)?;
+    Some(max.normal.max(max.operational).max(max.mandatory))
+}
+
+/// Fetch how much of the weight and length of the block that `storage` reads at has been
+/// used, relative to the limits that blocks have. This is useful for deciding on a tip
+/// when blocks are congested. See [`BlockUsage::utilization()`].
+///
+/// The weight used is read from the `System::BlockWeight` storage entry, and the limits
+/// from the `System::BlockWeights` and `System::BlockLength` constants. The length used
+/// is read from `System::AllExtrinsicsLen`, but since this is cleared when a block is
+/// finalized (in the runtime sense), it's usually absent, in which case the length is
+/// worked out from the extrinsics in the block instead.
+pub fn block_usage<T, Client>(
+    storage: &Storage<T, Client>,
+) -> impl Future<Output = Result<BlockUsage, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let constants = storage.client().constants();
+        let max_weight = constants
+            .at(&crate::dynamic::constant("System", "BlockWeights"))?
+            .to_value()?;
+        let max_weight = max_block_weight_from_value(&max_weight).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not find the maximum block weight in {max_weight}"
+            ))
+        })?;
+        let max_length = constants
+            .at(&crate::dynamic::constant("System", "BlockLength"))?
+            .to_value()?;
+        let max_length = max_block_length_from_value(&max_length).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not find the maximum block length in {max_length}"
+            ))
+        })?;
+
+        let weight_address = crate::dynamic::storage_root("System", "BlockWeight");
+        let weight = storage
+            .fetch_or_default(&weight_address)
+            .await?
+            .to_value()?;
+        let weight = block_weight_from_value(&weight).ok_or_else(|| {
+            Error::Other(format!("Could not interpret {weight} as a block weight"))
+        })?;
+
+        let length_address = crate::dynamic::storage_root("System", "AllExtrinsicsLen");
+        let length = match storage.fetch(&length_address).await? {
+            Some(length) => {
+                let length = length.to_value()?;
+                value_as_u32(&length).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not interpret {length} as an extrinsics length"
+                    ))
+                })?
+            }
+            None => {
+                let block_hash = storage.block_hash();
+                let block = storage
+                    .client()
+                    .rpc()
+                    .block(Some(block_hash))
+                    .await?
+                    .ok_or_else(|| {
+                        Error::Other(format!(
+                            "Could not find the block with hash {block_hash:?}"
+                        ))
+                    })?;
+                // Extrinsics are counted with their compact length prefix.
+                let length: usize = block
+                    .block
+                    .extrinsics
+                    .iter()
+                    .map(|ext| Compact(ext.0.len() as u32).encoded_size() + ext.0.len())
+                    .sum();
+                length as u32
+            }
+        };
+
+        Ok(BlockUsage {
+            weight,
+            max_weight,
+            length,
+            max_length,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn per_class(
+        normal: Value<()>,
+        operational: Value<()>,
+        mandatory: Value<()>,
+    ) -> Value<()> {
+        Value::named_composite([
+            ("normal", normal),
+            ("operational", operational),
+            ("mandatory", mandatory),
+        ])
+    }
+
+    fn weight(ref_time: u64, proof_size: u64) -> Value<()> {
+        Value::named_composite([
+            ("ref_time", Value::u128(ref_time as u128)),
+            ("proof_size", Value::u128(proof_size as u128)),
+        ])
+    }
+
+    #[test]
+    fn computes_block_utilization() {
+        let block_weight = per_class(weight(100, 10), weight(50, 0), weight(50, 10));
+        let block_weights = Value::named_composite([
+            ("base_block", weight(1, 0)),
+            ("max_block", weight(1000, 100)),
+        ]);
+        let block_length = Value::named_composite([(
+            "max",
+            per_class(Value::u128(75), Value::u128(100), Value::u128(100)),
+        )]);
+
+        let usage = BlockUsage {
+            weight: block_weight_from_value(&block_weight).unwrap(),
+            max_weight: max_block_weight_from_value(&block_weights).unwrap(),
+            length: 10,
+            max_length: max_block_length_from_value(&block_length).unwrap(),
+        };
+
+        assert_eq!(usage.total_weight(), Weight::from_parts(200, 20));
+        assert_eq!(usage.weight_utilization(), 0.2);
+        assert_eq!(usage.length_utilization(), 0.1);
+        assert_eq!(usage.utilization(), 0.2);
+    }
+}
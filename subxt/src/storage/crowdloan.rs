@@ -0,0 +1,243 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Read the contributions made to crowdloans of the `Crowdloan` pallet, which live in a
+//! child trie for each fund rather than in the pallet's storage.
+
+use super::{
+    utils::value_as_u128,
+    Storage,
+    StorageKeyIter,
+};
+use crate::{
+    client::OnlineClientT,
+    dynamic::Value,
+    error::Error,
+    metadata::EncodeStaticType,
+    Config,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use scale_value::At;
+use std::future::Future;
+
+/// The prefix of the storage keys of default child tries.
+const DEFAULT_CHILD_STORAGE_KEY_PREFIX: &[u8] = b":child_storage:default:";
+
+/// A contribution to a crowdloan, as stored in the fund's child trie.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CrowdloanContribution {
+    /// The SCALE encoded account ID of the contributor, which is what the contribution is
+    /// keyed by in the child trie.
+    pub contributor: Vec<u8>,
+    /// The total balance that the account has contributed.
+    pub amount: u128,
+    /// The memo that the account last attached to its contribution, if any.
+    pub memo: Vec<u8>,
+}
+
+impl CrowdloanContribution {
+    /// Decode a contribution from its key and value in the fund's child trie. The value is
+    /// a `(Balance, Vec<u8>)`, and is assumed to have a `u128` balance as it does in
+    /// Polkadot and Kusama, since the metadata doesn't describe child tries.
+    pub fn decode_entry(
+        key: &[u8],
+        mut value: &[u8],
+    ) -> Result<CrowdloanContribution, codec::Error> {
+        let (amount, memo) = <(u128, Vec<u8>)>::decode(&mut value)?;
+        Ok(CrowdloanContribution {
+            contributor: key.to_vec(),
+            amount,
+            memo,
+        })
+    }
+}
+
+/// The prefixed storage key of the child trie that holds the contributions to the fund
+/// with the given index, to hand to the `childstate_*` RPC methods.
+///
+/// The pallet derives the ID of the child trie from the fund index by hashing
+/// `b"crowdloan"` followed by the SCALE encoded (little endian `u32`) index with
+/// `blake2_256`, so that each fund gets a trie of its own that can be removed in one go
+/// once the fund is dissolved. As with every default child trie, the prefixed key is the
+/// ID with `:child_storage:default:` in front of it.
+///
+/// Older versions of the pallet called the fund index the trie index, but they derive
+/// the ID in the same way.
+pub fn crowdloan_child_key(fund_index: u32) -> Vec<u8> {
+    let mut id_preimage = b"crowdloan".to_vec();
+    fund_index.encode_to(&mut id_preimage);
+    let id = sp_core_hashing::blake2_256(&id_preimage);
+
+    let mut key = DEFAULT_CHILD_STORAGE_KEY_PREFIX.to_vec();
+    key.extend(id);
+    key
+}
+
+/// Find the fund index of a dynamically decoded `FundInfo` from `Crowdloan::Funds`.
+pub(crate) fn fund_index_from_value<T>(fund: &Value<T>) -> Option<u32> {
+    let index = fund.at("fund_index").or_else(|| fund.at("trie_index"))?;
+    u32::try_from(value_as_u128(index)?).ok()
+}
+
+/// Find the fund index of the crowdloan for the parachain with the given ID from
+/// `Crowdloan::Funds`, returning `None` if it has no crowdloan.
+pub fn crowdloan_fund_index<T, Client>(
+    storage: &Storage<T, Client>,
+    para_id: u32,
+) -> impl Future<Output = Result<Option<u32>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage(
+            "Crowdloan",
+            "Funds",
+            vec![EncodeStaticType(para_id)],
+        );
+        let Some(fund) = storage.fetch(&address).await? else {
+            return Ok(None)
+        };
+        let fund = fund.to_value()?;
+        let fund_index = fund_index_from_value(&fund).ok_or_else(|| {
+            Error::Other(format!("Could not find the fund index of {fund}"))
+        })?;
+        Ok(Some(fund_index))
+    }
+}
+
+/// Fetch every contribution to the crowdloan for the parachain with the given ID,
+/// looking up its fund index and then reading the contributions like
+/// [`crowdloan_contributions_by_fund_index()`]. This returns an error if the
+/// parachain has no crowdloan.
+pub fn crowdloan_contributions<T, Client>(
+    storage: &Storage<T, Client>,
+    para_id: u32,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<CrowdloanContribution>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let fund_index =
+            crowdloan_fund_index(&storage, para_id)
+                .await?
+                .ok_or_else(|| {
+                    Error::Other(format!("Parachain {para_id} has no crowdloan"))
+                })?;
+        crowdloan_contributions_by_fund_index(&storage, fund_index, page_size).await
+    }
+}
+
+/// Fetch every contribution to the crowdloan fund with the given index from the fund's
+/// child trie (see [`crowdloan_child_key()`]), `page_size` keys at a time. Contributions
+/// are handed back in order of the contributors' encoded account IDs. The child trie of
+/// a fund is removed once the fund has been dissolved.
+pub fn crowdloan_contributions_by_fund_index<T, Client>(
+    storage: &Storage<T, Client>,
+    fund_index: u32,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<CrowdloanContribution>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        if page_size == 0 {
+            return Err(Error::Other(
+                "Cannot read crowdloan contributions 0 keys at a time".into(),
+            ))
+        }
+        let child_key = crowdloan_child_key(fund_index);
+        let mut keys = StorageKeyIter::new_in_child_trie(
+            storage.clone(),
+            child_key.clone(),
+            Vec::new(),
+            page_size,
+        );
+        let rpc = storage.client().rpc();
+        let mut contributions = Vec::new();
+        while let Some(key) = keys.next().await? {
+            // Contributions may be withdrawn between the calls.
+            let Some(value) = rpc
+                .child_storage(&child_key, &key.0, Some(storage.block_hash()))
+                .await?
+            else {
+                continue
+            };
+            contributions.push(CrowdloanContribution::decode_entry(&key.0, &value.0)?);
+        }
+        Ok(contributions)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::client_with_keys;
+
+    #[test]
+    fn child_keys_are_derived_from_the_fund_index() {
+        let key = crowdloan_child_key(3);
+        assert_eq!(&key[..23], b":child_storage:default:");
+        assert_eq!(
+            key[23..],
+            sp_core_hashing::blake2_256(b"crowdloan\x03\x00\x00\x00")
+        );
+        assert_ne!(key, crowdloan_child_key(4));
+    }
+
+    #[test]
+    fn decodes_contributions() {
+        let account = [5u8; 32];
+        let value = (1_000_000u128, b"memo".to_vec()).encode();
+        assert_eq!(
+            CrowdloanContribution::decode_entry(&account, &value).unwrap(),
+            CrowdloanContribution {
+                contributor: account.to_vec(),
+                amount: 1_000_000,
+                memo: b"memo".to_vec(),
+            }
+        );
+        assert!(CrowdloanContribution::decode_entry(&account, &value[..8]).is_err());
+    }
+
+    #[test]
+    fn finds_fund_indexes() {
+        let fund = |field| {
+            Value::named_composite([
+                ("raised", Value::u128(100)),
+                (field, Value::u128(7)),
+            ])
+        };
+        assert_eq!(fund_index_from_value(&fund("fund_index")), Some(7));
+        assert_eq!(fund_index_from_value(&fund("trie_index")), Some(7));
+        assert_eq!(fund_index_from_value(&fund("other")), None);
+    }
+
+    #[tokio::test]
+    async fn contribution_pages_dedupe_boundary_keys() {
+        let keys: Vec<Vec<u8>> = (0u8..5).map(|n| vec![n; 32]).collect();
+        let client = client_with_keys(keys.clone());
+        let storage = client.storage().at(Some(Default::default())).await.unwrap();
+
+        let contributions = crowdloan_contributions_by_fund_index(&storage, 3, 10)
+            .await
+            .unwrap();
+        let amounts: Vec<_> = contributions.iter().map(|c| c.amount).collect();
+        assert_eq!(amounts, [0, 1, 2, 3, 4]);
+        assert_eq!(contributions[4].contributor, keys[4]);
+
+        assert!(crowdloan_contributions_by_fund_index(&storage, 3, 0)
+            .await
+            .is_err());
+    }
+}
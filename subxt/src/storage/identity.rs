@@ -0,0 +1,444 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    utils::{
+        list_from_value,
+        value_as_bytes,
+        value_as_u128,
+    },
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::EncodeStaticType,
+    Config,
+};
+use codec::Encode;
+use scale_value::{
+    At,
+    Composite,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// A piece of identity information, mirroring the `Data` type in the `Identity` pallet.
+/// Information is either stored directly, if it's no more than 32 bytes long, or as a hash.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub enum IdentityData {
+    /// No information.
+    #[default]
+    None,
+    /// The information itself, which is at most 32 bytes long.
+    Raw(Vec<u8>),
+    /// The BLAKE2-256 hash of the information.
+    BlakeTwo256([u8; 32]),
+    /// The SHA-256 hash of the information.
+    Sha256([u8; 32]),
+    /// The Keccak-256 hash of the information.
+    Keccak256([u8; 32]),
+    /// The SHA3-256 hash of the information.
+    ShaThree256([u8; 32]),
+}
+
+impl IdentityData {
+    /// The longest information which can be stored directly.
+    pub const MAX_RAW_LEN: usize = 32;
+
+    /// The raw information, if it's stored directly.
+    pub fn as_raw(&self) -> Option<&[u8]> {
+        match self {
+            IdentityData::Raw(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// The raw information as a string, if it's stored directly and is valid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        self.as_raw()
+            .and_then(|bytes| std::str::from_utf8(bytes).ok())
+    }
+
+    /// Attempt to interpret a dynamically decoded `Data` value as [`IdentityData`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<IdentityData> {
+        let ValueDef::Variant(variant) = &value.value else {
+            return None
+        };
+        let bytes = || {
+            let mut values = variant.values.values();
+            let bytes = values.next().map_or(Some(Vec::new()), value_as_bytes);
+            values.next().is_none().then_some(bytes).flatten()
+        };
+        let hash = || bytes().and_then(|bytes| <[u8; 32]>::try_from(bytes).ok());
+
+        let data = match variant.name.as_str() {
+            "None" => IdentityData::None,
+            "BlakeTwo256" => IdentityData::BlakeTwo256(hash()?),
+            "Sha256" => IdentityData::Sha256(hash()?),
+            "Keccak256" => IdentityData::Keccak256(hash()?),
+            "ShaThree256" => IdentityData::ShaThree256(hash()?),
+            name => {
+                // Raw data lives in variants named after its length, like "Raw5".
+                let len: usize = name.strip_prefix("Raw")?.parse().ok()?;
+                let bytes = bytes()?;
+                if bytes.len() != len || len > Self::MAX_RAW_LEN {
+                    return None
+                }
+                IdentityData::Raw(bytes)
+            }
+        };
+        Some(data)
+    }
+
+    /// Convert this into a [`Value`] which can be encoded as a `Data` value. Raw data
+    /// that's longer than [`IdentityData::MAX_RAW_LEN`] will fail to encode.
+    pub fn to_value(&self) -> Value<()> {
+        let hash = |name, hash: &[u8; 32]| {
+            Value::unnamed_variant(name, [Value::from_bytes(hash)])
+        };
+        match self {
+            IdentityData::None => Value::unnamed_variant("None", []),
+            IdentityData::Raw(bytes) => {
+                Value::unnamed_variant(
+                    format!("Raw{}", bytes.len()),
+                    [Value::from_bytes(bytes)],
+                )
+            }
+            IdentityData::BlakeTwo256(h) => hash("BlakeTwo256", h),
+            IdentityData::Sha256(h) => hash("Sha256", h),
+            IdentityData::Keccak256(h) => hash("Keccak256", h),
+            IdentityData::ShaThree256(h) => hash("ShaThree256", h),
+        }
+    }
+}
+
+impl From<&str> for IdentityData {
+    fn from(s: &str) -> Self {
+        match s {
+            "" => IdentityData::None,
+            s => IdentityData::Raw(s.as_bytes().to_vec()),
+        }
+    }
+}
+
+/// The identity information that an account has set, mirroring the `IdentityInfo` type
+/// in the `Identity` pallet.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct IdentityInfo {
+    /// Additional fields, as pairs of keys and values.
+    pub additional: Vec<(IdentityData, IdentityData)>,
+    /// A name to display.
+    pub display: IdentityData,
+    /// The full legal name.
+    pub legal: IdentityData,
+    /// A website.
+    pub web: IdentityData,
+    /// A Riot (Matrix) handle.
+    pub riot: IdentityData,
+    /// An email address.
+    pub email: IdentityData,
+    /// The fingerprint of a PGP key.
+    pub pgp_fingerprint: Option<[u8; 20]>,
+    /// An image.
+    pub image: IdentityData,
+    /// A Twitter handle.
+    pub twitter: IdentityData,
+}
+
+impl IdentityInfo {
+    /// Attempt to interpret a dynamically decoded `IdentityInfo` value as an
+    /// [`IdentityInfo`], returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<IdentityInfo> {
+        let field = |name| value.at(name).and_then(IdentityData::from_value);
+
+        let additional = list_from_value(value.at("additional")?, |pair| {
+            let (key, value) = pair_from_value(pair)?;
+            Some((
+                IdentityData::from_value(key)?,
+                IdentityData::from_value(value)?,
+            ))
+        })?;
+
+        let pgp_fingerprint = match &value.at("pgp_fingerprint")?.value {
+            ValueDef::Variant(v) if v.name == "None" => None,
+            ValueDef::Variant(v) if v.name == "Some" => {
+                let bytes = value_as_bytes(v.values.values().next()?)?;
+                Some(<[u8; 20]>::try_from(bytes).ok()?)
+            }
+            _ => return None,
+        };
+
+        Some(IdentityInfo {
+            additional,
+            display: field("display")?,
+            legal: field("legal")?,
+            web: field("web")?,
+            riot: field("riot")?,
+            email: field("email")?,
+            pgp_fingerprint,
+            image: field("image")?,
+            twitter: field("twitter")?,
+        })
+    }
+
+    /// Convert this into a [`Value`] which can be encoded as an `IdentityInfo` value.
+    pub fn to_value(&self) -> Value<()> {
+        let additional = self
+            .additional
+            .iter()
+            .map(|(k, v)| Value::unnamed_composite([k.to_value(), v.to_value()]));
+        let pgp_fingerprint = match &self.pgp_fingerprint {
+            Some(fingerprint) => {
+                Value::unnamed_variant("Some", [Value::from_bytes(fingerprint)])
+            }
+            None => Value::unnamed_variant("None", []),
+        };
+        Value::named_composite([
+            // `additional` is a `BoundedVec`, which is a newtype around a `Vec`.
+            (
+                "additional",
+                Value::unnamed_composite([Value::unnamed_composite(additional)]),
+            ),
+            ("display", self.display.to_value()),
+            ("legal", self.legal.to_value()),
+            ("web", self.web.to_value()),
+            ("riot", self.riot.to_value()),
+            ("email", self.email.to_value()),
+            ("pgp_fingerprint", pgp_fingerprint),
+            ("image", self.image.to_value()),
+            ("twitter", self.twitter.to_value()),
+        ])
+    }
+}
+
+/// A judgement given by a registrar on some identity, mirroring the `Judgement` type in
+/// the `Identity` pallet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Judgement {
+    /// No judgement has been given yet, but one may be requested.
+    Unknown,
+    /// A judgement has been requested, and the given fee has been reserved for it.
+    FeePaid(u128),
+    /// The identity is reasonable, but hasn't been verified in depth.
+    Reasonable,
+    /// The registrar has verified that the identity is correct.
+    KnownGood,
+    /// The identity was once correct, but is now out of date.
+    OutOfDate,
+    /// The identity is of low quality.
+    LowQuality,
+    /// The identity is wrong, and possibly malicious.
+    Erroneous,
+}
+
+impl Judgement {
+    /// Attempt to interpret a dynamically decoded `Judgement` value as a [`Judgement`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Judgement> {
+        let ValueDef::Variant(variant) = &value.value else {
+            return None
+        };
+        let judgement = match variant.name.as_str() {
+            "Unknown" => Judgement::Unknown,
+            "FeePaid" => {
+                Judgement::FeePaid(value_as_u128(variant.values.values().next()?)?)
+            }
+            "Reasonable" => Judgement::Reasonable,
+            "KnownGood" => Judgement::KnownGood,
+            "OutOfDate" => Judgement::OutOfDate,
+            "LowQuality" => Judgement::LowQuality,
+            "Erroneous" => Judgement::Erroneous,
+            _ => return None,
+        };
+        Some(judgement)
+    }
+}
+
+/// The identity of an account, as stored in `Identity::IdentityOf`. This is returned
+/// from [`identity()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Registration {
+    /// The judgements given on this identity, along with the index of the registrar that
+    /// gave each one.
+    pub judgements: Vec<(u32, Judgement)>,
+    /// The balance reserved for the identity.
+    pub deposit: u128,
+    /// The identity information.
+    pub info: IdentityInfo,
+}
+
+impl Registration {
+    /// Attempt to interpret a dynamically decoded `Registration` value as a
+    /// [`Registration`], returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Registration> {
+        let Some(judgements) = value.at("judgements") else {
+            // Newer runtimes store the registration alongside a username.
+            let (registration, _username) = pair_from_value(value)?;
+            return Registration::from_value(registration)
+        };
+        let judgements = list_from_value(judgements, |pair| {
+            let (index, judgement) = pair_from_value(pair)?;
+            let index = u32::try_from(value_as_u128(index)?).ok()?;
+            Some((index, Judgement::from_value(judgement)?))
+        })?;
+        Some(Registration {
+            judgements,
+            deposit: value.at("deposit").and_then(value_as_u128)?,
+            info: IdentityInfo::from_value(value.at("info")?)?,
+        })
+    }
+}
+
+fn pair_from_value<T>(value: &Value<T>) -> Option<(&Value<T>, &Value<T>)> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    match &vals[..] {
+        [a, b] => Some((a, b)),
+        _ => None,
+    }
+}
+
+/// Fetch the identity that an account has set in the `Identity` pallet, returning
+/// `None` if it hasn't set one. Identity information which is stored as a hash is handed
+/// back as a hash; the information itself has to be obtained some other way.
+pub fn identity<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<Option<Registration>, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage(
+            "Identity",
+            "IdentityOf",
+            vec![EncodeStaticType(account)],
+        );
+        let Some(value) = storage.fetch(&address).await? else {
+            return Ok(None)
+        };
+        let value = value.to_value()?;
+        let registration = Registration::from_value(&value).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not interpret {value} as an identity registration"
+            ))
+        })?;
+        Ok(Some(registration))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        decode,
+        types_of,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    // Mirrors a subset of the variants of the `Data` type, at the same indexes.
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Data {
+        #[codec(index = 0)]
+        None,
+        #[codec(index = 1)]
+        Raw0([u8; 0]),
+        #[codec(index = 6)]
+        Raw5([u8; 5]),
+        #[codec(index = 34)]
+        BlakeTwo256([u8; 32]),
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct Info {
+        additional: BoundedVec<(Data, Data)>,
+        display: Data,
+        legal: Data,
+        web: Data,
+        riot: Data,
+        email: Data,
+        pgp_fingerprint: Option<[u8; 20]>,
+        image: Data,
+        twitter: Data,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Judgement {
+        Unknown,
+        FeePaid(u128),
+        Reasonable,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct Reg {
+        judgements: BoundedVec<(u32, Judgement)>,
+        deposit: u128,
+        info: Info,
+    }
+
+    fn info() -> Info {
+        Info {
+            additional: BoundedVec(vec![(Data::Raw5(*b"hello"), Data::Raw0([]))]),
+            display: Data::Raw5(*b"alice"),
+            legal: Data::None,
+            web: Data::BlakeTwo256([1; 32]),
+            riot: Data::None,
+            email: Data::None,
+            pgp_fingerprint: Some([2; 20]),
+            image: Data::None,
+            twitter: Data::None,
+        }
+    }
+
+    #[test]
+    fn decodes_registrations() {
+        let reg = Reg {
+            judgements: BoundedVec(vec![(3, Judgement::FeePaid(10))]),
+            deposit: 100,
+            info: info(),
+        };
+        let reg = Registration::from_value(&decode(reg)).unwrap();
+
+        assert_eq!(reg.judgements, vec![(3, super::Judgement::FeePaid(10))]);
+        assert_eq!(reg.deposit, 100);
+        assert_eq!(reg.info.display.as_str(), Some("alice"));
+        assert_eq!(reg.info.web, IdentityData::BlakeTwo256([1; 32]));
+        assert_eq!(
+            reg.info.additional,
+            vec![(IdentityData::from("hello"), IdentityData::Raw(vec![]))]
+        );
+        assert_eq!(reg.info.pgp_fingerprint, Some([2; 20]));
+    }
+
+    #[test]
+    fn encodes_identity_info() {
+        let identity_info = IdentityInfo {
+            additional: vec![(IdentityData::from("hello"), IdentityData::Raw(vec![]))],
+            display: IdentityData::from("alice"),
+            web: IdentityData::BlakeTwo256([1; 32]),
+            pgp_fingerprint: Some([2; 20]),
+            ..Default::default()
+        };
+        let (id, types) = types_of::<Info>();
+        let mut bytes = Vec::new();
+        scale_value::scale::encode_as_type(
+            &identity_info.to_value(),
+            id,
+            &types,
+            &mut bytes,
+        )
+        .unwrap();
+        assert_eq!(bytes, info().encode());
+    }
+}
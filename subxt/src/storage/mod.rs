@@ -4,19 +4,112 @@
 
 //! Types associated with accessing and working with storage items.
 
+mod account_balance;
+pub mod bags_list;
+mod block_usage;
+mod crowdloan;
+mod identity;
+mod multisig;
+mod preimage;
+mod proxy;
+pub mod read_proof;
+mod scheduler;
+mod snapshot;
+mod staking;
 mod storage_address;
 mod storage_client;
 mod storage_map_key;
 mod storage_type;
+mod sudo;
+mod treasury;
+mod vesting;
+mod xcm;
 
 pub mod utils;
 
-pub use storage_client::StorageClient;
+pub use account_balance::{
+    account_balance,
+    account_info,
+    account_nonce,
+    AccountBalance,
+    AccountInfo,
+};
+pub use block_usage::{
+    block_usage,
+    BlockUsage,
+    PerDispatchClass,
+};
+pub use crowdloan::{
+    crowdloan_child_key,
+    crowdloan_contributions,
+    crowdloan_contributions_by_fund_index,
+    crowdloan_fund_index,
+    CrowdloanContribution,
+};
+pub use identity::{
+    identity,
+    IdentityData,
+    IdentityInfo,
+    Judgement,
+    Registration,
+};
+pub use multisig::{
+    multisigs,
+    Multisig,
+    Timepoint,
+};
+pub use preimage::decode_preimage;
+pub use proxy::{
+    proxies,
+    proxy_announcements,
+    Announcement,
+    ProxyDefinition,
+};
+pub use read_proof::verify_read_proof;
+pub use scheduler::{
+    scheduler_agenda,
+    scheduler_agendas,
+    ScheduledTask,
+};
+pub use snapshot::{
+    Snapshot,
+    SnapshotBuilder,
+    SnapshotConstant,
+    SnapshotStorage,
+};
+pub use staking::{
+    nominators,
+    NominatorIter,
+};
+pub use storage_client::{
+    StorageChange,
+    StorageClient,
+};
 
 pub use storage_type::{
     KeyIter,
     Storage,
+    StorageKeyIter,
+};
+pub use sudo::{
+    is_sudo,
+    sudo_key,
+};
+pub use treasury::{
+    treasury_approvals,
+    treasury_proposals,
+    treasury_spend_count,
+    treasury_spends,
+    SpendPaymentState,
+    TreasuryProposal,
+    TreasurySpend,
+};
+pub use vesting::{
+    vesting,
+    Vesting,
+    VestingSchedule,
 };
+pub use xcm::xcm_version_discovery_queue;
 
 // Re-export as this is used in the public API in this module:
 pub use crate::rpc::types::StorageKey;
@@ -0,0 +1,208 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    storage_type::lookup_storage_return_type,
+    utils::{
+        list_from_value,
+        storage_address_prefixes,
+        value_as_bytes,
+        value_as_u128,
+    },
+    KeyIter,
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::EncodeStaticType,
+    Config,
+};
+use codec::Encode;
+use scale_value::{
+    At,
+    Value,
+};
+use std::future::Future;
+
+/// A point in the chain's history: the block number and the index of an extrinsic
+/// within that block.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Timepoint {
+    /// The block number.
+    pub height: u64,
+    /// The index of the extrinsic in the block.
+    pub index: u32,
+}
+
+impl Timepoint {
+    /// Attempt to interpret a dynamically decoded `Timepoint` as a [`Timepoint`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Timepoint> {
+        let height = value
+            .at("height")
+            .and_then(value_as_u128)
+            .and_then(|n| u64::try_from(n).ok())?;
+        let index = value
+            .at("index")
+            .and_then(value_as_u128)
+            .and_then(|n| u32::try_from(n).ok())?;
+        Some(Timepoint { height, index })
+    }
+}
+
+/// A multisig operation which is waiting for approvals, as stored in
+/// `Multisig::Multisigs`. Accounts are handed back as their SCALE encoded bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Multisig {
+    /// When the operation was first approved.
+    pub when: Timepoint,
+    /// The amount reserved from the depositor for storing the operation.
+    pub deposit: u128,
+    /// The account that the deposit was reserved from.
+    pub depositor: Vec<u8>,
+    /// The accounts that have approved the operation so far.
+    pub approvals: Vec<Vec<u8>>,
+}
+
+impl Multisig {
+    /// Attempt to interpret a dynamically decoded `Multisig` as a [`Multisig`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Multisig> {
+        let when = value.at("when").and_then(Timepoint::from_value)?;
+        let deposit = value.at("deposit").and_then(value_as_u128)?;
+        let depositor = value.at("depositor").and_then(value_as_bytes)?;
+        let approvals = value
+            .at("approvals")
+            .and_then(|v| list_from_value(v, value_as_bytes))?;
+        Some(Multisig {
+            when,
+            deposit,
+            depositor,
+            approvals,
+        })
+    }
+}
+
+// How many pending multisig operations to fetch at a time.
+const MULTISIG_PAGE_SIZE: u32 = 64;
+
+/// Fetch the multisig operations of some multisig account which are waiting for
+/// approvals, from the `Multisig::Multisigs` double map. Each operation is handed
+/// back along with the hash of the call that it will dispatch.
+pub fn multisigs<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<Vec<([u8; 32], Multisig)>, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let return_type_id =
+            lookup_storage_return_type(&metadata, "Multisig", "Multisigs")?;
+
+        // Iterate over every entry whose first key is the account given.
+        let address = crate::dynamic::storage(
+            "Multisig",
+            "Multisigs",
+            vec![EncodeStaticType(account)],
+        );
+        let prefix = storage_address_prefixes(&address, &metadata)?.full();
+        let mut iter = KeyIter::<T, Client, DecodedValue>::new(
+            storage,
+            prefix,
+            MULTISIG_PAGE_SIZE,
+            metadata,
+            return_type_id,
+        );
+
+        let mut multisigs = Vec::new();
+        while let Some((key, value)) = iter.next().await? {
+            // The call hash is the second key, which is stored in full at the end
+            // of the storage key.
+            let call_hash = key
+                .0
+                .len()
+                .checked_sub(32)
+                .and_then(|start| <[u8; 32]>::try_from(&key.0[start..]).ok())
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not find the call hash in the storage key 0x{}",
+                        hex::encode(&key.0)
+                    ))
+                })?;
+            let multisig = Multisig::from_value(&value).ok_or_else(|| {
+                Error::Other(format!("Could not interpret {value} as a multisig"))
+            })?;
+            multisigs.push((call_hash, multisig));
+        }
+        Ok(multisigs)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        decode,
+        AccountId32,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[derive(Encode, TypeInfo)]
+    struct Timepoint {
+        height: u32,
+        index: u32,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct Multisig {
+        when: Timepoint,
+        deposit: u128,
+        depositor: AccountId32,
+        approvals: BoundedVec<AccountId32>,
+    }
+
+    #[test]
+    fn decodes_multisigs() {
+        let multisig = Multisig {
+            when: Timepoint {
+                height: 10,
+                index: 2,
+            },
+            deposit: 500,
+            depositor: AccountId32([1; 32]),
+            approvals: BoundedVec(vec![AccountId32([1; 32]), AccountId32([2; 32])]),
+        };
+
+        let value = decode(multisig);
+
+        assert_eq!(
+            super::Multisig::from_value(&value),
+            Some(super::Multisig {
+                when: super::Timepoint {
+                    height: 10,
+                    index: 2
+                },
+                deposit: 500,
+                depositor: vec![1; 32],
+                approvals: vec![vec![1; 32], vec![2; 32]],
+            })
+        );
+
+        // A single approval isn't mistaken for an account:
+        let approvals = BoundedVec(vec![AccountId32([3; 32])]);
+        let value = decode(approvals);
+        assert_eq!(
+            list_from_value(&value, value_as_bytes),
+            Some(vec![vec![3; 32]])
+        );
+    }
+}
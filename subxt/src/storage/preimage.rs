@@ -0,0 +1,173 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::Storage;
+use crate::{
+    client::OnlineClientT,
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        EncodeStaticType,
+        Metadata,
+    },
+    utils::Encoded,
+    Config,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::StorageEntryType;
+use scale_info::TypeDef;
+use scale_value::{
+    At,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// Newer versions of the preimage pallet key `PreimageFor` by the hash and length of the
+/// preimage, whereas older versions key it by the hash alone.
+pub(crate) fn preimage_keyed_by_len(metadata: &Metadata) -> bool {
+    let Ok(entry) = metadata
+        .pallet("Preimage")
+        .and_then(|pallet| pallet.storage("PreimageFor"))
+    else {
+        return false
+    };
+    match &entry.ty {
+        StorageEntryType::Map { key, .. } => {
+            matches!(
+                metadata.resolve_type(key.id()).map(|ty| ty.type_def()),
+                Some(TypeDef::Tuple(_))
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Obtain the length of a preimage from its `RequestStatus`, returning `None` if the
+/// preimage has been requested but not yet noted.
+pub(crate) fn preimage_len<T>(status: &Value<T>) -> Option<u32> {
+    let len = status.at("len")?;
+    // Requested preimages only have a length once they've been noted:
+    let len = match &len.value {
+        ValueDef::Variant(v) if v.name == "Some" => v.values.values().next()?,
+        ValueDef::Variant(_) => return None,
+        _ => len,
+    };
+    match &len.value {
+        ValueDef::Primitive(p) => p.as_u128().and_then(|n| u32::try_from(n).ok()),
+        _ => None,
+    }
+}
+
+/// Fetch the preimage with the given hash from the `Preimage` pallet, and decode it into
+/// a dynamic `RuntimeCall` value. This is useful for showing what a governance proposal
+/// which refers to a call by its hash will do.
+///
+/// This fails if the preimage hasn't been noted (even if it's been requested), or if the
+/// preimage doesn't decode into a call.
+pub fn decode_preimage<T, Client>(
+    storage: &Storage<T, Client>,
+    hash: T::Hash,
+) -> impl Future<Output = Result<DecodedValue, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let keys = if preimage_keyed_by_len(&metadata) {
+            let status_address = crate::dynamic::storage(
+                "Preimage",
+                "StatusFor",
+                vec![EncodeStaticType(hash)],
+            );
+            let status = storage
+                .fetch(&status_address)
+                .await?
+                .ok_or_else(|| {
+                    Error::Other(format!("No preimage has been noted for {hash:?}"))
+                })?
+                .to_value()?;
+            let len = preimage_len(&status).ok_or_else(|| {
+                Error::Other(format!(
+                    "The preimage for {hash:?} has been requested but not noted yet"
+                ))
+            })?;
+            vec![Encoded(hash.encode()), Encoded(len.encode())]
+        } else {
+            vec![Encoded(hash.encode())]
+        };
+
+        let address = crate::dynamic::storage(
+            "Preimage",
+            "PreimageFor",
+            keys.into_iter().map(EncodeStaticType).collect(),
+        );
+        let bytes = storage.fetch(&address).await?.ok_or_else(|| {
+            Error::Other(format!("No preimage has been noted for {hash:?}"))
+        })?;
+        // The preimage is stored as a bounded vec of bytes.
+        let preimage = <Vec<u8>>::decode(&mut bytes.encoded())?;
+
+        let call_ty = metadata.runtime_call_ty().ok_or_else(|| {
+            Error::Other("Could not find the RuntimeCall type in the metadata".into())
+        })?;
+        DecodedValue::decode_with_metadata(&mut &*preimage, call_ty, &metadata)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::decode;
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum RequestStatus {
+        Unrequested {
+            deposit: (u64, u128),
+            len: u32,
+        },
+        Requested {
+            deposit: Option<(u64, u128)>,
+            count: u32,
+            len: Option<u32>,
+        },
+    }
+
+    fn len_of(status: RequestStatus) -> Option<u32> {
+        let value = decode(status);
+        preimage_len(&value)
+    }
+
+    #[test]
+    fn finds_preimage_len() {
+        let unrequested = RequestStatus::Unrequested {
+            deposit: (1, 2),
+            len: 10,
+        };
+        assert_eq!(len_of(unrequested), Some(10));
+
+        let noted = RequestStatus::Requested {
+            deposit: None,
+            count: 1,
+            len: Some(20),
+        };
+        assert_eq!(len_of(noted), Some(20));
+
+        let not_noted = RequestStatus::Requested {
+            deposit: None,
+            count: 1,
+            len: None,
+        };
+        assert_eq!(len_of(not_noted), None);
+    }
+}
@@ -0,0 +1,233 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    utils::{
+        list_from_value,
+        value_as_bytes,
+        value_as_u128,
+    },
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::EncodeStaticType,
+    Config,
+};
+use codec::Encode;
+use scale_value::{
+    At,
+    Composite,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// An account which is allowed to act as a proxy for another, as stored in
+/// `Proxy::Proxies`. Accounts are handed back as their SCALE encoded bytes.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ProxyDefinition {
+    /// The account which can act as a proxy.
+    pub delegate: Vec<u8>,
+    /// The name of the `ProxyType` variant describing which calls the proxy can make.
+    /// The proxy types available are specific to each runtime.
+    pub proxy_type: String,
+    /// The number of blocks that must pass between the proxy announcing a call and
+    /// making it.
+    pub delay: u64,
+}
+
+impl ProxyDefinition {
+    /// Attempt to interpret a dynamically decoded `ProxyDefinition` as a
+    /// [`ProxyDefinition`], returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<ProxyDefinition> {
+        let delegate = value.at("delegate").and_then(value_as_bytes)?;
+        let proxy_type = match &value.at("proxy_type")?.value {
+            ValueDef::Variant(v) => v.name.clone(),
+            _ => return None,
+        };
+        let delay = value
+            .at("delay")
+            .and_then(value_as_u128)
+            .and_then(|n| u64::try_from(n).ok())?;
+        Some(ProxyDefinition {
+            delegate,
+            proxy_type,
+            delay,
+        })
+    }
+
+    /// Interpret the value stored in `Proxy::Proxies`, which is a list of proxy
+    /// definitions along with the deposit reserved for them.
+    pub(crate) fn list_from_value<T>(value: &Value<T>) -> Option<Vec<ProxyDefinition>> {
+        list_from_value(items_of_deposit_pair(value)?, ProxyDefinition::from_value)
+    }
+}
+
+/// A call which a proxy has announced that it will make on behalf of another account,
+/// as stored in `Proxy::Announcements`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Announcement {
+    /// The SCALE encoded account that the call will be made on behalf of.
+    pub real: Vec<u8>,
+    /// The hash of the call that will be made.
+    pub call_hash: Vec<u8>,
+    /// The block number at which the announcement was made.
+    pub height: u64,
+}
+
+impl Announcement {
+    /// Attempt to interpret a dynamically decoded `Announcement` as an [`Announcement`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Announcement> {
+        let real = value.at("real").and_then(value_as_bytes)?;
+        let call_hash = value.at("call_hash").and_then(value_as_bytes)?;
+        let height = value
+            .at("height")
+            .and_then(value_as_u128)
+            .and_then(|n| u64::try_from(n).ok())?;
+        Some(Announcement {
+            real,
+            call_hash,
+            height,
+        })
+    }
+
+    /// Interpret the value stored in `Proxy::Announcements`, which is a list of
+    /// announcements along with the deposit reserved for them.
+    pub(crate) fn list_from_value<T>(value: &Value<T>) -> Option<Vec<Announcement>> {
+        list_from_value(items_of_deposit_pair(value)?, Announcement::from_value)
+    }
+}
+
+// Both proxy storage maps hold a `(BoundedVec<Item>, Balance)` pair.
+fn items_of_deposit_pair<T>(value: &Value<T>) -> Option<&Value<T>> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    match &vals[..] {
+        [items, _deposit] => Some(items),
+        _ => None,
+    }
+}
+
+/// Fetch the accounts which can act as proxies for some account, from `Proxy::Proxies`.
+pub fn proxies<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<Vec<ProxyDefinition>, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address =
+            crate::dynamic::storage("Proxy", "Proxies", vec![EncodeStaticType(account)]);
+        let value = storage.fetch_or_default(&address).await?.to_value()?;
+        ProxyDefinition::list_from_value(&value).ok_or_else(|| {
+            Error::Other(format!("Could not interpret {value} as a list of proxies"))
+        })
+    }
+}
+
+/// Fetch the calls that some proxy account has announced it will make, from
+/// `Proxy::Announcements`.
+pub fn proxy_announcements<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<Vec<Announcement>, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage(
+            "Proxy",
+            "Announcements",
+            vec![EncodeStaticType(account)],
+        );
+        let value = storage.fetch_or_default(&address).await?.to_value()?;
+        Announcement::list_from_value(&value).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not interpret {value} as a list of proxy announcements"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{
+        decode,
+        AccountId32,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum ProxyType {
+        Any,
+        Governance,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct ProxyDefinition {
+        delegate: AccountId32,
+        proxy_type: ProxyType,
+        delay: u32,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct Announcement {
+        real: AccountId32,
+        call_hash: [u8; 32],
+        height: u32,
+    }
+
+    #[test]
+    fn decodes_proxies_and_announcements() {
+        let proxies = decode((
+            BoundedVec(vec![ProxyDefinition {
+                delegate: AccountId32([1; 32]),
+                proxy_type: ProxyType::Governance,
+                delay: 5,
+            }]),
+            100u128,
+        ));
+        assert_eq!(
+            super::ProxyDefinition::list_from_value(&proxies),
+            Some(vec![super::ProxyDefinition {
+                delegate: vec![1; 32],
+                proxy_type: "Governance".into(),
+                delay: 5,
+            }])
+        );
+
+        let announcements = decode((
+            BoundedVec(vec![
+                Announcement {
+                    real: AccountId32([2; 32]),
+                    call_hash: [3; 32],
+                    height: 7,
+                },
+                Announcement {
+                    real: AccountId32([4; 32]),
+                    call_hash: [5; 32],
+                    height: 8,
+                },
+            ]),
+            100u128,
+        ));
+        let announcements = super::Announcement::list_from_value(&announcements).unwrap();
+        assert_eq!(announcements.len(), 2);
+        assert_eq!(announcements[1].real, vec![4; 32]);
+        assert_eq!(announcements[1].call_hash, vec![5; 32]);
+        assert_eq!(announcements[1].height, 8);
+    }
+}
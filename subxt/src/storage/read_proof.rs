@@ -0,0 +1,357 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Verify storage read proofs, as handed back from [`crate::rpc::Rpc::read_proof()`],
+//! without trusting the node that produced them.
+//!
+//! Storage lives in a base-16 Merkle-Patricia trie, and a read proof is the set of encoded
+//! trie nodes on the path from the root of the trie to the key being read. Given a state
+//! root from a header that's trusted (for instance one that's been finalized), the value
+//! at the key is found by hashing each proof node, then walking down from the node whose
+//! hash is the state root. The proof is valid if every node needed along the way is in it,
+//! and a missing key is proven absent in the same way.
+//!
+//! Nodes are decoded following the Substrate trie layout, in both of its versions: with
+//! state version 1, values of 33 bytes or more are replaced in their node by their hash,
+//! and the values themselves are included in the proof alongside the nodes.
+
+use crate::{
+    config::Hasher,
+    error::Error,
+};
+use codec::{
+    Compact,
+    Decode,
+};
+use std::collections::HashMap;
+
+/// Verify that `proof` proves the value of `key` in the storage whose root is
+/// `state_root`, using the trie hasher `H` (`BlakeTwo256` for Substrate based chains).
+///
+/// Returns the value stored at the key, or `None` if the proof shows that there's no
+/// value at the key. An error is returned if the proof doesn't contain every node needed
+/// to reach the key from the root, or if some node in it can't be decoded; either way, the
+/// proof can't be trusted.
+pub fn verify_read_proof<H>(
+    state_root: &H::Output,
+    proof: &[impl AsRef<[u8]>],
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, Error>
+where
+    H: Hasher,
+    H::Output: AsRef<[u8]>,
+{
+    let hash_len = state_root.as_ref().len();
+    let nodes: HashMap<Vec<u8>, &[u8]> = proof
+        .iter()
+        .map(|node| (H::hash(node.as_ref()).as_ref().to_vec(), node.as_ref()))
+        .collect();
+    let lookup = |hash: &[u8]| {
+        nodes.get(hash).copied().ok_or_else(|| {
+            Error::Other(format!(
+                "The read proof is missing the trie node with hash 0x{}",
+                hex::encode(hash)
+            ))
+        })
+    };
+    let resolve = |value: NodeValue| -> Result<Vec<u8>, Error> {
+        match value {
+            NodeValue::Inline(value) => Ok(value.to_vec()),
+            NodeValue::Hashed(hash) => Ok(lookup(hash)?.to_vec()),
+        }
+    };
+
+    let key_nibbles: Vec<u8> = key.iter().flat_map(|b| [b >> 4, b & 0xf]).collect();
+    let mut remaining = &key_nibbles[..];
+    let mut node_bytes = lookup(state_root.as_ref())?;
+    loop {
+        match decode_node(node_bytes, hash_len)? {
+            Node::Empty => return Ok(None),
+            Node::Leaf { partial, value } => {
+                if remaining != partial {
+                    return Ok(None)
+                }
+                return resolve(value).map(Some)
+            }
+            Node::Branch {
+                partial,
+                value,
+                children,
+            } => {
+                let Some(rest) = remaining.strip_prefix(&partial[..]) else {
+                    return Ok(None)
+                };
+                let Some((&nibble, rest)) = rest.split_first() else {
+                    return value.map(resolve).transpose()
+                };
+                remaining = rest;
+                node_bytes = match children[nibble as usize] {
+                    None => return Ok(None),
+                    Some(ChildRef::Hash(hash)) => lookup(hash)?,
+                    Some(ChildRef::Inline(bytes)) => bytes,
+                };
+            }
+        }
+    }
+}
+
+// A trie node, borrowing from the encoded node.
+enum Node<'a> {
+    Empty,
+    Leaf {
+        partial: Vec<u8>,
+        value: NodeValue<'a>,
+    },
+    Branch {
+        partial: Vec<u8>,
+        value: Option<NodeValue<'a>>,
+        children: Box<[Option<ChildRef<'a>>; 16]>,
+    },
+}
+
+enum NodeValue<'a> {
+    Inline(&'a [u8]),
+    // A hash of the value, whose preimage is in the proof (state version 1 only).
+    Hashed(&'a [u8]),
+}
+
+enum ChildRef<'a> {
+    Hash(&'a [u8]),
+    // Nodes whose encoding is shorter than a hash are stored in their parent.
+    Inline(&'a [u8]),
+}
+
+// The first two bits of the header say what kind of node this is. Nodes with hashed
+// values use the otherwise unused headers starting with `0b00`.
+const LEAF: u8 = 0b01 << 6;
+const BRANCH_WITHOUT_VALUE: u8 = 0b10 << 6;
+const BRANCH_WITH_VALUE: u8 = 0b11 << 6;
+const EMPTY_TRIE: u8 = 0;
+const HASHED_VALUE_LEAF: u8 = 0b001 << 5;
+const HASHED_VALUE_BRANCH: u8 = 0b0001 << 4;
+
+fn decode_node(data: &[u8], hash_len: usize) -> Result<Node<'_>, Error> {
+    let mut input = NodeInput { data, rest: data };
+
+    let first = input.take(1)?[0];
+    if first == EMPTY_TRIE {
+        return Ok(Node::Empty)
+    }
+    // Whether this is a branch, whether there's a value and whether it's hashed, and how
+    // many bits of the header are used up saying so.
+    let (is_branch, has_value, value_is_hashed, prefix_bits) = match first & (0b11 << 6) {
+        LEAF => (false, true, false, 2),
+        BRANCH_WITHOUT_VALUE => (true, false, false, 2),
+        BRANCH_WITH_VALUE => (true, true, false, 2),
+        _ if first & (0b111 << 5) == HASHED_VALUE_LEAF => (false, true, true, 3),
+        _ if first & (0b1111 << 4) == HASHED_VALUE_BRANCH => (true, true, true, 4),
+        _ => return Err(input.invalid("unknown header")),
+    };
+
+    // The number of nibbles in the partial key is in the rest of the header, continuing
+    // into the following bytes if it doesn't fit.
+    let max_in_header = 255u8 >> prefix_bits;
+    let mut nibble_count = (first & max_in_header) as usize;
+    if nibble_count == max_in_header as usize {
+        loop {
+            let n = input.take(1)?[0];
+            nibble_count += n as usize;
+            if n < 255 {
+                break
+            }
+        }
+    }
+
+    // Partial keys with an odd number of nibbles are padded at the start.
+    let padding = nibble_count % 2;
+    let partial_bytes = input.take(nibble_count.div_ceil(2))?;
+    if padding == 1 && partial_bytes[0] >> 4 != 0 {
+        return Err(input.invalid("bad padding in the partial key"))
+    }
+    let partial: Vec<u8> = partial_bytes
+        .iter()
+        .flat_map(|b| [b >> 4, b & 0xf])
+        .skip(padding)
+        .collect();
+
+    let bitmap = if is_branch {
+        let mut bitmap = input.take(2)?;
+        Some(u16::decode(&mut bitmap).expect("two bytes are a u16; qed"))
+    } else {
+        None
+    };
+
+    let value = if !has_value {
+        None
+    } else if value_is_hashed {
+        Some(NodeValue::Hashed(input.take(hash_len)?))
+    } else {
+        let len = input.compact_len()?;
+        Some(NodeValue::Inline(input.take(len)?))
+    };
+
+    let Some(bitmap) = bitmap else {
+        let value = value.expect("leaves always have a value; qed");
+        return Ok(Node::Leaf { partial, value })
+    };
+
+    let mut children: Box<[Option<ChildRef>; 16]> = Default::default();
+    for (i, child) in children.iter_mut().enumerate() {
+        if bitmap & (1 << i) != 0 {
+            let len = input.compact_len()?;
+            let bytes = input.take(len)?;
+            *child = Some(
+                if len == hash_len {
+                    ChildRef::Hash(bytes)
+                } else {
+                    ChildRef::Inline(bytes)
+                },
+            );
+        }
+    }
+    Ok(Node::Branch {
+        partial,
+        value,
+        children,
+    })
+}
+
+// Reads the parts of an encoded node one after the other.
+struct NodeInput<'a> {
+    data: &'a [u8],
+    rest: &'a [u8],
+}
+
+impl<'a> NodeInput<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if self.rest.len() < len {
+            return Err(self.invalid("not enough bytes"))
+        }
+        let (taken, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(taken)
+    }
+
+    fn compact_len(&mut self) -> Result<usize, Error> {
+        let len = Compact::<u32>::decode(&mut self.rest)
+            .map_err(|_| self.invalid("bad compact length"))?;
+        Ok(len.0 as usize)
+    }
+
+    fn invalid(&self, reason: &str) -> Error {
+        Error::Other(format!(
+            "Invalid trie node 0x{} in the read proof: {reason}",
+            hex::encode(self.data)
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::substrate::BlakeTwo256;
+    use primitive_types::H256;
+    use sp_core::Blake2Hasher;
+    use sp_trie::{
+        LayoutV0,
+        LayoutV1,
+        MemoryDB,
+        Recorder,
+        Trie,
+        TrieDBBuilder,
+        TrieDBMutBuilder,
+        TrieLayout,
+        TrieMut,
+    };
+
+    fn entries() -> Vec<(&'static [u8], Vec<u8>)> {
+        vec![
+            (b"alice", vec![1; 4]),
+            (b"alicia", vec![2; 40]),
+            (b"bob", vec![3; 100]),
+            (b"b", vec![4]),
+            (&[0x12, 0x34], vec![5; 2]),
+            (&[0x12, 0x35], vec![6; 3]),
+        ]
+    }
+
+    // Build a trie of the entries using Substrate's trie implementation, and hand back
+    // its root and a function which produces the proof of some key, as a node would.
+    fn trie<L: TrieLayout<Hash = Blake2Hasher>>() -> (H256, impl Fn(&[u8]) -> Vec<Vec<u8>>)
+    {
+        let mut db = MemoryDB::<Blake2Hasher>::default();
+        let mut root = Default::default();
+        {
+            let mut trie = TrieDBMutBuilder::<L>::new(&mut db, &mut root).build();
+            for (key, value) in entries() {
+                trie.insert(key, &value).unwrap();
+            }
+        }
+        let prove = move |key: &[u8]| {
+            let mut recorder = Recorder::<L>::new();
+            TrieDBBuilder::<L>::new(&db, &root)
+                .with_recorder(&mut recorder)
+                .build()
+                .get(key)
+                .unwrap();
+            recorder
+                .drain()
+                .into_iter()
+                .map(|record| record.data)
+                .collect()
+        };
+        (root, prove)
+    }
+
+    fn check_proofs<L: TrieLayout<Hash = Blake2Hasher>>() {
+        let (root, prove) = trie::<L>();
+
+        for (key, value) in entries() {
+            let found =
+                verify_read_proof::<BlakeTwo256>(&root, &prove(key), key).unwrap();
+            assert_eq!(found, Some(value), "value at {key:?}");
+        }
+        for key in [&b"al"[..], b"alicee", b"carol", &[0x12], &[0x12, 0x36]] {
+            let found =
+                verify_read_proof::<BlakeTwo256>(&root, &prove(key), key).unwrap();
+            assert_eq!(found, None, "value at {key:?}");
+        }
+
+        // The proof is no good against some other root:
+        let other_root = H256::repeat_byte(1);
+        assert!(
+            verify_read_proof::<BlakeTwo256>(&other_root, &prove(b"bob"), b"bob")
+                .is_err()
+        );
+
+        // Nor is the proof of some other key, which may not have all of the nodes needed:
+        assert!(
+            verify_read_proof::<BlakeTwo256>(&root, &prove(&[0x12, 0x34]), b"bob")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn verifies_proofs_from_state_version_0_tries() {
+        check_proofs::<LayoutV0<Blake2Hasher>>();
+    }
+
+    #[test]
+    fn verifies_proofs_from_state_version_1_tries() {
+        check_proofs::<LayoutV1<Blake2Hasher>>();
+
+        // Long values are stored apart from their node, and are needed to prove them:
+        let (root, prove) = trie::<LayoutV1<Blake2Hasher>>();
+        let value_hash = BlakeTwo256::hash(&[3; 100]);
+        let proof = prove(b"bob");
+        assert!(proof
+            .iter()
+            .any(|node| BlakeTwo256::hash(node) == value_hash));
+        let without_value: Vec<_> = proof
+            .iter()
+            .filter(|node| BlakeTwo256::hash(node) != value_hash)
+            .collect();
+        assert!(verify_read_proof::<BlakeTwo256>(&root, &without_value, b"bob").is_err());
+    }
+}
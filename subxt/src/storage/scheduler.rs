@@ -0,0 +1,382 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Read the calls scheduled with `pallet-scheduler`.
+
+use super::{
+    preimage::decode_preimage,
+    utils::{
+        list_from_value,
+        value_as_bytes,
+        value_as_u128,
+    },
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    dynamic::{
+        DecodedValue,
+        Value,
+    },
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        EncodeStaticType,
+    },
+    Config,
+};
+use codec::Decode;
+use scale_value::{
+    At,
+    Composite,
+    ValueDef,
+};
+use std::future::Future;
+
+/// A call scheduled to be dispatched at some block, as stored in that block's
+/// `Scheduler::Agenda`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledTask {
+    /// Where the task is in its block's agenda. Tasks without an ID are cancelled by
+    /// this index (see [`crate::tx::cancel()`]).
+    pub index: u32,
+    /// The ID that the task was given, if it was scheduled with
+    /// [`crate::tx::schedule_named()`].
+    pub id: Option<Vec<u8>>,
+    /// The priority of the task; tasks in the same block are dispatched in order of
+    /// priority, with 0 being the highest.
+    pub priority: u8,
+    /// How many blocks apart the task is dispatched again, and how many more times, if it
+    /// repeats.
+    pub periodic: Option<(u64, u32)>,
+    /// The origin that the call is dispatched with.
+    pub origin: Value,
+    /// The hash of the call, if the task refers to it by its hash rather than holding it.
+    pub call_hash: Option<Vec<u8>>,
+    /// The call to dispatch, as a dynamic `RuntimeCall` value. This is `None` if the task
+    /// refers to the call by hash and its preimage couldn't be fetched and decoded, for
+    /// instance because it hasn't been noted yet.
+    pub call: Option<DecodedValue>,
+}
+
+/// How a task holds the call that it will dispatch. Depending on the version of the
+/// pallet, this is the call itself, a `MaybeHashed` (either the call or its hash) or a
+/// `Bounded` (the encoded call, or the hash of its preimage).
+pub(crate) enum ScheduledCall {
+    Call(DecodedValue),
+    Inline(Vec<u8>),
+    Hash(Vec<u8>),
+}
+
+impl ScheduledCall {
+    // The variants of `MaybeHashed` and `Bounded` aren't named like any pallet, which is
+    // what a `RuntimeCall` variant is named after, so they're told apart by name.
+    fn from_value(value: &DecodedValue) -> Option<ScheduledCall> {
+        let ValueDef::Variant(variant) = &value.value else {
+            return None
+        };
+        let only_field = || {
+            match &variant.values {
+                Composite::Unnamed(fields) if fields.len() == 1 => Some(&fields[0]),
+                _ => None,
+            }
+        };
+        let call = match variant.name.as_str() {
+            "Value" => ScheduledCall::Call(only_field()?.clone()),
+            "Hash" => ScheduledCall::Hash(value_as_bytes(only_field()?)?),
+            "Inline" => ScheduledCall::Inline(value_as_bytes(only_field()?)?),
+            "Legacy" | "Lookup" => {
+                ScheduledCall::Hash(value_as_bytes(value.at("hash")?)?)
+            }
+            _ => ScheduledCall::Call(value.clone()),
+        };
+        Some(call)
+    }
+}
+
+/// Interpret the value stored in `Scheduler::Agenda` for some block, which is a list with
+/// an optional task in each slot. Each task is handed back along with its call, which has
+/// yet to be resolved.
+pub(crate) fn agenda_from_value(
+    value: &DecodedValue,
+) -> Option<Vec<(ScheduledTask, ScheduledCall)>> {
+    let slots = list_from_value(value, |slot| {
+        match &slot.value {
+            ValueDef::Variant(v) if v.name == "None" => Some(None),
+            ValueDef::Variant(v) if v.name == "Some" => {
+                v.values.values().next().cloned().map(Some)
+            }
+            _ => None,
+        }
+    })?;
+    let mut tasks = Vec::new();
+    for (index, slot) in slots.into_iter().enumerate() {
+        let Some(task) = slot else { continue };
+        let id = match &task.at("maybe_id")?.value {
+            ValueDef::Variant(v) if v.name == "Some" => {
+                Some(value_as_bytes(v.values.values().next()?)?)
+            }
+            _ => None,
+        };
+        let periodic = match &task.at("maybe_periodic")?.value {
+            ValueDef::Variant(v) if v.name == "Some" => {
+                let period = v.values.values().next()?;
+                let period_and_count = |i: usize| period.at(i).and_then(value_as_u128);
+                Some((
+                    u64::try_from(period_and_count(0)?).ok()?,
+                    u32::try_from(period_and_count(1)?).ok()?,
+                ))
+            }
+            _ => None,
+        };
+        let call = ScheduledCall::from_value(task.at("call")?)?;
+        let call_hash = match &call {
+            ScheduledCall::Hash(hash) => Some(hash.clone()),
+            _ => None,
+        };
+        tasks.push((
+            ScheduledTask {
+                index: index as u32,
+                id,
+                priority: u8::try_from(task.at("priority").and_then(value_as_u128)?)
+                    .ok()?,
+                periodic,
+                origin: task.at("origin")?.clone().remove_context(),
+                call_hash,
+                call: None,
+            },
+            call,
+        ));
+    }
+    Some(tasks)
+}
+
+/// Fetch the tasks scheduled for block `when` from `Scheduler::Agenda`, in the order
+/// in which they sit in the block's agenda. Calls which tasks refer to by hash are
+/// looked up with [`crate::storage::decode_preimage()`].
+pub fn scheduler_agenda<T, Client>(
+    storage: &Storage<T, Client>,
+    when: u64,
+) -> impl Future<Output = Result<Vec<ScheduledTask>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let when = u32::try_from(when).map_err(|_| {
+            Error::Other(format!("Block number {when} does not fit in a u32"))
+        })?;
+        let address =
+            crate::dynamic::storage("Scheduler", "Agenda", vec![EncodeStaticType(when)]);
+        match storage.fetch(&address).await? {
+            Some(agenda) => resolve_agenda(&storage, &agenda.to_value()?).await,
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Fetch every block's agenda from `Scheduler::Agenda`, `page_size` blocks at a time,
+/// like [`scheduler_agenda()`]. This hands back the tasks scheduled for each block in
+/// order of block number, leaving out blocks with nothing scheduled.
+pub fn scheduler_agendas<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<(u64, Vec<ScheduledTask>)>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let entries = storage
+            .clone()
+            .index_keyed_entries("Scheduler", "Agenda", page_size)
+            .await?;
+        let mut agendas = Vec::new();
+        for (when, agenda) in entries {
+            let tasks = resolve_agenda(&storage, &agenda).await?;
+            if !tasks.is_empty() {
+                agendas.push((when as u64, tasks));
+            }
+        }
+        agendas.sort_by_key(|(when, _)| *when);
+        Ok(agendas)
+    }
+}
+
+// Interpret an agenda, and resolve the call of each task in it.
+async fn resolve_agenda<T, Client>(
+    storage: &Storage<T, Client>,
+    agenda: &DecodedValue,
+) -> Result<Vec<ScheduledTask>, Error>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let tasks = agenda_from_value(agenda).ok_or_else(|| {
+        Error::Other(format!(
+            "Could not interpret {agenda} as a scheduler agenda"
+        ))
+    })?;
+    let metadata = storage.client().metadata();
+    let mut resolved = Vec::with_capacity(tasks.len());
+    for (mut task, call) in tasks {
+        task.call = match call {
+            ScheduledCall::Call(call) => Some(call),
+            ScheduledCall::Inline(bytes) => {
+                let call_ty = metadata.runtime_call_ty().ok_or_else(|| {
+                    Error::Other(
+                        "Could not find the RuntimeCall type in the metadata".into(),
+                    )
+                })?;
+                Some(DecodedValue::decode_with_metadata(
+                    &mut &*bytes,
+                    call_ty,
+                    &metadata,
+                )?)
+            }
+            // The preimage may not have been noted yet, so this isn't an error.
+            ScheduledCall::Hash(hash) => {
+                match T::Hash::decode(&mut &*hash) {
+                    Ok(hash) => decode_preimage(storage, hash).await.ok(),
+                    Err(_) => None,
+                }
+            }
+        };
+        resolved.push(task);
+    }
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        decode,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[derive(Encode, TypeInfo)]
+    struct H256([u8; 32]);
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum RuntimeCall {
+        System(SystemCall),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum SystemCall {
+        #[codec(index = 1)]
+        Remark { remark: Vec<u8> },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Bounded {
+        Legacy { hash: H256 },
+        Inline(BoundedVec<u8>),
+        Lookup { hash: H256, len: u32 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum OriginCaller {
+        #[codec(index = 0)]
+        System(RawOrigin),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum RawOrigin {
+        Root,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct Scheduled<Call> {
+        maybe_id: Option<[u8; 32]>,
+        priority: u8,
+        call: Call,
+        maybe_periodic: Option<(u32, u32)>,
+        origin: OriginCaller,
+    }
+
+    fn remark() -> RuntimeCall {
+        RuntimeCall::System(SystemCall::Remark {
+            remark: vec![1, 2, 3],
+        })
+    }
+
+    #[test]
+    fn decodes_agendas_with_bounded_calls() {
+        let agenda = decode(BoundedVec(vec![
+            None,
+            Some(Scheduled {
+                maybe_id: Some([7; 32]),
+                priority: 63,
+                call: Bounded::Inline(BoundedVec(remark().encode())),
+                maybe_periodic: Some((10, 3)),
+                origin: OriginCaller::System(RawOrigin::Root),
+            }),
+            Some(Scheduled {
+                maybe_id: None,
+                priority: 0,
+                call: Bounded::Lookup {
+                    hash: H256([9; 32]),
+                    len: 100,
+                },
+                maybe_periodic: None,
+                origin: OriginCaller::System(RawOrigin::Root),
+            }),
+        ]));
+        let tasks = agenda_from_value(&agenda).unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        let (task, call) = &tasks[0];
+        assert_eq!(task.index, 1);
+        assert_eq!(task.id, Some(vec![7; 32]));
+        assert_eq!(task.priority, 63);
+        assert_eq!(task.periodic, Some((10, 3)));
+        assert_eq!(
+            task.origin,
+            Value::unnamed_variant("System", [Value::unnamed_variant("Root", [])])
+        );
+        assert_eq!(task.call_hash, None);
+        assert!(
+            matches!(call, ScheduledCall::Inline(bytes) if *bytes == remark().encode())
+        );
+
+        let (task, call) = &tasks[1];
+        assert_eq!(task.index, 2);
+        assert_eq!(task.id, None);
+        assert_eq!(task.periodic, None);
+        assert_eq!(task.call_hash, Some(vec![9; 32]));
+        assert!(matches!(call, ScheduledCall::Hash(hash) if *hash == [9; 32]));
+    }
+
+    #[test]
+    fn decodes_agendas_with_plain_calls() {
+        let agenda = decode(vec![Some(Scheduled {
+            maybe_id: None,
+            priority: 1,
+            call: remark(),
+            maybe_periodic: None,
+            origin: OriginCaller::System(RawOrigin::Root),
+        })]);
+        let tasks = agenda_from_value(&agenda).unwrap();
+        let (task, call) = &tasks[0];
+        assert_eq!(task.index, 0);
+        let ScheduledCall::Call(call) = call else {
+            panic!("expected the call to be held as it is")
+        };
+        let ValueDef::Variant(pallet) = &call.value else {
+            panic!("expected a RuntimeCall")
+        };
+        assert_eq!(pallet.name, "System");
+    }
+}
@@ -0,0 +1,257 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    storage_address::{
+        StorageAddress,
+        Yes,
+    },
+    storage_type::{
+        return_type_from_storage_entry_type,
+        validate_storage_address,
+    },
+};
+use crate::{
+    client::{
+        metadata_at_block,
+        OfflineClientT,
+        OnlineClientT,
+    },
+    constants::{
+        ConstantAddress,
+        ConstantsClient,
+    },
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        Metadata,
+    },
+    rpc::types::StorageKey,
+    Config,
+};
+use std::{
+    collections::HashMap,
+    future::Future,
+    marker::PhantomData,
+};
+
+/// Queue up a number of storage entries and constants, and then read them all at the same
+/// block. Create one of these via [`super::StorageClient::snapshot()`].
+///
+/// Each call to [`SnapshotBuilder::storage()`] or [`SnapshotBuilder::constant()`] hands back
+/// a handle which can be used to obtain the corresponding value from the [`Snapshot`] once
+/// it's been fetched. Storage values are fetched with a single `state_queryStorageAt` call,
+/// and everything is decoded using the metadata of the runtime in use at the block.
+pub struct SnapshotBuilder<T, Client> {
+    client: Client,
+    keys: Vec<Vec<u8>>,
+    _marker: PhantomData<T>,
+}
+
+impl<T, Client> SnapshotBuilder<T, Client>
+where
+    T: Config,
+    Client: OfflineClientT<T>,
+{
+    pub(crate) fn new(client: Client) -> Self {
+        SnapshotBuilder {
+            client,
+            keys: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Add a storage entry to the snapshot. This fails if the address doesn't line up with
+    /// the metadata that the client currently holds.
+    pub fn storage<Address>(
+        &mut self,
+        address: &Address,
+    ) -> Result<SnapshotStorage<Address::Target, Address::IsDefaultable>, Error>
+    where
+        Address: StorageAddress<IsFetchable = Yes>,
+    {
+        let metadata = self.client.metadata();
+        validate_storage_address(address, &metadata)?;
+        let key = super::utils::storage_address_bytes(address, &metadata)?;
+        self.keys.push(key);
+        Ok(SnapshotStorage {
+            index: self.keys.len() - 1,
+            pallet_name: address.pallet_name().to_owned(),
+            entry_name: address.entry_name().to_owned(),
+            _marker: PhantomData,
+        })
+    }
+
+    /// Add a constant to the snapshot. This fails if the address doesn't line up with the
+    /// metadata that the client currently holds.
+    pub fn constant<Address>(
+        &mut self,
+        address: &Address,
+    ) -> Result<SnapshotConstant<Address::Target>, Error>
+    where
+        Address: ConstantAddress,
+    {
+        ConstantsClient::new(self.client.clone()).validate(address)?;
+        Ok(SnapshotConstant {
+            pallet_name: address.pallet_name().to_owned(),
+            constant_name: address.constant_name().to_owned(),
+            _marker: PhantomData,
+        })
+    }
+}
+
+impl<T, Client> SnapshotBuilder<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    /// Fetch everything that's been added to the snapshot at the given block, or at the
+    /// latest block if `None` is given.
+    pub fn fetch(
+        &self,
+        block_hash: Option<T::Hash>,
+    ) -> impl Future<Output = Result<Snapshot<T>, Error>> + Send + 'static {
+        let client = self.client.clone();
+        let keys = self.keys.clone();
+        async move {
+            // If block hash is not provided, get the hash
+            // for the latest block and use that.
+            let block_hash = match block_hash {
+                Some(hash) => hash,
+                None => {
+                    client
+                        .rpc()
+                        .block_hash(None)
+                        .await?
+                        .expect("didn't pass a block number; qed")
+                }
+            };
+
+            let metadata = metadata_at_block(&client, block_hash).await?;
+
+            let mut found = HashMap::new();
+            if !keys.is_empty() {
+                let change_sets = client
+                    .rpc()
+                    .query_storage_at(keys.iter().map(|k| &**k), Some(block_hash))
+                    .await?;
+                for change_set in change_sets {
+                    for (key, value) in change_set.changes {
+                        if let Some(value) = value {
+                            found.insert(key, value.0);
+                        }
+                    }
+                }
+            }
+            let values = keys
+                .into_iter()
+                .map(|key| found.get(&StorageKey(key)).cloned())
+                .collect();
+
+            Ok(Snapshot {
+                block_hash,
+                metadata,
+                values,
+            })
+        }
+    }
+}
+
+/// A handle to a storage value in a [`Snapshot`].
+pub struct SnapshotStorage<ReturnTy, Defaultable> {
+    index: usize,
+    pallet_name: String,
+    entry_name: String,
+    _marker: PhantomData<(ReturnTy, Defaultable)>,
+}
+
+/// A handle to a constant in a [`Snapshot`].
+pub struct SnapshotConstant<ReturnTy> {
+    pallet_name: String,
+    constant_name: String,
+    _marker: PhantomData<ReturnTy>,
+}
+
+/// Storage values and constants which were all read at the same block. This is returned
+/// from [`SnapshotBuilder::fetch()`].
+pub struct Snapshot<T: Config> {
+    block_hash: T::Hash,
+    metadata: Metadata,
+    values: Vec<Option<Vec<u8>>>,
+}
+
+impl<T: Config> Snapshot<T> {
+    /// The hash of the block that everything was read at.
+    pub fn block_hash(&self) -> T::Hash {
+        self.block_hash
+    }
+
+    /// The metadata of the runtime in use at the block, which was used to decode everything.
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    /// Decode a storage value, returning `None` if there is no value at the address.
+    pub fn storage<ReturnTy, Defaultable>(
+        &self,
+        entry: &SnapshotStorage<ReturnTy, Defaultable>,
+    ) -> Result<Option<ReturnTy::Target>, Error>
+    where
+        ReturnTy: DecodeWithMetadata,
+    {
+        let Some(bytes) = self.values.get(entry.index).and_then(|v| v.as_deref()) else {
+            return Ok(None)
+        };
+        let val = ReturnTy::decode_storage_with_metadata(
+            &mut &*bytes,
+            &entry.pallet_name,
+            &entry.entry_name,
+            &self.metadata,
+        )?;
+        Ok(Some(val))
+    }
+
+    /// Decode a storage value, falling back to the default value for the storage entry if
+    /// there is no value at the address.
+    pub fn storage_or_default<ReturnTy>(
+        &self,
+        entry: &SnapshotStorage<ReturnTy, Yes>,
+    ) -> Result<ReturnTy::Target, Error>
+    where
+        ReturnTy: DecodeWithMetadata,
+    {
+        if let Some(val) = self.storage(entry)? {
+            return Ok(val)
+        }
+        let storage_metadata = self
+            .metadata
+            .pallet(&entry.pallet_name)?
+            .storage(&entry.entry_name)?;
+        let return_ty_id = return_type_from_storage_entry_type(&storage_metadata.ty);
+        ReturnTy::decode_with_metadata(
+            &mut &storage_metadata.default[..],
+            return_ty_id,
+            &self.metadata,
+        )
+    }
+
+    /// Decode a constant.
+    pub fn constant<ReturnTy>(
+        &self,
+        constant: &SnapshotConstant<ReturnTy>,
+    ) -> Result<ReturnTy::Target, Error>
+    where
+        ReturnTy: DecodeWithMetadata,
+    {
+        let constant_metadata = self
+            .metadata
+            .pallet(&constant.pallet_name)?
+            .constant(&constant.constant_name)?;
+        ReturnTy::decode_with_metadata(
+            &mut &*constant_metadata.value,
+            constant_metadata.ty.id(),
+            &self.metadata,
+        )
+    }
+}
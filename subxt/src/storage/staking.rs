@@ -0,0 +1,162 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    storage_map_key::StorageHasher,
+    storage_type::{
+        map_prefix,
+        single_key_map,
+    },
+    KeyIter,
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::DecodeStaticType,
+    rpc::types::StorageKey,
+    Config,
+};
+use codec::Decode;
+use std::future::Future;
+
+// The start of `Staking::Nominations`. The `submitted_in` era and `suppressed` flag
+// which come after the targets aren't decoded.
+#[derive(Decode)]
+pub(super) struct NominationTargets<AccountId> {
+    targets: Vec<AccountId>,
+}
+
+/// Iterates over every nominator in `Staking::Nominators`, along with the validators
+/// that each one nominates. Obtain one using [`nominators()`].
+///
+/// Keys are fetched a page at a time, and the values for each page are then fetched
+/// together, so iterating needs two round trips per page. There are tens of thousands of
+/// nominators on Polkadot and Kusama, so it's worth using a page size in the hundreds to
+/// keep the number of round trips down.
+pub struct NominatorIter<T: Config, Client> {
+    entries: KeyIter<T, Client, DecodeStaticType<NominationTargets<T::AccountId>>>,
+    // The length of the storage key up to the encoded nominator account.
+    account_offset: usize,
+}
+
+impl<T, Client> NominatorIter<T, Client>
+where
+    T: Config,
+    T::AccountId: Decode,
+    Client: OnlineClientT<T>,
+{
+    pub(super) fn new(
+        entries: KeyIter<T, Client, DecodeStaticType<NominationTargets<T::AccountId>>>,
+        hasher: &StorageHasher,
+    ) -> Result<Self, Error> {
+        let hash_len = concat_hash_len(hasher).ok_or_else(|| {
+            Error::Other(format!(
+                "Cannot find the nominators in Staking::Nominators keys hashed with {hasher:?}"
+            ))
+        })?;
+        Ok(NominatorIter {
+            entries,
+            account_offset: 32 + hash_len,
+        })
+    }
+
+    /// Returns the next nominator and the accounts that it nominates.
+    pub async fn next(
+        &mut self,
+    ) -> Result<Option<(T::AccountId, Vec<T::AccountId>)>, Error> {
+        let Some((key, nominations)) = self.entries.next().await? else {
+            return Ok(None)
+        };
+        let nominator = nominator_from_key(&key, self.account_offset)?;
+        Ok(Some((nominator, nominations.targets)))
+    }
+}
+
+// The length of the hash in front of the key, for hashers which keep the key itself.
+pub(super) fn concat_hash_len(hasher: &StorageHasher) -> Option<usize> {
+    match hasher {
+        StorageHasher::Identity => Some(0),
+        StorageHasher::Twox64Concat => Some(8),
+        StorageHasher::Blake2_128Concat => Some(16),
+        _ => None,
+    }
+}
+
+fn nominator_from_key<AccountId: Decode>(
+    key: &StorageKey,
+    account_offset: usize,
+) -> Result<AccountId, Error> {
+    let invalid = || {
+        Error::Other(format!(
+            "Could not find a nominator in the storage key 0x{}",
+            hex::encode(&key.0)
+        ))
+    };
+    let mut account_bytes = key.0.get(account_offset..).ok_or_else(invalid)?;
+    let account = AccountId::decode(&mut account_bytes).map_err(|_| invalid())?;
+    if !account_bytes.is_empty() {
+        return Err(invalid())
+    }
+    Ok(account)
+}
+
+/// Iterate over every nominator in `Staking::Nominators`, along with the validators
+/// that each one nominates. Nominators are fetched `page_size` at a time; see
+/// [`NominatorIter`] for more on how long this takes.
+pub fn nominators<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<Output = Result<NominatorIter<T, Client>, Error>> + 'static
+where
+    T: Config,
+    T::AccountId: Decode,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let (hasher, return_type_id) =
+            single_key_map(&metadata, "Staking", "Nominators")?;
+        let prefix = map_prefix("Staking", "Nominators");
+        let entries = KeyIter::new(storage, prefix, page_size, metadata, return_type_id);
+        NominatorIter::new(entries, &hasher)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        storage::storage_map_key::StorageMapKey,
+        utils::AccountId32,
+    };
+    use codec::Encode;
+
+    #[test]
+    fn decodes_nominators_and_targets() {
+        let nominator = AccountId32([1; 32]);
+        let mut key = map_prefix("Staking", "Nominators");
+        StorageMapKey::new(&nominator, StorageHasher::Twox64Concat).to_bytes(&mut key);
+
+        let offset = 32 + concat_hash_len(&StorageHasher::Twox64Concat).unwrap();
+        let decoded: AccountId32 =
+            nominator_from_key(&StorageKey(key.clone()), offset).unwrap();
+        assert_eq!(decoded, nominator);
+
+        // A truncated key has no nominator in it:
+        key.pop();
+        assert!(nominator_from_key::<AccountId32>(&StorageKey(key), offset).is_err());
+
+        // Targets, followed by the era they were submitted in and the `suppressed` flag:
+        let targets = vec![AccountId32([2; 32]), AccountId32([3; 32])];
+        let value = (targets.clone(), 10u32, false).encode();
+        let nominations =
+            NominationTargets::<AccountId32>::decode(&mut &value[..]).unwrap();
+        assert_eq!(nominations.targets, targets);
+
+        // Hashers which don't keep the key are no good:
+        assert_eq!(concat_hash_len(&StorageHasher::Blake2_256), None);
+    }
+}
@@ -54,12 +54,32 @@ pub trait StorageAddress {
         bytes: &mut Vec<u8>,
     ) -> Result<(), Error>;
 
+    /// Output the bytes for each of the keys separately. Concatenated, these are the bytes
+    /// output by [`StorageAddress::append_entry_bytes()`]. By default, these are all
+    /// handed back as one.
+    fn entry_key_bytes(&self, metadata: &Metadata) -> Result<Vec<Vec<u8>>, Error> {
+        let mut bytes = Vec::new();
+        self.append_entry_bytes(metadata, &mut bytes)?;
+        Ok(if bytes.is_empty() {
+            vec![]
+        } else {
+            vec![bytes]
+        })
+    }
+
     /// An optional hash which, if present, will be checked against
     /// the node metadata to confirm that the return type matches what
     /// we are expecting.
     fn validation_hash(&self) -> Option<[u8; 32]> {
         None
     }
+
+    /// The keys of the address along with the hashers they're hashed with, if the address
+    /// chose the hashers itself rather than looking them up in the metadata. These are
+    /// checked against the metadata by [`crate::storage::StorageClient::validate_keys()`].
+    fn map_keys(&self) -> Option<&[StorageMapKey]> {
+        None
+    }
 }
 
 /// Used to signal whether a [`StorageAddress`] can be iterated,
@@ -153,9 +173,25 @@ where
         Ok(())
     }
 
+    fn entry_key_bytes(&self, _metadata: &Metadata) -> Result<Vec<Vec<u8>>, Error> {
+        Ok(self
+            .storage_entry_keys
+            .iter()
+            .map(|entry| {
+                let mut bytes = Vec::new();
+                entry.to_bytes(&mut bytes);
+                bytes
+            })
+            .collect())
+    }
+
     fn validation_hash(&self) -> Option<[u8; 32]> {
         self.validation_hash
     }
+
+    fn map_keys(&self) -> Option<&[StorageMapKey]> {
+        Some(&self.storage_entry_keys)
+    }
 }
 
 /// This represents a dynamically generated storage address.
@@ -215,6 +251,30 @@ where
         metadata: &Metadata,
         bytes: &mut Vec<u8>,
     ) -> Result<(), Error> {
+        for key_bytes in self.hashed_keys(metadata, false)? {
+            bytes.extend(key_bytes);
+        }
+        Ok(())
+    }
+
+    // Some of the keys may be left off here, so that the prefix of a group of entries in a
+    // map can be found.
+    fn entry_key_bytes(&self, metadata: &Metadata) -> Result<Vec<Vec<u8>>, Error> {
+        self.hashed_keys(metadata, true)
+    }
+}
+
+impl<'a, Encodable> DynamicStorageAddress<'a, Encodable>
+where
+    Encodable: EncodeWithMetadata,
+{
+    // Encode and hash the keys given. If `allow_partial` is true, then fewer keys than the
+    // map has may be given, as long as each key has its own hasher.
+    fn hashed_keys(
+        &self,
+        metadata: &Metadata,
+        allow_partial: bool,
+    ) -> Result<Vec<Vec<u8>>, Error> {
         let pallet = metadata.pallet(&self.pallet_name)?;
         let storage = pallet.storage(&self.entry_name)?;
 
@@ -227,7 +287,7 @@ where
                     }
                     .into())
                 } else {
-                    Ok(())
+                    Ok(vec![])
                 }
             }
             StorageEntryType::Map { hashers, key, .. } => {
@@ -237,7 +297,7 @@ where
 
                 // If the key is a tuple, we encode each value to the corresponding tuple type.
                 // If the key is not a tuple, encode a single value to the key type.
-                let type_ids = match ty.type_def() {
+                let type_ids: Vec<u32> = match ty.type_def() {
                     TypeDef::Tuple(tuple) => {
                         tuple.fields().iter().map(|f| f.id()).collect()
                     }
@@ -246,7 +306,10 @@ where
                     }
                 };
 
-                if type_ids.len() != self.storage_entry_keys.len() {
+                let is_partial = self.storage_entry_keys.len() < type_ids.len()
+                    && allow_partial
+                    && hashers.len() == type_ids.len();
+                if type_ids.len() != self.storage_entry_keys.len() && !is_partial {
                     return Err(StorageAddressError::WrongNumberOfKeys {
                         expected: type_ids.len(),
                         actual: self.storage_entry_keys.len(),
@@ -260,18 +323,22 @@ where
                     for (key, type_id) in self.storage_entry_keys.iter().zip(type_ids) {
                         key.encode_with_metadata(type_id, metadata, &mut input)?;
                     }
-                    super::storage_map_key::hash_bytes(&input, &hashers[0], bytes);
-                    Ok(())
+                    let mut bytes = Vec::new();
+                    super::storage_map_key::hash_bytes(&input, &hashers[0], &mut bytes);
+                    Ok(vec![bytes])
                 } else if hashers.len() == type_ids.len() {
                     // A hasher per field; encode and hash each field independently.
+                    let mut all_bytes = Vec::new();
                     for ((key, type_id), hasher) in
                         self.storage_entry_keys.iter().zip(type_ids).zip(hashers)
                     {
                         let mut input = Vec::new();
                         key.encode_with_metadata(type_id, metadata, &mut input)?;
-                        super::storage_map_key::hash_bytes(&input, hasher, bytes);
+                        let mut bytes = Vec::new();
+                        super::storage_map_key::hash_bytes(&input, hasher, &mut bytes);
+                        all_bytes.push(bytes);
                     }
-                    Ok(())
+                    Ok(all_bytes)
                 } else {
                     // Mismatch; wrong number of hashers/fields.
                     Err(StorageAddressError::WrongNumberOfHashers {
@@ -3,8 +3,11 @@
 // see LICENSE for license details.
 
 use super::{
+    snapshot::SnapshotBuilder,
+    storage_address::Yes,
     storage_type::{
         validate_storage_address,
+        validate_storage_keys,
         Storage,
     },
     StorageAddress,
@@ -16,6 +19,7 @@ use crate::{
         OnlineClientT,
     },
     error::Error,
+    metadata::DecodeWithMetadata,
     Config,
 };
 use derivative::Derivative;
@@ -57,6 +61,28 @@ where
     ) -> Result<(), Error> {
         validate_storage_address(address, &self.client.metadata())
     }
+
+    /// Check that the keys of a storage address are hashed with the hashers that the
+    /// metadata expects, and that each key decodes as the key type in the metadata.
+    /// Returns a [`StorageAddressError::KeysDoNotMatch`](crate::error::StorageAddressError::KeysDoNotMatch)
+    /// listing every mismatch otherwise.
+    ///
+    /// An address whose hashers are out of date still computes a key, so reading from it
+    /// just finds nothing there. This isn't checked when fetching from an address, so call
+    /// this once for the generated addresses you use, for example at startup. Dynamic
+    /// addresses look their hashers up in the metadata, and so always pass.
+    pub fn validate_keys<Address: StorageAddress>(
+        &self,
+        address: &Address,
+    ) -> Result<(), Error> {
+        validate_storage_keys(address, &self.client.metadata())
+    }
+
+    /// Start building a [`SnapshotBuilder`], to read a number of storage entries and
+    /// constants at the same block.
+    pub fn snapshot(&self) -> SnapshotBuilder<T, Client> {
+        SnapshotBuilder::new(self.client.clone())
+    }
 }
 
 impl<T, Client> StorageClient<T, Client>
@@ -89,4 +115,92 @@ where
             Ok(Storage::new(client, block_hash))
         }
     }
+
+    /// Obtain storage at the current finalized block.
+    ///
+    /// The hash of the finalized block is fetched once, when this is called, and every read
+    /// made through the returned [`Storage`] is made at that block. This means that the reads
+    /// are consistent with each other even if another block is finalized in the meantime.
+    /// Call this again to read from a more recently finalized block.
+    pub fn at_finalized(
+        &self,
+    ) -> impl Future<Output = Result<Storage<T, Client>, Error>> + Send + 'static {
+        let client = self.client.clone();
+        async move {
+            let block_hash = client.rpc().finalized_head().await?;
+            Ok(Storage::new(client, block_hash))
+        }
+    }
+
+    /// Find each block between `from` and `to` (inclusive) at which the value at the given
+    /// address changed, along with the value that it changed to. The first change handed
+    /// back is the value at `from`. If `to` is `None`, changes up to the latest block are
+    /// returned.
+    ///
+    /// This uses the `state_queryStorage` RPC method, which is far cheaper than reading the
+    /// value at every block in the range. Values are decoded using the metadata that the
+    /// client currently holds, so the range shouldn't span runtime upgrades which change the
+    /// shape of the value.
+    ///
+    /// # Note
+    ///
+    /// This needs to be run against an archive node in order to look at blocks whose state
+    /// has been pruned. Nodes also limit the number of blocks that can be queried at once,
+    /// and return an error for larger ranges.
+    pub fn changes<'a, Address>(
+        &self,
+        address: &'a Address,
+        from: T::Hash,
+        to: Option<T::Hash>,
+    ) -> impl Future<
+        Output = Result<
+            Vec<StorageChange<T::Hash, <Address::Target as DecodeWithMetadata>::Target>>,
+            Error,
+        >,
+    > + 'a
+    where
+        Address: StorageAddress<IsFetchable = Yes> + 'a,
+    {
+        let client = self.client.clone();
+        async move {
+            let metadata = client.metadata();
+            validate_storage_address(address, &metadata)?;
+            let key = super::utils::storage_address_bytes(address, &metadata)?;
+
+            let change_sets = client.rpc().query_storage([&*key], from, to).await?;
+            let mut changes = Vec::with_capacity(change_sets.len());
+            for change_set in change_sets {
+                let Some((_, data)) =
+                    change_set.changes.into_iter().find(|(k, _)| k.0 == key)
+                else {
+                    continue
+                };
+                let value = data
+                    .map(|data| {
+                        <Address::Target as DecodeWithMetadata>::decode_storage_with_metadata(
+                            &mut &*data.0,
+                            address.pallet_name(),
+                            address.entry_name(),
+                            &metadata,
+                        )
+                    })
+                    .transpose()?;
+                changes.push(StorageChange {
+                    block_hash: change_set.block,
+                    value,
+                });
+            }
+            Ok(changes)
+        }
+    }
+}
+
+/// A change to a storage value at some block. This is returned from
+/// [`StorageClient::changes()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageChange<Hash, Value> {
+    /// The hash of the block at which the value changed.
+    pub block_hash: Hash,
+    /// The value that was changed to, or `None` if the value was removed.
+    pub value: Option<Value>,
 }
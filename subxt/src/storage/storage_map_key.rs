@@ -26,6 +26,16 @@ impl StorageMapKey {
         }
     }
 
+    /// The hasher that the key is hashed with.
+    pub fn hasher(&self) -> &StorageHasher {
+        &self.hasher
+    }
+
+    /// The SCALE encoded key, before it's hashed.
+    pub fn encoded_value(&self) -> &[u8] {
+        &self.value
+    }
+
     /// Convert this [`StorageMapKey`] into bytes and append them to some existing bytes.
     pub fn to_bytes(&self, bytes: &mut Vec<u8>) {
         hash_bytes(&self.value, &self.hasher, bytes)
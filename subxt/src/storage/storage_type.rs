@@ -2,16 +2,26 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::storage_address::{
-    StorageAddress,
-    Yes,
+use super::{
+    staking::concat_hash_len,
+    storage_address::{
+        StorageAddress,
+        Yes,
+    },
+    storage_map_key::StorageHasher,
 };
 use crate::{
     client::{
+        metadata_at_block,
         OfflineClientT,
         OnlineClientT,
     },
-    error::Error,
+    dynamic::DecodedValue,
+    error::{
+        Error,
+        StorageAddressError,
+        StorageKeyMismatch,
+    },
     metadata::{
         DecodeWithMetadata,
         Metadata,
@@ -22,10 +32,15 @@ use crate::{
     },
     Config,
 };
+use codec::DecodeAll;
 use derivative::Derivative;
 use frame_metadata::StorageEntryType;
-use scale_info::form::PortableForm;
+use scale_info::{
+    form::PortableForm,
+    TypeDef,
+};
 use std::{
+    collections::VecDeque,
     future::Future,
     marker::PhantomData,
 };
@@ -48,6 +63,11 @@ impl<T: Config, Client> Storage<T, Client> {
             _marker: PhantomData,
         }
     }
+
+    /// The client that storage is read with.
+    pub(super) fn client(&self) -> &Client {
+        &self.client
+    }
 }
 
 impl<T, Client> Storage<T, Client>
@@ -55,6 +75,11 @@ where
     T: Config,
     Client: OfflineClientT<T>,
 {
+    /// The hash of the block that storage is read at.
+    pub fn block_hash(&self) -> T::Hash {
+        self.block_hash
+    }
+
     /// Run the validation logic against some storage address you'd like to access.
     ///
     /// Method has the same meaning as [`StorageClient::validate`](super::storage_client::StorageClient::validate).
@@ -64,6 +89,16 @@ where
     ) -> Result<(), Error> {
         validate_storage_address(address, &self.client.metadata())
     }
+
+    /// Check the hashers and key types of a storage address against the metadata.
+    ///
+    /// Method has the same meaning as [`StorageClient::validate_keys`](super::storage_client::StorageClient::validate_keys).
+    pub fn validate_keys<Address: StorageAddress>(
+        &self,
+        address: &Address,
+    ) -> Result<(), Error> {
+        validate_storage_keys(address, &self.client.metadata())
+    }
 }
 
 impl<T, Client> Storage<T, Client>
@@ -127,26 +162,84 @@ where
     {
         let client = self.clone();
         async move {
-            // Metadata validation checks whether the static address given
-            // is likely to actually correspond to a real storage entry or not.
-            // if not, it means static codegen doesn't line up with runtime
-            // metadata.
-            client.validate(address)?;
-
-            // Look up the return type ID to enable DecodeWithMetadata:
             let metadata = client.client.metadata();
-            let lookup_bytes = super::utils::storage_address_bytes(address, &metadata)?;
-            if let Some(data) = client.fetch_raw(&lookup_bytes).await? {
-                let val = <Address::Target as DecodeWithMetadata>::decode_storage_with_metadata(
+            client.fetch_with_metadata(address, &metadata).await
+        }
+    }
+
+    /// Fetch a decoded value from storage at a given address, decoding it using the
+    /// metadata of the runtime that was active at this block rather than the metadata
+    /// the client currently holds.
+    ///
+    /// This is useful when reading storage across a range of historic blocks which spans
+    /// runtime upgrades. The metadata for each runtime version is fetched on first use
+    /// and cached by the client, so subsequent reads at blocks with the same runtime
+    /// version don't need to download it again.
+    pub fn fetch_versioned<'a, Address>(
+        &self,
+        address: &'a Address,
+    ) -> impl Future<
+        Output = Result<Option<<Address::Target as DecodeWithMetadata>::Target>, Error>,
+    > + 'a
+    where
+        Address: StorageAddress<IsFetchable = Yes> + 'a,
+    {
+        let client = self.clone();
+        async move {
+            let metadata = metadata_at_block(&client.client, client.block_hash).await?;
+            client.fetch_with_metadata(address, &metadata).await
+        }
+    }
+
+    /// Fetch and decode a value at the given address using the [`Metadata`] provided.
+    async fn fetch_with_metadata<Address>(
+        &self,
+        address: &Address,
+        metadata: &Metadata,
+    ) -> Result<Option<<Address::Target as DecodeWithMetadata>::Target>, Error>
+    where
+        Address: StorageAddress<IsFetchable = Yes>,
+    {
+        // Metadata validation checks whether the static address given
+        // is likely to actually correspond to a real storage entry or not.
+        // if not, it means static codegen doesn't line up with runtime
+        // metadata.
+        validate_storage_address(address, metadata)?;
+
+        // Look up the return type ID to enable DecodeWithMetadata:
+        let lookup_bytes = super::utils::storage_address_bytes(address, metadata)?;
+        if let Some(data) = self.fetch_raw(&lookup_bytes).await? {
+            let val =
+                <Address::Target as DecodeWithMetadata>::decode_storage_with_metadata(
                     &mut &*data,
                     address.pallet_name(),
                     address.entry_name(),
-                    &metadata,
+                    metadata,
                 )?;
-                Ok(Some(val))
-            } else {
-                Ok(None)
-            }
+            Ok(Some(val))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetch the length in bytes of the SCALE encoded value at the given address, without
+    /// fetching the value itself. This returns `None` if there is no value at the address.
+    ///
+    /// This is useful for deciding whether a potentially large value is worth fetching.
+    pub fn fetch_size<'a, Address>(
+        &self,
+        address: &'a Address,
+    ) -> impl Future<Output = Result<Option<u64>, Error>> + 'a
+    where
+        Address: StorageAddress<IsFetchable = Yes> + 'a,
+    {
+        let client = self.client.clone();
+        let block_hash = self.block_hash;
+        async move {
+            let metadata = client.metadata();
+            validate_storage_address(address, &metadata)?;
+            let key = super::utils::storage_address_bytes(address, &metadata)?;
+            client.rpc().storage_size(&key, Some(block_hash)).await
         }
     }
 
@@ -186,6 +279,46 @@ where
         }
     }
 
+    // Fetch every entry of a map keyed by a `u32` index, decoding the values dynamically.
+    pub(super) async fn index_keyed_entries(
+        self,
+        pallet_name: &'static str,
+        entry_name: &'static str,
+        page_size: u32,
+    ) -> Result<Vec<(u32, DecodedValue)>, Error> {
+        let metadata = self.client.metadata();
+        let (hasher, return_type_id) =
+            single_key_map(&metadata, pallet_name, entry_name)?;
+        let index_offset = 32 + concat_hash_len(&hasher).ok_or_else(|| {
+            Error::Other(format!(
+                "Cannot find the indexes in {pallet_name}::{entry_name} keys hashed with {hasher:?}"
+            ))
+        })?;
+
+        let mut iter = KeyIter::<T, Client, DecodedValue>::new(
+            self,
+            map_prefix(pallet_name, entry_name),
+            page_size,
+            metadata,
+            return_type_id,
+        );
+        let mut entries = Vec::new();
+        while let Some((key, value)) = iter.next().await? {
+            let index = key
+                .0
+                .get(index_offset..)
+                .and_then(|mut bytes| u32::decode_all(&mut bytes).ok())
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not find an index in the storage key 0x{}",
+                        hex::encode(&key.0)
+                    ))
+                })?;
+            entries.push((index, value));
+        }
+        Ok(entries)
+    }
+
     /// Fetch up to `count` keys for a storage map in lexicographic order.
     ///
     /// Supports pagination by passing a value to `start_key`.
@@ -246,7 +379,6 @@ where
         Address: StorageAddress<IsIterable = Yes> + 'static,
     {
         let client = self.clone();
-        let block_hash = self.block_hash;
         async move {
             // Metadata validation checks whether the static address given
             // is likely to actually correspond to a real storage entry or not.
@@ -269,34 +401,147 @@ where
             let address_root_bytes = super::utils::storage_address_root_bytes(&address);
 
             Ok(KeyIter {
-                client,
-                address_root_bytes,
+                keys: StorageKeyIter::new(client, address_root_bytes, page_size),
                 metadata,
                 return_type_id,
-                block_hash,
-                count: page_size,
-                start_key: None,
                 buffer: Default::default(),
                 _marker: std::marker::PhantomData,
             })
         }
     }
+
+    /// Fetch every entry in a storage map at once, using the `state_getPairs` RPC method,
+    /// and decode the values.
+    ///
+    /// This is a convenience for small maps, and for nodes that are run locally: the node
+    /// reads the whole map to answer, so many public nodes refuse the call, and those that
+    /// don't may time out on large maps. Use [`Storage::iter()`], which fetches a page at a
+    /// time, in anything that's run against a public node.
+    pub fn fetch_pairs<Address>(
+        &self,
+        address: Address,
+    ) -> impl Future<
+        Output = Result<
+            Vec<(StorageKey, <Address::Target as DecodeWithMetadata>::Target)>,
+            Error,
+        >,
+    > + 'static
+    where
+        Address: StorageAddress<IsIterable = Yes> + 'static,
+    {
+        let client = self.clone();
+        async move {
+            client.validate(&address)?;
+            let metadata = client.client.metadata();
+            let return_type_id = lookup_storage_return_type(
+                &metadata,
+                address.pallet_name(),
+                address.entry_name(),
+            )?;
+            let address_root_bytes = super::utils::storage_address_root_bytes(&address);
+            let pairs = client
+                .client
+                .rpc()
+                .storage_pairs(&address_root_bytes, Some(client.block_hash))
+                .await?;
+            pairs
+                .into_iter()
+                .map(|(key, value)| {
+                    let value = Address::Target::decode_with_metadata(
+                        &mut &value.0[..],
+                        return_type_id,
+                        &metadata,
+                    )?;
+                    Ok((key, value))
+                })
+                .collect()
+        }
+    }
+
+    /// Returns an iterator over the keys in a storage map, without fetching the
+    /// corresponding values. Keys are fetched `page_size` at a time.
+    ///
+    /// This needs one round trip per page, whereas [`Storage::iter()`] needs two, so prefer
+    /// this if only the keys are needed.
+    pub fn iter_keys<Address>(
+        &self,
+        address: Address,
+        page_size: u32,
+    ) -> impl Future<Output = Result<StorageKeyIter<T, Client>, Error>> + 'static
+    where
+        Address: StorageAddress<IsIterable = Yes> + 'static,
+    {
+        let client = self.clone();
+        async move {
+            client.validate(&address)?;
+            let address_root_bytes = super::utils::storage_address_root_bytes(&address);
+            Ok(StorageKeyIter::new(client, address_root_bytes, page_size))
+        }
+    }
+}
+
+// The hasher and value type of a storage map with a single key.
+pub(super) fn single_key_map(
+    metadata: &Metadata,
+    pallet_name: &str,
+    entry_name: &str,
+) -> Result<(StorageHasher, u32), Error> {
+    let entry = metadata.pallet(pallet_name)?.storage(entry_name)?;
+    match &entry.ty {
+        StorageEntryType::Map { hashers, value, .. } if hashers.len() == 1 => {
+            Ok((hashers[0].clone(), value.id()))
+        }
+        _ => {
+            Err(Error::Other(format!(
+                "{pallet_name}::{entry_name} storage is not a map with one hasher"
+            )))
+        }
+    }
+}
+
+// The start of every key in a storage map.
+pub(super) fn map_prefix(pallet_name: &str, entry_name: &str) -> Vec<u8> {
+    let mut prefix = Vec::new();
+    prefix.extend(sp_core_hashing::twox_128(pallet_name.as_bytes()));
+    prefix.extend(sp_core_hashing::twox_128(entry_name.as_bytes()));
+    prefix
 }
 
 /// Iterates over key value pairs in a map.
+///
+/// For each page of keys, the values are fetched in a single `state_queryStorageAt` call.
 pub struct KeyIter<T: Config, Client, ReturnTy> {
-    client: Storage<T, Client>,
-    address_root_bytes: Vec<u8>,
+    keys: StorageKeyIter<T, Client>,
     return_type_id: u32,
     metadata: Metadata,
-    count: u32,
-    block_hash: T::Hash,
-    start_key: Option<StorageKey>,
     buffer: Vec<(StorageKey, StorageData)>,
     _marker: std::marker::PhantomData<ReturnTy>,
 }
 
-impl<'a, T, Client, ReturnTy> KeyIter<T, Client, ReturnTy>
+impl<T, Client, ReturnTy> KeyIter<T, Client, ReturnTy>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    // Iterate over every entry whose key starts with the prefix given.
+    pub(super) fn new(
+        client: Storage<T, Client>,
+        prefix: Vec<u8>,
+        page_size: u32,
+        metadata: Metadata,
+        return_type_id: u32,
+    ) -> Self {
+        KeyIter {
+            keys: StorageKeyIter::new(client, prefix, page_size),
+            metadata,
+            return_type_id,
+            buffer: Default::default(),
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T, Client, ReturnTy> KeyIter<T, Client, ReturnTy>
 where
     T: Config,
     Client: OnlineClientT<T>,
@@ -315,27 +560,20 @@ where
                 )?;
                 return Ok(Some((k, val)))
             } else {
-                let start_key = self.start_key.take();
-                let keys = self
-                    .client
-                    .fetch_keys(
-                        &self.address_root_bytes,
-                        self.count,
-                        start_key.as_ref().map(|k| &*k.0),
-                    )
-                    .await?;
-
+                let keys = self.keys.next_page().await?;
                 if keys.is_empty() {
                     return Ok(None)
                 }
 
-                self.start_key = keys.last().cloned();
-
                 let change_sets = self
+                    .keys
                     .client
                     .client
                     .rpc()
-                    .query_storage_at(keys.iter().map(|k| &*k.0), Some(self.block_hash))
+                    .query_storage_at(
+                        keys.iter().map(|k| &*k.0),
+                        Some(self.keys.client.block_hash),
+                    )
                     .await?;
                 for change_set in change_sets {
                     for (k, v) in change_set.changes {
@@ -350,6 +588,106 @@ where
     }
 }
 
+/// Iterates over the keys in a map.
+pub struct StorageKeyIter<T: Config, Client> {
+    client: Storage<T, Client>,
+    // The prefixed key of the child trie that the keys are in, if they're not in the
+    // main trie.
+    child_key: Option<Vec<u8>>,
+    address_root_bytes: Vec<u8>,
+    count: u32,
+    start_key: Option<StorageKey>,
+    finished: bool,
+    buffer: VecDeque<StorageKey>,
+}
+
+impl<T, Client> StorageKeyIter<T, Client>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    fn new(client: Storage<T, Client>, address_root_bytes: Vec<u8>, count: u32) -> Self {
+        StorageKeyIter {
+            client,
+            child_key: None,
+            address_root_bytes,
+            count,
+            start_key: None,
+            finished: false,
+            buffer: Default::default(),
+        }
+    }
+
+    // Iterate over the keys with the given prefix in a child trie instead.
+    pub(super) fn new_in_child_trie(
+        client: Storage<T, Client>,
+        child_key: Vec<u8>,
+        prefix: Vec<u8>,
+        count: u32,
+    ) -> Self {
+        StorageKeyIter {
+            child_key: Some(child_key),
+            ..StorageKeyIter::new(client, prefix, count)
+        }
+    }
+
+    /// Returns the next key from the map.
+    pub async fn next(&mut self) -> Result<Option<StorageKey>, Error> {
+        if self.buffer.is_empty() {
+            self.buffer = self.next_page().await?.into();
+        }
+        Ok(self.buffer.pop_front())
+    }
+
+    // Fetch the next page of keys, returning an empty page once there are no more.
+    async fn next_page(&mut self) -> Result<Vec<StorageKey>, Error> {
+        if self.finished {
+            return Ok(Vec::new())
+        }
+
+        let start_key = self.start_key.take();
+        let start_key_bytes = start_key.as_ref().map(|k| &*k.0);
+        // Some nodes include the start key in the page they return, so ask for one more
+        // key than we want in that case; otherwise a page of them could hold nothing new.
+        let count = match start_key {
+            Some(_) => self.count.saturating_add(1),
+            None => self.count,
+        };
+        let mut keys = match &self.child_key {
+            Some(child_key) => {
+                self.client
+                    .client
+                    .rpc()
+                    .child_storage_keys_paged(
+                        child_key,
+                        &self.address_root_bytes,
+                        count,
+                        start_key_bytes,
+                        Some(self.client.block_hash),
+                    )
+                    .await?
+            }
+            None => {
+                self.client
+                    .fetch_keys(&self.address_root_bytes, count, start_key_bytes)
+                    .await?
+            }
+        };
+
+        // Drop anything we've already seen. A page may contain fewer keys than were
+        // asked for even when there are more to come, so we only stop once we get back
+        // a page with no new keys in it, which (since we asked for one more key than the
+        // start key) means that there are none left.
+        if let Some(start_key) = &start_key {
+            keys.retain(|key| key > start_key);
+        }
+
+        self.start_key = keys.last().cloned();
+        self.finished = keys.is_empty();
+        Ok(keys)
+    }
+}
+
 /// Validate a storage address against the metadata.
 pub(crate) fn validate_storage_address<Address: StorageAddress>(
     address: &Address,
@@ -361,6 +699,83 @@ pub(crate) fn validate_storage_address<Address: StorageAddress>(
     Ok(())
 }
 
+/// Check that the keys of a storage address are hashed with the hashers in the metadata,
+/// and that each decodes as its key type in the metadata. Addresses which don't choose
+/// their own hashers always pass.
+pub(crate) fn validate_storage_keys<Address: StorageAddress>(
+    address: &Address,
+    metadata: &Metadata,
+) -> Result<(), Error> {
+    let Some(keys) = address.map_keys() else {
+        return Ok(())
+    };
+    let pallet_name = address.pallet_name();
+    let entry_name = address.entry_name();
+    let (hashers, type_ids) = match &metadata.pallet(pallet_name)?.storage(entry_name)?.ty
+    {
+        StorageEntryType::Plain(_) => (vec![], vec![]),
+        StorageEntryType::Map { hashers, key, .. } if hashers.len() == 1 => {
+            (hashers.clone(), vec![key.id()])
+        }
+        StorageEntryType::Map { hashers, key, .. } => {
+            let ty = metadata
+                .resolve_type(key.id())
+                .ok_or_else(|| StorageAddressError::TypeNotFound(key.id()))?;
+            let type_ids: Vec<u32> = match ty.type_def() {
+                TypeDef::Tuple(tuple) => tuple.fields().iter().map(|f| f.id()).collect(),
+                _ => vec![key.id()],
+            };
+            if type_ids.len() != hashers.len() {
+                return Err(StorageAddressError::WrongNumberOfHashers {
+                    hashers: hashers.len(),
+                    fields: type_ids.len(),
+                }
+                .into())
+            }
+            (hashers.clone(), type_ids)
+        }
+    };
+
+    let mut mismatches = Vec::new();
+    if keys.len() > hashers.len() {
+        mismatches.push(StorageKeyMismatch::NumberOfKeys {
+            expected: hashers.len(),
+            actual: keys.len(),
+        });
+    }
+    // Fewer keys than hashers is fine; the address points at a group of map entries.
+    for (index, (key, (hasher, type_id))) in keys
+        .iter()
+        .zip(hashers.into_iter().zip(type_ids))
+        .enumerate()
+    {
+        if *key.hasher() != hasher {
+            mismatches.push(StorageKeyMismatch::Hasher {
+                index,
+                expected: hasher,
+                actual: key.hasher().clone(),
+            });
+        }
+        let mut bytes = key.encoded_value();
+        let decoded =
+            scale_value::scale::decode_as_type(&mut bytes, type_id, metadata.types());
+        if decoded.is_err() || !bytes.is_empty() {
+            mismatches.push(StorageKeyMismatch::KeyType { index, type_id });
+        }
+    }
+
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(StorageAddressError::KeysDoNotMatch {
+            pallet_name: pallet_name.into(),
+            entry_name: entry_name.into(),
+            mismatches,
+        }
+        .into())
+    }
+}
+
 /// Validate a storage entry against the metadata.
 fn validate_storage(
     pallet_name: &str,
@@ -385,7 +800,7 @@ fn validate_storage(
 }
 
 /// look up a return type ID for some storage entry.
-fn lookup_storage_return_type(
+pub(super) fn lookup_storage_return_type(
     metadata: &Metadata,
     pallet: &str,
     entry: &str,
@@ -396,9 +811,208 @@ fn lookup_storage_return_type(
 }
 
 /// Fetch the return type out of a [`StorageEntryType`].
-fn return_type_from_storage_entry_type(entry: &StorageEntryType<PortableForm>) -> u32 {
+pub(crate) fn return_type_from_storage_entry_type(
+    entry: &StorageEntryType<PortableForm>,
+) -> u32 {
     match entry {
         StorageEntryType::Plain(ty) => ty.id(),
         StorageEntryType::Map { value, .. } => value.id(),
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::client_with_keys;
+
+    #[tokio::test]
+    async fn key_iteration_dedupes_boundary_keys() {
+        let address = crate::dynamic::storage_root("System", "BlockHash");
+        let root = super::super::utils::storage_address_root_bytes(&address);
+        let keys: Vec<Vec<u8>> = (0u8..5)
+            .map(|n| root.iter().copied().chain([n]).collect())
+            .collect();
+
+        let client = client_with_keys(keys.clone());
+        // Ask for more keys per page than the node will give us.
+        let mut iter = client
+            .storage()
+            .at(Some(Default::default()))
+            .await
+            .unwrap()
+            .iter(address, 10)
+            .await
+            .unwrap();
+
+        let mut seen = Vec::new();
+        while let Some((key, _)) = iter.next().await.unwrap() {
+            seen.push(key.0);
+        }
+        seen.sort();
+        assert_eq!(seen, keys);
+    }
+
+    #[tokio::test]
+    async fn key_iteration_with_pages_of_one_key() {
+        let address = crate::dynamic::storage_root("System", "BlockHash");
+        let root = super::super::utils::storage_address_root_bytes(&address);
+        let keys: Vec<Vec<u8>> = (0u8..5)
+            .map(|n| root.iter().copied().chain([n]).collect())
+            .collect();
+
+        // Each page after the first holds the start key again, which we've already seen.
+        let client = client_with_keys(keys.clone());
+        let mut iter = client
+            .storage()
+            .at(Some(Default::default()))
+            .await
+            .unwrap()
+            .iter_keys(address, 1)
+            .await
+            .unwrap();
+
+        let mut seen = Vec::new();
+        while let Some(key) = iter.next().await.unwrap() {
+            seen.push(key.0);
+        }
+        assert_eq!(seen, keys);
+    }
+
+    #[tokio::test]
+    async fn key_only_iteration_dedupes_boundary_keys() {
+        let address = crate::dynamic::storage_root("System", "BlockHash");
+        let root = super::super::utils::storage_address_root_bytes(&address);
+        let keys: Vec<Vec<u8>> = (0u8..5)
+            .map(|n| root.iter().copied().chain([n]).collect())
+            .collect();
+
+        let client = client_with_keys(keys.clone());
+        let mut iter = client
+            .storage()
+            .at(Some(Default::default()))
+            .await
+            .unwrap()
+            .iter_keys(address, 10)
+            .await
+            .unwrap();
+
+        let mut seen = Vec::new();
+        while let Some(key) = iter.next().await.unwrap() {
+            seen.push(key.0);
+        }
+        assert_eq!(seen, keys);
+    }
+
+    #[tokio::test]
+    async fn fetches_and_decodes_all_pairs_at_once() {
+        let address = crate::dynamic::storage_root("System", "BlockHash");
+        let root = super::super::utils::storage_address_root_bytes(&address);
+        let keys: Vec<Vec<u8>> = (0u8..3)
+            .map(|n| root.iter().copied().chain([n]).collect())
+            .collect();
+
+        let client = client_with_keys(keys.clone());
+        let pairs = client
+            .storage()
+            .at(Some(Default::default()))
+            .await
+            .unwrap()
+            .fetch_pairs(address)
+            .await
+            .unwrap();
+
+        assert_eq!(pairs.len(), 3);
+        for (n, (key, value)) in pairs.into_iter().enumerate() {
+            assert_eq!(key.0, keys[n]);
+            assert_eq!(value.encoded(), &[n as u8; 32]);
+            assert!(value.to_value().is_ok());
+        }
+    }
+
+    #[test]
+    fn storage_keys_are_checked_against_the_metadata() {
+        use crate::{
+            error::{
+                StorageAddressError,
+                StorageKeyMismatch,
+            },
+            storage::address::{
+                StaticStorageAddress,
+                StorageHasher,
+                StorageMapKey,
+            },
+            Error,
+        };
+
+        let client = client_with_keys(vec![]);
+        let validate = |pallet, entry, keys| {
+            let address =
+                StaticStorageAddress::<crate::dynamic::DecodedValue, (), (), ()>::new(
+                    pallet, entry, keys, [0; 32],
+                );
+            client.storage().validate_keys(&address)
+        };
+        let mismatches = |res: Result<(), Error>| {
+            match res {
+                Err(Error::StorageAddress(StorageAddressError::KeysDoNotMatch {
+                    mismatches,
+                    ..
+                })) => mismatches,
+                res => panic!("expected the keys not to match, got {res:?}"),
+            }
+        };
+        let account = [1u8; 32];
+
+        // System::Account is keyed by an AccountId32 hashed with Blake2_128Concat:
+        let key = |hasher| StorageMapKey::new(account, hasher);
+        assert!(validate("System", "Account", vec![]).is_ok());
+        assert!(validate(
+            "System",
+            "Account",
+            vec![key(StorageHasher::Blake2_128Concat)]
+        )
+        .is_ok());
+        assert_eq!(
+            mismatches(validate(
+                "System",
+                "Account",
+                vec![key(StorageHasher::Twox64Concat)]
+            )),
+            [StorageKeyMismatch::Hasher {
+                index: 0,
+                expected: StorageHasher::Blake2_128Concat,
+                actual: StorageHasher::Twox64Concat,
+            }]
+        );
+        assert!(matches!(
+            mismatches(validate(
+                "System",
+                "Account",
+                vec![StorageMapKey::new(1u8, StorageHasher::Blake2_128Concat)]
+            ))[..],
+            [StorageKeyMismatch::KeyType { index: 0, .. }]
+        ));
+
+        // Staking::ErasStakers has a Twox64Concat hasher for each of its two keys:
+        let era = StorageMapKey::new(5u32, StorageHasher::Twox64Concat);
+        let stash = StorageMapKey::new(account, StorageHasher::Twox64Concat);
+        assert!(validate("Staking", "ErasStakers", vec![era.clone()]).is_ok());
+        assert!(
+            validate("Staking", "ErasStakers", vec![era.clone(), stash.clone()]).is_ok()
+        );
+        assert!(matches!(
+            mismatches(validate(
+                "Staking",
+                "ErasStakers",
+                vec![stash.clone(), era.clone(), era]
+            ))[..],
+            [
+                StorageKeyMismatch::NumberOfKeys {
+                    expected: 2,
+                    actual: 3
+                },
+                StorageKeyMismatch::KeyType { index: 0, .. },
+                StorageKeyMismatch::KeyType { index: 1, .. },
+            ]
+        ));
+    }
+}
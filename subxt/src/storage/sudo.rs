@@ -0,0 +1,101 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Read who holds the sudo key of the `Sudo` pallet.
+
+use super::Storage;
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    Config,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use std::future::Future;
+
+/// Fetch the account which can make calls using the `Sudo` pallet, from `Sudo::Key`.
+/// This is `None` if no account holds the sudo key.
+///
+/// This fails if the runtime has no `Sudo` pallet, which is the case for most chains
+/// other than development and test networks.
+pub fn sudo_key<T, Client>(
+    storage: &Storage<T, Client>,
+) -> impl Future<Output = Result<Option<T::AccountId>, Error>> + 'static
+where
+    T: Config,
+    T::AccountId: Decode,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let Some(bytes) = sudo_key_bytes(&storage).await? else {
+            return Ok(None)
+        };
+        Ok(Some(T::AccountId::decode(&mut &*bytes)?))
+    }
+}
+
+/// Check whether some account holds the sudo key. See [`sudo_key()`].
+pub fn is_sudo<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<bool, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let key = sudo_key_bytes(&storage).await?;
+        Ok(key.as_deref() == Some(&*account.encode()))
+    }
+}
+
+// Fetch the encoded account ID in `Sudo::Key`, if there is one.
+async fn sudo_key_bytes<T, Client>(
+    storage: &Storage<T, Client>,
+) -> Result<Option<Vec<u8>>, Error>
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let metadata = storage.client().metadata();
+    if metadata
+        .pallet("Sudo")
+        .and_then(|pallet| pallet.storage("Key"))
+        .is_err()
+    {
+        return Err(Error::Other(
+            "The runtime has no Sudo pallet, so nobody holds a sudo key".into(),
+        ))
+    }
+    let address = crate::dynamic::storage_root("Sudo", "Key");
+    let key = storage.fetch(&address).await?;
+    Ok(key.map(|key| key.into_encoded()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        online_client,
+        MockRpc,
+    };
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn needs_a_sudo_pallet() {
+        // Polkadot has no Sudo pallet, so we fail before asking the node for anything.
+        let client = online_client(Arc::new(MockRpc::new(|method, _| {
+            panic!("unexpected method {method}")
+        })));
+        let storage = client.storage().at(Some(Default::default())).await.unwrap();
+        let err = sudo_key(&storage).await.unwrap_err();
+        assert!(err.to_string().contains("no Sudo pallet"), "{err}");
+        let account = crate::utils::AccountId32([1; 32]);
+        assert!(is_sudo(&storage, &account).await.is_err());
+    }
+}
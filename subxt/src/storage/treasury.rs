@@ -0,0 +1,340 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Read the proposals and spends of `pallet-treasury`.
+
+use super::{
+    utils::{
+        value_as_bytes,
+        value_as_u128,
+        value_as_u32,
+    },
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    dynamic::Value,
+    error::Error,
+    Config,
+};
+use scale_value::{
+    At,
+    Composite,
+    ValueDef,
+};
+use std::future::Future;
+
+/// A proposal to pay some balance out of the treasury, as stored in `Treasury::Proposals`
+/// and keyed by its index. Accounts are handed back as their SCALE encoded bytes.
+///
+/// Anybody could propose a payment to some account by reserving a bond, and proposals that
+/// were approved are listed in `Treasury::Approvals` until they're paid out at the end of
+/// the spend period. Every version of the pallet has proposals, though newer versions no
+/// longer take new ones, and pay out using [`TreasurySpend`]s instead.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TreasuryProposal {
+    /// The account which made the proposal.
+    pub proposer: Vec<u8>,
+    /// The balance to pay out.
+    pub value: u128,
+    /// The account to pay the balance to.
+    pub beneficiary: Vec<u8>,
+    /// The balance reserved from the proposer, which is lost if the proposal is rejected.
+    pub bond: u128,
+}
+
+impl TreasuryProposal {
+    /// Attempt to interpret a dynamically decoded `Proposal` as a [`TreasuryProposal`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<TreasuryProposal> {
+        Some(TreasuryProposal {
+            proposer: value.at("proposer").and_then(value_as_bytes)?,
+            value: value.at("value").and_then(value_as_u128)?,
+            beneficiary: value.at("beneficiary").and_then(value_as_bytes)?,
+            bond: value.at("bond").and_then(value_as_u128)?,
+        })
+    }
+}
+
+/// An approved spend from the treasury, as stored in `Treasury::Spends` and keyed by its
+/// index. The number of spends ever made is in `Treasury::SpendCount`.
+///
+/// Spends were added to the pallet along with the `spend` call which pays out assets other
+/// than the native token, and runtimes from before then have neither storage item. A spend
+/// is approved when it's made, and is then claimed by calling `payout` between its
+/// `valid_from` and `expire_at` blocks.
+///
+/// The kind of asset and the beneficiary are types that each runtime configures (Polkadot,
+/// for instance, uses versioned XCM locations for both), so they're handed back as they
+/// were decoded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreasurySpend {
+    /// The asset to pay out.
+    pub asset_kind: Value,
+    /// The amount of the asset to pay out.
+    pub amount: u128,
+    /// Who to pay the asset to.
+    pub beneficiary: Value,
+    /// The block from which the spend can be claimed.
+    pub valid_from: u64,
+    /// The block after which the spend can no longer be claimed.
+    pub expire_at: u64,
+    /// Whether paying out the spend has been attempted.
+    pub status: SpendPaymentState,
+}
+
+/// Where paying out a [`TreasurySpend`] has got to.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpendPaymentState {
+    /// The spend hasn't been claimed yet.
+    Pending,
+    /// The payment has been made, and it's waiting to be checked on. The ID of the
+    /// payment is specific to how the runtime pays out assets.
+    Attempted {
+        /// The ID of the payment.
+        id: Value,
+    },
+    /// The payment failed, and can be attempted again.
+    Failed,
+}
+
+impl TreasurySpend {
+    /// Attempt to interpret a dynamically decoded `SpendStatus` as a [`TreasurySpend`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T: Clone>(value: &Value<T>) -> Option<TreasurySpend> {
+        let block_number = |name| {
+            value
+                .at(name)
+                .and_then(value_as_u128)
+                .and_then(|n| u64::try_from(n).ok())
+        };
+        let status = match &value.at("status")?.value {
+            ValueDef::Variant(v) => {
+                match v.name.as_str() {
+                    "Pending" => SpendPaymentState::Pending,
+                    "Failed" => SpendPaymentState::Failed,
+                    "Attempted" => {
+                        let id = match &v.values {
+                            Composite::Named(fields) => {
+                                fields.iter().find(|(name, _)| name == "id")?.1.clone()
+                            }
+                            Composite::Unnamed(fields) => fields.first()?.clone(),
+                        };
+                        SpendPaymentState::Attempted {
+                            id: id.remove_context(),
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        Some(TreasurySpend {
+            asset_kind: value.at("asset_kind")?.clone().remove_context(),
+            amount: value.at("amount").and_then(value_as_u128)?,
+            beneficiary: value.at("beneficiary")?.clone().remove_context(),
+            valid_from: block_number("valid_from")?,
+            expire_at: block_number("expire_at")?,
+            status,
+        })
+    }
+}
+
+/// Interpret the value stored in `Treasury::Approvals`, which is a list of the indexes of
+/// approved proposals.
+pub(crate) fn approvals_from_value<T>(value: &Value<T>) -> Option<Vec<u32>> {
+    super::utils::list_from_value(value, |v| {
+        value_as_u128(v).and_then(|n| u32::try_from(n).ok())
+    })
+}
+
+/// Fetch every proposal in `Treasury::Proposals`, along with its index, `page_size` at
+/// a time and in no particular order. See [`TreasuryProposal`] for which runtimes have
+/// proposals.
+pub fn treasury_proposals<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<(u32, TreasuryProposal)>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let entries = storage
+            .index_keyed_entries("Treasury", "Proposals", page_size)
+            .await?;
+        entries
+            .into_iter()
+            .map(|(index, value)| {
+                let proposal = TreasuryProposal::from_value(&value).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not interpret {value} as a treasury proposal"
+                    ))
+                })?;
+                Ok((index, proposal))
+            })
+            .collect()
+    }
+}
+
+/// Fetch the indexes of the approved proposals in `Treasury::Approvals`, which are
+/// paid out at the end of the current spend period.
+pub fn treasury_approvals<T, Client>(
+    storage: &Storage<T, Client>,
+) -> impl Future<Output = Result<Vec<u32>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage_root("Treasury", "Approvals");
+        let approvals = storage.fetch_or_default(&address).await?.to_value()?;
+        approvals_from_value(&approvals).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not interpret {approvals} as a list of treasury approvals"
+            ))
+        })
+    }
+}
+
+/// Fetch every spend in `Treasury::Spends`, along with its index, `page_size` at a time
+/// and in no particular order. Spends are removed once they're paid out or expire.
+///
+/// This fails for runtimes from before spends were added to the treasury pallet; see
+/// [`TreasurySpend`].
+pub fn treasury_spends<T, Client>(
+    storage: &Storage<T, Client>,
+    page_size: u32,
+) -> impl Future<Output = Result<Vec<(u32, TreasurySpend)>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let entries = storage
+            .index_keyed_entries("Treasury", "Spends", page_size)
+            .await?;
+        entries
+            .into_iter()
+            .map(|(index, value)| {
+                let spend = TreasurySpend::from_value(&value).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not interpret {value} as a treasury spend"
+                    ))
+                })?;
+                Ok((index, spend))
+            })
+            .collect()
+    }
+}
+
+/// Fetch the number of spends that have ever been made from the treasury, from
+/// `Treasury::SpendCount`. This is also the index that the next spend will be given.
+///
+/// Like [`treasury_spends()`], this fails for runtimes without spends.
+pub fn treasury_spend_count<T, Client>(
+    storage: &Storage<T, Client>,
+) -> impl Future<Output = Result<u32, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage_root("Treasury", "SpendCount");
+        let count = storage.fetch_or_default(&address).await?.to_value()?;
+        value_as_u32(&count).ok_or_else(|| {
+            Error::Other(format!("Could not interpret {count} as a spend count"))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        decode,
+        AccountId32,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[derive(Encode, TypeInfo)]
+    struct Proposal {
+        proposer: AccountId32,
+        value: u128,
+        beneficiary: AccountId32,
+        bond: u128,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    enum AssetKind {
+        Native,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum PaymentState {
+        Pending,
+        Attempted { id: u64 },
+        Failed,
+    }
+
+    #[derive(Encode, TypeInfo)]
+    struct SpendStatus {
+        asset_kind: AssetKind,
+        amount: u128,
+        beneficiary: AccountId32,
+        valid_from: u32,
+        expire_at: u32,
+        status: PaymentState,
+    }
+
+    #[test]
+    fn decodes_proposals_and_approvals() {
+        let proposal = decode(Proposal {
+            proposer: AccountId32([1; 32]),
+            value: 1000,
+            beneficiary: AccountId32([2; 32]),
+            bond: 50,
+        });
+        assert_eq!(
+            TreasuryProposal::from_value(&proposal),
+            Some(TreasuryProposal {
+                proposer: vec![1; 32],
+                value: 1000,
+                beneficiary: vec![2; 32],
+                bond: 50,
+            })
+        );
+
+        let approvals = decode(BoundedVec(vec![3u32, 7]));
+        assert_eq!(approvals_from_value(&approvals), Some(vec![3, 7]));
+    }
+
+    #[test]
+    fn decodes_spends() {
+        let spend = decode(SpendStatus {
+            asset_kind: AssetKind::Native,
+            amount: 500,
+            beneficiary: AccountId32([3; 32]),
+            valid_from: 10,
+            expire_at: 20,
+            status: PaymentState::Attempted { id: 9 },
+        });
+        let spend = TreasurySpend::from_value(&spend).unwrap();
+        assert_eq!(spend.amount, 500);
+        assert_eq!(spend.valid_from, 10);
+        assert_eq!(spend.expire_at, 20);
+        assert_eq!(spend.asset_kind, Value::unnamed_variant("Native", []));
+        assert_eq!(
+            spend.status,
+            SpendPaymentState::Attempted { id: Value::u128(9) }
+        );
+    }
+}
@@ -8,9 +8,14 @@
 
 use super::StorageAddress;
 use crate::{
+    dynamic::Value,
     error::Error,
     metadata::Metadata,
 };
+use scale_value::{
+    Composite,
+    ValueDef,
+};
 
 /// Return the root of a given [`StorageAddress`]: hash the pallet name and entry name
 /// and append those bytes to the output.
@@ -40,3 +45,168 @@ pub fn storage_address_root_bytes<Address: StorageAddress>(addr: &Address) -> Ve
     write_storage_address_root_bytes(addr, &mut bytes);
     bytes
 }
+
+/// The prefixes of the storage keys under some [`StorageAddress`]: the 32 byte root
+/// that every entry of a storage item shares, and the bytes that each key given in the
+/// address extends it by. Obtain these using [`storage_address_prefixes`].
+///
+/// This is handy when subscribing to storage changes, asking for proofs or listing keys
+/// (for instance with `state_getKeys`) outside of subxt.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StorageAddressPrefixes {
+    root: [u8; 32],
+    keys: Vec<Vec<u8>>,
+}
+
+impl StorageAddressPrefixes {
+    /// The `twox128(pallet) ++ twox128(entry)` prefix shared by every entry of the
+    /// storage item.
+    pub fn root(&self) -> [u8; 32] {
+        self.root
+    }
+
+    /// The hashed bytes of each key given in the address, in order.
+    pub fn keys(&self) -> &[Vec<u8>] {
+        &self.keys
+    }
+
+    /// The root followed by the first `num_keys` keys. For example, in a double map,
+    /// `prefix(1)` is the prefix shared by every entry with the same first key.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_keys` is more than the number of keys given in the address.
+    pub fn prefix(&self, num_keys: usize) -> Vec<u8> {
+        let mut bytes = self.root.to_vec();
+        for key in &self.keys[..num_keys] {
+            bytes.extend(key);
+        }
+        bytes
+    }
+
+    /// The root followed by every key given in the address.
+    pub fn full(&self) -> Vec<u8> {
+        self.prefix(self.keys.len())
+    }
+}
+
+/// Work out the [`StorageAddressPrefixes`] of a [`StorageAddress`]. This works offline,
+/// using only the metadata given. Dynamic addresses into maps with a hasher per key can
+/// be given fewer keys than the map has, to find the prefix of a group of entries.
+pub fn storage_address_prefixes<Address: StorageAddress>(
+    addr: &Address,
+    metadata: &Metadata,
+) -> Result<StorageAddressPrefixes, Error> {
+    let mut root = [0; 32];
+    root.copy_from_slice(&storage_address_root_bytes(addr));
+    let keys = addr.entry_key_bytes(metadata)?;
+    Ok(StorageAddressPrefixes { root, keys })
+}
+
+/// Interpret a dynamically decoded number, which may be wrapped in any number of newtypes
+/// (as balances, indexes and `Compact` values often are).
+pub(crate) fn value_as_u128<T>(value: &Value<T>) -> Option<u128> {
+    match &value.value {
+        ValueDef::Primitive(p) => p.as_u128(),
+        ValueDef::Composite(Composite::Unnamed(vals)) if vals.len() == 1 => {
+            value_as_u128(&vals[0])
+        }
+        _ => None,
+    }
+}
+
+/// Like [`value_as_u128`], for numbers which fit in a `u64`.
+pub(crate) fn value_as_u64<T>(value: &Value<T>) -> Option<u64> {
+    value_as_u128(value).and_then(|n| u64::try_from(n).ok())
+}
+
+/// Like [`value_as_u128`], for numbers which fit in a `u32`.
+pub(crate) fn value_as_u32<T>(value: &Value<T>) -> Option<u32> {
+    value_as_u128(value).and_then(|n| u32::try_from(n).ok())
+}
+
+/// Interpret a dynamically decoded sequence or array of bytes (such as an account ID, a
+/// hash or a `BoundedVec<u8>`), which may be wrapped in any number of newtypes, handing
+/// back the bytes.
+pub(crate) fn value_as_bytes<T>(value: &Value<T>) -> Option<Vec<u8>> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    match &vals[..] {
+        [inner @ Value {
+            value: ValueDef::Composite(_),
+            ..
+        }] => value_as_bytes(inner),
+        _ => {
+            vals.iter()
+                .map(|v| value_as_u128(v).and_then(|n| u8::try_from(n).ok()))
+                .collect()
+        }
+    }
+}
+
+/// Interpret a dynamically decoded list of items, which may be wrapped in any number of
+/// newtypes (as a `BoundedVec` is). Items may be newtypes themselves, so the innermost
+/// list that can be interpreted wins; otherwise a `BoundedVec` holding a single account
+/// would look like a single account.
+pub(crate) fn list_from_value<T, Item>(
+    value: &Value<T>,
+    item_from_value: fn(&Value<T>) -> Option<Item>,
+) -> Option<Vec<Item>> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    if let [inner] = &vals[..] {
+        if let Some(items) = list_from_value(inner, item_from_value) {
+            return Some(items)
+        }
+    }
+    vals.iter().map(item_from_value).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        dynamic::Value,
+        test_utils::metadata,
+    };
+
+    #[test]
+    fn prefixes_extend_the_root_by_each_key() {
+        let metadata = metadata();
+        let twox64_concat = |key: &[u8]| {
+            let mut bytes = sp_core_hashing::twox_64(key).to_vec();
+            bytes.extend(key);
+            bytes
+        };
+
+        // `Staking::ErasStakers` is a double map with a hasher per key.
+        let era = Value::u128(7);
+        let account = Value::from_bytes([1; 32]);
+        let address =
+            crate::dynamic::storage("Staking", "ErasStakers", vec![era.clone(), account]);
+        let prefixes = storage_address_prefixes(&address, &metadata).unwrap();
+        assert_eq!(
+            prefixes.root().to_vec(),
+            storage_address_root_bytes(&address)
+        );
+        assert_eq!(prefixes.keys().len(), 2);
+        assert_eq!(prefixes.keys()[0], twox64_concat(&7u32.to_le_bytes()));
+        assert_eq!(prefixes.keys()[1], twox64_concat(&[1; 32]));
+        assert_eq!(
+            prefixes.full(),
+            storage_address_bytes(&address, &metadata).unwrap()
+        );
+
+        // Just the first key can be given to find the prefix of every entry in an era:
+        let address = crate::dynamic::storage("Staking", "ErasStakers", vec![era]);
+        let prefixes = storage_address_prefixes(&address, &metadata).unwrap();
+        let mut expected = storage_address_root_bytes(&address);
+        expected.extend(twox64_concat(&7u32.to_le_bytes()));
+        assert_eq!(prefixes.full(), expected);
+        assert_eq!(prefixes.prefix(0), storage_address_root_bytes(&address));
+        // This address still can't be used to fetch an entry:
+        assert!(storage_address_bytes(&address, &metadata).is_err());
+    }
+}
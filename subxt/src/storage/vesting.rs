@@ -0,0 +1,257 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::{
+    utils::{
+        value_as_u128,
+        value_as_u64,
+    },
+    Storage,
+};
+use crate::{
+    client::OnlineClientT,
+    error::Error,
+    metadata::EncodeStaticType,
+    Config,
+};
+use codec::Encode;
+use scale_value::{
+    At,
+    Composite,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// A vesting schedule, as stored in `Vesting::Vesting`. Starting at `starting_block`,
+/// `per_block` of the `locked` balance is unlocked each block until none of it
+/// remains locked.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct VestingSchedule {
+    /// The balance which is locked at the start of the schedule.
+    pub locked: u128,
+    /// The balance which is unlocked each block once the schedule starts.
+    pub per_block: u128,
+    /// The block number at which balances start to unlock.
+    pub starting_block: u64,
+}
+
+impl VestingSchedule {
+    /// Attempt to interpret a dynamically decoded `VestingInfo` as a [`VestingSchedule`],
+    /// returning `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<VestingSchedule> {
+        let locked = value.at("locked").and_then(value_as_u128)?;
+        let per_block = value.at("per_block").and_then(value_as_u128)?;
+        let starting_block = value
+            .at("starting_block")
+            .and_then(value_as_u128)
+            .and_then(|n| u64::try_from(n).ok())?;
+        Some(VestingSchedule {
+            locked,
+            per_block,
+            starting_block,
+        })
+    }
+
+    /// Interpret the value stored in `Vesting::Vesting` as a list of schedules. Newer
+    /// runtimes store a list of schedules for each account, whereas older runtimes
+    /// store a single schedule.
+    pub fn list_from_value<T>(value: &Value<T>) -> Option<Vec<VestingSchedule>> {
+        if let Some(schedule) = VestingSchedule::from_value(value) {
+            return Some(vec![schedule])
+        }
+        match &value.value {
+            ValueDef::Composite(Composite::Unnamed(vals)) => {
+                // A bounded vec may be wrapped in a newtype.
+                if let [inner] = &vals[..] {
+                    if VestingSchedule::from_value(inner).is_none() {
+                        return VestingSchedule::list_from_value(inner)
+                    }
+                }
+                vals.iter().map(VestingSchedule::from_value).collect()
+            }
+            _ => None,
+        }
+    }
+
+    /// The balance which is still locked at the given block number.
+    pub fn locked_at(&self, block_number: u64) -> u128 {
+        let vested_block_count = block_number.saturating_sub(self.starting_block);
+        (vested_block_count as u128)
+            .checked_mul(self.per_block)
+            .map(|unlocked| self.locked.saturating_sub(unlocked))
+            .unwrap_or(0)
+    }
+
+    /// The balance which has been unlocked by the given block number.
+    pub fn vested_at(&self, block_number: u64) -> u128 {
+        self.locked - self.locked_at(block_number)
+    }
+
+    /// The first block number at which nothing remains locked.
+    pub fn ending_block(&self) -> Option<u64> {
+        if self.per_block == 0 {
+            return (self.locked == 0).then_some(self.starting_block)
+        }
+        // Round up, since a partial unlock still takes a whole block.
+        let mut blocks = self.locked / self.per_block;
+        if blocks * self.per_block < self.locked {
+            blocks += 1;
+        }
+        u64::try_from(blocks)
+            .ok()
+            .and_then(|blocks| self.starting_block.checked_add(blocks))
+    }
+}
+
+/// The vesting schedules of some account, along with the locked and vested balances at
+/// some block. This is returned from [`vesting()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Vesting {
+    /// The block number at which the balances were computed.
+    pub block_number: u64,
+    /// Each of the vesting schedules of the account. Schedules run concurrently.
+    pub schedules: Vec<VestingSchedule>,
+    /// The balance which is still locked across all of the schedules.
+    pub locked: u128,
+    /// The balance which has been unlocked across all of the schedules.
+    pub vested: u128,
+}
+
+impl Vesting {
+    /// Compute the locked and vested balances of the given schedules at some block.
+    pub fn at_block(schedules: Vec<VestingSchedule>, block_number: u64) -> Vesting {
+        let locked = schedules.iter().fold(0u128, |acc, s| {
+            acc.saturating_add(s.locked_at(block_number))
+        });
+        let vested = schedules.iter().fold(0u128, |acc, s| {
+            acc.saturating_add(s.vested_at(block_number))
+        });
+        Vesting {
+            block_number,
+            schedules,
+            locked,
+            vested,
+        }
+    }
+}
+
+/// Fetch the vesting schedules of some account from `Vesting::Vesting`, and compute the
+/// balances that are locked and vested as of the block that `storage` reads at. Accounts
+/// without any vesting schedules have nothing locked or vested.
+///
+/// This uses the block number of the block being read from. Chains whose vesting pallet
+/// counts blocks differently (for instance, parachains using relay chain block numbers)
+/// should use [`Vesting::at_block()`] with the appropriate block number instead.
+pub fn vesting<'a, T, Client>(
+    storage: &Storage<T, Client>,
+    account: impl Encode + 'a,
+) -> impl Future<Output = Result<Vesting, Error>> + 'a
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let address = crate::dynamic::storage(
+            "Vesting",
+            "Vesting",
+            vec![EncodeStaticType(account)],
+        );
+        let schedules = match storage.fetch(&address).await? {
+            Some(value) => {
+                let value = value.to_value()?;
+                VestingSchedule::list_from_value(&value).ok_or_else(|| {
+                    Error::Other(format!(
+                        "Could not interpret {value} as a list of vesting schedules"
+                    ))
+                })?
+            }
+            None => Vec::new(),
+        };
+
+        let number_address = crate::dynamic::storage_root("System", "Number");
+        let number = storage
+            .fetch_or_default(&number_address)
+            .await?
+            .to_value()?;
+        let block_number = value_as_u64(&number).ok_or_else(|| {
+            Error::Other(format!("Could not interpret {number} as a block number"))
+        })?;
+
+        Ok(Vesting::at_block(schedules, block_number))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::BoundedVec;
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[derive(Encode, TypeInfo)]
+    struct VestingInfo {
+        locked: u128,
+        per_block: u128,
+        starting_block: u32,
+    }
+
+    fn decode_as<T: TypeInfo + 'static>(bytes: &[u8]) -> Option<Vec<VestingSchedule>> {
+        let value = crate::test_utils::decode_as::<T>(bytes)?;
+        VestingSchedule::list_from_value(&value)
+    }
+
+    fn schedule(locked: u128, per_block: u128, starting_block: u64) -> VestingSchedule {
+        VestingSchedule {
+            locked,
+            per_block,
+            starting_block,
+        }
+    }
+
+    #[test]
+    fn decodes_one_or_many_schedules() {
+        let info = || {
+            VestingInfo {
+                locked: 100,
+                per_block: 10,
+                starting_block: 5,
+            }
+        };
+        let expected = schedule(100, 10, 5);
+
+        assert_eq!(
+            decode_as::<VestingInfo>(&info().encode()),
+            Some(vec![expected])
+        );
+        assert_eq!(
+            decode_as::<Vec<VestingInfo>>(&vec![info(), info()].encode()),
+            Some(vec![expected, expected])
+        );
+        assert_eq!(
+            decode_as::<BoundedVec<VestingInfo>>(&BoundedVec(vec![info()]).encode()),
+            Some(vec![expected])
+        );
+        assert_eq!(decode_as::<u32>(&1u32.encode()), None);
+    }
+
+    #[test]
+    fn computes_locked_and_vested_balances() {
+        let s = schedule(100, 10, 5);
+        assert_eq!(s.locked_at(0), 100);
+        assert_eq!(s.locked_at(5), 100);
+        assert_eq!(s.locked_at(7), 80);
+        assert_eq!(s.vested_at(7), 20);
+        assert_eq!(s.locked_at(15), 0);
+        assert_eq!(s.locked_at(u64::MAX), 0);
+        assert_eq!(s.ending_block(), Some(15));
+        assert_eq!(schedule(101, 10, 5).ending_block(), Some(16));
+        assert_eq!(schedule(100, 0, 5).ending_block(), None);
+
+        let vesting = Vesting::at_block(vec![s, schedule(50, 1, 10)], 12);
+        assert_eq!(vesting.locked, 30 + 48);
+        assert_eq!(vesting.vested, 70 + 2);
+    }
+}
@@ -0,0 +1,113 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::Storage;
+use crate::{
+    client::OnlineClientT,
+    dynamic::DecodedValue,
+    error::Error,
+    events::xcm_pallet_name,
+    Config,
+};
+use scale_value::{
+    Composite,
+    Value,
+    ValueDef,
+};
+use std::future::Future;
+
+/// Interpret the value stored in the `VersionDiscoveryQueue` of the XCM pallet as a list
+/// of locations and the number of times that each has been queued.
+pub(crate) fn version_discovery_queue_from_value<T: Clone>(
+    value: &Value<T>,
+) -> Option<Vec<(Value<T>, u32)>> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    if let Some(entries) = vals.iter().map(queue_entry_from_value).collect() {
+        return Some(entries)
+    }
+    // The bounded vec may be wrapped in a newtype.
+    match &vals[..] {
+        [inner] => version_discovery_queue_from_value(inner),
+        _ => None,
+    }
+}
+
+fn queue_entry_from_value<T: Clone>(value: &Value<T>) -> Option<(Value<T>, u32)> {
+    let ValueDef::Composite(Composite::Unnamed(vals)) = &value.value else {
+        return None
+    };
+    let [location, count] = &vals[..] else {
+        return None
+    };
+    let count = match &count.value {
+        ValueDef::Primitive(p) => p.as_u128().and_then(|n| u32::try_from(n).ok())?,
+        _ => return None,
+    };
+    Some((location.clone(), count))
+}
+
+/// Fetch the `VersionDiscoveryQueue` of the XCM pallet. This hands back each location
+/// whose XCM version is waiting to be discovered, as a dynamic `VersionedMultiLocation`
+/// value, along with the number of times that it's been queued.
+///
+/// This fails if the runtime has no XCM pallet (see [`crate::events::XCM_PALLET_NAMES`]).
+pub fn xcm_version_discovery_queue<T, Client>(
+    storage: &Storage<T, Client>,
+) -> impl Future<Output = Result<Vec<(DecodedValue, u32)>, Error>> + 'static
+where
+    T: Config,
+    Client: OnlineClientT<T>,
+{
+    let storage = storage.clone();
+    async move {
+        let metadata = storage.client().metadata();
+        let pallet_name = xcm_pallet_name(&metadata).ok_or_else(|| {
+            Error::Other("Could not find an XCM pallet in the metadata".into())
+        })?;
+        let address = crate::dynamic::storage_root(pallet_name, "VersionDiscoveryQueue");
+        let queue = storage.fetch_or_default(&address).await?.to_value()?;
+        version_discovery_queue_from_value(&queue).ok_or_else(|| {
+            Error::Other(format!(
+                "Could not interpret {queue} as an XCM version discovery queue"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::{
+        decode,
+        BoundedVec,
+    };
+    use codec::Encode;
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum VersionedMultiLocation {
+        V1(u8),
+        V3(u8),
+    }
+
+    #[test]
+    fn decodes_version_discovery_queue() {
+        let queue = BoundedVec(vec![
+            (VersionedMultiLocation::V1(1), 2u32),
+            (VersionedMultiLocation::V3(3), 4),
+        ]);
+        let value = decode(queue);
+        let entries = version_discovery_queue_from_value(&value).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(matches!(&entries[1].0.value, ValueDef::Variant(v) if v.name == "V3"));
+        assert_eq!(entries[1].1, 4);
+
+        let empty = BoundedVec::<(VersionedMultiLocation, u32)>(vec![]);
+        let value = decode(empty);
+        assert_eq!(version_discovery_queue_from_value(&value), Some(vec![]));
+    }
+}
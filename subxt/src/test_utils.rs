@@ -0,0 +1,275 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Test utilities shared by the tests throughout this crate.
+
+use crate::{
+    dynamic::DecodedValue,
+    error::RpcError,
+    rpc::{
+        types::RuntimeVersion,
+        RawValue,
+        RpcClientT,
+        RpcFuture,
+        RpcSubscription,
+    },
+    Metadata,
+    OfflineClient,
+    OnlineClient,
+    PolkadotConfig,
+};
+use codec::{
+    Decode,
+    Encode,
+};
+use frame_metadata::RuntimeMetadataPrefixed;
+use scale_info::{
+    PortableRegistry,
+    TypeInfo,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+/// The SCALE encoded Polkadot metadata in the `artifacts` folder.
+pub const METADATA: &[u8] = include_bytes!("../../artifacts/polkadot_metadata.scale");
+
+/// The Polkadot metadata in the `artifacts` folder.
+pub fn metadata() -> Metadata {
+    let metadata = RuntimeMetadataPrefixed::decode(&mut &METADATA[..]).unwrap();
+    metadata.try_into().unwrap()
+}
+
+/// The runtime version that the clients handed back from here are on.
+pub fn runtime_version() -> RuntimeVersion {
+    RuntimeVersion {
+        spec_version: 1,
+        transaction_version: 2,
+        other: Default::default(),
+    }
+}
+
+/// An offline client with the Polkadot [`metadata()`].
+pub fn offline_client() -> OfflineClient<PolkadotConfig> {
+    OfflineClient::new(Default::default(), runtime_version(), metadata())
+}
+
+/// An online client with the Polkadot [`metadata()`], which talks to the given node.
+pub fn online_client(rpc: Arc<MockRpc>) -> OnlineClient<PolkadotConfig> {
+    OnlineClient::from_rpc_client_with(
+        Default::default(),
+        runtime_version(),
+        metadata(),
+        rpc,
+    )
+    .unwrap()
+}
+
+/// A type registry with just `T` (and the types that it's made of) in it, along with the
+/// ID of `T` in it.
+pub fn types_of<T: TypeInfo + 'static>() -> (u32, PortableRegistry) {
+    let mut types = scale_info::Registry::new();
+    let id = types.register_type(&scale_info::meta_type::<T>()).id();
+    (id, types.into())
+}
+
+/// Decode the given bytes as a `T` into a [`DecodedValue`], using the registry from
+/// [`types_of()`]. This returns `None` if the bytes aren't a valid `T`.
+pub fn decode_as<T: TypeInfo + 'static>(bytes: &[u8]) -> Option<DecodedValue> {
+    let (id, types) = types_of::<T>();
+    scale_value::scale::decode_as_type(&mut &*bytes, id, &types).ok()
+}
+
+/// Encode the given value and decode it again into a [`DecodedValue`], like
+/// [`decode_as()`].
+pub fn decode<T: Encode + TypeInfo + 'static>(value: T) -> DecodedValue {
+    decode_as::<T>(&value.encode()).unwrap()
+}
+
+/// Like an `AccountId32` in the metadata, for building values to [`decode()`].
+#[derive(Encode, TypeInfo)]
+pub struct AccountId32(pub [u8; 32]);
+
+/// Like a `BoundedVec<T>` in the metadata, for building values to [`decode()`].
+#[derive(Encode, TypeInfo)]
+pub struct BoundedVec<T>(pub Vec<T>);
+
+type Respond = Box<
+    dyn Fn(&str, serde_json::Value) -> Result<serde_json::Value, RpcError> + Send + Sync,
+>;
+type Subscribe = Box<
+    dyn Fn(&str, serde_json::Value) -> Result<RpcSubscription, RpcError> + Send + Sync,
+>;
+
+/// A node which answers requests by calling a function with their method and params, and
+/// which keeps track of the methods that it's been asked to call.
+pub struct MockRpc {
+    respond: Respond,
+    subscribe: Subscribe,
+    requests: Mutex<Vec<String>>,
+}
+
+impl MockRpc {
+    /// Answer requests with the given function. Subscriptions are dropped straight away.
+    pub fn new(
+        respond: impl Fn(&str, serde_json::Value) -> Result<serde_json::Value, RpcError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        MockRpc {
+            respond: Box::new(respond),
+            subscribe: Box::new(|_, _| Err(RpcError::SubscriptionDropped)),
+            requests: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Answer subscriptions with the given function.
+    pub fn with_subscriptions(
+        mut self,
+        subscribe: impl Fn(&str, serde_json::Value) -> Result<RpcSubscription, RpcError>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.subscribe = Box::new(subscribe);
+        self
+    }
+
+    /// The number of requests that have been made.
+    pub fn requests(&self) -> usize {
+        self.requests.lock().unwrap().len()
+    }
+
+    /// The number of requests that have been made to the given method.
+    pub fn requests_to(&self, method: &str) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|m| *m == method)
+            .count()
+    }
+}
+
+/// A subscription which hands back each of the given items and then ends.
+pub fn subscription(items: Vec<serde_json::Value>) -> RpcSubscription {
+    let items: Vec<_> = items
+        .iter()
+        .map(|item| Ok(RawValue::from_string(item.to_string()).unwrap()))
+        .collect();
+    RpcSubscription {
+        stream: Box::pin(futures::stream::iter(items)),
+        id: None,
+    }
+}
+
+fn parse_params(params: Option<Box<RawValue>>) -> serde_json::Value {
+    params
+        .map(|p| serde_json::from_str(p.get()).unwrap())
+        .unwrap_or_default()
+}
+
+impl RpcClientT for MockRpc {
+    fn request_raw<'a>(
+        &'a self,
+        method: &'a str,
+        params: Option<Box<RawValue>>,
+    ) -> RpcFuture<'a, Box<RawValue>> {
+        self.requests.lock().unwrap().push(method.to_owned());
+        let res = (self.respond)(method, parse_params(params));
+        Box::pin(async move { Ok(RawValue::from_string(res?.to_string()).unwrap()) })
+    }
+
+    fn subscribe_raw<'a>(
+        &'a self,
+        sub: &'a str,
+        params: Option<Box<RawValue>>,
+        _unsub: &'a str,
+    ) -> RpcFuture<'a, RpcSubscription> {
+        let res = (self.subscribe)(sub, parse_params(params));
+        Box::pin(async move { res })
+    }
+}
+
+// A node which returns at most 2 keys per page, and includes the start key in the
+// page it returns if there is one.
+struct BoundaryInclusiveRpc {
+    keys: Vec<Vec<u8>>,
+}
+
+impl BoundaryInclusiveRpc {
+    fn keys_page(&self, start_key: &serde_json::Value) -> serde_json::Value {
+        let start_key = start_key
+            .as_str()
+            .map(|k| hex::decode(k.trim_start_matches("0x")).unwrap());
+        let keys: Vec<_> = self
+            .keys
+            .iter()
+            .filter(|k| {
+                match &start_key {
+                    Some(start_key) => *k >= start_key,
+                    None => true,
+                }
+            })
+            .take(2)
+            .map(|k| format!("0x{}", hex::encode(k)))
+            .collect();
+        serde_json::json!(keys)
+    }
+
+    fn respond(&self, method: &str, params: serde_json::Value) -> serde_json::Value {
+        let params = params.as_array().cloned().unwrap_or_default();
+        match method {
+            "state_getKeysPaged" => self.keys_page(&params[2]),
+            // Every child trie holds the same keys, and each value is a crowdloan
+            // contribution of the key's last byte, with no memo.
+            "childstate_getKeysPaged" => self.keys_page(&params[3]),
+            "childstate_getStorage" => {
+                let key =
+                    hex::decode(params[1].as_str().unwrap().trim_start_matches("0x"))
+                        .unwrap();
+                let value = (key[key.len() - 1] as u128, Vec::<u8>::new()).encode();
+                serde_json::json!(format!("0x{}", hex::encode(value)))
+            }
+            "state_queryStorageAt" => {
+                let changes: Vec<_> = params[0]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|k| serde_json::json!([k, format!("0x{}", "00".repeat(32))]))
+                    .collect();
+                let block = format!("0x{}", "00".repeat(32));
+                serde_json::json!([{ "block": block, "changes": changes }])
+            }
+            "state_getPairs" => {
+                let prefix =
+                    hex::decode(params[0].as_str().unwrap().trim_start_matches("0x"))
+                        .unwrap();
+                let pairs: Vec<_> = self
+                    .keys
+                    .iter()
+                    .filter(|k| k.starts_with(&prefix))
+                    .map(|k| {
+                        let value = format!("0x{}", hex::encode([k[k.len() - 1]; 32]));
+                        serde_json::json!([format!("0x{}", hex::encode(k)), value])
+                    })
+                    .collect();
+                serde_json::json!(pairs)
+            }
+            _ => panic!("unexpected method {method}"),
+        }
+    }
+}
+
+/// An online client which talks to a node holding the given storage keys. The node hands
+/// back at most 2 keys per page, and includes the start key in the page that it hands back
+/// if there is one. Every child trie holds the same keys.
+pub fn client_with_keys(keys: Vec<Vec<u8>>) -> OnlineClient<PolkadotConfig> {
+    let node = BoundaryInclusiveRpc { keys };
+    online_client(Arc::new(MockRpc::new(move |method, params| {
+        Ok(node.respond(method, params))
+    })))
+}
@@ -0,0 +1,130 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Construct balance transfers, picking the right `Balances` call for the runtime.
+
+use super::tx_payload::TxPayload;
+use crate::{
+    error::Error,
+    metadata::Metadata,
+    Config,
+};
+use codec::{
+    Compact,
+    Encode,
+};
+
+/// A transaction payload which transfers some balance to another account. Construct one
+/// using [`transfer()`].
+///
+/// Encoding the call data fails if the runtime has no `Balances` pallet.
+#[derive(Clone, Debug)]
+pub struct TransferTxPayload {
+    // The destination is stored as an encoded `T::Address`, so that this isn't generic
+    // over the config.
+    dest: Vec<u8>,
+    amount: u128,
+    keep_alive: bool,
+}
+
+/// Transfer `amount` to the account `dest`, which is wrapped in the `T::Address` type of
+/// the chain (usually a `MultiAddress`).
+///
+/// If `keep_alive` is true, this is a `Balances::transfer_keep_alive` call, which fails
+/// rather than leave the sender with less than the existential deposit. Otherwise, it's a
+/// `Balances::transfer_allow_death` call, which may reap the sender's account (or a
+/// `Balances::transfer` call, on runtimes which predate `transfer_allow_death`).
+pub fn transfer<T: Config>(
+    dest: T::AccountId,
+    amount: u128,
+    keep_alive: bool,
+) -> TransferTxPayload {
+    TransferTxPayload {
+        dest: T::Address::from(dest).encode(),
+        amount,
+        keep_alive,
+    }
+}
+
+impl TransferTxPayload {
+    /// The amount being transferred.
+    pub fn amount(&self) -> u128 {
+        self.amount
+    }
+
+    /// Will the transfer fail rather than reap the sender's account?
+    pub fn keeps_alive(&self) -> bool {
+        self.keep_alive
+    }
+}
+
+impl TxPayload for TransferTxPayload {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let pallet = metadata.pallet("Balances").map_err(|_| {
+            Error::Other(
+                "Cannot construct a transfer: the runtime has no Balances pallet".into(),
+            )
+        })?;
+
+        let call_index = if self.keep_alive {
+            pallet.call_index("transfer_keep_alive")?
+        } else {
+            // `transfer` was renamed to `transfer_allow_death`.
+            pallet
+                .call_index("transfer_allow_death")
+                .or_else(|_| pallet.call_index("transfer"))?
+        };
+
+        pallet.index().encode_to(out);
+        call_index.encode_to(out);
+        out.extend(&self.dest);
+        Compact(self.amount).encode_to(out);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        dynamic::Value,
+        test_utils::metadata,
+        tx::dynamic,
+        utils::AccountId32,
+        PolkadotConfig,
+    };
+
+    #[test]
+    fn transfers_pick_the_right_call() {
+        let metadata = metadata();
+        let dest = AccountId32([7; 32]);
+        let args = || {
+            vec![
+                Value::unnamed_variant("Id", vec![Value::from_bytes(dest.0)]),
+                Value::u128(1_000),
+            ]
+        };
+
+        let keep_alive = transfer::<PolkadotConfig>(dest.clone(), 1_000, true);
+        assert_eq!(
+            keep_alive.encode_call_data(&metadata).unwrap(),
+            dynamic("Balances", "transfer_keep_alive", args())
+                .encode_call_data(&metadata)
+                .unwrap()
+        );
+
+        // This metadata predates `transfer_allow_death`:
+        let allow_death = transfer::<PolkadotConfig>(dest.clone(), 1_000, false);
+        assert_eq!(
+            allow_death.encode_call_data(&metadata).unwrap(),
+            dynamic("Balances", "transfer", args())
+                .encode_call_data(&metadata)
+                .unwrap()
+        );
+    }
+}
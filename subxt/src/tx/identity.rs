@@ -0,0 +1,25 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use super::tx_payload::{
+    dynamic,
+    DynamicTxPayload,
+};
+use crate::storage::IdentityInfo;
+
+/// Construct an `Identity::set_identity` call, which sets the identity of the account
+/// that signs it to the information given.
+pub fn set_identity(info: &IdentityInfo) -> DynamicTxPayload<'static> {
+    dynamic("Identity", "set_identity", vec![info.to_value()])
+}
+
+/// Construct an `Identity::clear_identity` call, which clears the identity of the account
+/// that signs it.
+pub fn clear_identity() -> DynamicTxPayload<'static> {
+    dynamic(
+        "Identity",
+        "clear_identity",
+        Vec::<crate::dynamic::Value>::new(),
+    )
+}
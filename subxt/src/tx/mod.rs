@@ -9,10 +9,18 @@
 //! additional and signed extra parameters are used when constructing an extrinsic, and is a part
 //! of the chain configuration (see [`crate::config::Config`]).
 
+mod balances;
+mod identity;
+mod pipeline;
+mod referenda;
+mod scheduler;
+mod signed_extensions;
 mod signer;
+mod sudo;
 mod tx_client;
 mod tx_payload;
 mod tx_progress;
+mod utility;
 
 // The PairSigner impl currently relies on Substrate bits and pieces, so make it an optional
 // feature if we want to avoid needing sp_core and sp_runtime.
@@ -20,14 +28,56 @@ mod tx_progress;
 pub use self::signer::PairSigner;
 
 pub use self::{
+    balances::{
+        transfer,
+        TransferTxPayload,
+    },
+    identity::{
+        clear_identity,
+        set_identity,
+    },
+    pipeline::{
+        PipelinedSubmission,
+        TxPipeline,
+    },
+    referenda::{
+        place_decision_deposit,
+        submit_referendum,
+        submit_referendum_preimage,
+        vote,
+        AccountVote,
+        Conviction,
+        Enactment,
+        SubmitReferendumTxPayload,
+        Vote,
+    },
+    scheduler::{
+        cancel,
+        cancel_named,
+        schedule,
+        schedule_named,
+        ScheduleTxPayload,
+    },
+    signed_extensions::SignedExtensionDetails,
     signer::Signer,
+    sudo::{
+        sudo,
+        sudo_unchecked_weight,
+        SudoTxPayload,
+    },
     tx_client::{
+        DecodedDryRunError,
+        DecodedDryRunResult,
+        RawSignedExtra,
         SubmittableExtrinsic,
         TxClient,
+        SIGNATURE_ALLOWANCE,
     },
     tx_payload::{
         dynamic,
+        CallHashChecked,
         DynamicTxPayload,
+        PreparedCall,
         StaticTxPayload,
         TxPayload,
     },
@@ -36,4 +86,11 @@ pub use self::{
         TxProgress,
         TxStatus,
     },
+    utility::{
+        batch,
+        batch_all,
+        force_batch,
+        BatchResult,
+        BatchTxPayload,
+    },
 };
@@ -0,0 +1,264 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Sign and submit lots of calls from a pool of accounts, keeping track of each account's
+//! nonce locally so that several transactions from the same account can be in flight at once.
+
+use super::{
+    Signer,
+    TxClient,
+    TxPayload,
+};
+use crate::{
+    client::OnlineClientT,
+    config::{
+        Config,
+        ExtrinsicParams,
+    },
+    error::Error,
+    utils::{
+        Encoded,
+        PhantomDataSendSync,
+    },
+};
+use derivative::Derivative;
+use futures::{
+    lock::Mutex as AsyncMutex,
+    Stream,
+    StreamExt,
+};
+use std::sync::Arc;
+
+/// Signs and submits calls from a pool of signers. Obtain one using
+/// [`TxClient::pipeline()`].
+///
+/// Calls are handed to each signer in turn. The nonce of each signer is fetched from the
+/// node the first time it's needed, and incremented locally after that, so that many
+/// transactions can be signed and submitted concurrently without waiting for earlier ones
+/// to make it into a block.
+///
+/// If a submission fails, the nonce of that signer is fetched from the node again before
+/// it's next used. Any transactions from the same signer which were given later nonces and
+/// already submitted may then wait in the transaction pool until they're replaced or dropped,
+/// so it's worth checking the results and resubmitting calls which failed.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "C: Clone"))]
+pub struct TxPipeline<T: Config, C, S> {
+    client: C,
+    signers: Arc<Vec<S>>,
+    nonces: Arc<AsyncMutex<Vec<Option<u64>>>>,
+    concurrency: usize,
+    _marker: PhantomDataSendSync<T>,
+}
+
+/// The outcome of submitting one of the calls given to [`TxPipeline::submit_all()`].
+#[derive(Derivative)]
+#[derivative(Debug(bound = ""))]
+pub struct PipelinedSubmission<T: Config> {
+    /// The position of the call in the stream of calls given.
+    pub call_index: usize,
+    /// The index of the signer in the pool which signed the call.
+    pub signer_index: usize,
+    /// The nonce that the call was signed with, if we got as far as working it out.
+    pub nonce: Option<u64>,
+    /// The hash of the submitted extrinsic, or the reason that it couldn't be submitted.
+    /// Success means that the transaction was accepted into the transaction pool, and not
+    /// that it has made it into a block.
+    pub result: Result<T::Hash, Error>,
+}
+
+impl<T: Config, C, S> TxPipeline<T, C, S> {
+    pub(crate) fn new(client: C, signers: Vec<S>) -> Self {
+        let nonces = vec![None; signers.len()];
+        TxPipeline {
+            client,
+            signers: Arc::new(signers),
+            nonces: Arc::new(AsyncMutex::new(nonces)),
+            concurrency: 8,
+            _marker: PhantomDataSendSync::new(),
+        }
+    }
+}
+
+impl<T, C, S> TxPipeline<T, C, S>
+where
+    T: Config,
+    T::Index: TryFrom<u64>,
+    T::AccountId: Sync,
+    C: OnlineClientT<T>,
+    S: Signer<T> + Send + Sync + 'static,
+{
+    /// The number of calls to sign and submit at the same time. This defaults to 8, and
+    /// a value of 0 is treated as 1.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sign and submit each of the calls given, handing back the outcome of each
+    /// submission as soon as it's known. Outcomes may therefore be handed back in a
+    /// different order to the calls; use [`PipelinedSubmission::call_index`] to match
+    /// them up. Default parameters are used to construct the "signed extra" and
+    /// "additional" payloads.
+    pub fn submit_all<Call, Calls>(
+        &self,
+        calls: Calls,
+    ) -> impl Stream<Item = PipelinedSubmission<T>> + Send + 'static
+    where
+        Call: TxPayload + Send + 'static,
+        Calls: Stream<Item = Call> + Send + 'static,
+        <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams: Default,
+    {
+        let pipeline = self.clone();
+        calls
+            .enumerate()
+            .map(move |(call_index, call)| {
+                let pipeline = pipeline.clone();
+                async move {
+                    let signer_index = call_index % pipeline.signers.len().max(1);
+                    let (nonce, result) = pipeline.submit_one(signer_index, call).await;
+                    PipelinedSubmission {
+                        call_index,
+                        signer_index,
+                        nonce,
+                        result,
+                    }
+                }
+            })
+            .buffer_unordered(self.concurrency)
+    }
+
+    async fn submit_one<Call: TxPayload>(
+        &self,
+        signer_index: usize,
+        call: Call,
+    ) -> (Option<u64>, Result<T::Hash, Error>)
+    where
+        <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams: Default,
+    {
+        let Some(signer) = self.signers.get(signer_index) else {
+            return (
+                None,
+                Err(Error::Other("The pipeline has no signers".into())),
+            )
+        };
+        let nonce = match self.next_nonce(signer_index, signer).await {
+            Ok(nonce) => nonce,
+            Err(e) => return (None, Err(e)),
+        };
+        let Ok(index) = T::Index::try_from(nonce) else {
+            return (
+                Some(nonce),
+                Err(Error::Other(format!(
+                    "The nonce {nonce} doesn't fit in T::Index"
+                ))),
+            )
+        };
+
+        let tx = TxClient::new(self.client.clone());
+        let ext = tx
+            .create_signed_with_nonce(&call, signer, index, Default::default())
+            .map(|ext| Encoded(ext.into_encoded()));
+        let result = match ext {
+            Ok(ext) => self.client.rpc().submit_extrinsic(ext).await,
+            Err(e) => Err(e),
+        };
+        if result.is_err() {
+            // Our nonce may now be ahead of the chain, so ask the node again next time.
+            self.nonces.lock().await[signer_index] = None;
+        }
+        (Some(nonce), result)
+    }
+
+    // Hand out the next nonce for some signer, asking the node for it if we don't know it.
+    async fn next_nonce(&self, signer_index: usize, signer: &S) -> Result<u64, Error> {
+        let mut nonces = self.nonces.lock().await;
+        let nonce = match nonces[signer_index] {
+            Some(nonce) => nonce,
+            None => {
+                let nonce = self
+                    .client
+                    .rpc()
+                    .system_account_next_index(signer.account_id())
+                    .await?;
+                nonce.into()
+            }
+        };
+        nonces[signer_index] = Some(nonce + 1);
+        Ok(nonce)
+    }
+}
+
+#[cfg(all(test, feature = "substrate-compat"))]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{
+            online_client,
+            MockRpc,
+        },
+        tx::PairSigner,
+        PolkadotConfig,
+    };
+    use sp_core::Pair;
+
+    // A node which says that every account's next nonce is 5, and accepts everything.
+    fn pool_rpc() -> Arc<MockRpc> {
+        Arc::new(MockRpc::new(|method, _| {
+            let res = match method {
+                "system_accountNextIndex" => serde_json::json!(5),
+                "author_submitExtrinsic" => {
+                    serde_json::json!(format!(
+                        "{:?}",
+                        <PolkadotConfig as Config>::Hash::zero()
+                    ))
+                }
+                _ => panic!("unexpected method {method}"),
+            };
+            Ok(res)
+        }))
+    }
+
+    #[tokio::test]
+    async fn nonces_are_tracked_per_signer() {
+        let rpc = pool_rpc();
+        let client = online_client(rpc.clone());
+
+        let signers = vec![
+            PairSigner::new(sp_core::sr25519::Pair::from_seed(&[1; 32])),
+            PairSigner::new(sp_core::sr25519::Pair::from_seed(&[2; 32])),
+        ];
+        let calls = (0..5u8).map(|n| {
+            crate::tx::dynamic(
+                "System",
+                "remark",
+                vec![crate::dynamic::Value::from_bytes([n])],
+            )
+        });
+        let mut results: Vec<_> = client
+            .tx()
+            .pipeline(signers)
+            .with_concurrency(3)
+            .submit_all(futures::stream::iter(calls))
+            .collect()
+            .await;
+        results.sort_by_key(|r| r.call_index);
+
+        assert!(results.iter().all(|r| r.result.is_ok()));
+        let nonces_of = |signer_index| {
+            let mut nonces: Vec<_> = results
+                .iter()
+                .filter(|r| r.signer_index == signer_index)
+                .map(|r| r.nonce.unwrap())
+                .collect();
+            nonces.sort();
+            nonces
+        };
+        assert_eq!(nonces_of(0), vec![5, 6, 7]);
+        assert_eq!(nonces_of(1), vec![5, 6]);
+        // Each signer's nonce was only fetched from the node once:
+        assert_eq!(rpc.requests_to("system_accountNextIndex"), 2);
+        assert_eq!(rpc.requests_to("author_submitExtrinsic"), 5);
+    }
+}
@@ -0,0 +1,471 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Take part in OpenGov: submit referenda with the `Referenda` pallet, and vote on them
+//! with the `ConvictionVoting` pallet.
+
+use super::{
+    scheduler::encode_scheduled_call,
+    sudo::call_arg_ty,
+    tx_payload::{
+        dynamic,
+        DynamicTxPayload,
+        TxPayload,
+    },
+};
+use crate::{
+    dynamic::Value,
+    error::Error,
+    metadata::{
+        EncodeWithMetadata,
+        Metadata,
+    },
+};
+use codec::Encode;
+
+/// How long the balance voted with is locked for once the poll is over, in multiples of
+/// the runtime's vote locking period, in return for the vote counting more.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Conviction {
+    /// The vote counts for a tenth of the balance, which isn't locked beyond the poll.
+    None,
+    /// The vote counts for the balance, which is locked for one period.
+    Locked1x,
+    /// The vote counts for twice the balance, which is locked for two periods.
+    Locked2x,
+    /// The vote counts for three times the balance, which is locked for four periods.
+    Locked3x,
+    /// The vote counts for four times the balance, which is locked for eight periods.
+    Locked4x,
+    /// The vote counts for five times the balance, which is locked for 16 periods.
+    Locked5x,
+    /// The vote counts for six times the balance, which is locked for 32 periods.
+    Locked6x,
+}
+
+/// A vote for or against a poll, with some conviction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Vote {
+    /// Whether the vote is for the poll.
+    pub aye: bool,
+    /// How long the balance voted with is locked for.
+    pub conviction: Conviction,
+}
+
+impl Vote {
+    /// The byte that the vote is packed into: the top bit is set for an aye vote, and the
+    /// rest is the conviction.
+    pub fn to_byte(&self) -> u8 {
+        let aye = if self.aye { 0b1000_0000 } else { 0 };
+        aye | self.conviction as u8
+    }
+}
+
+/// How an account votes on a poll, with some of its balance.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AccountVote {
+    /// Vote for or against the poll with the balance given.
+    Standard {
+        /// The vote.
+        vote: Vote,
+        /// The balance to vote with.
+        balance: u128,
+    },
+    /// Split the balance voted with between aye and nay, without conviction.
+    Split {
+        /// The balance to vote aye with.
+        aye: u128,
+        /// The balance to vote nay with.
+        nay: u128,
+    },
+    /// Split the balance voted with between aye, nay and abstaining, without conviction.
+    /// Some runtimes (and the `Democracy` pallet) don't allow abstaining.
+    SplitAbstain {
+        /// The balance to vote aye with.
+        aye: u128,
+        /// The balance to vote nay with.
+        nay: u128,
+        /// The balance to abstain with.
+        abstain: u128,
+    },
+}
+
+impl AccountVote {
+    /// The vote as a [`Value`] which encodes to the runtime's `AccountVote` type.
+    pub fn to_value(&self) -> Value {
+        match *self {
+            AccountVote::Standard { vote, balance } => {
+                Value::named_variant(
+                    "Standard",
+                    [
+                        (
+                            "vote",
+                            Value::unnamed_composite([Value::u128(
+                                vote.to_byte() as u128
+                            )]),
+                        ),
+                        ("balance", Value::u128(balance)),
+                    ],
+                )
+            }
+            AccountVote::Split { aye, nay } => {
+                Value::named_variant(
+                    "Split",
+                    [("aye", Value::u128(aye)), ("nay", Value::u128(nay))],
+                )
+            }
+            AccountVote::SplitAbstain { aye, nay, abstain } => {
+                Value::named_variant(
+                    "SplitAbstain",
+                    [
+                        ("aye", Value::u128(aye)),
+                        ("nay", Value::u128(nay)),
+                        ("abstain", Value::u128(abstain)),
+                    ],
+                )
+            }
+        }
+    }
+}
+
+/// Construct a `ConvictionVoting::vote` call, which votes on the poll (the referendum) with
+/// the given index on behalf of the account that signs it.
+pub fn vote(poll_index: u32, vote: AccountVote) -> DynamicTxPayload<'static> {
+    dynamic(
+        "ConvictionVoting",
+        "vote",
+        vec![Value::u128(poll_index as u128), vote.to_value()],
+    )
+}
+
+/// Construct a `Referenda::place_decision_deposit` call, which places the deposit needed
+/// for the referendum with the given index to start being decided, from the account that
+/// signs it.
+pub fn place_decision_deposit(index: u32) -> DynamicTxPayload<'static> {
+    dynamic(
+        "Referenda",
+        "place_decision_deposit",
+        vec![Value::u128(index as u128)],
+    )
+}
+
+/// When a call is dispatched once the referendum proposing it is approved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Enactment {
+    /// At the given block.
+    At(u64),
+    /// The given number of blocks after the referendum is approved.
+    After(u64),
+}
+
+/// A transaction payload which submits a referendum to dispatch some call with the
+/// `Referenda::submit` call. Construct one using [`submit_referendum()`].
+///
+/// Encoding the call data fails if the runtime has no `Referenda` pallet. The proposed call
+/// is inlined in the referendum, which runtimes only accept for calls up to some length
+/// (128 bytes in Polkadot). Longer calls need their preimage to be noted and submitted by
+/// hash using [`submit_referendum_preimage()`] instead.
+#[derive(Clone, Debug)]
+pub struct SubmitReferendumTxPayload<Call> {
+    origin: Value,
+    call: Call,
+    enactment: Enactment,
+}
+
+/// Submit a referendum to dispatch `call` with the given origin once it is approved.
+///
+/// The origins that referenda can be submitted with are specific to each runtime, and
+/// decide which track the referendum is decided on. They're given as the runtime's
+/// `OriginCaller`, for instance
+/// `Value::unnamed_variant("Origins", [Value::unnamed_variant("SmallSpender", [])])`
+/// for Polkadot's small spender track.
+pub fn submit_referendum<Call: TxPayload>(
+    origin: Value,
+    call: Call,
+    enactment: Enactment,
+) -> SubmitReferendumTxPayload<Call> {
+    SubmitReferendumTxPayload {
+        origin,
+        call,
+        enactment,
+    }
+}
+
+/// Submit a referendum to dispatch the call whose preimage has been noted with the given
+/// hash and length, like [`submit_referendum()`].
+pub fn submit_referendum_preimage(
+    origin: Value,
+    hash: [u8; 32],
+    len: u32,
+    enactment: Enactment,
+) -> DynamicTxPayload<'static> {
+    let proposal = Value::named_variant(
+        "Lookup",
+        [
+            ("hash", Value::from_bytes(hash)),
+            ("len", Value::u128(len as u128)),
+        ],
+    );
+    dynamic(
+        "Referenda",
+        "submit",
+        vec![origin, proposal, enactment_value(enactment)],
+    )
+}
+
+impl<Call> SubmitReferendumTxPayload<Call> {
+    /// The call being proposed.
+    pub fn call(&self) -> &Call {
+        &self.call
+    }
+}
+
+impl<Call: TxPayload> TxPayload for SubmitReferendumTxPayload<Call> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let pallet = metadata.pallet("Referenda").map_err(|_| {
+            Error::Other(
+                "Cannot construct a Referenda::submit call: the runtime has no Referenda pallet"
+                    .into(),
+            )
+        })?;
+        let arg_ty =
+            |arg_name| call_arg_ty(metadata, pallet.call_ty_id(), "submit", arg_name);
+
+        pallet.index().encode_to(out);
+        pallet.call_index("submit")?.encode_to(out);
+        self.origin
+            .encode_with_metadata(arg_ty("proposal_origin")?, metadata, out)?;
+        encode_scheduled_call(&self.call, arg_ty("proposal")?, metadata, out)?;
+        enactment_value(self.enactment).encode_with_metadata(
+            arg_ty("enactment_moment")?,
+            metadata,
+            out,
+        )
+    }
+}
+
+fn enactment_value(enactment: Enactment) -> Value {
+    match enactment {
+        Enactment::At(n) => Value::unnamed_variant("At", [Value::u128(n as u128)]),
+        Enactment::After(n) => Value::unnamed_variant("After", [Value::u128(n as u128)]),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+    use codec::Compact;
+    use frame_metadata::{
+        ExtrinsicMetadata,
+        PalletCallMetadata,
+        PalletMetadata,
+        RuntimeMetadataPrefixed,
+        RuntimeMetadataV14,
+    };
+    use scale_info::{
+        meta_type,
+        TypeInfo,
+    };
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum RawOrigin {
+        Root,
+        Signed([u8; 32]),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Origin {
+        StakingAdmin,
+        SmallSpender,
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(Encode, TypeInfo)]
+    enum OriginCaller {
+        system(RawOrigin),
+        Origins(Origin),
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum Bounded {
+        Legacy { hash: [u8; 32] },
+        Inline(Vec<u8>),
+        Lookup { hash: [u8; 32], len: u32 },
+    }
+
+    #[allow(dead_code)]
+    #[derive(Encode, TypeInfo)]
+    enum DispatchTime {
+        At(u32),
+        After(u32),
+    }
+
+    #[allow(dead_code, non_camel_case_types)]
+    #[derive(Encode, TypeInfo)]
+    enum Call {
+        submit {
+            proposal_origin: Box<OriginCaller>,
+            proposal: Bounded,
+            enactment_moment: DispatchTime,
+        },
+        place_decision_deposit {
+            index: u32,
+        },
+    }
+
+    // Metadata with just a `Referenda` pallet, whose calls look like those in Polkadot.
+    fn referenda_metadata() -> Metadata {
+        let pallet = PalletMetadata {
+            index: 21,
+            name: "Referenda",
+            calls: Some(PalletCallMetadata {
+                ty: meta_type::<Call>(),
+            }),
+            storage: None,
+            constants: vec![],
+            event: None,
+            error: None,
+        };
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<()>(),
+            version: 0,
+            signed_extensions: vec![],
+        };
+        let v14 = RuntimeMetadataV14::new(vec![pallet], extrinsic, meta_type::<()>());
+        let runtime_metadata: RuntimeMetadataPrefixed = v14.into();
+        Metadata::try_from(runtime_metadata).unwrap()
+    }
+
+    #[test]
+    fn votes_are_packed_into_a_byte() {
+        let vote = |aye, conviction| Vote { aye, conviction }.to_byte();
+        assert_eq!(vote(false, Conviction::None), 0x00);
+        assert_eq!(vote(true, Conviction::None), 0x80);
+        assert_eq!(vote(true, Conviction::Locked1x), 0x81);
+        assert_eq!(vote(false, Conviction::Locked3x), 0x03);
+        assert_eq!(vote(true, Conviction::Locked6x), 0x86);
+        assert_eq!(vote(false, Conviction::Locked6x), 0x06);
+    }
+
+    #[test]
+    fn encodes_account_votes() {
+        // This metadata predates OpenGov, but `Democracy::vote` takes the same
+        // `AccountVote` as `ConvictionVoting::vote` does.
+        let metadata = metadata();
+        let pallet = metadata.pallet("Democracy").unwrap();
+        let democracy_vote = |vote: AccountVote| {
+            dynamic("Democracy", "vote", vec![Value::u128(7), vote.to_value()])
+                .encode_call_data(&metadata)
+                .unwrap()
+        };
+        let expected = |vote: &[u8]| {
+            let mut expected = vec![pallet.index(), pallet.call_index("vote").unwrap()];
+            Compact(7u32).encode_to(&mut expected);
+            expected.extend(vote);
+            expected
+        };
+
+        let call_data = democracy_vote(AccountVote::Standard {
+            vote: Vote {
+                aye: true,
+                conviction: Conviction::Locked2x,
+            },
+            balance: 1000,
+        });
+        let mut vote = vec![0, 0x82];
+        1000u128.encode_to(&mut vote);
+        assert_eq!(call_data, expected(&vote));
+
+        let call_data = democracy_vote(AccountVote::Split { aye: 5, nay: 6 });
+        let mut vote = vec![1];
+        (5u128, 6u128).encode_to(&mut vote);
+        assert_eq!(call_data, expected(&vote));
+    }
+
+    #[test]
+    fn encodes_referendum_submissions() {
+        let metadata = referenda_metadata();
+        let proposed_call_data = place_decision_deposit(3)
+            .encode_call_data(&metadata)
+            .unwrap();
+        assert_eq!(proposed_call_data, [21, 1, 3, 0, 0, 0]);
+
+        let origin = Value::unnamed_variant(
+            "Origins",
+            [Value::unnamed_variant("SmallSpender", [])],
+        );
+        let submit = |enactment| {
+            submit_referendum(origin.clone(), place_decision_deposit(3), enactment)
+                .encode_call_data(&metadata)
+                .unwrap()
+        };
+        let expected = |enactment_moment| {
+            let call = Call::submit {
+                proposal_origin: Box::new(OriginCaller::Origins(Origin::SmallSpender)),
+                proposal: Bounded::Inline(proposed_call_data.clone()),
+                enactment_moment,
+            };
+            let mut expected = vec![21];
+            call.encode_to(&mut expected);
+            expected
+        };
+
+        // The proposed call is inlined with its length prefix:
+        let call_data = submit(Enactment::After(10));
+        assert_eq!(
+            call_data,
+            [
+                &[21, 0, 1, 1, 1, 24][..],
+                &proposed_call_data,
+                &[1, 10, 0, 0, 0]
+            ]
+            .concat()
+        );
+        assert_eq!(call_data, expected(DispatchTime::After(10)));
+        assert_eq!(
+            submit(Enactment::At(1000)),
+            expected(DispatchTime::At(1000))
+        );
+
+        // Calls with preimages are submitted by hash instead:
+        let call_data =
+            submit_referendum_preimage(origin, [7; 32], 6, Enactment::After(10))
+                .encode_call_data(&metadata)
+                .unwrap();
+        let call = Call::submit {
+            proposal_origin: Box::new(OriginCaller::Origins(Origin::SmallSpender)),
+            proposal: Bounded::Lookup {
+                hash: [7; 32],
+                len: 6,
+            },
+            enactment_moment: DispatchTime::After(10),
+        };
+        assert_eq!(call_data, [vec![21], call.encode()].concat());
+    }
+
+    #[test]
+    fn governance_calls_need_their_pallets() {
+        let metadata = metadata();
+        let remark = dynamic("System", "remark", vec![Value::from_bytes([1, 2])]);
+        let origin =
+            Value::unnamed_variant("system", [Value::unnamed_variant("Root", [])]);
+        assert!(submit_referendum(origin, remark, Enactment::After(10))
+            .encode_call_data(&metadata)
+            .is_err());
+        assert!(vote(0, AccountVote::Split { aye: 1, nay: 1 })
+            .encode_call_data(&metadata)
+            .is_err());
+        assert!(place_decision_deposit(0)
+            .encode_call_data(&metadata)
+            .is_err());
+    }
+}
@@ -0,0 +1,256 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Schedule calls to be dispatched at some future block using the `Scheduler` pallet,
+//! and cancel them again.
+
+use super::{
+    sudo::call_arg_ty,
+    tx_payload::{
+        dynamic,
+        DynamicTxPayload,
+        TxPayload,
+        ValidationDetails,
+    },
+};
+use crate::{
+    dynamic::Value,
+    error::{
+        Error,
+        MetadataError,
+    },
+    metadata::{
+        EncodeWithMetadata,
+        Metadata,
+    },
+};
+use codec::Encode;
+use scale_info::TypeDef;
+
+/// A transaction payload which schedules some other call using `Scheduler::schedule` or
+/// `Scheduler::schedule_named`. Construct one using [`schedule()`] or
+/// [`schedule_named()`].
+///
+/// Encoding the call data fails if the runtime has no `Scheduler` pallet. Depending on
+/// the runtime, the scheduled call is handed to the pallet as it is, wrapped in a
+/// `MaybeHashed`, or inlined in a `Bounded` call. In the last case, runtimes only accept
+/// calls up to some length (128 bytes in Polkadot), and longer calls need their preimage
+/// to be noted and scheduled by hash instead.
+#[derive(Clone, Debug)]
+pub struct ScheduleTxPayload<Call> {
+    id: Option<[u8; 32]>,
+    when: u64,
+    maybe_periodic: Option<(u64, u32)>,
+    priority: u8,
+    call: Call,
+}
+
+/// Schedule a call to be dispatched at block `when`. Calls scheduled for the same block
+/// are dispatched in order of `priority`, with 0 being the highest priority.
+pub fn schedule<Call: TxPayload>(
+    when: u64,
+    priority: u8,
+    call: Call,
+) -> ScheduleTxPayload<Call> {
+    ScheduleTxPayload {
+        id: None,
+        when,
+        maybe_periodic: None,
+        priority,
+        call,
+    }
+}
+
+/// Schedule a call to be dispatched at block `when`, like [`schedule()`], but give the
+/// scheduled call an ID so that it can be cancelled with [`cancel_named()`].
+pub fn schedule_named<Call: TxPayload>(
+    id: [u8; 32],
+    when: u64,
+    priority: u8,
+    call: Call,
+) -> ScheduleTxPayload<Call> {
+    ScheduleTxPayload {
+        id: Some(id),
+        when,
+        maybe_periodic: None,
+        priority,
+        call,
+    }
+}
+
+/// Cancel the call scheduled at block `when` with the given index in that block's agenda.
+/// The index is given in the `Scheduler::Scheduled` event emitted when the call was
+/// scheduled.
+pub fn cancel(when: u64, index: u32) -> DynamicTxPayload<'static> {
+    dynamic(
+        "Scheduler",
+        "cancel",
+        vec![Value::u128(when as u128), Value::u128(index as u128)],
+    )
+}
+
+/// Cancel the call scheduled with [`schedule_named()`] using the given ID.
+pub fn cancel_named(id: [u8; 32]) -> DynamicTxPayload<'static> {
+    dynamic("Scheduler", "cancel_named", vec![Value::from_bytes(id)])
+}
+
+impl<Call> ScheduleTxPayload<Call> {
+    /// Dispatch the call again every `period` blocks after the first time, `repetitions`
+    /// more times.
+    pub fn periodic(mut self, period: u64, repetitions: u32) -> Self {
+        self.maybe_periodic = Some((period, repetitions));
+        self
+    }
+
+    /// The call being scheduled.
+    pub fn call(&self) -> &Call {
+        &self.call
+    }
+
+    fn call_name(&self) -> &'static str {
+        match self.id {
+            Some(_) => "schedule_named",
+            None => "schedule",
+        }
+    }
+}
+
+impl<Call: TxPayload> TxPayload for ScheduleTxPayload<Call> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let call_name = self.call_name();
+        let pallet = metadata.pallet("Scheduler").map_err(|_| {
+            Error::Other(format!(
+                "Cannot construct a Scheduler::{call_name} call: the runtime has no Scheduler pallet"
+            ))
+        })?;
+        let arg_ty =
+            |arg_name| call_arg_ty(metadata, pallet.call_ty_id(), call_name, arg_name);
+
+        pallet.index().encode_to(out);
+        pallet.call_index(call_name)?.encode_to(out);
+        if let Some(id) = &self.id {
+            Value::from_bytes(id).encode_with_metadata(arg_ty("id")?, metadata, out)?;
+        }
+        Value::u128(self.when as u128).encode_with_metadata(
+            arg_ty("when")?,
+            metadata,
+            out,
+        )?;
+        let maybe_periodic = match self.maybe_periodic {
+            Some((period, repetitions)) => {
+                Value::variant(
+                    "Some",
+                    scale_value::Composite::unnamed([Value::unnamed_composite([
+                        Value::u128(period as u128),
+                        Value::u128(repetitions as u128),
+                    ])]),
+                )
+            }
+            None => Value::variant("None", scale_value::Composite::unnamed([])),
+        };
+        maybe_periodic.encode_with_metadata(arg_ty("maybe_periodic")?, metadata, out)?;
+        Value::u128(self.priority as u128).encode_with_metadata(
+            arg_ty("priority")?,
+            metadata,
+            out,
+        )?;
+        encode_scheduled_call(&self.call, arg_ty("call")?, metadata, out)
+    }
+
+    // The scheduled call is checked here, since it's what the runtime will dispatch.
+    fn validation_details(&self) -> Option<ValidationDetails<'_>> {
+        self.call.validation_details()
+    }
+}
+
+// Encode the call being scheduled according to the type that the runtime expects it as.
+// Referenda take the call that they propose in the same way.
+pub(super) fn encode_scheduled_call<Call: TxPayload>(
+    call: &Call,
+    call_ty_id: u32,
+    metadata: &Metadata,
+    out: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let call_ty = metadata
+        .resolve_type(call_ty_id)
+        .ok_or(MetadataError::TypeNotFound(call_ty_id))?;
+    let variant_index = |name: &str| {
+        let TypeDef::Variant(variants) = call_ty.type_def() else {
+            return Err(Error::Other(format!(
+                "Expected the scheduled call type {:?} to be an enum",
+                call_ty.path()
+            )))
+        };
+        variants
+            .variants()
+            .iter()
+            .find(|v| v.name() == name)
+            .map(|v| v.index())
+            .ok_or_else(|| {
+                Error::Other(format!(
+                    "Expected the scheduled call type {:?} to have a {name} variant",
+                    call_ty.path()
+                ))
+            })
+    };
+
+    match call_ty.path().ident().as_deref() {
+        // Older runtimes take `MaybeHashed::Value(call)`.
+        Some("MaybeHashed") => {
+            variant_index("Value")?.encode_to(out);
+            call.encode_call_data_to(metadata, out)
+        }
+        // Newer runtimes take `Bounded::Inline(encoded_call)`.
+        Some("Bounded") => {
+            variant_index("Inline")?.encode_to(out);
+            call.encode_call_data(metadata)?.encode_to(out);
+            Ok(())
+        }
+        // Otherwise, the call is taken as it is.
+        _ => call.encode_call_data_to(metadata, out),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+
+    #[test]
+    fn encodes_scheduled_calls() {
+        let metadata = metadata();
+        let remark = || dynamic("System", "remark", vec![Value::from_bytes([1, 2])]);
+        let remark_data = remark().encode_call_data(&metadata).unwrap();
+        let pallet = metadata.pallet("Scheduler").unwrap();
+
+        // Polkadot takes the scheduled call as it is:
+        let call_data = schedule_named([7; 32], 100, 63, remark())
+            .periodic(10, 3)
+            .encode_call_data(&metadata)
+            .unwrap();
+        let mut expected =
+            vec![pallet.index(), pallet.call_index("schedule_named").unwrap()];
+        expected.extend([7; 32]);
+        (100u32, Some((10u32, 3u32)), 63u8).encode_to(&mut expected);
+        expected.extend(&remark_data);
+        assert_eq!(call_data, expected);
+
+        let call_data = schedule(100, 0, remark())
+            .encode_call_data(&metadata)
+            .unwrap();
+        let mut expected = vec![pallet.index(), pallet.call_index("schedule").unwrap()];
+        (100u32, None::<(u32, u32)>, 0u8).encode_to(&mut expected);
+        expected.extend(&remark_data);
+        assert_eq!(call_data, expected);
+
+        let call_data = cancel(100, 2).encode_call_data(&metadata).unwrap();
+        let mut expected = vec![pallet.index(), pallet.call_index("cancel").unwrap()];
+        (100u32, 2u32).encode_to(&mut expected);
+        assert_eq!(call_data, expected);
+    }
+}
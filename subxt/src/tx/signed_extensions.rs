@@ -0,0 +1,271 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    dynamic::DecodedValue,
+    error::Error,
+    metadata::{
+        DecodeWithMetadata,
+        Metadata,
+    },
+};
+use scale_info::TypeDef;
+
+/// A signed extension that an extrinsic will be signed with, along with the values that
+/// it has been given. See [`crate::tx::TxClient::signed_extensions()`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedExtensionDetails {
+    /// The name that the runtime gives the signed extension, for example `CheckNonce`.
+    pub identifier: String,
+    /// The "signed extra" value, which is sent along with the extrinsic.
+    pub extra: DecodedValue,
+    /// The "additional signed" value, which is not sent but is signed over.
+    pub additional: DecodedValue,
+}
+
+/// Split the encoded "signed extra" and "additional" parameters of an extrinsic up into
+/// one value for each of the signed extensions listed in the metadata.
+pub(crate) fn decode_signed_extensions(
+    metadata: &Metadata,
+    mut extra: &[u8],
+    mut additional: &[u8],
+) -> Result<Vec<SignedExtensionDetails>, Error> {
+    let signed_extensions = &metadata.runtime_metadata().extrinsic.signed_extensions;
+    let mut details = Vec::with_capacity(signed_extensions.len());
+    for ext in signed_extensions {
+        let extra_value =
+            DecodedValue::decode_with_metadata(&mut extra, ext.ty.id(), metadata)?;
+        let additional_value = DecodedValue::decode_with_metadata(
+            &mut additional,
+            ext.additional_signed.id(),
+            metadata,
+        )?;
+        details.push(SignedExtensionDetails {
+            identifier: ext.identifier.clone(),
+            extra: extra_value,
+            additional: additional_value,
+        });
+    }
+
+    if !extra.is_empty() || !additional.is_empty() {
+        return Err(Error::Other(format!(
+            "{} signed extra and {} additional bytes are left over after decoding every signed extension",
+            extra.len(),
+            additional.len()
+        )))
+    }
+    Ok(details)
+}
+
+/// Check that the signed extensions which some [`crate::config::ExtrinsicParams`] provide
+/// values for, as given by `expected`, match up with those that the runtime takes values
+/// for. Extensions which take no values are skipped on both sides.
+pub(crate) fn check_signed_extensions(
+    metadata: &Metadata,
+    expected: &[&str],
+) -> Result<(), Error> {
+    let actual: Vec<&str> = metadata
+        .runtime_metadata()
+        .extrinsic
+        .signed_extensions
+        .iter()
+        .filter(|ext| {
+            !is_zero_sized(ext.ty.id(), metadata)
+                || !is_zero_sized(ext.additional_signed.id(), metadata)
+        })
+        .map(|ext| normalize_identifier(&ext.identifier))
+        .collect();
+    let expected: Vec<&str> =
+        expected.iter().map(|id| normalize_identifier(id)).collect();
+
+    if actual == expected {
+        return Ok(())
+    }
+
+    let missing: Vec<&str> = actual
+        .iter()
+        .filter(|id| !expected.contains(id))
+        .copied()
+        .collect();
+    let unexpected: Vec<&str> = expected
+        .iter()
+        .filter(|id| !actual.contains(id))
+        .copied()
+        .collect();
+    let mut problems = Vec::new();
+    if !missing.is_empty() {
+        problems.push(format!(
+            "no values are given for {}, which the runtime expects",
+            missing.join(", ")
+        ));
+    }
+    if !unexpected.is_empty() {
+        problems.push(format!(
+            "values are given for {}, which the runtime does not expect",
+            unexpected.join(", ")
+        ));
+    }
+    if problems.is_empty() {
+        problems.push(format!(
+            "the values are given in the order {}, but the runtime expects {}",
+            expected.join(", "),
+            actual.join(", ")
+        ));
+    }
+    Err(Error::Other(format!(
+        "The extrinsic params don't match the runtime's signed extensions: {}",
+        problems.join("; ")
+    )))
+}
+
+// `CheckEra` is the old name of `CheckMortality`.
+fn normalize_identifier(identifier: &str) -> &str {
+    match identifier {
+        "CheckEra" => "CheckMortality",
+        other => other,
+    }
+}
+
+// Does the type encode to nothing at all? Signed extensions such as `CheckWeight` are
+// unit-like structs, or hold nothing but a `PhantomData`.
+fn is_zero_sized(type_id: u32, metadata: &Metadata) -> bool {
+    let Some(ty) = metadata.types().resolve(type_id) else {
+        return false
+    };
+    match ty.type_def() {
+        TypeDef::Composite(composite) => {
+            composite
+                .fields()
+                .iter()
+                .all(|field| is_zero_sized(field.ty().id(), metadata))
+        }
+        TypeDef::Tuple(tuple) => {
+            tuple
+                .fields()
+                .iter()
+                .all(|field| is_zero_sized(field.id(), metadata))
+        }
+        TypeDef::Array(array) => {
+            array.len() == 0 || is_zero_sized(array.type_param().id(), metadata)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        config::{
+            extrinsic_params::MetadataHashMode,
+            polkadot::{
+                PolkadotExtrinsicParams,
+                PolkadotExtrinsicParamsBuilder,
+            },
+            substrate::{
+                SubstrateExtrinsicParams,
+                H256,
+            },
+            ExtrinsicParams,
+            PolkadotConfig,
+            SubstrateConfig,
+        },
+        test_utils::metadata,
+    };
+    use scale_value::{
+        Primitive,
+        ValueDef,
+    };
+
+    #[test]
+    fn decodes_each_signed_extension() {
+        let params = <PolkadotExtrinsicParams<PolkadotConfig> as ExtrinsicParams<
+            u32,
+            _,
+        >>::new(9000, 12, 5, Default::default(), Default::default());
+        let mut extra = Vec::new();
+        params.encode_extra_to(&mut extra);
+        let mut additional = Vec::new();
+        params.encode_additional_to(&mut additional);
+
+        let details = decode_signed_extensions(&metadata(), &extra, &additional).unwrap();
+        let identifiers: Vec<_> = details.iter().map(|d| &*d.identifier).collect();
+        assert!(identifiers.contains(&"CheckMortality"));
+        assert!(identifiers.contains(&"ChargeTransactionPayment"));
+
+        let primitive = |identifier: &str, additional: bool| {
+            let ext = details.iter().find(|d| d.identifier == identifier).unwrap();
+            let value = if additional {
+                &ext.additional
+            } else {
+                &ext.extra
+            };
+            // The values may be wrapped in a newtype, such as `Compact<Index>`.
+            let mut value = &value.value;
+            while let ValueDef::Composite(c) = value {
+                value = &c.values().next().unwrap().value;
+            }
+            match value {
+                ValueDef::Primitive(p) => p.clone(),
+                other => panic!("expected a primitive, got {other:?}"),
+            }
+        };
+        assert_eq!(primitive("CheckNonce", false), Primitive::u128(5));
+        assert_eq!(primitive("CheckSpecVersion", true), Primitive::u128(9000));
+        assert_eq!(primitive("CheckTxVersion", true), Primitive::u128(12));
+
+        // Bytes that don't belong to any signed extension are an error:
+        extra.push(0);
+        assert!(decode_signed_extensions(&metadata(), &extra, &additional).is_err());
+    }
+
+    fn expected_extensions<E: ExtrinsicParams<u32, H256>>(
+        other_params: E::OtherParams,
+    ) -> Vec<&'static str> {
+        E::new(9000, 12, 5, H256::zero(), other_params)
+            .signed_extensions()
+            .unwrap()
+    }
+
+    #[test]
+    fn checks_signed_extensions_against_the_runtime() {
+        let metadata = metadata();
+
+        // `CheckNonZeroSender` and `CheckWeight` take no values, so aren't needed:
+        let expected = expected_extensions::<PolkadotExtrinsicParams<PolkadotConfig>>(
+            Default::default(),
+        );
+        check_signed_extensions(&metadata, &expected).unwrap();
+
+        // Polkadot doesn't take tips in other assets:
+        let expected = expected_extensions::<SubstrateExtrinsicParams<SubstrateConfig>>(
+            Default::default(),
+        );
+        let err = check_signed_extensions(&metadata, &expected)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no values are given for ChargeTransactionPayment"));
+        assert!(err.contains("values are given for ChargeAssetTxPayment"));
+
+        // Nor does this runtime check the metadata hash:
+        let expected = expected_extensions::<PolkadotExtrinsicParams<PolkadotConfig>>(
+            PolkadotExtrinsicParamsBuilder::new()
+                .metadata_hash(MetadataHashMode::Enabled([1; 32])),
+        );
+        let err = check_signed_extensions(&metadata, &expected)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("values are given for CheckMetadataHash"));
+
+        // Extensions in the wrong order:
+        let mut expected = expected_extensions::<PolkadotExtrinsicParams<PolkadotConfig>>(
+            Default::default(),
+        );
+        expected.swap(0, 1);
+        let err = check_signed_extensions(&metadata, &expected)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("in the order CheckTxVersion, CheckSpecVersion"));
+    }
+}
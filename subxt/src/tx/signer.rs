@@ -33,7 +33,10 @@ pub use pair_signer::PairSigner;
 mod pair_signer {
     use super::Signer;
     use crate::Config;
-    use sp_core::Pair as PairT;
+    use sp_core::{
+        crypto::SecretStringError,
+        Pair as PairT,
+    };
     use sp_runtime::{
         traits::{
             IdentifyAccount,
@@ -70,6 +73,13 @@ mod pair_signer {
             }
         }
 
+        /// Creates a new [`Signer`] from some raw seed bytes (for instance the 32 byte seed
+        /// of an sr25519 or ed25519 key), without having to construct the [`sp_core::Pair`]
+        /// first. An error is returned if the seed is not the right length for `Pair`.
+        pub fn from_seed_bytes(seed: &[u8]) -> Result<Self, SecretStringError> {
+            Ok(Self::new(Pair::from_seed_slice(seed)?))
+        }
+
         /// Returns the [`sp_core::Pair`] implementation used to construct this.
         pub fn signer(&self) -> &Pair {
             &self.signer
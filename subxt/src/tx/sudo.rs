@@ -0,0 +1,142 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Wrap calls in a call to the `Sudo` pallet, so that they are dispatched with a root origin.
+
+use super::tx_payload::{
+    TxPayload,
+    ValidationDetails,
+};
+use crate::{
+    error::{
+        Error,
+        MetadataError,
+    },
+    metadata::{
+        EncodeWithMetadata,
+        Metadata,
+    },
+    utils::Weight,
+};
+use codec::Encode;
+use scale_info::TypeDef;
+
+/// A transaction payload which wraps some other call in `Sudo::sudo` or
+/// `Sudo::sudo_unchecked_weight`. Construct one using [`sudo()`] or
+/// [`sudo_unchecked_weight()`].
+///
+/// Encoding the call data fails if the runtime has no `Sudo` pallet. Only the key
+/// set in the `Sudo` pallet can successfully submit these.
+#[derive(Clone, Debug)]
+pub struct SudoTxPayload<Call> {
+    call: Call,
+    weight: Option<Weight>,
+}
+
+/// Wrap a call in `Sudo::sudo`, so that it is dispatched with a root origin.
+pub fn sudo<Call: TxPayload>(call: Call) -> SudoTxPayload<Call> {
+    SudoTxPayload { call, weight: None }
+}
+
+/// Wrap a call in `Sudo::sudo_unchecked_weight`, so that it is dispatched with a root
+/// origin and the weight given, rather than the weight that the call would normally
+/// have. This is useful for calls that would otherwise be too heavy to fit in a block.
+pub fn sudo_unchecked_weight<Call: TxPayload>(
+    call: Call,
+    weight: Weight,
+) -> SudoTxPayload<Call> {
+    SudoTxPayload {
+        call,
+        weight: Some(weight),
+    }
+}
+
+impl<Call> SudoTxPayload<Call> {
+    /// The call being wrapped.
+    pub fn call(&self) -> &Call {
+        &self.call
+    }
+}
+
+impl<Call: TxPayload> TxPayload for SudoTxPayload<Call> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let call_name = match self.weight {
+            Some(_) => "sudo_unchecked_weight",
+            None => "sudo",
+        };
+        let pallet = metadata.pallet("Sudo").map_err(|_| {
+            Error::Other(format!(
+                "Cannot construct a Sudo::{call_name} call: the runtime has no Sudo pallet"
+            ))
+        })?;
+
+        pallet.index().encode_to(out);
+        pallet.call_index(call_name)?.encode_to(out);
+        self.call.encode_call_data_to(metadata, out)?;
+        if let Some(weight) = &self.weight {
+            let weight_ty =
+                call_arg_ty(metadata, pallet.call_ty_id(), call_name, "weight")?;
+            weight.encode_with_metadata(weight_ty, metadata, out)?;
+        }
+        Ok(())
+    }
+
+    // The sudo calls themselves take any call, so we validate the call being wrapped.
+    fn validation_details(&self) -> Option<ValidationDetails<'_>> {
+        self.call.validation_details()
+    }
+}
+
+// Find the type of a named argument to some call.
+pub(super) fn call_arg_ty(
+    metadata: &Metadata,
+    call_ty_id: Option<u32>,
+    call_name: &str,
+    arg_name: &str,
+) -> Result<u32, Error> {
+    let call_ty_id = call_ty_id.ok_or(MetadataError::CallNotFound)?;
+    let call_ty = metadata
+        .resolve_type(call_ty_id)
+        .ok_or(MetadataError::TypeNotFound(call_ty_id))?;
+    let TypeDef::Variant(variants) = call_ty.type_def() else {
+        return Err(MetadataError::CallNotFound.into())
+    };
+    variants
+        .variants()
+        .iter()
+        .find(|v| v.name() == call_name)
+        .and_then(|v| {
+            v.fields()
+                .iter()
+                .find(|f| f.name().map(|n| n.as_str()) == Some(arg_name))
+        })
+        .map(|f| f.ty().id())
+        .ok_or_else(|| MetadataError::CallNotFound.into())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::metadata,
+        tx::dynamic,
+    };
+
+    #[test]
+    fn runtimes_without_sudo_are_rejected() {
+        // Polkadot has no Sudo pallet:
+        let metadata = metadata();
+        let remark = dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([1])],
+        );
+        let err = sudo(remark).encode_call_data(&metadata).unwrap_err();
+        assert!(err.to_string().contains("no Sudo pallet"), "{err}");
+    }
+}
@@ -2,9 +2,19 @@
 // This file is dual-licensed as Apache-2.0 or GPL-3.0.
 // see LICENSE for license details.
 
-use super::TxPayload;
+use super::{
+    pipeline::TxPipeline,
+    signed_extensions::{
+        check_signed_extensions,
+        decode_signed_extensions,
+        SignedExtensionDetails,
+    },
+    PreparedCall,
+    TxPayload,
+};
 use crate::{
     client::{
+        metadata_at_block,
         OfflineClientT,
         OnlineClientT,
     },
@@ -13,18 +23,30 @@ use crate::{
         ExtrinsicParams,
         Hasher,
     },
-    error::Error,
+    error::{
+        DispatchError,
+        Error,
+    },
+    metadata::Metadata,
+    rpc::types::{
+        FeeDetails,
+        RuntimeDispatchInfo,
+        RuntimeVersion,
+    },
     tx::{
         Signer as SignerT,
         TxProgress,
     },
     utils::{
+        weight::DispatchClass,
         Encoded,
         PhantomDataSendSync,
+        Weight,
     },
 };
 use codec::{
     Compact,
+    Decode,
     Encode,
 };
 use derivative::Derivative;
@@ -32,6 +54,36 @@ use derivative::Derivative;
 // This is returned from an API below, so expose it here.
 pub use crate::rpc::types::DryRunResult;
 
+/// The result of dry running an extrinsic at some block, with any errors decoded using the
+/// metadata of the runtime at that block. See [`SubmittableExtrinsic::dry_run_at()`].
+pub type DecodedDryRunResult = Result<(), DecodedDryRunError>;
+
+/// An error dry running an extrinsic at some block.
+#[derive(Debug)]
+pub enum DecodedDryRunError {
+    /// The extrinsic will not be included in the block. This holds the SCALE encoded
+    /// `TransactionValidityError` returned from the node.
+    TransactionValidityError(Vec<u8>),
+    /// The extrinsic will be included in the block, but the call failed to dispatch.
+    DispatchError(DispatchError),
+}
+
+/// The number of bytes set aside for the signer's address and signature when estimating the
+/// length of a signed extrinsic with [`TxClient::estimate_signed_len()`]. This is enough
+/// for the largest `MultiAddress::Id` and `MultiSignature` (an ECDSA signature), which are
+/// used by the Substrate and Polkadot configurations.
+pub const SIGNATURE_ALLOWANCE: usize = 33 + 66;
+
+/// The encoded "signed extra" and "additional" parameters of an extrinsic, as handed back
+/// from the closure given to [`TxClient::create_signed_with_raw_extra()`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RawSignedExtra {
+    /// The "signed extra" bytes, which are sent in the extrinsic and signed over.
+    pub extra: Vec<u8>,
+    /// The "additional" bytes, which are signed over but not sent.
+    pub additional: Vec<u8>,
+}
+
 /// A client for working with transactions.
 #[derive(Derivative)]
 #[derivative(Clone(bound = "Client: Clone"))]
@@ -85,6 +137,57 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
         Ok(bytes)
     }
 
+    /// Validate and encode a call once, handing back a [`PreparedCall`] which can be used
+    /// to create as many extrinsics as needed without encoding the call again. This is
+    /// handy when submitting the same call repeatedly.
+    pub fn prepare<Call>(&self, call: &Call) -> Result<PreparedCall, Error>
+    where
+        Call: TxPayload,
+    {
+        self.validate(call)?;
+        PreparedCall::new(call, &self.client.metadata())
+    }
+
+    /// Estimate the length in bytes of the signed extrinsic that would be created from
+    /// the call and parameters given, without needing a signer. [`SIGNATURE_ALLOWANCE`]
+    /// bytes are set aside for the signer's address and signature, so the estimate
+    /// is an upper bound for configurations using `MultiAddress` and `MultiSignature`.
+    ///
+    /// This is the length that transaction fees are computed from, and that counts towards
+    /// the block length limit. The exact length of an extrinsic that has already been
+    /// created is `SubmittableExtrinsic::encoded().len()`.
+    pub fn estimate_signed_len<Call>(
+        &self,
+        call: &Call,
+        account_nonce: T::Index,
+        other_params: <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams,
+    ) -> Result<usize, Error>
+    where
+        Call: TxPayload,
+    {
+        let call_data_len = self.call_data(call)?.len();
+        let extra_len = {
+            let runtime = self.client.runtime_version();
+            let params = <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::new(
+                runtime.spec_version,
+                runtime.transaction_version,
+                account_nonce,
+                self.client.genesis_hash(),
+                other_params,
+            );
+            let mut bytes = Vec::new();
+            params.encode_extra_to(&mut bytes);
+            bytes.len()
+        };
+
+        // The version byte, then the signature details, then the call data:
+        let inner_len = 1 + SIGNATURE_ALLOWANCE + extra_len + call_data_len;
+        let len_prefix = Compact(
+            u32::try_from(inner_len).expect("extrinsic size expected to be <4GB"),
+        );
+        Ok(len_prefix.encoded_size() + inner_len)
+    }
+
     /// Creates an unsigned extrinsic without submitting it.
     pub fn create_unsigned<Call>(
         &self,
@@ -142,38 +245,172 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
         let call_data = Encoded(self.call_data(call)?);
 
         // 3. Construct our custom additional/extra params.
-        let additional_and_extra_params = {
-            // Obtain spec version and transaction version from the runtime version of the client.
-            let runtime = self.client.runtime_version();
-            <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::new(
-                runtime.spec_version,
-                runtime.transaction_version,
-                account_nonce,
-                self.client.genesis_hash(),
-                other_params,
-            )
+        let RawSignedExtra { extra, additional } =
+            self.encode_extrinsic_params(account_nonce, other_params);
+
+        self.create_signed_from_parts(&call_data, signer, &extra, &additional)
+    }
+
+    /// Describe the signed extensions that an extrinsic created with
+    /// [`TxClient::create_signed_with_nonce()`] and the same nonce and parameters would be
+    /// signed with. Each of the runtime's signed extensions is handed back in the order that
+    /// the metadata lists them, along with the "signed extra" and "additional" values that
+    /// [`Config::ExtrinsicParams`] gives it.
+    ///
+    /// An error is returned if the encoded parameters don't line up with the signed
+    /// extensions that the runtime expects, which is a common cause of extrinsics being
+    /// rejected with a bad signature.
+    pub fn signed_extensions(
+        &self,
+        account_nonce: T::Index,
+        other_params: <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams,
+    ) -> Result<Vec<SignedExtensionDetails>, Error> {
+        let raw = self.encode_extrinsic_params(account_nonce, other_params);
+        self.describe_raw_signed_extra(&raw)
+    }
+
+    /// Like [`TxClient::signed_extensions()`], but describes the bytes given to
+    /// [`TxClient::create_signed_with_raw_extra()`].
+    pub fn describe_raw_signed_extra(
+        &self,
+        raw: &RawSignedExtra,
+    ) -> Result<Vec<SignedExtensionDetails>, Error> {
+        decode_signed_extensions(&self.client.metadata(), &raw.extra, &raw.additional)
+    }
+
+    /// The identifiers of the signed extensions that the runtime has, in the order that the
+    /// metadata lists them.
+    pub fn supported_signed_extensions(&self) -> Vec<String> {
+        self.client
+            .metadata()
+            .runtime_metadata()
+            .extrinsic
+            .signed_extensions
+            .iter()
+            .map(|ext| ext.identifier.clone())
+            .collect()
+    }
+
+    /// Check that [`Config::ExtrinsicParams`], constructed with the nonce and parameters
+    /// given, provides values for exactly the signed extensions that the runtime takes
+    /// values for, and in the same order. Extensions which take no values, such as
+    /// `CheckWeight`, are ignored.
+    ///
+    /// A mismatch means that every extrinsic will be rejected, usually with an unhelpful
+    /// "bad signature" error, so this is worth calling once at startup. The error says which
+    /// extensions are missing, unexpected or out of order. An error is also returned if the
+    /// extrinsic params don't say which extensions they provide values for.
+    pub fn check_signed_extensions(
+        &self,
+        account_nonce: T::Index,
+        other_params: <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams,
+    ) -> Result<(), Error> {
+        let params = self.extrinsic_params(account_nonce, other_params);
+        let Some(expected) = params.signed_extensions() else {
+            return Err(Error::Other(
+                "The extrinsic params don't say which signed extensions they provide values for"
+                    .into(),
+            ))
         };
+        check_signed_extensions(&self.client.metadata(), &expected)
+    }
+
+    // Construct the configured extrinsic params.
+    fn extrinsic_params(
+        &self,
+        account_nonce: T::Index,
+        other_params: <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams,
+    ) -> T::ExtrinsicParams {
+        // Obtain spec version and transaction version from the runtime version of the client.
+        let runtime = self.client.runtime_version();
+        <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::new(
+            runtime.spec_version,
+            runtime.transaction_version,
+            account_nonce,
+            self.client.genesis_hash(),
+            other_params,
+        )
+    }
+
+    // Construct the configured extrinsic params and encode them.
+    fn encode_extrinsic_params(
+        &self,
+        account_nonce: T::Index,
+        other_params: <T::ExtrinsicParams as ExtrinsicParams<T::Index, T::Hash>>::OtherParams,
+    ) -> RawSignedExtra {
+        let additional_and_extra_params =
+            self.extrinsic_params(account_nonce, other_params);
 
         tracing::debug!(
             "tx additional_and_extra_params: {:?}",
             additional_and_extra_params
         );
 
+        let mut extra = Vec::new();
+        additional_and_extra_params.encode_extra_to(&mut extra);
+        let mut additional = Vec::new();
+        additional_and_extra_params.encode_additional_to(&mut additional);
+        RawSignedExtra { extra, additional }
+    }
+
+    /// Creates a raw signed extrinsic without submitting it, using the "signed extra" and
+    /// "additional" bytes handed back from the `assemble` closure rather than those which
+    /// [`Config::ExtrinsicParams`] would produce. The closure is given the account that is
+    /// signing, the nonce given here, and the runtime version that the client is using.
+    ///
+    /// This is an escape hatch for chains whose signed extensions can't be expressed with
+    /// an [`ExtrinsicParams`] implementation.
+    ///
+    /// Both byte blobs must contain one entry for each of the runtime's signed extensions,
+    /// in the order that the metadata lists them:
+    ///
+    /// - `extra` is the SCALE encoded "signed extra" value of each extension, one after the
+    ///   other. These bytes are sent in the extrinsic, after the signature and before the
+    ///   call data.
+    /// - `additional` is the SCALE encoded "additional signed" value of each extension, one
+    ///   after the other. These bytes are not sent, but are signed over.
+    ///
+    /// The payload which is signed is the call data, then `extra`, then `additional`. This is
+    /// hashed with blake2-256 before signing if it's longer than 256 bytes.
+    pub fn create_signed_with_raw_extra<Call, Signer, F>(
+        &self,
+        call: &Call,
+        signer: &Signer,
+        account_nonce: T::Index,
+        assemble: F,
+    ) -> Result<SubmittableExtrinsic<T, C>, Error>
+    where
+        Call: TxPayload,
+        Signer: SignerT<T>,
+        F: FnOnce(&T::AccountId, T::Index, &RuntimeVersion) -> RawSignedExtra,
+    {
+        self.validate(call)?;
+        let call_data = Encoded(self.call_data(call)?);
+        let RawSignedExtra { extra, additional } = assemble(
+            signer.account_id(),
+            account_nonce,
+            &self.client.runtime_version(),
+        );
+        self.create_signed_from_parts(&call_data, signer, &extra, &additional)
+    }
+
+    // Sign and encode an extrinsic given the call data and the encoded "signed extra"
+    // and "additional" parameters.
+    fn create_signed_from_parts<Signer>(
+        &self,
+        call_data: &Encoded,
+        signer: &Signer,
+        extra: &[u8],
+        additional: &[u8],
+    ) -> Result<SubmittableExtrinsic<T, C>, Error>
+    where
+        Signer: SignerT<T>,
+    {
         // 4. Construct signature. This is compatible with the Encode impl
         //    for SignedPayload (which is this payload of bytes that we'd like)
         //    to sign. See:
         //    https://github.com/paritytech/substrate/blob/9a6d706d8db00abb6ba183839ec98ecd9924b1f8/primitives/runtime/src/generic/unchecked_extrinsic.rs#L215)
-        let signature = {
-            let mut bytes = Vec::new();
-            call_data.encode_to(&mut bytes);
-            additional_and_extra_params.encode_extra_to(&mut bytes);
-            additional_and_extra_params.encode_additional_to(&mut bytes);
-            if bytes.len() > 256 {
-                signer.sign(T::Hasher::hash_of(&Encoded(bytes)).as_ref())
-            } else {
-                signer.sign(&bytes)
-            }
-        };
+        let signature = signer.sign(&signer_payload(call_data, extra, additional));
 
         tracing::debug!("tx signature: {}", hex::encode(signature.encode()));
 
@@ -188,7 +425,7 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
             // the signature bytes
             signature.encode_to(&mut encoded_inner);
             // attach custom extra params
-            additional_and_extra_params.encode_extra_to(&mut encoded_inner);
+            encoded_inner.extend_from_slice(extra);
             // and now, call data
             call_data.encode_to(&mut encoded_inner);
             // now, prefix byte length:
@@ -211,6 +448,21 @@ impl<T: Config, C: OfflineClientT<T>> TxClient<T, C> {
     }
 }
 
+/// The bytes that are signed to produce the signature of an extrinsic: the call data, then
+/// the "signed extra" and "additional" parameters. Like the runtime, payloads longer than 256
+/// bytes are replaced with their blake2-256 hash, regardless of the [`Config::Hasher`] used.
+fn signer_payload(call_data: &Encoded, extra: &[u8], additional: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    call_data.encode_to(&mut bytes);
+    bytes.extend_from_slice(extra);
+    bytes.extend_from_slice(additional);
+    if bytes.len() > 256 {
+        sp_core_hashing::blake2_256(&bytes).to_vec()
+    } else {
+        bytes
+    }
+}
+
 impl<T, C> TxClient<T, C>
 where
     T: Config,
@@ -322,6 +574,27 @@ where
             .submit()
             .await
     }
+
+    /// Create a [`TxPipeline`] which signs and submits lots of calls concurrently using the
+    /// signers given, keeping track of their nonces locally.
+    pub fn pipeline<Signer: SignerT<T>>(
+        &self,
+        signers: Vec<Signer>,
+    ) -> TxPipeline<T, C, Signer> {
+        TxPipeline::new(self.client.clone(), signers)
+    }
+
+    /// Dry run some already-signed extrinsic bytes against the state at the given block.
+    /// See [`SubmittableExtrinsic::dry_run_at()`].
+    pub async fn dry_run_at(
+        &self,
+        signed_bytes: &[u8],
+        block_hash: T::Hash,
+    ) -> Result<DecodedDryRunResult, Error> {
+        SubmittableExtrinsic::from_bytes(self.client.clone(), signed_bytes.to_vec())
+            .dry_run_at(block_hash)
+            .await
+    }
 }
 
 /// This represents an extrinsic that has been signed and is ready to submit.
@@ -400,4 +673,354 @@ where
     pub async fn dry_run(&self, at: Option<T::Hash>) -> Result<DryRunResult, Error> {
         self.client.rpc().dry_run(self.encoded(), at).await
     }
+
+    /// Submits the extrinsic to the dry_run RPC, to test if it would have succeeded against
+    /// the state at the given block.
+    ///
+    /// Unlike [`SubmittableExtrinsic::dry_run()`], any `DispatchError` is decoded using the
+    /// metadata of the runtime that was active at that block.
+    pub async fn dry_run_at(
+        &self,
+        block_hash: T::Hash,
+    ) -> Result<DecodedDryRunResult, Error> {
+        let result_bytes = self
+            .client
+            .rpc()
+            .dry_run_raw(self.encoded(), Some(block_hash))
+            .await?;
+        let metadata = metadata_at_block(&self.client, block_hash).await?;
+        decode_dry_run_result_with_metadata(&result_bytes.0, &metadata)
+    }
+
+    /// Fetch the weight, class and fee that the node works out for this extrinsic at the
+    /// latest block.
+    ///
+    /// The `TransactionPaymentApi` runtime API is used if the runtime reports supporting
+    /// it, and otherwise the older `payment_queryInfo` RPC method is used if the node
+    /// exposes it. Fees are assumed to be `u128` balances.
+    pub async fn query_info(&self) -> Result<RuntimeDispatchInfo, Error> {
+        match fee_api(&self.client).await? {
+            FeeApi::RuntimeApi(version) => {
+                let bytes = self
+                    .client
+                    .rpc()
+                    .state_call(
+                        "TransactionPaymentApi_query_info",
+                        Some(&self.fee_api_params()),
+                        None,
+                    )
+                    .await?;
+                decode_query_info(&bytes, version)
+            }
+            FeeApi::Rpc => {
+                self.client
+                    .rpc()
+                    .payment_query_info(self.encoded(), None)
+                    .await
+            }
+        }
+    }
+
+    /// Fetch a breakdown of the fee that the node works out for this extrinsic at the
+    /// latest block. Like [`SubmittableExtrinsic::query_info()`], this falls back to the
+    /// `payment_queryFeeDetails` RPC method for older nodes.
+    pub async fn fee_details(&self) -> Result<FeeDetails, Error> {
+        match fee_api(&self.client).await? {
+            FeeApi::RuntimeApi(_) => {
+                let bytes = self
+                    .client
+                    .rpc()
+                    .state_call(
+                        "TransactionPaymentApi_query_fee_details",
+                        Some(&self.fee_api_params()),
+                        None,
+                    )
+                    .await?;
+                Ok(FeeDetails::decode(&mut &*bytes)?)
+            }
+            FeeApi::Rpc => {
+                self.client
+                    .rpc()
+                    .payment_query_fee_details(self.encoded(), None)
+                    .await
+            }
+        }
+    }
+
+    /// Estimate the fee that would be charged for this extrinsic, not including any tip.
+    /// See [`SubmittableExtrinsic::query_info()`].
+    pub async fn partial_fee_estimate(&self) -> Result<u128, Error> {
+        Ok(self.query_info().await?.partial_fee)
+    }
+
+    // The runtime API takes the extrinsic followed by its length.
+    fn fee_api_params(&self) -> Vec<u8> {
+        let encoded = self.encoded();
+        let mut params = encoded.to_vec();
+        (encoded.len() as u32).encode_to(&mut params);
+        params
+    }
+}
+
+// How fees can be estimated from the node.
+#[derive(Debug, PartialEq)]
+enum FeeApi {
+    // The `TransactionPaymentApi` runtime API, with the given version.
+    RuntimeApi(u32),
+    // The `payment_*` RPC methods.
+    Rpc,
+}
+
+async fn fee_api<T: Config, C: OnlineClientT<T>>(client: &C) -> Result<FeeApi, Error> {
+    let runtime_api_version = client
+        .runtime_version()
+        .runtime_api_version("TransactionPaymentApi");
+    if let Some(version) = runtime_api_version {
+        return Ok(FeeApi::RuntimeApi(version))
+    }
+    // Older nodes have dedicated RPC methods instead.
+    let methods = client.rpc().rpc_methods().await?;
+    rpc_fee_api(&methods.methods)
+}
+
+fn rpc_fee_api(rpc_methods: &[String]) -> Result<FeeApi, Error> {
+    if rpc_methods.iter().any(|m| m == "payment_queryInfo") {
+        return Ok(FeeApi::Rpc)
+    }
+    Err(Error::Other(
+        "The node supports neither the TransactionPaymentApi runtime API nor the payment_queryInfo RPC method".into(),
+    ))
+}
+
+/// Decode the `RuntimeDispatchInfo` returned from `TransactionPaymentApi_query_info`.
+/// Version 1 of the runtime API hands back a "v1" weight, and later versions a "v2" one.
+fn decode_query_info(
+    mut bytes: &[u8],
+    api_version: u32,
+) -> Result<RuntimeDispatchInfo, Error> {
+    let weight = if api_version < 2 {
+        Weight::from_ref_time(u64::decode(&mut bytes)?)
+    } else {
+        let ref_time = Compact::<u64>::decode(&mut bytes)?.0;
+        let proof_size = Compact::<u64>::decode(&mut bytes)?.0;
+        Weight::from_parts(ref_time, proof_size)
+    };
+    let class = match u8::decode(&mut bytes)? {
+        0 => DispatchClass::Normal,
+        1 => DispatchClass::Operational,
+        2 => DispatchClass::Mandatory,
+        n => return Err(Error::Other(format!("Invalid dispatch class {n}"))),
+    };
+    let partial_fee = u128::decode(&mut bytes)?;
+    Ok(RuntimeDispatchInfo {
+        weight,
+        class,
+        partial_fee,
+    })
+}
+
+/// Decode the `ApplyExtrinsicResult` returned from `system_dryRun`, which is a
+/// `Result<Result<(), DispatchError>, TransactionValidityError>`, resolving any
+/// `DispatchError` using the metadata given.
+fn decode_dry_run_result_with_metadata(
+    bytes: &[u8],
+    metadata: &Metadata,
+) -> Result<DecodedDryRunResult, Error> {
+    match bytes {
+        [0, 0] => Ok(Ok(())),
+        [0, 1, dispatch_error @ ..] if !dispatch_error.is_empty() => {
+            Ok(Err(DecodedDryRunError::DispatchError(
+                DispatchError::decode_from(dispatch_error, metadata),
+            )))
+        }
+        [1, validity_error @ ..] if !validity_error.is_empty() => {
+            Ok(Err(DecodedDryRunError::TransactionValidityError(
+                validity_error.to_vec(),
+            )))
+        }
+        _ => Err(codec::Error::from("Could not decode ApplyExtrinsicResult").into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use frame_metadata::{
+        ExtrinsicMetadata,
+        RuntimeMetadataPrefixed,
+        RuntimeMetadataV14,
+    };
+    use scale_info::meta_type;
+    use sp_runtime::{
+        transaction_validity::{
+            InvalidTransaction as SpInvalidTransaction,
+            TransactionValidityError as SpTransactionValidityError,
+        },
+        ApplyExtrinsicResult as SpApplyExtrinsicResult,
+        DispatchError as SpDispatchError,
+    };
+
+    fn empty_metadata() -> Metadata {
+        let extrinsic = ExtrinsicMetadata {
+            ty: meta_type::<()>(),
+            version: 0,
+            signed_extensions: vec![],
+        };
+        let v14 = RuntimeMetadataV14::new(vec![], extrinsic, meta_type::<()>());
+        let runtime_metadata: RuntimeMetadataPrefixed = v14.into();
+        Metadata::try_from(runtime_metadata).unwrap()
+    }
+
+    fn decode(result: SpApplyExtrinsicResult) -> DecodedDryRunResult {
+        decode_dry_run_result_with_metadata(&result.encode(), &empty_metadata()).unwrap()
+    }
+
+    #[test]
+    fn long_signer_payloads_are_hashed() {
+        let extra = [1u8; 10];
+        let additional = [2u8; 40];
+
+        // A payload of exactly 256 bytes is signed as it is:
+        let call_data = Encoded(vec![3; 206]);
+        let payload = signer_payload(&call_data, &extra, &additional);
+        assert_eq!(payload.len(), 256);
+        assert_eq!(payload, [&call_data.0[..], &extra, &additional].concat());
+
+        // One byte longer, and its blake2-256 hash is signed instead:
+        let call_data = Encoded(vec![3; 207]);
+        let payload = signer_payload(&call_data, &extra, &additional);
+        let expected = [&call_data.0[..], &extra, &additional].concat();
+        assert_eq!(expected.len(), 257);
+        assert_eq!(payload, sp_core_hashing::blake2_256(&expected));
+    }
+
+    #[test]
+    fn decodes_substrate_dry_run_results() {
+        assert!(matches!(decode(Ok(Ok(()))), Ok(())));
+
+        let validity_error =
+            SpTransactionValidityError::Invalid(SpInvalidTransaction::BadProof);
+        assert!(matches!(
+            decode(Err(validity_error)),
+            Err(DecodedDryRunError::TransactionValidityError(bytes)) if bytes == validity_error.encode()
+        ));
+
+        // The metadata doesn't know about `DispatchError`, so we'll get the bytes back.
+        assert!(matches!(
+            decode(Ok(Err(SpDispatchError::BadOrigin))),
+            Err(DecodedDryRunError::DispatchError(DispatchError::Other(bytes))) if bytes == SpDispatchError::BadOrigin.encode()
+        ));
+    }
+
+    #[test]
+    fn rejects_invalid_dry_run_results() {
+        for bytes in [&[][..], &[0], &[0, 1], &[0, 2], &[2, 0]] {
+            assert!(
+                decode_dry_run_result_with_metadata(bytes, &empty_metadata()).is_err()
+            );
+        }
+    }
+
+    #[cfg(feature = "substrate-compat")]
+    #[test]
+    fn signed_len_estimate_is_an_upper_bound() {
+        use crate::{
+            test_utils::offline_client,
+            tx::PairSigner,
+        };
+        use sp_core::Pair;
+
+        let client = offline_client();
+        let tx_client = client.tx();
+
+        let remark = crate::tx::dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([0; 300])],
+        );
+        let estimate = tx_client
+            .estimate_signed_len(&remark, 1, Default::default())
+            .unwrap();
+
+        let sr25519 = PairSigner::new(sp_core::sr25519::Pair::from_seed(&[1; 32]));
+        let sr25519_len = tx_client
+            .create_signed_with_nonce(&remark, &sr25519, 1, Default::default())
+            .unwrap()
+            .encoded()
+            .len();
+        // sr25519 signatures are a byte shorter than ECDSA ones:
+        assert_eq!(estimate, sr25519_len + 1);
+
+        let ecdsa = PairSigner::new(sp_core::ecdsa::Pair::from_seed(&[1; 32]));
+        let ecdsa_len = tx_client
+            .create_signed_with_nonce(&remark, &ecdsa, 1, Default::default())
+            .unwrap()
+            .encoded()
+            .len();
+        assert_eq!(estimate, ecdsa_len);
+    }
+
+    #[cfg(feature = "substrate-compat")]
+    #[test]
+    fn raw_signed_extra_matches_extrinsic_params() {
+        use crate::{
+            config::ExtrinsicParams,
+            test_utils::offline_client,
+            tx::PairSigner,
+            PolkadotConfig,
+        };
+        use sp_core::Pair;
+
+        let client = offline_client();
+        let tx_client = client.tx();
+
+        let remark = crate::tx::dynamic(
+            "System",
+            "remark",
+            vec![crate::dynamic::Value::from_bytes([0; 8])],
+        );
+        // ECDSA signatures are deterministic, so the extrinsics can be compared.
+        let signer = PairSigner::new(sp_core::ecdsa::Pair::from_seed(&[1; 32]));
+
+        let expected = tx_client
+            .create_signed_with_nonce(&remark, &signer, 3, Default::default())
+            .unwrap();
+        let actual = tx_client
+            .create_signed_with_raw_extra(&remark, &signer, 3, |_, nonce, version| {
+                let params = <PolkadotConfig as Config>::ExtrinsicParams::new(
+                    version.spec_version,
+                    version.transaction_version,
+                    nonce,
+                    Default::default(),
+                    Default::default(),
+                );
+                let mut raw = RawSignedExtra::default();
+                params.encode_extra_to(&mut raw.extra);
+                params.encode_additional_to(&mut raw.additional);
+                raw
+            })
+            .unwrap();
+        assert_eq!(actual.encoded(), expected.encoded());
+    }
+
+    #[test]
+    fn decodes_fee_info_from_either_runtime_api_version() {
+        let v1 = (1234u64, 1u8, 5000u128).encode();
+        let info = decode_query_info(&v1, 1).unwrap();
+        assert_eq!(info.weight, Weight::from_ref_time(1234));
+        assert_eq!(info.class, DispatchClass::Operational);
+        assert_eq!(info.partial_fee, 5000);
+
+        let v2 = (Compact(1234u64), Compact(56u64), 0u8, 5000u128).encode();
+        let info = decode_query_info(&v2, 2).unwrap();
+        assert_eq!(info.weight, Weight::from_parts(1234, 56));
+        assert_eq!(info.class, DispatchClass::Normal);
+    }
+
+    #[test]
+    fn falls_back_to_the_payment_rpc_methods() {
+        let methods = vec!["payment_queryInfo".to_string()];
+        assert_eq!(rpc_fee_api(&methods).unwrap(), FeeApi::Rpc);
+        assert!(rpc_fee_api(&["state_call".to_string()]).is_err());
+    }
 }
@@ -14,8 +14,10 @@ use crate::{
     metadata::Metadata,
 };
 use codec::Encode;
+use scale_info::TypeDef;
 use scale_value::{
     Composite,
+    Primitive,
     ValueDef,
     Variant,
 };
@@ -45,6 +47,19 @@ pub trait TxPayload {
     fn validation_details(&self) -> Option<ValidationDetails<'_>> {
         None
     }
+
+    /// Check that the call data that this payload encodes to is what's expected before
+    /// an extrinsic is built from it: encoding the call data of the [`CallHashChecked`]
+    /// payload handed back fails unless the blake2-256 hash of it is the hash given.
+    fn expect_call_hash(self, hash: [u8; 32]) -> CallHashChecked<Self>
+    where
+        Self: Sized,
+    {
+        CallHashChecked {
+            payload: self,
+            expected_hash: hash,
+        }
+    }
 }
 
 pub struct ValidationDetails<'a> {
@@ -127,6 +142,7 @@ pub struct DynamicTxPayload<'a> {
     pallet_name: Cow<'a, str>,
     call_name: Cow<'a, str>,
     fields: Composite<()>,
+    max_lens: Vec<(Cow<'a, str>, usize)>,
 }
 
 impl<'a> DynamicTxPayload<'a> {
@@ -140,6 +156,76 @@ impl<'a> DynamicTxPayload<'a> {
         &self.call_name
     }
 
+    /// Check, when the call data is encoded, that the named argument contains no more
+    /// than `max_len` items (or bytes, if it's given as a string), returning an error
+    /// if it exceeds this rather than having the transaction rejected on-chain.
+    ///
+    /// This is intended for `BoundedVec` and similar arguments. The metadata doesn't
+    /// record the bounds of these types, so they need to be provided here. Pallets often
+    /// expose them as constants, which can be looked up with [`crate::constants`].
+    pub fn with_max_len(
+        mut self,
+        arg_name: impl Into<Cow<'a, str>>,
+        max_len: usize,
+    ) -> Self {
+        self.max_lens.push((arg_name.into(), max_len));
+        self
+    }
+
+    // Check each argument that has been given a maximum length against it.
+    fn validate_lens(&self, metadata: &Metadata, call_ty_id: u32) -> Result<(), Error> {
+        if self.max_lens.is_empty() {
+            return Ok(())
+        }
+
+        let call_ty = metadata
+            .resolve_type(call_ty_id)
+            .ok_or(MetadataError::TypeNotFound(call_ty_id))?;
+        let TypeDef::Variant(variants) = call_ty.type_def() else {
+            return Err(MetadataError::CallNotFound.into())
+        };
+        let call = variants
+            .variants()
+            .iter()
+            .find(|v| v.name() == &*self.call_name)
+            .ok_or(MetadataError::CallNotFound)?;
+
+        for (arg_name, max_len) in &self.max_lens {
+            let idx = call
+                .fields()
+                .iter()
+                .position(|f| f.name().map(|n| n.as_str()) == Some(&**arg_name))
+                .ok_or_else(|| {
+                    Error::Other(format!(
+                        "{}::{} has no argument called {arg_name}",
+                        self.pallet_name, self.call_name
+                    ))
+                })?;
+            let value = match &self.fields {
+                Composite::Named(fields) => {
+                    fields
+                        .iter()
+                        .find(|(n, _)| n == &**arg_name)
+                        .map(|(_, v)| v)
+                }
+                Composite::Unnamed(fields) => fields.get(idx),
+            };
+            let len = match value.map(|v| &v.value) {
+                Some(ValueDef::Composite(c)) => c.len(),
+                Some(ValueDef::Primitive(Primitive::String(s))) => s.len(),
+                // Any other value can't be a collection, so encoding will fail anyway.
+                _ => continue,
+            };
+            if len > *max_len {
+                return Err(Error::Other(format!(
+                    "The {arg_name} argument to {}::{} has a length of {len}, which exceeds max length {max_len}",
+                    self.pallet_name, self.call_name
+                )))
+            }
+        }
+        Ok(())
+    }
+
     /// Convert the dynamic payload into a [`Value`]. This is useful
     /// if you need to submit this as part of a larger call.
     pub fn into_value(self) -> Value<()> {
@@ -165,6 +251,7 @@ pub fn dynamic<'a>(
         pallet_name: pallet_name.into(),
         call_name: call_name.into(),
         fields: fields.into(),
+        max_lens: Vec::new(),
     }
 }
 
@@ -176,6 +263,7 @@ impl<'a> TxPayload for DynamicTxPayload<'a> {
     ) -> Result<(), Error> {
         let pallet = metadata.pallet(&self.pallet_name)?;
         let call_id = pallet.call_ty_id().ok_or(MetadataError::CallNotFound)?;
+        self.validate_lens(metadata, call_id)?;
         let call_value = Value {
             context: (),
             value: ValueDef::Variant(Variant {
@@ -189,3 +277,180 @@ impl<'a> TxPayload for DynamicTxPayload<'a> {
         Ok(())
     }
 }
+
+/// A transaction payload whose call data must hash to a known value. This guards against
+/// the call being encoded differently than expected, for instance because the types used
+/// to build it don't line up with the runtime. Construct one using
+/// [`TxPayload::expect_call_hash()`].
+#[derive(Clone, Debug)]
+pub struct CallHashChecked<Call> {
+    payload: Call,
+    expected_hash: [u8; 32],
+}
+
+impl<Call> CallHashChecked<Call> {
+    /// The payload whose call data is being checked.
+    pub fn payload(&self) -> &Call {
+        &self.payload
+    }
+
+    /// The blake2-256 hash that the call data is expected to have.
+    pub fn expected_hash(&self) -> [u8; 32] {
+        self.expected_hash
+    }
+}
+
+impl<Call: TxPayload> TxPayload for CallHashChecked<Call> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let call_data = self.payload.encode_call_data(metadata)?;
+        let hash = sp_core_hashing::blake2_256(&call_data);
+        if hash != self.expected_hash {
+            return Err(Error::Other(format!(
+                "The call data has hash 0x{}, but 0x{} was expected",
+                hex::encode(hash),
+                hex::encode(self.expected_hash)
+            )))
+        }
+        out.extend(call_data);
+        Ok(())
+    }
+
+    fn validation_details(&self) -> Option<ValidationDetails<'_>> {
+        self.payload.validation_details()
+    }
+}
+
+/// A call which has been encoded up front, so that it can be signed and submitted many
+/// times (with different nonces or eras, say) without being encoded each time. Obtain one
+/// using [`crate::tx::TxClient::prepare()`].
+///
+/// The call data is encoded according to the metadata at the time that the call was
+/// prepared. The validation details of the original call are kept, so creating an
+/// extrinsic from a static call which no longer lines up with the metadata still fails,
+/// but dynamic calls should be prepared again after runtime upgrades.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PreparedCall {
+    call_data: Vec<u8>,
+    validation: Option<(String, String, [u8; 32])>,
+}
+
+impl PreparedCall {
+    pub(crate) fn new<Call: TxPayload>(
+        call: &Call,
+        metadata: &Metadata,
+    ) -> Result<Self, Error> {
+        let call_data = call.encode_call_data(metadata)?;
+        let validation = call.validation_details().map(|details| {
+            (
+                details.pallet_name.to_owned(),
+                details.call_name.to_owned(),
+                details.hash,
+            )
+        });
+        Ok(PreparedCall {
+            call_data,
+            validation,
+        })
+    }
+
+    /// The SCALE encoded call data.
+    pub fn call_data(&self) -> &[u8] {
+        &self.call_data
+    }
+}
+
+impl TxPayload for PreparedCall {
+    fn encode_call_data_to(
+        &self,
+        _metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        out.extend(&self.call_data);
+        Ok(())
+    }
+
+    fn validation_details(&self) -> Option<ValidationDetails<'_>> {
+        self.validation
+            .as_ref()
+            .map(|(pallet_name, call_name, hash)| {
+                ValidationDetails {
+                    pallet_name,
+                    call_name,
+                    hash: *hash,
+                }
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::metadata;
+
+    #[test]
+    fn arguments_longer_than_their_bound_are_rejected() {
+        let metadata = metadata();
+        let remark = |bytes: &[u8]| {
+            dynamic("System", "remark", vec![Value::from_bytes(bytes)])
+                .with_max_len("remark", 4)
+        };
+
+        assert!(remark(&[1; 4]).encode_call_data(&metadata).is_ok());
+        let err = remark(&[1; 5]).encode_call_data(&metadata).unwrap_err();
+        assert!(err.to_string().contains("exceeds max length 4"), "{err}");
+
+        let err = dynamic("System", "remark", vec![Value::from_bytes([1])])
+            .with_max_len("not_an_arg", 4)
+            .encode_call_data(&metadata)
+            .unwrap_err();
+        assert!(err.to_string().contains("no argument called"), "{err}");
+    }
+
+    #[test]
+    fn call_data_must_have_the_expected_hash() {
+        let metadata = metadata();
+        let remark = || dynamic("System", "remark", vec![Value::from_bytes([1, 2, 3])]);
+        let call_data = remark().encode_call_data(&metadata).unwrap();
+        let hash = sp_core_hashing::blake2_256(&call_data);
+
+        let checked = remark().expect_call_hash(hash);
+        assert_eq!(checked.encode_call_data(&metadata).unwrap(), call_data);
+
+        let err = remark()
+            .expect_call_hash([0; 32])
+            .encode_call_data(&metadata)
+            .unwrap_err();
+        assert!(err.to_string().contains("was expected"), "{err}");
+    }
+
+    #[test]
+    fn prepared_calls_keep_their_call_data_and_validation_details() {
+        let metadata = metadata();
+        let remark = dynamic("System", "remark", vec![Value::from_bytes([1, 2, 3])]);
+        let prepared = PreparedCall::new(&remark, &metadata).unwrap();
+        assert_eq!(
+            prepared.call_data(),
+            &remark.encode_call_data(&metadata).unwrap()[..]
+        );
+        assert!(prepared.validation_details().is_none());
+
+        let call_data = prepared.call_data().to_vec();
+        let hash = metadata.call_hash("System", "remark").unwrap();
+        let remark = StaticTxPayload::new(
+            "System",
+            "remark",
+            crate::utils::Encoded(call_data),
+            hash,
+        );
+        let prepared = PreparedCall::new(&remark, &metadata).unwrap();
+        let details = prepared.validation_details().unwrap();
+        assert_eq!(
+            (details.pallet_name, details.call_name, details.hash),
+            ("System", "remark", hash)
+        );
+    }
+}
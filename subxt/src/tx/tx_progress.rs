@@ -19,6 +19,7 @@ use crate::{
         Subscription,
         SubstrateTxStatus,
     },
+    tx::BatchResult,
     Config,
 };
 use derivative::Derivative;
@@ -73,6 +74,56 @@ where
         self.next().await
     }
 
+    /// Drive the status stream until `f` picks out a status, and return what `f` hands back
+    /// for it, or an error if the subscription ends first. `f` is given each status as it's
+    /// emitted, and returns `None` to keep waiting. This is the basis of the other `wait_for_*`
+    /// methods, and can be used to wait for some other status.
+    ///
+    /// **Note:** consumes `self`. If you'd like to perform multiple actions as the state of the
+    /// transaction progresses, use [`TxProgress::next_item()`] instead.
+    ///
+    /// **Note:** a `FinalityTimeout` status which `f` doesn't pick out ends the subscription,
+    /// and so is returned as an error.
+    pub async fn wait_for_status<R>(
+        mut self,
+        mut f: impl FnMut(TxStatus<T, C>) -> Option<R>,
+    ) -> Result<R, Error> {
+        while let Some(status) = self.next_item().await {
+            let status = status?;
+            let timed_out = matches!(status, TxStatus::FinalityTimeout(_));
+            if let Some(res) = f(status) {
+                return Ok(res)
+            }
+            if timed_out {
+                return Err(TransactionError::FinalitySubscriptionTimeout.into())
+            }
+        }
+        Err(RpcError::SubscriptionDropped.into())
+    }
+
+    /// Wait for the transaction to be broadcast to other nodes, and return the peers that
+    /// it was broadcast to.
+    ///
+    /// Nodes without peers, such as development nodes, never broadcast transactions. If the
+    /// transaction makes it into a block before it's reported as broadcast, this returns an
+    /// empty list of peers rather than waiting forever.
+    ///
+    /// **Note:** consumes `self`. If you'd like to perform multiple actions as the state of the
+    /// transaction progresses, use [`TxProgress::next_item()`] instead.
+    ///
+    /// **Note:** as with [`TxProgress::wait_for_in_block()`], statuses like `Invalid` and
+    /// `Usurped` are ignored.
+    pub async fn wait_for_broadcast(self) -> Result<Vec<String>, Error> {
+        self.wait_for_status(|status| {
+            match status {
+                TxStatus::Broadcast(peers) => Some(peers),
+                TxStatus::InBlock(_) | TxStatus::Finalized(_) => Some(Vec::new()),
+                _ => None,
+            }
+        })
+        .await
+    }
+
     /// Wait for the transaction to be in a block (but not necessarily finalized), and return
     /// an [`TxInBlock`] instance when this happens, or an error if there was a problem
     /// waiting for this to happen.
@@ -84,20 +135,16 @@ where
     /// may well indicate with some probability that the transaction will not make it into a block,
     /// there is no guarantee that this is true. Thus, we prefer to "play it safe" here. Use the lower
     /// level [`TxProgress::next_item()`] API if you'd like to handle these statuses yourself.
-    pub async fn wait_for_in_block(mut self) -> Result<TxInBlock<T, C>, Error> {
-        while let Some(status) = self.next_item().await {
-            match status? {
+    pub async fn wait_for_in_block(self) -> Result<TxInBlock<T, C>, Error> {
+        self.wait_for_status(|status| {
+            match status {
                 // Finalized or otherwise in a block! Return.
-                TxStatus::InBlock(s) | TxStatus::Finalized(s) => return Ok(s),
-                // Error scenarios; return the error.
-                TxStatus::FinalityTimeout(_) => {
-                    return Err(TransactionError::FinalitySubscriptionTimeout.into())
-                }
+                TxStatus::InBlock(s) | TxStatus::Finalized(s) => Some(s),
                 // Ignore anything else and wait for next status event:
-                _ => continue,
+                _ => None,
             }
-        }
-        Err(RpcError::SubscriptionDropped.into())
+        })
+        .await
     }
 
     /// Wait for the transaction to be finalized, and return a [`TxInBlock`]
@@ -110,20 +157,16 @@ where
     /// may well indicate with some probability that the transaction will not make it into a block,
     /// there is no guarantee that this is true. Thus, we prefer to "play it safe" here. Use the lower
     /// level [`TxProgress::next_item()`] API if you'd like to handle these statuses yourself.
-    pub async fn wait_for_finalized(mut self) -> Result<TxInBlock<T, C>, Error> {
-        while let Some(status) = self.next_item().await {
-            match status? {
+    pub async fn wait_for_finalized(self) -> Result<TxInBlock<T, C>, Error> {
+        self.wait_for_status(|status| {
+            match status {
                 // Finalized! Return.
-                TxStatus::Finalized(s) => return Ok(s),
-                // Error scenarios; return the error.
-                TxStatus::FinalityTimeout(_) => {
-                    return Err(TransactionError::FinalitySubscriptionTimeout.into())
-                }
+                TxStatus::Finalized(s) => Some(s),
                 // Ignore and wait for next status event:
-                _ => continue,
+                _ => None,
             }
-        }
-        Err(RpcError::SubscriptionDropped.into())
+        })
+        .await
     }
 
     /// Wait for the transaction to be finalized, and for the transaction events to indicate
@@ -353,6 +396,40 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
         Ok(events)
     }
 
+    /// Work out what happened to each of the calls in a batch submitted with this
+    /// transaction (see [`crate::tx::batch()`] and friends), returning an error if the
+    /// transaction as a whole failed (as a `batch_all` does if any of its calls fail).
+    ///
+    /// ```rust,no_run
+    /// # use subxt::{tx, OnlineClient, PolkadotConfig};
+    /// # async fn example<S: tx::Signer<PolkadotConfig> + Send + Sync>(
+    /// #     api: OnlineClient<PolkadotConfig>,
+    /// #     signer: &S,
+    /// #     calls: Vec<tx::DynamicTxPayload<'static>>,
+    /// # ) -> Result<(), subxt::Error> {
+    /// let result = api
+    ///     .tx()
+    ///     .sign_and_submit_then_watch_default(&tx::batch(calls), signer)
+    ///     .await?
+    ///     .wait_for_finalized()
+    ///     .await?
+    ///     .fetch_batch_result()
+    ///     .await?;
+    ///
+    /// if let Some((index, error)) = result.interrupted {
+    ///     println!("Call {index} failed: {error:?}");
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// **Note:** This has to download block details from the node and decode events
+    /// from them.
+    pub async fn fetch_batch_result(&self) -> Result<BatchResult, Error> {
+        let events = self.wait_for_success().await?;
+        BatchResult::from_events(events.iter(), &self.client.metadata())
+    }
+
     /// Fetch all of the events associated with this transaction. This succeeds whether
     /// the transaction was a success or not; it's up to you to handle the error and
     /// success events however you prefer.
@@ -389,3 +466,80 @@ impl<T: Config, C: OnlineClientT<T>> TxInBlock<T, C> {
         ))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        test_utils::{
+            online_client,
+            subscription,
+            MockRpc,
+        },
+        OnlineClient,
+        PolkadotConfig,
+    };
+    use std::sync::Arc;
+
+    async fn progress(
+        statuses: Vec<serde_json::Value>,
+    ) -> TxProgress<PolkadotConfig, OnlineClient<PolkadotConfig>> {
+        // A node which hands back the given statuses to any subscription.
+        let rpc = MockRpc::new(|method, _| panic!("unexpected method {method}"))
+            .with_subscriptions(move |_, _| Ok(subscription(statuses.clone())));
+        let client = online_client(Arc::new(rpc));
+        let sub = client.rpc().watch_extrinsic(()).await.unwrap();
+        TxProgress::new(sub, client, Default::default())
+    }
+
+    fn block_hash(n: u8) -> <PolkadotConfig as Config>::Hash {
+        [n; 32].into()
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_status_asked_for() {
+        let in_block = serde_json::json!({ "inBlock": block_hash(1) });
+        let statuses = vec![
+            serde_json::json!("ready"),
+            serde_json::json!({ "broadcast": ["peer"] }),
+            in_block.clone(),
+            serde_json::json!({ "finalized": block_hash(2) }),
+        ];
+
+        let peers = progress(statuses.clone()).await.wait_for_broadcast().await;
+        assert_eq!(peers.unwrap(), vec!["peer".to_owned()]);
+        let in_block = progress(statuses.clone()).await.wait_for_in_block().await;
+        assert_eq!(in_block.unwrap().block_hash(), block_hash(1));
+        let finalized = progress(statuses.clone()).await.wait_for_finalized().await;
+        assert_eq!(finalized.unwrap().block_hash(), block_hash(2));
+
+        // Transactions that are never broadcast are done with broadcasting once in a block:
+        let peers = progress(statuses[2..].to_vec())
+            .await
+            .wait_for_broadcast()
+            .await;
+        assert_eq!(peers.unwrap(), Vec::<String>::new());
+
+        // The subscription ending first is an error:
+        let res = progress(statuses[..2].to_vec())
+            .await
+            .wait_for_in_block()
+            .await;
+        assert!(matches!(
+            res,
+            Err(Error::Rpc(RpcError::SubscriptionDropped))
+        ));
+        let res = progress(vec![
+            serde_json::json!({ "finalityTimeout": block_hash(1) }),
+        ])
+        .await
+        .wait_for_status(|status| status.as_in_block().map(|_| ()))
+        .await;
+        assert!(matches!(
+            res,
+            Err(Error::Transaction(
+                TransactionError::FinalitySubscriptionTimeout
+            ))
+        ));
+    }
+}
@@ -0,0 +1,209 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Wrap several calls in a single call to the `Utility` pallet, and work out what
+//! happened to each of them from the events that the batch emits.
+
+use super::tx_payload::TxPayload;
+use crate::{
+    error::{
+        DispatchError,
+        Error,
+    },
+    events::EventDetails,
+    metadata::Metadata,
+};
+use codec::{
+    Compact,
+    Decode,
+    Encode,
+};
+
+/// A transaction payload which wraps a list of calls in `Utility::batch`,
+/// `Utility::batch_all` or `Utility::force_batch`. Construct one using [`batch()`],
+/// [`batch_all()`] or [`force_batch()`].
+///
+/// Encoding the call data fails if the runtime has no `Utility` pallet.
+#[derive(Clone, Debug)]
+pub struct BatchTxPayload<Call> {
+    calls: Vec<Call>,
+    call_name: &'static str,
+}
+
+/// Wrap some calls in `Utility::batch`. The calls are dispatched in order, stopping at
+/// the first one that fails. The calls before it are not reverted.
+pub fn batch<Call: TxPayload>(calls: Vec<Call>) -> BatchTxPayload<Call> {
+    BatchTxPayload {
+        calls,
+        call_name: "batch",
+    }
+}
+
+/// Wrap some calls in `Utility::batch_all`. The calls are dispatched in order, and if
+/// any of them fail then they're all reverted.
+pub fn batch_all<Call: TxPayload>(calls: Vec<Call>) -> BatchTxPayload<Call> {
+    BatchTxPayload {
+        calls,
+        call_name: "batch_all",
+    }
+}
+
+/// Wrap some calls in `Utility::force_batch`. Every call is dispatched in order, even
+/// if some of them fail.
+pub fn force_batch<Call: TxPayload>(calls: Vec<Call>) -> BatchTxPayload<Call> {
+    BatchTxPayload {
+        calls,
+        call_name: "force_batch",
+    }
+}
+
+impl<Call> BatchTxPayload<Call> {
+    /// The calls being batched.
+    pub fn calls(&self) -> &[Call] {
+        &self.calls
+    }
+}
+
+impl<Call: TxPayload> TxPayload for BatchTxPayload<Call> {
+    fn encode_call_data_to(
+        &self,
+        metadata: &Metadata,
+        out: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let pallet = metadata.pallet("Utility").map_err(|_| {
+            Error::Other(format!(
+                "Cannot construct a Utility::{} call: the runtime has no Utility pallet",
+                self.call_name
+            ))
+        })?;
+
+        pallet.index().encode_to(out);
+        pallet.call_index(self.call_name)?.encode_to(out);
+        // The call data of each call is the encoding of a `RuntimeCall`.
+        Compact(self.calls.len() as u32).encode_to(out);
+        for call in &self.calls {
+            call.encode_call_data_to(metadata, out)?;
+        }
+        Ok(())
+    }
+}
+
+/// What happened to each of the calls in a batch, as worked out from the `Utility`
+/// pallet's events. Obtain one using [`crate::tx::TxInBlock::fetch_batch_result()`].
+#[derive(Debug)]
+pub struct BatchResult {
+    /// The result of each call that was dispatched, in order. For `batch` and
+    /// `batch_all`, these are all `Ok`, since the batch stops (or is reverted) when
+    /// a call fails. For `force_batch`, any of these may have failed.
+    pub items: Vec<Result<(), DispatchError>>,
+    /// If a `batch` was interrupted, the index of the call which failed and the error
+    /// that it failed with. The calls after this one were not dispatched.
+    pub interrupted: Option<(u32, DispatchError)>,
+}
+
+impl BatchResult {
+    /// Work out what happened to each call in a batch from the events emitted by the
+    /// extrinsic which dispatched it.
+    pub fn from_events(
+        events: impl IntoIterator<Item = Result<EventDetails, Error>>,
+        metadata: &Metadata,
+    ) -> Result<BatchResult, Error> {
+        let mut result = BatchResult {
+            items: Vec::new(),
+            interrupted: None,
+        };
+        for ev in events {
+            let ev = ev?;
+            if ev.pallet_name() != "Utility" {
+                continue
+            }
+            match ev.variant_name() {
+                "ItemCompleted" => result.items.push(Ok(())),
+                "ItemFailed" => {
+                    let error = DispatchError::decode_from(ev.field_bytes(), metadata);
+                    result.items.push(Err(error));
+                }
+                "BatchInterrupted" => {
+                    let mut bytes = ev.field_bytes();
+                    let index = u32::decode(&mut bytes)?;
+                    let error = DispatchError::decode_from(bytes, metadata);
+                    result.interrupted = Some((index, error));
+                }
+                _ => {}
+            }
+        }
+        Ok(result)
+    }
+
+    /// The number of calls which were dispatched successfully.
+    pub fn succeeded(&self) -> usize {
+        self.items.iter().filter(|item| item.is_ok()).count()
+    }
+
+    /// Returns true if every call in the batch was dispatched successfully.
+    pub fn is_success(&self) -> bool {
+        self.interrupted.is_none() && self.items.iter().all(|item| item.is_ok())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::events::{
+        events_type::test_utils,
+        Phase,
+    };
+    use scale_info::TypeInfo;
+
+    #[allow(dead_code)]
+    #[derive(Clone, Decode, Encode, TypeInfo)]
+    enum Event {
+        BatchInterrupted { index: u32, error: u8 },
+        BatchCompleted,
+        BatchCompletedWithErrors,
+        ItemCompleted,
+        ItemFailed { error: u8 },
+    }
+
+    fn batch_result(events: Vec<Event>) -> BatchResult {
+        let metadata = test_utils::metadata_with_pallet::<Event>("Utility");
+        let records = events
+            .into_iter()
+            .map(|ev| test_utils::event_record(Phase::ApplyExtrinsic(0), ev))
+            .collect();
+        let events = test_utils::events(metadata.clone(), records);
+        BatchResult::from_events(events.iter(), &metadata).unwrap()
+    }
+
+    #[test]
+    fn finds_where_a_batch_was_interrupted() {
+        let result = batch_result(vec![
+            Event::ItemCompleted,
+            Event::ItemCompleted,
+            Event::BatchInterrupted { index: 2, error: 7 },
+        ]);
+        assert_eq!(result.succeeded(), 2);
+        assert!(!result.is_success());
+        // The test metadata has no `DispatchError` type, so we get the bytes back:
+        assert!(matches!(
+            result.interrupted,
+            Some((2, DispatchError::Other(bytes))) if bytes == vec![7]
+        ));
+    }
+
+    #[test]
+    fn collects_each_result_of_a_forced_batch() {
+        let result = batch_result(vec![
+            Event::ItemCompleted,
+            Event::ItemFailed { error: 3 },
+            Event::ItemCompleted,
+            Event::BatchCompletedWithErrors,
+        ]);
+        assert_eq!(result.items.len(), 3);
+        assert_eq!(result.succeeded(), 2);
+        assert!(result.items[1].is_err());
+        assert!(result.interrupted.is_none());
+        assert!(!result.is_success());
+    }
+}
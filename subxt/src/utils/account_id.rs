@@ -8,6 +8,7 @@
 
 use codec::{
     Decode,
+    DecodeAll,
     Encode,
 };
 use serde::{
@@ -40,6 +41,27 @@ impl From<[u8; 32]> for AccountId32 {
 }
 
 impl AccountId32 {
+    /// Convert this into some other account ID type, such as the [`crate::Config::AccountId`]
+    /// of a chain whose account IDs wrap a 32 byte ID. The conversion is done by SCALE decoding
+    /// the target type from the 32 bytes of this ID, and fails unless all of the bytes are
+    /// used; for instance, converting into a 20 byte account ID type like `H160` fails.
+    pub fn try_into_account_id<A: Decode>(&self) -> Result<A, AccountIdConversionError> {
+        A::decode_all(&mut &self.0[..]).map_err(AccountIdConversionError::Decode)
+    }
+
+    /// Convert some other account ID type into an [`AccountId32`]. This is the reverse of
+    /// [`AccountId32::try_into_account_id()`], and fails unless the account ID SCALE encodes
+    /// to exactly 32 bytes.
+    pub fn try_from_account_id<A: Encode>(
+        account_id: &A,
+    ) -> Result<Self, AccountIdConversionError> {
+        let bytes = account_id.encode();
+        let bytes: [u8; 32] = bytes
+            .try_into()
+            .map_err(|bytes: Vec<u8>| AccountIdConversionError::BadLength(bytes.len()))?;
+        Ok(AccountId32(bytes))
+    }
+
     // Return the ss58-check string for this key. Adapted from `sp_core::crypto`. We need this to
     // serialize our account appropriately but otherwise don't care.
     fn to_ss58check(&self) -> String {
@@ -63,6 +85,12 @@ impl AccountId32 {
     // implement the logic needed to decode an AccountId32 from an SS58 encoded string. This is exposed
     // via a `FromStr` impl.
     fn from_ss58check(s: &str) -> Result<Self, FromSs58Error> {
+        AccountId32::from_ss58check_with_prefix(s).map(|(account, _)| account)
+    }
+
+    /// Decode an SS58 encoded address, handing back the account ID along with the SS58
+    /// prefix that the address was encoded with.
+    pub fn from_ss58check_with_prefix(s: &str) -> Result<(Self, u16), FromSs58Error> {
         const CHECKSUM_LEN: usize = 2;
         let body_len = 32;
 
@@ -71,9 +99,16 @@ impl AccountId32 {
         if data.len() < 2 {
             return Err(FromSs58Error::BadLength)
         }
-        let prefix_len = match data[0] {
-            0..=63 => 1,
-            64..=127 => 2,
+        let (prefix_len, prefix) = match data[0] {
+            0..=63 => (1, data[0] as u16),
+            64..=127 => {
+                // Adapted from `sp_core::crypto`: the lower 6 bits of the first byte and
+                // the upper 2 bits of the second byte are the low byte of the prefix, and
+                // the lower 6 bits of the second byte are its high byte.
+                let lower = (data[0] << 2) | (data[1] >> 6);
+                let upper = data[1] & 0b0011_1111;
+                (2, (lower as u16) | ((upper as u16) << 8))
+            }
             _ => return Err(FromSs58Error::InvalidPrefix),
         };
         if data.len() != prefix_len + body_len + CHECKSUM_LEN {
@@ -90,7 +125,23 @@ impl AccountId32 {
         let result = data[prefix_len..body_len + prefix_len]
             .try_into()
             .map_err(|_| FromSs58Error::BadLength)?;
-        Ok(AccountId32(result))
+        Ok((AccountId32(result), prefix))
+    }
+
+    /// Decode an SS58 encoded address, checking that it was encoded with the given SS58
+    /// prefix. This catches addresses which are meant for a different chain.
+    pub fn from_ss58check_for_prefix(
+        s: &str,
+        expected_prefix: u16,
+    ) -> Result<Self, FromSs58Error> {
+        let (account, prefix) = AccountId32::from_ss58check_with_prefix(s)?;
+        if prefix != expected_prefix {
+            return Err(FromSs58Error::PrefixMismatch {
+                expected: expected_prefix,
+                found: prefix,
+            })
+        }
+        Ok(account)
     }
 }
 
@@ -106,6 +157,20 @@ pub enum FromSs58Error {
     InvalidChecksum,
     #[error("Invalid SS58 prefix byte.")]
     InvalidPrefix,
+    #[error("The address has SS58 prefix {found}, but prefix {expected} was expected.")]
+    PrefixMismatch { expected: u16, found: u16 },
+}
+
+/// An error obtained from trying to convert between an [`AccountId32`] and some other
+/// account ID type.
+#[derive(thiserror::Error, Clone, Eq, PartialEq, Debug)]
+pub enum AccountIdConversionError {
+    /// The account ID being converted doesn't encode to 32 bytes.
+    #[error("Account ID is {0} bytes long, but 32 bytes are expected")]
+    BadLength(usize),
+    /// The target type couldn't be decoded from the 32 bytes of the [`AccountId32`].
+    #[error("Could not convert 32 byte account ID: {0}")]
+    Decode(codec::Error),
 }
 
 // We do this just to get a checksum to help verify the validity of the address in to_ss58check
@@ -163,6 +228,11 @@ mod substrate_impls {
             Self(value.into())
         }
     }
+    impl From<AccountId32> for sp_runtime::AccountId32 {
+        fn from(value: AccountId32) -> Self {
+            Self::new(value.0)
+        }
+    }
     impl From<sp_core::sr25519::Public> for AccountId32 {
         fn from(value: sp_core::sr25519::Public) -> Self {
             let acc: sp_runtime::AccountId32 = value.into();
@@ -213,4 +283,67 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn ss58_prefixes_are_decoded_and_checked() {
+        use sp_core::crypto::Ss58AddressFormat;
+
+        let substrate_account = AccountKeyring::Alice.to_account_id();
+        let local_account = AccountId32(substrate_account.clone().into());
+
+        // Prefixes which take up one byte, and those which take up two:
+        for prefix in [0, 2, 42, 63, 64, 1284, 16383] {
+            let ss58 = substrate_account
+                .to_ss58check_with_version(Ss58AddressFormat::custom(prefix));
+            assert_eq!(
+                AccountId32::from_ss58check_with_prefix(&ss58).unwrap(),
+                (local_account.clone(), prefix)
+            );
+            assert_eq!(
+                AccountId32::from_ss58check_for_prefix(&ss58, prefix).unwrap(),
+                local_account
+            );
+        }
+
+        let kusama =
+            substrate_account.to_ss58check_with_version(Ss58AddressFormat::custom(2));
+        assert_eq!(
+            AccountId32::from_ss58check_for_prefix(&kusama, 0),
+            Err(FromSs58Error::PrefixMismatch {
+                expected: 0,
+                found: 2
+            })
+        );
+    }
+
+    #[test]
+    fn converts_to_and_from_other_account_ids() {
+        #[derive(Debug, PartialEq, Encode, Decode)]
+        struct WrappedAccountId(AccountId32);
+
+        let account = AccountId32([7; 32]);
+        let wrapped: WrappedAccountId = account.try_into_account_id().unwrap();
+        assert_eq!(wrapped, WrappedAccountId(account.clone()));
+        assert_eq!(AccountId32::try_from_account_id(&wrapped).unwrap(), account);
+
+        let substrate_account: sp_core::crypto::AccountId32 =
+            account.try_into_account_id().unwrap();
+        assert_eq!(
+            AccountId32::try_from_account_id(&substrate_account).unwrap(),
+            account
+        );
+    }
+
+    #[test]
+    fn converting_to_and_from_other_length_account_ids_fails() {
+        let account = AccountId32([7; 32]);
+        assert!(matches!(
+            account.try_into_account_id::<crate::utils::H160>(),
+            Err(AccountIdConversionError::Decode(_))
+        ));
+        assert_eq!(
+            AccountId32::try_from_account_id(&crate::utils::H160([1; 20])),
+            Err(AccountIdConversionError::BadLength(20))
+        );
+    }
 }
@@ -8,6 +8,9 @@ pub mod account_id;
 pub mod bits;
 pub mod multi_address;
 pub mod multi_signature;
+pub mod retry;
+pub mod trie;
+pub mod weight;
 
 use codec::{
     Decode,
@@ -16,9 +19,13 @@ use codec::{
 };
 use derivative::Derivative;
 
-pub use account_id::AccountId32;
+pub use account_id::{
+    AccountId32,
+    AccountIdConversionError,
+};
 pub use multi_address::MultiAddress;
 pub use multi_signature::MultiSignature;
+pub use weight::Weight;
 
 // Used in codegen
 #[doc(hidden)]
@@ -0,0 +1,204 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Retry an operation, such as fetching storage or events, which may fail for transient reasons.
+//!
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use subxt::{ utils::retry::{ retry, RetryPolicy }, OnlineClient, PolkadotConfig };
+//!
+//! # #[tokio::main]
+//! # async fn main() -> Result<(), subxt::Error> {
+//! let api = OnlineClient::<PolkadotConfig>::new().await?;
+//! let policy = RetryPolicy::new(3).with_delay(Duration::from_millis(500));
+//!
+//! let events = retry(&policy, tokio::time::sleep, || api.events().at(None)).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    error::Error,
+    rpc::{
+        DefaultErrorClassifier,
+        ErrorAction,
+        ErrorClassifier,
+    },
+};
+use std::{
+    future::Future,
+    sync::Arc,
+    time::Duration,
+};
+
+/// How many times, and how often, to retry an operation with [`retry()`].
+///
+/// Which errors are worth retrying is decided by an [`ErrorClassifier`]. By default this is
+/// the [`DefaultErrorClassifier`], and an operation is retried if the error is classified as
+/// [`ErrorAction::Retry`] or [`ErrorAction::Reconnect`]. This means IO errors, dropped
+/// subscriptions, transport errors and timed out requests are retried, whereas errors that
+/// the node responds with, decode errors and the like are handed straight back, since trying
+/// again won't change the outcome.
+///
+/// Retrying doesn't reconnect by itself. For that, the client's RPC calls should go through
+/// a [`crate::rpc::ReconnectingClient`], which replaces a broken connection so that the
+/// retried operation runs over a working one.
+#[derive(Clone)]
+pub struct RetryPolicy {
+    max_retries: usize,
+    delay: Duration,
+    backoff: u32,
+    max_delay: Duration,
+    classifier: Arc<dyn ErrorClassifier>,
+}
+
+impl RetryPolicy {
+    /// Retry an operation up to `max_retries` times, waiting one second between each attempt.
+    pub fn new(max_retries: usize) -> Self {
+        RetryPolicy {
+            max_retries,
+            delay: Duration::from_secs(1),
+            backoff: 1,
+            max_delay: Duration::MAX,
+            classifier: Arc::new(DefaultErrorClassifier),
+        }
+    }
+
+    /// How long to wait before the first retry.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Multiply the delay by this factor after each retry, up to a maximum delay.
+    pub fn with_backoff(mut self, factor: u32, max_delay: Duration) -> Self {
+        self.backoff = factor;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Decide which errors are retried using the given [`ErrorClassifier`] rather than the
+    /// [`DefaultErrorClassifier`]. Errors classified as [`ErrorAction::Propagate`] are handed
+    /// back; anything else is retried.
+    pub fn with_classifier(mut self, classifier: impl ErrorClassifier + 'static) -> Self {
+        self.classifier = Arc::new(classifier);
+        self
+    }
+
+    /// The delay before the given retry, counting from 0.
+    fn delay_before(&self, retry: usize) -> Duration {
+        let mut delay = self.delay;
+        for _ in 0..retry {
+            delay = delay.saturating_mul(self.backoff).min(self.max_delay);
+        }
+        delay.min(self.max_delay)
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_retries", &self.max_retries)
+            .field("delay", &self.delay)
+            .field("backoff", &self.backoff)
+            .field("max_delay", &self.max_delay)
+            .finish()
+    }
+}
+
+/// Run the operation given, retrying it according to the [`RetryPolicy`] if it fails with
+/// an error which is worth retrying. If it is still failing after the policy's retries are
+/// used up, the last error is handed back.
+///
+/// Since subxt doesn't depend on any particular async runtime, a function which sleeps for
+/// the given duration must also be provided, such as `tokio::time::sleep`.
+pub async fn retry<T, Op, Fut, Sleep, SleepFut>(
+    policy: &RetryPolicy,
+    sleep: Sleep,
+    mut op: Op,
+) -> Result<T, Error>
+where
+    Op: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+    Sleep: Fn(Duration) -> SleepFut,
+    SleepFut: Future<Output = ()>,
+{
+    let mut retries = 0;
+    loop {
+        let err = match op().await {
+            Ok(val) => return Ok(val),
+            Err(err) => err,
+        };
+        if retries >= policy.max_retries
+            || policy.classifier.classify(&err) == ErrorAction::Propagate
+        {
+            return Err(err)
+        }
+        tracing::debug!("Retrying after error: {err}");
+        sleep(policy.delay_before(retries)).await;
+        retries += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::RpcError;
+    use std::sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    };
+
+    async fn no_sleep(_: Duration) {}
+
+    #[tokio::test]
+    async fn retries_transient_errors_only() {
+        let policy = RetryPolicy::new(3);
+
+        // Dropped subscriptions are retried until the operation succeeds:
+        let attempts = AtomicUsize::new(0);
+        let res = retry(&policy, no_sleep, || {
+            async {
+                match attempts.fetch_add(1, Ordering::SeqCst) {
+                    0 | 1 => Err(RpcError::SubscriptionDropped.into()),
+                    _ => Ok(42),
+                }
+            }
+        })
+        .await;
+        assert_eq!(res.unwrap(), 42);
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+
+        // Other errors are handed straight back:
+        let attempts = AtomicUsize::new(0);
+        let res: Result<(), _> = retry(&policy, no_sleep, || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Err(Error::Other("not found".into())) }
+        })
+        .await;
+        assert!(matches!(res, Err(Error::Other(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        // We stop once the retries are used up:
+        let attempts = AtomicUsize::new(0);
+        let res: Result<(), _> = retry(&policy, no_sleep, || {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            async { Err(RpcError::SubscriptionDropped.into()) }
+        })
+        .await;
+        assert!(res.is_err());
+        assert_eq!(attempts.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn backs_off_up_to_the_max_delay() {
+        let policy = RetryPolicy::new(5)
+            .with_delay(Duration::from_secs(1))
+            .with_backoff(2, Duration::from_secs(5));
+        let delays: Vec<_> = (0..5).map(|n| policy.delay_before(n).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 5, 5]);
+    }
+}
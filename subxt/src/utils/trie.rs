@@ -0,0 +1,309 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Compute the root of a trie laid out in the same way as Substrate's, for instance
+//! to check the `extrinsics_root` of a block header against the extrinsics in the block.
+
+use crate::config::Hasher;
+use codec::{
+    Compact,
+    Encode,
+};
+use std::collections::BTreeMap;
+
+/// The version of the trie layout in use. Substrate based runtimes declare which version
+/// they use in their runtime version (see [`crate::rpc::types::RuntimeVersion::state_version()`]).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum StateVersion {
+    /// Values are always stored inline in the trie nodes.
+    #[default]
+    V0,
+    /// Values of 33 bytes or more are hashed, and the hash is stored in the trie
+    /// nodes in place of the value.
+    V1,
+}
+
+impl StateVersion {
+    // Values at least this long are hashed rather than being stored inline.
+    fn value_hash_threshold(&self) -> Option<usize> {
+        match self {
+            StateVersion::V0 => None,
+            StateVersion::V1 => Some(33),
+        }
+    }
+}
+
+/// Compute the root of a trie whose keys are the compact encoded indexes of the values given.
+/// This is how the `extrinsics_root` in a block header is calculated from the (SCALE encoded)
+/// extrinsics in the block.
+pub fn ordered_trie_root<H, I>(values: I, state_version: StateVersion) -> H::Output
+where
+    H: Hasher,
+    H::Output: AsRef<[u8]>,
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+{
+    let input = values
+        .into_iter()
+        .enumerate()
+        .map(|(idx, value)| (Compact(idx as u32).encode(), value));
+    trie_root::<H, _, _>(input, state_version)
+}
+
+/// Compute the root of a trie containing the key-value pairs given. If the same key is given
+/// more than once, the last value given for it is used.
+pub fn trie_root<H, K, V>(
+    input: impl IntoIterator<Item = (K, V)>,
+    state_version: StateVersion,
+) -> H::Output
+where
+    H: Hasher,
+    H::Output: AsRef<[u8]>,
+    K: AsRef<[u8]> + Ord,
+    V: AsRef<[u8]>,
+{
+    // Sort the entries by key and remove duplicates, and then convert the keys to nibbles.
+    let input: Vec<(Vec<u8>, V)> = input
+        .into_iter()
+        .collect::<BTreeMap<_, _>>()
+        .into_iter()
+        .map(|(key, value)| {
+            let nibbles = key
+                .as_ref()
+                .iter()
+                .flat_map(|b| [b >> 4, b & 0x0F])
+                .collect();
+            (nibbles, value)
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    build_trie::<H, V>(&input, 0, state_version, &mut out);
+    H::hash(&out)
+}
+
+const EMPTY_TRIE: u8 = 0;
+const LEAF_PREFIX_MASK: u8 = 0b01 << 6;
+const BRANCH_WITHOUT_VALUE_MASK: u8 = 0b10 << 6;
+const BRANCH_WITH_VALUE_MASK: u8 = 0b11 << 6;
+const HASHED_VALUE_LEAF_PREFIX_MASK: u8 = 0b1 << 5;
+const HASHED_VALUE_BRANCH_WITH_MASK: u8 = 0b01 << 4;
+
+// A value to be stored in a node; either the value itself or its hash.
+enum Value<'a> {
+    Inline(&'a [u8]),
+    Hashed(Vec<u8>),
+}
+
+impl<'a> Value<'a> {
+    fn new<H>(value: &'a [u8], state_version: StateVersion) -> Self
+    where
+        H: Hasher,
+        H::Output: AsRef<[u8]>,
+    {
+        match state_version.value_hash_threshold() {
+            Some(threshold) if value.len() >= threshold => {
+                Value::Hashed(H::hash(value).as_ref().to_vec())
+            }
+            _ => Value::Inline(value),
+        }
+    }
+
+    fn encode_to(&self, out: &mut Vec<u8>) {
+        match self {
+            Value::Inline(value) => {
+                Compact(value.len() as u32).encode_to(out);
+                out.extend_from_slice(value);
+            }
+            Value::Hashed(hash) => out.extend_from_slice(hash),
+        }
+    }
+}
+
+// Append the trie node(s) for the given entries, whose keys (in nibbles) are sorted and
+// all share the first `cursor` nibbles.
+fn build_trie<H, V>(
+    input: &[(Vec<u8>, V)],
+    cursor: usize,
+    state_version: StateVersion,
+    out: &mut Vec<u8>,
+) where
+    H: Hasher,
+    H::Output: AsRef<[u8]>,
+    V: AsRef<[u8]>,
+{
+    match input {
+        [] => out.push(EMPTY_TRIE),
+        [(key, value)] => {
+            let partial_key = &key[cursor..];
+            match Value::new::<H>(value.as_ref(), state_version) {
+                value @ Value::Inline(_) => {
+                    encode_node_header(out, partial_key, LEAF_PREFIX_MASK, 2);
+                    value.encode_to(out);
+                }
+                value @ Value::Hashed(_) => {
+                    encode_node_header(
+                        out,
+                        partial_key,
+                        HASHED_VALUE_LEAF_PREFIX_MASK,
+                        3,
+                    );
+                    value.encode_to(out);
+                }
+            }
+        }
+        [(key, value), rest @ ..] => {
+            // The branch node takes the longest prefix shared by every key.
+            let shared_nibbles = rest.iter().fold(key.len(), |acc, (k, _)| {
+                let shared = key.iter().zip(k).take_while(|(a, b)| a == b).count();
+                acc.min(shared)
+            });
+            let (partial_key, cursor) = if shared_nibbles > cursor {
+                (&key[cursor..shared_nibbles], shared_nibbles)
+            } else {
+                (&key[0..0], cursor)
+            };
+
+            // If the first key ends here, its value is stored in the branch node. Since keys
+            // are sorted, the rest are grouped into children by their next nibble.
+            let value = (cursor == key.len())
+                .then(|| Value::new::<H>(value.as_ref(), state_version));
+            let children_start = if value.is_some() { 1 } else { 0 };
+            let mut child_counts = [0usize; 16];
+            let mut begin = children_start;
+            for (nibble, count) in child_counts.iter_mut().enumerate() {
+                *count = input[begin..]
+                    .iter()
+                    .take_while(|(k, _)| k[cursor] == nibble as u8)
+                    .count();
+                begin += *count;
+            }
+
+            match &value {
+                None => {
+                    encode_node_header(out, partial_key, BRANCH_WITHOUT_VALUE_MASK, 2)
+                }
+                Some(Value::Inline(_)) => {
+                    encode_node_header(out, partial_key, BRANCH_WITH_VALUE_MASK, 2)
+                }
+                Some(Value::Hashed(_)) => {
+                    encode_node_header(out, partial_key, HASHED_VALUE_BRANCH_WITH_MASK, 4)
+                }
+            }
+            let bitmap = child_counts
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .fold(0u16, |bitmap, (idx, _)| bitmap | (1 << idx));
+            out.extend(bitmap.to_le_bytes());
+            if let Some(value) = &value {
+                value.encode_to(out);
+            }
+
+            // Children are stored inline if they are small enough, and hashed otherwise.
+            let mut begin = children_start;
+            for count in child_counts.into_iter().filter(|count| *count > 0) {
+                let mut child = Vec::new();
+                build_trie::<H, V>(
+                    &input[begin..begin + count],
+                    cursor + 1,
+                    state_version,
+                    &mut child,
+                );
+                if child.len() < 32 {
+                    child.encode_to(out);
+                } else {
+                    H::hash(&child).as_ref().encode_to(out);
+                }
+                begin += count;
+            }
+        }
+    }
+}
+
+// Encode the node type and the partial key (in nibbles) of a node. The node type occupies
+// the first `prefix_bits` bits of the first byte, and the number of nibbles the remainder.
+fn encode_node_header(out: &mut Vec<u8>, nibbles: &[u8], prefix: u8, prefix_bits: u32) {
+    let max_value = 255u8 >> prefix_bits;
+    let first = (max_value as usize - 1).min(nibbles.len());
+    if first == nibbles.len() {
+        out.push(prefix + first as u8);
+    } else {
+        out.push(prefix + max_value);
+        let mut rem = nibbles.len() - first;
+        while rem > 0 {
+            if rem < 256 {
+                out.push((rem - 1) as u8);
+                rem = 0;
+            } else {
+                out.push(255);
+                rem = rem.saturating_sub(255);
+            }
+        }
+    }
+
+    // An odd number of nibbles has the first one stored on its own.
+    let (odd, rest) = nibbles.split_at(nibbles.len() % 2);
+    out.extend(odd);
+    out.extend(rest.chunks(2).map(|pair| pair[0] << 4 | pair[1]));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::substrate::BlakeTwo256;
+    use sp_runtime::{
+        traits::{
+            BlakeTwo256 as SpBlakeTwo256,
+            Hash as _,
+        },
+        StateVersion as SpStateVersion,
+    };
+
+    #[test]
+    fn ordered_trie_root_is_substrate_compatible() {
+        let versions = [
+            (StateVersion::V0, SpStateVersion::V0),
+            (StateVersion::V1, SpStateVersion::V1),
+        ];
+        for num_values in [0, 1, 2, 3, 16, 17, 100, 300] {
+            for value_len in [0, 1, 31, 32, 33, 100] {
+                let values: Vec<Vec<u8>> = (0..num_values)
+                    .map(|n| (0..value_len).map(|b| (b + n) as u8).collect())
+                    .collect();
+
+                for (version, sp_version) in versions {
+                    let actual = ordered_trie_root::<BlakeTwo256, _>(&values, version);
+                    let expected =
+                        SpBlakeTwo256::ordered_trie_root(values.clone(), sp_version);
+                    assert_eq!(
+                        actual, expected,
+                        "{num_values} values of length {value_len} ({version:?})"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn trie_root_is_substrate_compatible() {
+        // Keys of varying lengths, some of which are prefixes of others:
+        let input = vec![
+            (vec![], vec![1]),
+            (vec![0x12], vec![2; 40]),
+            (vec![0x12, 0x34], vec![3]),
+            (vec![0x12, 0x35, 0x00], vec![4; 10]),
+            (vec![0xff; 40], vec![5; 33]),
+            (vec![0xff; 41], vec![6]),
+        ];
+        for (version, sp_version) in [
+            (StateVersion::V0, SpStateVersion::V0),
+            (StateVersion::V1, SpStateVersion::V1),
+        ] {
+            let actual = trie_root::<BlakeTwo256, _, _>(input.clone(), version);
+            let expected = SpBlakeTwo256::trie_root(input.clone(), sp_version);
+            assert_eq!(actual, expected, "{version:?}");
+        }
+    }
+}
@@ -0,0 +1,423 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+//! Types for working with the weight of dispatchables across runtime versions.
+//!
+//! Older runtimes represent a weight as a single `u64` ("v1" weights), whereas newer
+//! runtimes use a struct with `ref_time` and `proof_size` fields ("v2" weights). The
+//! types here look at the metadata to work out which representation is in use, so that
+//! the same code can decode weights from either kind of runtime.
+
+use crate::{
+    dynamic::DecodedValue,
+    error::{
+        Error,
+        MetadataError,
+    },
+    metadata::{
+        DecodeWithMetadata,
+        EncodeWithMetadata,
+        Metadata,
+    },
+    storage::utils::value_as_u64,
+};
+use scale_info::TypeDef;
+use scale_value::{
+    At,
+    Composite,
+    Value,
+    ValueDef,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+/// The weight of some dispatchable. For runtimes using "v1" weights, the
+/// [`Weight::proof_size()`] is always 0.
+#[derive(
+    Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize,
+)]
+pub struct Weight {
+    ref_time: u64,
+    proof_size: u64,
+}
+
+impl Weight {
+    /// Construct a [`Weight`] from its constituent parts.
+    pub const fn from_parts(ref_time: u64, proof_size: u64) -> Self {
+        Weight {
+            ref_time,
+            proof_size,
+        }
+    }
+
+    /// Construct a [`Weight`] from a "v1" scalar weight.
+    pub const fn from_ref_time(ref_time: u64) -> Self {
+        Weight {
+            ref_time,
+            proof_size: 0,
+        }
+    }
+
+    /// The amount of computational time used, in picoseconds.
+    pub const fn ref_time(&self) -> u64 {
+        self.ref_time
+    }
+
+    /// The size of the storage proof in bytes. This is 0 for "v1" weights.
+    pub const fn proof_size(&self) -> u64 {
+        self.proof_size
+    }
+
+    /// Add two weights together, saturating each component at `u64::MAX`.
+    pub const fn saturating_add(self, other: Weight) -> Weight {
+        Weight {
+            ref_time: self.ref_time.saturating_add(other.ref_time),
+            proof_size: self.proof_size.saturating_add(other.proof_size),
+        }
+    }
+
+    /// Attempt to interpret a dynamically decoded value as a weight, returning `None`
+    /// if it doesn't have the shape of either a "v1" or "v2" weight.
+    pub fn from_value<T>(value: &Value<T>) -> Option<Weight> {
+        match &value.value {
+            // V1: a plain (possibly compact encoded) number.
+            ValueDef::Primitive(p) => {
+                p.as_u128()
+                    .and_then(|n| u64::try_from(n).ok())
+                    .map(Weight::from_ref_time)
+            }
+            // V2: a struct with `ref_time` and `proof_size` (an intermediate
+            // version of this struct had only `ref_time`).
+            ValueDef::Composite(composite @ Composite::Named(_)) => {
+                let ref_time = composite.at("ref_time").and_then(value_as_u64)?;
+                let proof_size = match composite.at("proof_size") {
+                    Some(v) => value_as_u64(v)?,
+                    None => 0,
+                };
+                Some(Weight::from_parts(ref_time, proof_size))
+            }
+            // A newtype wrapper around either of the above.
+            ValueDef::Composite(Composite::Unnamed(vals)) if vals.len() == 1 => {
+                Weight::from_value(&vals[0])
+            }
+            _ => None,
+        }
+    }
+}
+
+impl DecodeWithMetadata for Weight {
+    type Target = Self;
+
+    fn decode_with_metadata(
+        bytes: &mut &[u8],
+        type_id: u32,
+        metadata: &Metadata,
+    ) -> Result<Self::Target, Error> {
+        let value = DecodedValue::decode_with_metadata(bytes, type_id, metadata)?;
+        Weight::from_value(&value).ok_or_else(|| {
+            Error::Other(format!(
+                "Type with ID {type_id} does not look like a weight: {value}"
+            ))
+        })
+    }
+}
+
+impl EncodeWithMetadata for Weight {
+    fn encode_with_metadata(
+        &self,
+        type_id: u32,
+        metadata: &Metadata,
+        bytes: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.to_value(type_id, metadata)?
+            .encode_with_metadata(type_id, metadata, bytes)
+    }
+}
+
+impl Weight {
+    // Build a value in the shape of the weight type given, failing if the weight has a
+    // proof size but the type is a "v1" weight.
+    fn to_value(self, type_id: u32, metadata: &Metadata) -> Result<Value<()>, Error> {
+        let ty = metadata
+            .resolve_type(type_id)
+            .ok_or(MetadataError::TypeNotFound(type_id))?;
+        match ty.type_def() {
+            TypeDef::Primitive(_) | TypeDef::Compact(_) if self.proof_size == 0 => {
+                Ok(Value::u128(self.ref_time as u128))
+            }
+            TypeDef::Composite(c) if c.fields().iter().all(|f| f.name().is_some()) => {
+                let fields = c
+                    .fields()
+                    .iter()
+                    .map(|f| {
+                        let name = f.name().expect("checked above; qed");
+                        let value = match name.as_str() {
+                            "ref_time" => self.ref_time,
+                            "proof_size" => self.proof_size,
+                            _ => {
+                                return Err(Error::Other(format!(
+                                    "Type with ID {type_id} does not look like a weight"
+                                )))
+                            }
+                        };
+                        Ok((name.clone(), Value::u128(value as u128)))
+                    })
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(Value::named_composite(fields))
+            }
+            TypeDef::Composite(c) if c.fields().len() == 1 => {
+                let inner = self.to_value(c.fields()[0].ty().id(), metadata)?;
+                Ok(Value::unnamed_composite(vec![inner]))
+            }
+            _ => {
+                Err(Error::Other(format!(
+                    "Cannot encode {self:?} as the type with ID {type_id}"
+                )))
+            }
+        }
+    }
+}
+
+// Nodes serialize weights as either a number or an object, depending on the
+// version in use, and have used both snake and camel case for the fields.
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum WeightRepr {
+            V1(u64),
+            V2 {
+                #[serde(alias = "refTime")]
+                ref_time: u64,
+                #[serde(default, alias = "proofSize")]
+                proof_size: u64,
+            },
+        }
+
+        Ok(match WeightRepr::deserialize(deserializer)? {
+            WeightRepr::V1(ref_time) => Weight::from_ref_time(ref_time),
+            WeightRepr::V2 {
+                ref_time,
+                proof_size,
+            } => Weight::from_parts(ref_time, proof_size),
+        })
+    }
+}
+
+/// The class of a dispatchable.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DispatchClass {
+    /// A normal dispatch.
+    Normal,
+    /// An operational dispatch.
+    Operational,
+    /// A mandatory dispatch.
+    Mandatory,
+}
+
+/// Whether the dispatchable pays a fee or not.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Pays {
+    /// The dispatchable pays a fee.
+    Yes,
+    /// The dispatchable does not pay a fee.
+    No,
+}
+
+/// Information about a dispatchable, as reported in `System.ExtrinsicSuccess`
+/// and `System.ExtrinsicFailed` events.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DispatchInfo {
+    /// The weight of the dispatchable.
+    pub weight: Weight,
+    /// The class of the dispatchable.
+    pub class: DispatchClass,
+    /// Whether the dispatchable pays a fee.
+    pub pays_fee: Pays,
+}
+
+impl DispatchInfo {
+    /// Attempt to interpret a dynamically decoded value as a [`DispatchInfo`], returning
+    /// `None` if it doesn't have the expected shape.
+    pub fn from_value<T>(value: &Value<T>) -> Option<DispatchInfo> {
+        let weight = value.at("weight").and_then(Weight::from_value)?;
+        let class = match variant_name(value.at("class")?)? {
+            "Normal" => DispatchClass::Normal,
+            "Operational" => DispatchClass::Operational,
+            "Mandatory" => DispatchClass::Mandatory,
+            _ => return None,
+        };
+        let pays_fee = match variant_name(value.at("pays_fee")?)? {
+            "Yes" => Pays::Yes,
+            "No" => Pays::No,
+            _ => return None,
+        };
+        Some(DispatchInfo {
+            weight,
+            class,
+            pays_fee,
+        })
+    }
+}
+
+fn variant_name<T>(value: &Value<T>) -> Option<&str> {
+    match &value.value {
+        ValueDef::Variant(v) => Some(&v.name),
+        _ => None,
+    }
+}
+
+impl DecodeWithMetadata for DispatchInfo {
+    type Target = Self;
+
+    fn decode_with_metadata(
+        bytes: &mut &[u8],
+        type_id: u32,
+        metadata: &Metadata,
+    ) -> Result<Self::Target, Error> {
+        let value = DecodedValue::decode_with_metadata(bytes, type_id, metadata)?;
+        DispatchInfo::from_value(&value).ok_or_else(|| {
+            Error::Other(format!(
+                "Type with ID {type_id} does not look like a DispatchInfo: {value}"
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use codec::{
+        Compact,
+        Encode,
+    };
+    use scale_info::TypeInfo;
+
+    fn decode_as<T: TypeInfo + 'static>(bytes: &[u8]) -> Option<Weight> {
+        let value = crate::test_utils::decode_as::<T>(bytes)?;
+        Weight::from_value(&value)
+    }
+
+    #[test]
+    fn decodes_v1_weights() {
+        #[derive(Encode, TypeInfo)]
+        struct OldWeight(u64);
+
+        assert_eq!(
+            decode_as::<u64>(&123u64.encode()),
+            Some(Weight::from_ref_time(123))
+        );
+        assert_eq!(
+            decode_as::<OldWeight>(&OldWeight(456).encode()),
+            Some(Weight::from_ref_time(456))
+        );
+    }
+
+    #[test]
+    fn decodes_v2_weights() {
+        #[derive(Encode, TypeInfo)]
+        struct WeightV2 {
+            #[codec(compact)]
+            ref_time: u64,
+            #[codec(compact)]
+            proof_size: u64,
+        }
+        #[derive(Encode, TypeInfo)]
+        struct WeightV1_5 {
+            ref_time: u64,
+        }
+
+        let v2 = WeightV2 {
+            ref_time: 1,
+            proof_size: 2,
+        };
+        assert_eq!(
+            decode_as::<WeightV2>(&v2.encode()),
+            Some(Weight::from_parts(1, 2))
+        );
+        let v1_5 = WeightV1_5 { ref_time: 3 };
+        assert_eq!(
+            decode_as::<WeightV1_5>(&v1_5.encode()),
+            Some(Weight::from_ref_time(3))
+        );
+        assert_eq!(
+            decode_as::<Compact<u64>>(&Compact(4u64).encode()),
+            Some(Weight::from_ref_time(4))
+        );
+    }
+
+    #[test]
+    fn rejects_non_weights() {
+        assert_eq!(decode_as::<bool>(&true.encode()), None);
+        assert_eq!(decode_as::<(u64, u64)>(&(1u64, 2u64).encode()), None);
+    }
+
+    #[test]
+    fn encodes_as_either_weight_repr() {
+        #[derive(Encode, TypeInfo)]
+        struct WeightV2 {
+            #[codec(compact)]
+            ref_time: u64,
+            #[codec(compact)]
+            proof_size: u64,
+        }
+
+        // Fake metadata is built around an event enum, so we wrap the weight type in one.
+        #[derive(TypeInfo)]
+        enum Event<T> {
+            _Weight(T),
+        }
+
+        // Encode a weight as the type `T`, using metadata which knows about it.
+        fn encode_as<T: TypeInfo + 'static>(weight: Weight) -> Result<Vec<u8>, Error> {
+            let metadata = crate::events::events_type::test_utils::metadata::<Event<T>>();
+            let event_ty = metadata.runtime_metadata().pallets[0]
+                .event
+                .as_ref()
+                .unwrap()
+                .ty
+                .id();
+            let TypeDef::Variant(v) = metadata.resolve_type(event_ty).unwrap().type_def()
+            else {
+                unreachable!()
+            };
+            let type_id = v.variants()[0].fields()[0].ty().id();
+            let mut bytes = Vec::new();
+            weight.encode_with_metadata(type_id, &metadata, &mut bytes)?;
+            Ok(bytes)
+        }
+
+        assert_eq!(
+            encode_as::<u64>(Weight::from_ref_time(1)).unwrap(),
+            1u64.encode()
+        );
+        assert_eq!(
+            encode_as::<WeightV2>(Weight::from_parts(1, 2)).unwrap(),
+            WeightV2 {
+                ref_time: 1,
+                proof_size: 2
+            }
+            .encode()
+        );
+        // A "v1" weight can't hold a proof size:
+        assert!(encode_as::<u64>(Weight::from_parts(1, 2)).is_err());
+    }
+
+    #[test]
+    fn deserializes_either_weight_repr() {
+        let v1: Weight = serde_json::from_str("10").unwrap();
+        assert_eq!(v1, Weight::from_ref_time(10));
+        let v2: Weight =
+            serde_json::from_str(r#"{"ref_time":1,"proof_size":2}"#).unwrap();
+        assert_eq!(v2, Weight::from_parts(1, 2));
+        let v2: Weight = serde_json::from_str(r#"{"refTime":3,"proofSize":4}"#).unwrap();
+        assert_eq!(v2, Weight::from_parts(3, 4));
+    }
+}
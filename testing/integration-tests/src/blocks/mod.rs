@@ -6,6 +6,7 @@ use crate::test_context;
 use codec::{
     Compact,
     Decode,
+    Encode,
 };
 use frame_metadata::RuntimeMetadataPrefixed;
 use futures::StreamExt;
@@ -119,3 +120,127 @@ async fn runtime_api_call() -> Result<(), subxt::Error> {
     assert_eq!(&metadata_call, metadata);
     Ok(())
 }
+
+// Check that prefetched blocks are handed back in order, along with their details.
+#[tokio::test]
+async fn prefetched_finalized_blocks_subscription() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let mut sub = api.blocks().subscribe_finalized_prefetched(3).await?;
+
+    let mut last_number = None;
+    for _ in 0..3 {
+        let prefetched = sub.next().await.unwrap()?;
+        let number = prefetched.block().number();
+        if let Some(last_number) = last_number {
+            assert_eq!(number, last_number + 1);
+        }
+        last_number = Some(number);
+
+        // Every block has at least the timestamp inherent, and events are fetched for it:
+        assert!(prefetched.body().extrinsics().count() > 0);
+        assert_eq!(prefetched.events().block_hash(), prefetched.block().hash());
+    }
+    Ok(())
+}
+
+// Check that the extrinsics root we compute lines up with the one in the block header.
+#[tokio::test]
+async fn extrinsics_root_matches_header() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let mut sub = api.blocks().subscribe_finalized().await?;
+    let block = sub.next().await.unwrap()?;
+    let state_version = api
+        .rpc()
+        .runtime_version(Some(block.hash()))
+        .await?
+        .state_version()
+        .unwrap_or_default();
+
+    let body = block.body().await?;
+    assert_eq!(
+        body.extrinsics_root(state_version),
+        block.header().extrinsics_root
+    );
+    Ok(())
+}
+
+// Check that we can find an extrinsic given only its hash.
+#[tokio::test]
+async fn wait_for_inclusion_finds_submitted_extrinsics() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice = crate::pair_signer(sp_keyring::AccountKeyring::Alice.pair());
+    let remark = crate::node_runtime::tx().system().remark(vec![1, 2, 3]);
+    let ext = api
+        .tx()
+        .create_signed(&remark, &alice, Default::default())
+        .await?;
+
+    // The extrinsic won't be finalized by the time we start looking for it:
+    let ext_hash = ext.submit().await?;
+    let (tx_in_block, index) = api
+        .blocks()
+        .wait_for_inclusion(
+            ext_hash,
+            tokio::time::sleep(std::time::Duration::from_secs(60)),
+        )
+        .await?
+        .expect("extrinsic should be included");
+
+    assert_eq!(tx_in_block.extrinsic_hash(), ext_hash);
+    let block = api
+        .rpc()
+        .block(Some(tx_in_block.block_hash()))
+        .await?
+        .unwrap();
+    let found = &block.block.extrinsics[index as usize];
+    assert_eq!(found.0.encode(), ext.encoded());
+
+    Ok(())
+}
+
+// Check that extrinsics in finalized blocks are decoded along with their events.
+#[tokio::test]
+async fn finalized_extrinsics_subscription() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let mut sub = api.blocks().subscribe_finalized_extrinsics().await?;
+
+    let alice = crate::pair_signer(sp_keyring::AccountKeyring::Alice.pair());
+    let remark = crate::node_runtime::tx().system().remark(vec![1, 2, 3]);
+    let ext = api
+        .tx()
+        .create_signed(&remark, &alice, Default::default())
+        .await?;
+    ext.submit().await?;
+
+    let alice_address = <subxt::SubstrateConfig as subxt::Config>::Address::from(
+        alice.account_id().clone(),
+    )
+    .encode();
+    let found = loop {
+        let ext = sub.next().await.expect("subscription ended")?;
+        if ext.address_bytes() == Some(&alice_address[..]) {
+            break ext
+        }
+    };
+
+    assert_eq!(found.pallet_name(), "System");
+    assert_eq!(found.call_name(), "remark");
+    // Extrinsics in blocks don't have the length prefix:
+    let mut encoded = ext.encoded();
+    let _len = Compact::<u32>::decode(&mut encoded)?;
+    assert_eq!(found.bytes(), encoded);
+    assert!(found.signed_extensions().and_then(|e| e.nonce()).is_some());
+    assert!(found
+        .events()
+        .has::<crate::node_runtime::system::events::ExtrinsicSuccess>()?);
+
+    Ok(())
+}
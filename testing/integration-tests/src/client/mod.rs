@@ -31,6 +31,7 @@ use subxt::{
         DryRunError,
         FollowEvent,
         Initialized,
+        OffchainStorageKind,
         RuntimeEvent,
         RuntimeVersionEvent,
     },
@@ -156,6 +157,45 @@ async fn test_iter() {
     assert_eq!(i, 13);
 }
 
+#[tokio::test]
+async fn test_iter_keys() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let addr = node_runtime::storage().system().account_root();
+    let mut iter = api
+        .storage()
+        .at(None)
+        .await
+        .unwrap()
+        .iter_keys(addr, 10)
+        .await
+        .unwrap();
+    let mut i = 0;
+    while iter.next().await.unwrap().is_some() {
+        i += 1;
+    }
+    assert_eq!(i, 13);
+}
+
+#[tokio::test]
+async fn offchain_local_storage() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let key = b"subxt-test-key";
+    api.rpc()
+        .offchain_local_storage_set(OffchainStorageKind::Persistent, key, b"value")
+        .await
+        .unwrap();
+    let value = api
+        .rpc()
+        .offchain_local_storage_get(OffchainStorageKind::Persistent, key)
+        .await
+        .unwrap();
+    assert_eq!(value.map(|v| v.0), Some(b"value".to_vec()));
+}
+
 #[tokio::test]
 async fn fetch_system_info() {
     let ctx = test_context().await;
@@ -166,6 +206,54 @@ async fn fetch_system_info() {
     assert!(!api.rpc().system_version().await.unwrap().is_empty());
 }
 
+#[tokio::test]
+async fn check_rpc_methods() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let methods = api.rpc().rpc_methods().await.unwrap();
+    assert!(methods.methods.iter().any(|m| m == "state_getStorage"));
+
+    // The test node supports everything that subxt uses:
+    let report = api.rpc().check_rpc_methods().await.unwrap();
+    assert!(report.missing.is_empty(), "{report:?}");
+}
+
+#[tokio::test]
+async fn chain_info_is_cached() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let info = api.chain_info().await.unwrap();
+    assert_eq!(info.chain, api.rpc().system_chain().await.unwrap());
+    assert_eq!(info.node_name, api.rpc().system_name().await.unwrap());
+
+    // The second call is served from the cache:
+    assert_eq!(api.chain_info().await.unwrap(), info);
+}
+
+#[tokio::test]
+async fn validate_address_checks_the_ss58_prefix() {
+    use sp_core::crypto::{
+        Ss58AddressFormat,
+        Ss58Codec,
+    };
+
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice = AccountKeyring::Alice.to_account_id();
+    let substrate_address =
+        alice.to_ss58check_with_version(Ss58AddressFormat::custom(42));
+    let polkadot_address = alice.to_ss58check_with_version(Ss58AddressFormat::custom(0));
+
+    let account = api.validate_address(&substrate_address).await.unwrap();
+    assert_eq!(account, subxt::utils::AccountId32(alice.into()));
+
+    let err = api.validate_address(&polkadot_address).await.unwrap_err();
+    assert!(err.to_string().contains("prefix 0"), "{err}");
+}
+
 #[tokio::test]
 async fn dry_run_passes() {
     let ctx = test_context().await;
@@ -218,6 +218,50 @@ async fn tx_dynamic_transfer() -> Result<(), subxt::Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn find_transfers_in_block_range() -> Result<(), subxt::Error> {
+    use futures::StreamExt;
+    use subxt::config::Header;
+
+    let alice = pair_signer(AccountKeyring::Alice.pair());
+    let bob: AccountId32 = AccountKeyring::Bob.to_account_id().into();
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let tx = node_runtime::tx()
+        .balances()
+        .transfer(bob.clone().into(), 12_345);
+    let in_block = api
+        .tx()
+        .sign_and_submit_then_watch_default(&tx, &alice)
+        .await?
+        .wait_for_finalized()
+        .await?;
+    let block_hash = in_block.block_hash();
+    let block_number = api
+        .rpc()
+        .header(Some(block_hash))
+        .await?
+        .expect("block header should exist")
+        .number() as u64;
+
+    let transfers: Vec<_> = api
+        .events()
+        .find_in_range::<balances::events::Transfer>(0..block_number + 1, 4)
+        .collect()
+        .await;
+    let transfer = transfers
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|ev| ev.event.to == bob && ev.event.amount == 12_345)
+        .expect("Failed to find the transfer");
+
+    assert_eq!(transfer.block_hash, block_hash);
+    assert_eq!(transfer.block_number, block_number);
+    Ok(())
+}
+
 #[tokio::test]
 async fn multiple_transfers_work_nonce_incremented() -> Result<(), subxt::Error> {
     let alice = pair_signer(AccountKeyring::Alice.pair());
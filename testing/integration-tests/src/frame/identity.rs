@@ -0,0 +1,56 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    pair_signer,
+    test_context,
+};
+use sp_keyring::AccountKeyring;
+use subxt::{
+    storage::{
+        identity,
+        IdentityData,
+        IdentityInfo,
+    },
+    utils::AccountId32,
+};
+
+#[tokio::test]
+async fn identities_can_be_set_and_read() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let dave = pair_signer(AccountKeyring::Dave.pair());
+    let dave_id: AccountId32 = AccountKeyring::Dave.to_account_id().into();
+
+    let info = IdentityInfo {
+        display: IdentityData::from("Dave"),
+        email: IdentityData::from("dave@example.com"),
+        ..Default::default()
+    };
+    api.tx()
+        .sign_and_submit_then_watch_default(&subxt::tx::set_identity(&info), &dave)
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    let storage = api.storage().at(None).await?;
+    let registration = identity(&storage, &dave_id)
+        .await?
+        .expect("identity was set");
+    assert_eq!(registration.info, info);
+    assert!(registration.judgements.is_empty());
+
+    api.tx()
+        .sign_and_submit_then_watch_default(&subxt::tx::clear_identity(), &dave)
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    let storage = api.storage().at(None).await?;
+    let registration = identity(&storage, &dave_id).await?;
+    assert_eq!(registration, None);
+
+    Ok(())
+}
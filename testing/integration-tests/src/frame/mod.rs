@@ -6,7 +6,10 @@
 
 mod balances;
 mod contracts;
+mod identity;
+mod preimage;
 mod staking;
 mod sudo;
 mod system;
 mod timestamp;
+mod utility;
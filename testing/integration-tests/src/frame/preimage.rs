@@ -0,0 +1,54 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    node_runtime,
+    pair_signer,
+    test_context,
+};
+use sp_core::H256;
+use sp_keyring::AccountKeyring;
+use subxt::{
+    ext::scale_value::ValueDef,
+    storage::decode_preimage,
+};
+
+#[tokio::test]
+async fn noted_preimages_can_be_decoded() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice = pair_signer(AccountKeyring::Alice.pair());
+    let remark = node_runtime::tx().system().remark(vec![1, 2, 3]);
+    let call_data = api.tx().call_data(&remark)?;
+    let hash = H256(sp_core::blake2_256(&call_data));
+
+    api.tx()
+        .sign_and_submit_then_watch_default(
+            &node_runtime::tx().preimage().note_preimage(call_data),
+            &alice,
+        )
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    let storage = api.storage().at(None).await?;
+    let call = decode_preimage(&storage, hash).await?;
+    let ValueDef::Variant(pallet) = call.value else {
+        panic!("expected a call variant")
+    };
+    assert_eq!(pallet.name, "System");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn missing_preimages_cannot_be_decoded() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let storage = api.storage().at(None).await.unwrap();
+    let res = decode_preimage(&storage, H256([0xAB; 32])).await;
+    assert!(res.is_err());
+}
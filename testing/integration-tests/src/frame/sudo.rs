@@ -76,3 +76,26 @@ async fn test_sudo_unchecked_weight() -> Result<(), subxt::Error> {
     assert!(found_event);
     Ok(())
 }
+
+#[tokio::test]
+async fn test_sudo_helper() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice = pair_signer(AccountKeyring::Alice.pair());
+    let bob = AccountKeyring::Bob.to_account_id().into();
+
+    let call = node_runtime::tx().balances().transfer(bob, 10_000);
+    let tx = subxt::tx::sudo_unchecked_weight(call, subxt::utils::Weight::default());
+
+    let found_event = api
+        .tx()
+        .sign_and_submit_then_watch_default(&tx, &alice)
+        .await?
+        .wait_for_finalized_success()
+        .await?
+        .has::<sudo::events::Sudid>()?;
+
+    assert!(found_event);
+    Ok(())
+}
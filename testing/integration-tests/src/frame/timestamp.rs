@@ -22,3 +22,26 @@ async fn storage_get_current_timestamp() {
 
     assert!(timestamp.is_ok())
 }
+
+#[tokio::test]
+async fn block_timestamp_matches_storage() {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let block_hash = api.rpc().block_hash(None).await.unwrap().unwrap();
+    let from_storage = api
+        .storage()
+        .at(Some(block_hash))
+        .await
+        .unwrap()
+        .fetch_or_default(&node_runtime::storage().timestamp().now())
+        .await
+        .unwrap();
+    let timestamp = api
+        .blocks()
+        .block_timestamp(Some(block_hash))
+        .await
+        .unwrap();
+
+    assert_eq!(timestamp, from_storage);
+}
@@ -0,0 +1,48 @@
+// Copyright 2019-2022 Parity Technologies (UK) Ltd.
+// This file is dual-licensed as Apache-2.0 or GPL-3.0.
+// see LICENSE for license details.
+
+use crate::{
+    node_runtime,
+    pair_signer,
+    test_context,
+};
+use sp_keyring::AccountKeyring;
+use subxt::error::DispatchError;
+
+#[tokio::test]
+async fn batch_result_finds_the_failed_call() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice = pair_signer(AccountKeyring::Alice.pair());
+    let bob = AccountKeyring::Bob.to_account_id();
+
+    let calls = vec![
+        node_runtime::tx()
+            .balances()
+            .transfer(bob.clone().into(), 10_000),
+        // Nobody has this much to transfer:
+        node_runtime::tx()
+            .balances()
+            .transfer(bob.clone().into(), u128::MAX),
+        node_runtime::tx().balances().transfer(bob.into(), 10_000),
+    ];
+
+    let result = api
+        .tx()
+        .sign_and_submit_then_watch_default(&subxt::tx::batch(calls), &alice)
+        .await?
+        .wait_for_finalized()
+        .await?
+        .fetch_batch_result()
+        .await?;
+
+    assert_eq!(result.succeeded(), 1);
+    assert!(matches!(
+        result.interrupted,
+        Some((1, DispatchError::Module(ref err))) if err.pallet == "Balances"
+    ));
+
+    Ok(())
+}
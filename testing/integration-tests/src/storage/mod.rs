@@ -9,7 +9,19 @@ use crate::{
     utils::wait_for_blocks,
 };
 use sp_keyring::AccountKeyring;
-use subxt::utils::AccountId32;
+use subxt::{
+    storage::{
+        account_balance,
+        account_info,
+        account_nonce,
+        block_usage,
+        multisigs,
+        proxies,
+        proxy_announcements,
+        vesting,
+    },
+    utils::AccountId32,
+};
 
 #[tokio::test]
 async fn storage_plain_lookup() -> Result<(), subxt::Error> {
@@ -92,6 +104,222 @@ async fn storage_n_mapish_key_is_properly_created() -> Result<(), subxt::Error>
     Ok(())
 }
 
+#[tokio::test]
+async fn storage_at_finalized() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    wait_for_blocks(&api).await;
+
+    let finalized_head = api.rpc().finalized_head().await?;
+    let storage = api.storage().at_finalized().await?;
+    assert_eq!(storage.block_hash(), finalized_head);
+
+    // Reads stay at the pinned block as further blocks are finalized:
+    let addr = node_runtime::storage().timestamp().now();
+    let before = storage.fetch_or_default(&addr).await?;
+    wait_for_blocks(&api).await;
+    let after = storage.fetch_or_default(&addr).await?;
+    assert_eq!(before, after);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_changes() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    wait_for_blocks(&api).await;
+
+    let from = api
+        .rpc()
+        .block_hash(Some(1u32.into()))
+        .await?
+        .expect("block 1 exists");
+    let to = api.rpc().block_hash(None).await?;
+
+    // The timestamp is set in every block, so we should see it change a few times:
+    let addr = node_runtime::storage().timestamp().now();
+    let changes = api.storage().changes(&addr, from, to).await?;
+    assert!(changes.len() >= 2);
+    assert_eq!(changes[0].block_hash, from);
+    for pair in changes.windows(2) {
+        assert!(pair[0].value < pair[1].value);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_fetch_size() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice: AccountId32 = AccountKeyring::Alice.to_account_id().into();
+    let storage = api.storage().at(None).await?;
+
+    let addr = node_runtime::storage().system().account(alice);
+    let size = storage.fetch_size(&addr).await?;
+    let value = storage.fetch_raw(&addr.to_bytes()).await?;
+    assert_eq!(size, value.map(|v| v.len() as u64));
+    assert!(size.is_some());
+
+    // Nothing is stored for accounts that have never been used:
+    let addr = node_runtime::storage()
+        .system()
+        .account(AccountId32::from([255; 32]));
+    assert_eq!(storage.fetch_size(&addr).await?, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_block_usage() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    wait_for_blocks(&api).await;
+    let storage = api.storage().at(None).await?;
+    let usage = block_usage(&storage).await?;
+
+    // Every block includes the timestamp inherent:
+    assert!(usage.length > 0);
+    assert!(usage.weight.mandatory.ref_time() > 0);
+    assert!(usage.max_length > usage.length);
+    assert!(usage.utilization() > 0.0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_account_balance() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice: AccountId32 = AccountKeyring::Alice.to_account_id().into();
+    let storage = api.storage().at(None).await?;
+
+    let balance = account_balance(&storage, &alice).await?;
+    let account = storage
+        .fetch_or_default(&node_runtime::storage().system().account(&alice))
+        .await?;
+    assert_eq!(balance.free, account.data.free);
+    assert_eq!(balance.reserved, account.data.reserved);
+
+    // Accounts that don't exist have no balance.
+    let nobody = AccountId32([0xAB; 32]);
+    assert_eq!(
+        account_balance(&storage, &nobody).await?,
+        Default::default()
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_account_nonce() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice: AccountId32 = AccountKeyring::Alice.to_account_id().into();
+    let storage = api.storage().at(None).await?;
+
+    let nonce = account_nonce(&storage, &alice).await?;
+    let account = storage
+        .fetch_or_default(&node_runtime::storage().system().account(&alice))
+        .await?;
+    assert_eq!(nonce, account.nonce as u64);
+
+    let nobody = AccountId32([0xAB; 32]);
+    assert_eq!(account_nonce(&storage, &nobody).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_account_info() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let alice: AccountId32 = AccountKeyring::Alice.to_account_id().into();
+    let storage = api.storage().at(None).await?;
+
+    let info = account_info(&storage, &alice).await?;
+    let account = storage
+        .fetch_or_default(&node_runtime::storage().system().account(&alice))
+        .await?;
+    assert_eq!(info.nonce, account.nonce as u64);
+    assert_eq!(info.consumers, account.consumers);
+    assert_eq!(info.providers, account.providers);
+    assert_eq!(info.sufficients, account.sufficients);
+    assert_eq!(info.balance.free, account.data.free);
+
+    let nobody = AccountId32([0xAB; 32]);
+    let info = account_info(&storage, &nobody).await?;
+    assert!(info.is_dead());
+    assert_eq!(info.balance.free, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_vesting_without_schedules() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    wait_for_blocks(&api).await;
+
+    let bob: AccountId32 = AccountKeyring::Bob.to_account_id().into();
+    let storage = api.storage().at(None).await?;
+    let vesting = vesting(&storage, &bob).await?;
+    assert!(vesting.block_number > 0);
+    assert!(vesting.schedules.is_empty());
+    assert_eq!(vesting.locked, 0);
+    assert_eq!(vesting.vested, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn storage_snapshot() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    wait_for_blocks(&api).await;
+
+    let alice: AccountId32 = AccountKeyring::Alice.to_account_id().into();
+    let mut snapshot = api.storage().snapshot();
+    let now = snapshot.storage(&node_runtime::storage().timestamp().now())?;
+    let account = snapshot.storage(&node_runtime::storage().system().account(&alice))?;
+    let existential_deposit =
+        snapshot.constant(&node_runtime::constants().balances().existential_deposit())?;
+    let snapshot = snapshot.fetch(None).await?;
+
+    // Everything should line up with what we'd get reading each value at the same block:
+    let storage = api.storage().at(Some(snapshot.block_hash())).await?;
+    assert_eq!(
+        snapshot.storage_or_default(&now)?,
+        storage
+            .fetch_or_default(&node_runtime::storage().timestamp().now())
+            .await?
+    );
+    assert_eq!(
+        snapshot.storage(&account)?.map(|a| a.nonce),
+        storage
+            .fetch(&node_runtime::storage().system().account(&alice))
+            .await?
+            .map(|a| a.nonce)
+    );
+    assert_eq!(
+        snapshot.constant(&existential_deposit)?,
+        api.constants()
+            .at(&node_runtime::constants().balances().existential_deposit())?
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn storage_n_map_storage_lookup() -> Result<(), subxt::Error> {
     let ctx = test_context().await;
@@ -127,3 +355,41 @@ async fn storage_n_map_storage_lookup() -> Result<(), subxt::Error> {
     assert_eq!(entry.map(|a| a.amount), Some(123));
     Ok(())
 }
+
+#[tokio::test]
+async fn storage_proxies() -> Result<(), subxt::Error> {
+    let ctx = test_context().await;
+    let api = ctx.client();
+
+    let signer = pair_signer(AccountKeyring::Charlie.pair());
+    let charlie: AccountId32 = AccountKeyring::Charlie.to_account_id().into();
+    let dave: AccountId32 = AccountKeyring::Dave.to_account_id().into();
+
+    let tx = subxt::dynamic::tx(
+        "Proxy",
+        "add_proxy",
+        vec![
+            subxt::dynamic::Value::unnamed_variant(
+                "Id",
+                vec![subxt::dynamic::Value::from_bytes(dave.0)],
+            ),
+            subxt::dynamic::Value::unnamed_variant("Any", vec![]),
+            subxt::dynamic::Value::u128(0),
+        ],
+    );
+    api.tx()
+        .sign_and_submit_then_watch_default(&tx, &signer)
+        .await?
+        .wait_for_finalized_success()
+        .await?;
+
+    let storage = api.storage().at(None).await?;
+    let proxies = proxies(&storage, &charlie).await?;
+    assert_eq!(proxies.len(), 1);
+    assert_eq!(proxies[0].delegate, dave.0.to_vec());
+    assert_eq!(proxies[0].proxy_type, "Any");
+    assert!(proxy_announcements(&storage, &charlie).await?.is_empty());
+    assert!(multisigs(&storage, &charlie).await?.is_empty());
+
+    Ok(())
+}
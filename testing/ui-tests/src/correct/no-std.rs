@@ -0,0 +1,18 @@
+#![no_std]
+
+// Generated code in `no_std` mode refers to `alloc` rather than `std`, and so builds in a
+// crate without `std` in scope.
+extern crate alloc;
+
+#[subxt::subxt(
+    runtime_metadata_path = "../../../../artifacts/polkadot_metadata.scale",
+    generate_item_names,
+    generate_type_conversions,
+    no_std
+)]
+pub mod node_runtime {}
+
+fn main() {
+    let _ = node_runtime::storage().system().account_root();
+    let _ = node_runtime::tx().system().remark(alloc::vec![1, 2, 3]);
+}